@@ -0,0 +1,10 @@
+// Any source is either parsed or a parse error
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tego_parser::ast::Prog;
+
+fuzz_target!(|source: &str| {
+    let _ = tego_parser::tokenize(source);
+    let _ = tego_parser::prog::<Prog>(source.into());
+});
@@ -0,0 +1,30 @@
+// Random programs are written as source and parsed again, which has to give
+// the same program, and then they're run with a limit on how long they can run
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use std::io;
+use tego_interpreter::capability::EvalConfig;
+use tego_interpreter::Engine;
+use tego_parser::ast::Prog;
+use tego_parser::ParseError;
+
+fuzz_target!(|prog: Prog| {
+    let source = prog.to_string();
+    let parsed = tego_parser::prog::<Prog>(source.as_str().into())
+        .map(|(_, parsed)| parsed)
+        .map_err(ParseError::from);
+    assert_eq!(parsed, Ok(prog), "{}", source);
+
+    let mut engine = Engine::new();
+    engine.set_config(EvalConfig {
+        max_steps: Some(10_000),
+        max_tuple_len: Some(10_000),
+        ..EvalConfig::sandboxed()
+    });
+    engine.set_output(io::sink());
+    // Programs that import files can't be loaded, since files aren't allowed
+    if engine.load_source(&source).is_ok() {
+        let _ = engine.run();
+    }
+});
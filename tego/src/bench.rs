@@ -0,0 +1,167 @@
+// Times the benchmarks in a file (see `tego bench`), which are the
+// declarations whose names start with `bench`
+//
+//     benchSum = sum (range 0 1000)
+//
+// Each benchmark is evaluated (and run, if it's a Command) a number of times
+// without being timed first, so that the timings aren't skewed by whatever
+// happens the first time
+
+use crate::codefile;
+use crate::test_runner;
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::path::Path;
+use std::rc::Rc;
+use std::time::Instant;
+use tego_analysis::diagnostics;
+use tego_interpreter as interpreter;
+use tego_interpreter::output;
+use tego_parser::ast::Prog;
+
+const BENCH_PREFIX: &str = "bench";
+
+pub struct Settings<'a> {
+    // Only benchmarks with this in their name are run
+    pub filter: Option<&'a str>,
+    // Runs that aren't timed
+    pub warmup: usize,
+    pub iterations: usize,
+}
+
+pub fn run<P: AsRef<Path>>(
+    path: P,
+    config: &diagnostics::Config,
+    settings: &Settings,
+) -> io::Result<i32> {
+    let mut stdout = io::BufWriter::new(io::stdout());
+    let mut stderr = io::BufWriter::new(io::stderr());
+    let dir = codefile::dir_of(path.as_ref());
+    let decls = match codefile::load(path.as_ref(), config, &mut stderr)? {
+        Ok(Prog::Binary(_, decls)) | Ok(Prog::Library(decls)) => decls,
+        Err(code) => return codefile::wrap_up(stderr, stdout, code),
+    };
+    let benches = test_runner::declarations(&decls, BENCH_PREFIX, settings.filter);
+    if benches.is_empty() {
+        writeln!(
+            stdout,
+            "No benchmarks found (benchmarks are declarations whose names start with '{}')",
+            BENCH_PREFIX
+        )?;
+        return codefile::wrap_up(stderr, stdout, codefile::SUCCESS);
+    }
+    let env = match interpreter::program_env(&decls, &dir) {
+        Ok(env) => env,
+        Err(e) => {
+            codefile::load_error(&mut stderr, Some(path.as_ref()), &e)?;
+            return codefile::wrap_up(stderr, stdout, codefile::RUNTIME_ERROR);
+        }
+    };
+
+    // Output from the benchmarks would get in the way of the timings
+    let previous = output::set_output(Some(Rc::new(RefCell::new(io::sink()))));
+    let mut code = codefile::SUCCESS;
+    for (name, expr) in benches {
+        write!(stdout, "bench {} ... ", name)?;
+        stdout.flush()?;
+        let run = || {
+            let value = interpreter::eval_expr(expr.clone(), &env);
+            value.run().unwrap_or(value)
+        };
+        let mut times = vec![];
+        let mut error = None;
+        for i in 0..settings.warmup + settings.iterations {
+            let start = Instant::now();
+            let value = run();
+            if value.is_error() {
+                error = Some(value);
+                break;
+            }
+            if i >= settings.warmup {
+                times.push(start.elapsed().as_secs_f64());
+            }
+        }
+        match (error, Stats::new(&times)) {
+            (Some(error), _) => {
+                writeln!(stdout, "FAILED\n  {}", error)?;
+                code = codefile::RUNTIME_ERROR;
+            }
+            (None, Some(stats)) => writeln!(
+                stdout,
+                "mean {}, median {}, stddev {} ({} runs)",
+                format_time(stats.mean),
+                format_time(stats.median),
+                format_time(stats.stddev),
+                times.len()
+            )?,
+            (None, None) => writeln!(stdout, "not timed (0 runs)")?,
+        }
+    }
+    output::set_output(previous);
+    codefile::wrap_up(stderr, stdout, code)
+}
+
+// In seconds
+#[derive(Debug, PartialEq)]
+struct Stats {
+    mean: f64,
+    median: f64,
+    stddev: f64,
+}
+
+impl Stats {
+    fn new(times: &[f64]) -> Option<Stats> {
+        if times.is_empty() {
+            return None;
+        }
+        let len = times.len() as f64;
+        let mean = times.iter().sum::<f64>() / len;
+        let mut sorted = times.to_vec();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let middle = sorted.len() / 2;
+        let median = if sorted.len().is_multiple_of(2) {
+            (sorted[middle - 1] + sorted[middle]) / 2.0
+        } else {
+            sorted[middle]
+        };
+        let variance = times.iter().map(|time| (time - mean).powi(2)).sum::<f64>() / len;
+        Some(Stats {
+            mean,
+            median,
+            stddev: variance.sqrt(),
+        })
+    }
+}
+
+// Uses whichever unit keeps the number readable
+fn format_time(secs: f64) -> String {
+    if secs >= 1.0 {
+        format!("{:.3} s", secs)
+    } else if secs >= 1e-3 {
+        format!("{:.3} ms", secs * 1e3)
+    } else if secs >= 1e-6 {
+        format!("{:.3} µs", secs * 1e6)
+    } else {
+        format!("{:.0} ns", secs * 1e9)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    basic_test! {
+        stats_test
+        Stats::new(&[]) => None;
+        Stats::new(&[2.0]) => Some(Stats { mean: 2.0, median: 2.0, stddev: 0.0 });
+        Stats::new(&[4.0, 1.0, 2.0, 1.0]) => Some(Stats { mean: 2.0, median: 1.5, stddev: 1.224744871391589 })
+    }
+
+    basic_test! {
+        format_time_test
+        format_time(2.5) => "2.500 s";
+        format_time(0.0125) => "12.500 ms";
+        format_time(0.000_001_5) => "1.500 µs";
+        format_time(0.000_000_042) => "42 ns"
+    }
+}
@@ -1,53 +1,298 @@
+use crate::message;
 use std::fs;
-use std::io::{self, Write};
-use std::path::Path;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use tego_analysis::diagnostics;
 use tego_interpreter as interpreter;
 use tego_parser as parser;
+use tego_parser::ast::{Decl, Expr, Prog};
 
-pub fn run<P: AsRef<Path>>(path: P) -> io::Result<()> {
+// Exit codes
+pub const SUCCESS: i32 = 0;
+pub const RUNTIME_ERROR: i32 = 1;
+pub const LOAD_ERROR: i32 = 2; // File couldn't be read, parsed, or type checked
+pub const USAGE_ERROR: i32 = 64; // Arguments couldn't be parsed (the same as `EX_USAGE`)
+
+const STDIN_PATH: &str = "-";
+
+pub fn run<P: AsRef<Path>>(path: P, config: &diagnostics::Config) -> io::Result<i32> {
+    let mut stdout = io::BufWriter::new(io::stdout());
+    let mut stderr = io::BufWriter::new(io::stderr());
+    let dir = dir_of(path.as_ref());
+    let code = match load(path, config, &mut stderr)? {
+        Ok(program) => run_program(program, &dir, &mut stdout, &mut stderr)?,
+        Err(code) => code,
+    };
+    wrap_up(stderr, stdout, code)
+}
+
+// Runs source from the command line (see `tego -e`), which is either an
+// expression or a whole program
+// Imports are relative to the current directory
+pub fn eval(source: &str, config: &diagnostics::Config) -> io::Result<i32> {
     let mut stdout = io::BufWriter::new(io::stdout());
     let mut stderr = io::BufWriter::new(io::stderr());
-    let file = match open_file(path) {
-        Ok(f) => f,
+    // An expression is run as if it was the program's `main`
+    let program = match parser::complete(parser::expr::<Expr>)(source.into()) {
+        Ok((_, main)) => analyze(
+            Prog::Binary(main.clone(), vec![Decl::Expression("main".into(), main)]),
+            None,
+            source,
+            config,
+            &mut stderr,
+        )?,
+        Err(expr_error) => match parser::prog(source.into()) {
+            Ok((_, prog)) => analyze(prog, None, source, config, &mut stderr)?,
+            // The error found furthest into the source is most likely the
+            // one that was meant
+            Err(prog_error) => {
+                let expr_error = parser::ParseError::from(expr_error);
+                let prog_error = parser::ParseError::from(prog_error);
+                let error = if (expr_error.line(), expr_error.column())
+                    > (prog_error.line(), prog_error.column())
+                {
+                    expr_error
+                } else {
+                    prog_error
+                };
+                message::parse_error(&mut stderr, None, source, &error)?;
+                Err(LOAD_ERROR)
+            }
+        },
+    };
+    let code = match program {
+        Ok(program) => run_program(program, Path::new("."), &mut stdout, &mut stderr)?,
+        Err(code) => code,
+    };
+    wrap_up(stderr, stdout, code)
+}
+
+// Prints the result of `main`, unless it's a Command (which is run instead)
+fn run_program(
+    program: Prog,
+    dir: &Path,
+    stdout: &mut io::BufWriter<io::Stdout>,
+    stderr: &mut io::BufWriter<io::Stderr>,
+) -> io::Result<i32> {
+    let result = match interpreter::run_prog(program, dir) {
+        Ok(r) => r,
+        Err(interpreter::RuntimeError::Exit(code)) => return Ok(code),
         Err(e) => {
-            writeln!(stdout, "Error reading file: {}", e)?;
-            return wrap_up(stderr, stdout);
+            runtime_error(stderr, e.number(), &e)?;
+            return Ok(RUNTIME_ERROR);
         }
     };
-    let program = match parser::prog(file.as_str().into()) {
-        Ok((_, prog)) => prog,
-        Err(err) => {
-            parser::ParseError::from(err).verbose_from_source(&file, &mut stderr)?;
-            return wrap_up(stderr, stdout);
+    if let interpreter::value::Value::Error(error) = &result {
+        runtime_error(stderr, error.number(), &result)?;
+        return Ok(RUNTIME_ERROR);
+    }
+    match result.run() {
+        Err(()) => {
+            writeln!(stdout, "{}", result)?;
+            Ok(SUCCESS)
         }
+        // Command was run
+        Ok(result) => match &result {
+            interpreter::value::Value::Error(interpreter::RuntimeError::Exit(code)) => Ok(*code),
+            interpreter::value::Value::Error(error) => {
+                runtime_error(stderr, error.number(), &result)?;
+                Ok(RUNTIME_ERROR)
+            }
+            _ => Ok(SUCCESS),
+        },
+    }
+}
+
+fn runtime_error(
+    out: &mut impl Write,
+    number: u16,
+    error: &dyn std::fmt::Display,
+) -> io::Result<()> {
+    message::error(
+        out,
+        "runtime-error",
+        Some(number),
+        "Error running file",
+        None,
+        error,
+    )
+}
+
+// Loads the file and everything it imports, and reports everything that's
+// found by analyzing it, without running it
+pub fn check<P: AsRef<Path>>(path: P, config: &diagnostics::Config) -> io::Result<i32> {
+    let stdout = io::BufWriter::new(io::stdout());
+    let mut stderr = io::BufWriter::new(io::stderr());
+    let path = path.as_ref();
+    let dir = dir_of(path);
+    // The source is kept to find where diagnostics are
+    let source = match read(path, &mut stderr)? {
+        Ok(source) => source,
+        Err(code) => return wrap_up(stderr, stdout, code),
     };
-    let result = match interpreter::run_prog(program) {
-        Ok(r) => r,
+    let decls = match parse(&source, Some(path), config, &mut stderr)? {
+        Ok(Prog::Binary(_, decls)) | Ok(Prog::Library(decls)) => decls,
+        Err(code) => return wrap_up(stderr, stdout, code),
+    };
+    let env = match interpreter::program_env(&decls, &dir) {
+        Ok(env) => env,
         Err(e) => {
-            writeln!(stderr, "Error running file: {}", e)?;
-            return wrap_up(stderr, stdout);
+            load_error(&mut stderr, Some(path), &e)?;
+            return wrap_up(stderr, stdout, RUNTIME_ERROR);
         }
     };
-    if result.is_error() {
-        writeln!(stderr, "Error running file: {}", result)?;
-    } else if let Err(()) = result.run() {
-        writeln!(stdout, "{}", result)?;
+    let idents = interpreter::VarEnv::idents(&env);
+    let undeclared = diagnostics::undeclared(
+        &decls,
+        |ident| interpreter::VarEnv::get(&env, ident).is_some(),
+        |ident| {
+            interpreter::suggest::similar(ident, idents.iter().map(String::as_str))
+                .map(String::from)
+        },
+    );
+    message::diagnostics(&mut stderr, Some(path), &source, &undeclared)?;
+    let code = if undeclared.is_empty() {
+        SUCCESS
     } else {
-        // Command was run
+        LOAD_ERROR
+    };
+    wrap_up(stderr, stdout, code)
+}
+
+// Prints each token in the file with its position, kind, and lexeme
+pub fn tokens<P: AsRef<Path>>(path: P) -> io::Result<i32> {
+    let mut stdout = io::BufWriter::new(io::stdout());
+    let mut stderr = io::BufWriter::new(io::stderr());
+    let source = match open_file(path) {
+        Ok(source) => source,
+        Err(e) => {
+            writeln!(stderr, "Error reading file: {}", e)?;
+            return wrap_up(stderr, stdout, LOAD_ERROR);
+        }
+    };
+    for token in parser::tokenize(&source) {
+        let position = format!("{}:{}", token.span.line(), token.span.column());
+        writeln!(
+            stdout,
+            "{:<8}{:<12}{:?}",
+            position,
+            token.kind.to_string(),
+            token.span.to_str()
+        )?;
+    }
+    wrap_up(stderr, stdout, SUCCESS)
+}
+
+// Reads and parses the file, resulting in the exit code if that fails
+pub fn load<P: AsRef<Path>>(
+    path: P,
+    config: &diagnostics::Config,
+    stderr: &mut io::BufWriter<io::Stderr>,
+) -> io::Result<Result<Prog, i32>> {
+    let path = path.as_ref();
+    match read(path, stderr)? {
+        Ok(source) => parse(&source, Some(path), config, stderr),
+        Err(code) => Ok(Err(code)),
     }
+}
 
-    wrap_up(stderr, stdout)
+fn read(path: &Path, stderr: &mut io::BufWriter<io::Stderr>) -> io::Result<Result<String, i32>> {
+    match open_file(path) {
+        Ok(source) => Ok(Ok(source)),
+        Err(e) => {
+            message::error(
+                stderr,
+                "read-error",
+                None,
+                "Error reading file",
+                Some(path),
+                &e,
+            )?;
+            Ok(Err(LOAD_ERROR))
+        }
+    }
+}
+
+// `file` is where the source was read from, unless it was given on the
+// command line
+fn parse(
+    source: &str,
+    file: Option<&Path>,
+    config: &diagnostics::Config,
+    stderr: &mut io::BufWriter<io::Stderr>,
+) -> io::Result<Result<Prog, i32>> {
+    match parser::prog(source.into()) {
+        Ok((_, prog)) => analyze(prog, file, source, config, stderr),
+        Err(err) => {
+            message::parse_error(stderr, file, source, &parser::ParseError::from(err))?;
+            Ok(Err(LOAD_ERROR))
+        }
+    }
 }
 
+// Type errors are found before anything is run, and warnings are only reported
+fn analyze(
+    prog: Prog,
+    file: Option<&Path>,
+    source: &str,
+    config: &diagnostics::Config,
+    stderr: &mut io::BufWriter<io::Stderr>,
+) -> io::Result<Result<Prog, i32>> {
+    let decls = match &prog {
+        Prog::Binary(_, decls) | Prog::Library(decls) => decls,
+    };
+    let diagnostics = diagnostics::check(decls, config);
+    message::diagnostics(stderr, file, source, &diagnostics)?;
+    if diagnostics.iter().any(|diagnostic| diagnostic.is_error()) {
+        Ok(Err(LOAD_ERROR))
+    } else {
+        Ok(Ok(prog))
+    }
+}
+
+// Where the file's imports are relative to (the current directory for a file
+// in it, or for stdin)
+pub fn dir_of(path: &Path) -> PathBuf {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_owned(),
+        _ => PathBuf::from("."),
+    }
+}
+
+// The program is read from stdin when the path is `-` (ex. `cat main.tgo | tego run -`)
 fn open_file<P: AsRef<Path>>(path: P) -> io::Result<String> {
-    fs::read_to_string(path)
+    if path.as_ref() == Path::new(STDIN_PATH) {
+        let mut source = String::new();
+        io::stdin().read_to_string(&mut source)?;
+        Ok(source)
+    } else {
+        fs::read_to_string(path)
+    }
 }
 
-fn wrap_up(
+// The file's imports couldn't be loaded
+pub fn load_error(
+    out: &mut impl Write,
+    file: Option<&Path>,
+    error: &dyn std::fmt::Display,
+) -> io::Result<()> {
+    let number = interpreter::RuntimeError::Import(error.to_string()).number();
+    message::error(
+        out,
+        "load-error",
+        Some(number),
+        "Error loading file",
+        file,
+        error,
+    )
+}
+
+pub fn wrap_up(
     mut stderr: io::BufWriter<io::Stderr>,
     mut stdout: io::BufWriter<io::Stdout>,
-) -> io::Result<()> {
+    code: i32,
+) -> io::Result<i32> {
     stderr.flush()?;
     stdout.flush()?;
-    Ok(())
+    Ok(code)
 }
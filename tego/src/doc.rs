@@ -0,0 +1,184 @@
+// Writes documentation for a file and every file it imports (see `tego doc`),
+// with one page per module
+
+use crate::codefile;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::str::FromStr;
+use tego_analysis::docs::{self, ModuleDoc};
+use tego_interpreter as interpreter;
+use tego_parser as parser;
+use tego_parser::ast::Prog;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Format {
+    Markdown,
+    Html,
+}
+
+impl Format {
+    fn extension(self) -> &'static str {
+        match self {
+            Format::Markdown => "md",
+            Format::Html => "html",
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "markdown" | "md" => Ok(Format::Markdown),
+            "html" => Ok(Format::Html),
+            _ => Err(format!(
+                "Unknown format '{}' (expected 'markdown' or 'html')",
+                s
+            )),
+        }
+    }
+}
+
+// The pages are written to `out`
+pub fn run(path: &Path, format: Format, out: &Path) -> io::Result<i32> {
+    let mut stdout = io::BufWriter::new(io::stdout());
+    let mut stderr = io::BufWriter::new(io::stderr());
+    let mut files = vec![path.to_owned()];
+    let mut code = codefile::SUCCESS;
+    let mut i = 0;
+    while let Some(file) = files.get(i).cloned() {
+        i += 1;
+        let source = match fs::read_to_string(&file) {
+            Ok(source) => source,
+            Err(e) => {
+                writeln!(stderr, "Error reading '{}': {}", file.display(), e)?;
+                code = codefile::LOAD_ERROR;
+                continue;
+            }
+        };
+        let decls = match parser::prog(source.as_str().into()) {
+            Ok((_, Prog::Binary(_, decls))) | Ok((_, Prog::Library(decls))) => decls,
+            Err(error) => {
+                writeln!(stderr, "Error parsing '{}':", file.display())?;
+                parser::ParseError::from(error).verbose_from_source(&source, &mut stderr)?;
+                code = codefile::LOAD_ERROR;
+                continue;
+            }
+        };
+        for import in interpreter::module::dependencies(&decls, Some(&codefile::dir_of(&file))) {
+            if !files.iter().any(|file| same_file(file, &import)) {
+                files.push(import);
+            }
+        }
+
+        let name = module_name(&file);
+        let page = match format {
+            Format::Markdown => markdown(&name, &docs::document(&source, &decls)),
+            Format::Html => html(&name, &docs::document(&source, &decls)),
+        };
+        let page_path = out.join(format!("{}.{}", name, format.extension()));
+        fs::create_dir_all(out)?;
+        fs::write(&page_path, page)?;
+        writeln!(
+            stdout,
+            "Documented '{}' in '{}'",
+            file.display(),
+            page_path.display()
+        )?;
+    }
+    codefile::wrap_up(stderr, stdout, code)
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+fn module_name(file: &Path) -> String {
+    file.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "module".into())
+}
+
+fn markdown(name: &str, module: &ModuleDoc) -> String {
+    let mut page = format!("# {}\n", name);
+    if let Some(doc) = &module.doc {
+        page.push_str(&format!("\n{}\n", doc));
+    }
+    for item in &module.items {
+        page.push_str(&format!("\n## `{}`\n", item.name));
+        if let Some(type_) = &item.type_ {
+            page.push_str(&format!("\n```\n{} : {}\n```\n", item.name, type_));
+        }
+        if let Some(doc) = &item.doc {
+            page.push_str(&format!("\n{}\n", doc));
+        }
+    }
+    page
+}
+
+fn html(name: &str, module: &ModuleDoc) -> String {
+    let mut page = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{0}</title>\n</head>\n<body>\n<h1>{0}</h1>\n",
+        escape(name)
+    );
+    if let Some(doc) = &module.doc {
+        page.push_str(&format!("<p>{}</p>\n", escape(doc)));
+    }
+    for item in &module.items {
+        page.push_str(&format!(
+            "<h2 id=\"{0}\"><code>{0}</code></h2>\n",
+            escape(&item.name)
+        ));
+        if let Some(type_) = &item.type_ {
+            page.push_str(&format!(
+                "<pre><code>{} : {}</code></pre>\n",
+                escape(&item.name),
+                escape(type_)
+            ));
+        }
+        if let Some(doc) = &item.doc {
+            page.push_str(&format!("<p>{}</p>\n", escape(doc)));
+        }
+    }
+    page.push_str("</body>\n</html>\n");
+    page
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tego_analysis::docs::ItemDoc;
+
+    fn module() -> ModuleDoc {
+        ModuleDoc {
+            doc: Some("Numbers".into()),
+            items: vec![ItemDoc {
+                name: "double".into(),
+                type_: Some("Int -> Int".into()),
+                doc: Some("Doubles `n`".into()),
+            }],
+        }
+    }
+
+    basic_test! {
+        markdown_test
+        markdown("Math", &module()) => "# Math\n\nNumbers\n\n## `double`\n\n```\ndouble : Int -> Int\n```\n\nDoubles `n`\n"
+    }
+
+    basic_test! {
+        html_test
+        html("Math", &module()).contains("<pre><code>double : Int -&gt; Int</code></pre>\n<p>Doubles `n`</p>\n") => true
+    }
+}
@@ -0,0 +1,857 @@
+// Longer descriptions of each kind of error and warning, which `tego explain`
+// prints by its code (ex. `E0007`)
+//
+// The numbers come from the parser (1 to 100), the analysis before a program
+// is run (101 to 200, see `diagnostics::NUMBERS`), and the interpreter (from
+// 201, see `RuntimeError::number`)
+
+use crate::codefile;
+use std::io::{self, Write};
+
+pub fn code(number: u16) -> String {
+    format!("E{:04}", number)
+}
+
+// `E0007`, `e0007`, and `7` are all the same code
+fn number(code: &str) -> Option<u16> {
+    let digits = code.strip_prefix(|c| c == 'E' || c == 'e').unwrap_or(code);
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+fn explanation(number: u16) -> Option<&'static str> {
+    EXPLANATIONS
+        .iter()
+        .find(|(explained, _)| *explained == number)
+        .map(|(_, explanation)| *explanation)
+}
+
+pub fn run(code: &str) -> io::Result<i32> {
+    match number(code).and_then(|number| Some((number, explanation(number)?))) {
+        Some((number, explanation)) => {
+            let mut stdout = io::stdout();
+            writeln!(stdout, "{}: {}", self::code(number), explanation.trim())?;
+            Ok(codefile::SUCCESS)
+        }
+        None => {
+            eprintln!("'{}' isn't an error code (they look like 'E0007')", code);
+            Ok(codefile::USAGE_ERROR)
+        }
+    }
+}
+
+const EXPLANATIONS: &[(u16, &str)] = &[
+    // Parse errors
+    (
+        1,
+        r#"
+A token is missing
+
+The parser expected a specific token (ex. '=' or ')') that wasn't there. The
+message says which one.
+"#,
+    ),
+    (
+        2,
+        r#"
+A character literal is invalid
+
+Characters are a single character in single quotes, or an escape:
+
+    a = 'a'
+    newline = '\n'
+"#,
+    ),
+    (
+        3,
+        r#"
+A string literal is invalid
+
+Strings are in double quotes, and have to be closed on the same line:
+
+    greeting = "Hello, world"
+"#,
+    ),
+    (
+        4,
+        r#"
+A number literal is invalid
+
+Numbers are digits, with an optional '-' in front:
+
+    a = 42
+    b = -7
+"#,
+    ),
+    (
+        5,
+        r#"
+A keyword is used as a name
+
+Keywords (ex. 'match', 'let', or 'if') can't be used as the names of
+declarations, parameters, or bindings:
+
+    -- Error
+    match = 1
+    -- Ok
+    matches = 1
+"#,
+    ),
+    (
+        6,
+        r#"
+An invalid character is in the source
+
+A character that isn't part of any token was found where an expression was
+expected.
+"#,
+    ),
+    (
+        7,
+        r#"
+A parenthesis isn't closed
+
+Every '(' needs a ')' after it. The message also points to where the '(' is:
+
+    -- Error
+    a = (1 + 2
+    -- Ok
+    a = (1 + 2)
+"#,
+    ),
+    (
+        8,
+        r#"
+A function is missing its '->'
+
+Functions have parameters, then '->', then their body:
+
+    -- Error
+    double = fn n n * 2
+    -- Ok
+    double = fn n -> n * 2
+"#,
+    ),
+    (
+        9,
+        r#"
+A match arm is missing its '|'
+
+Each arm of a 'match' starts with '|':
+
+    isZero n = match n to
+    	| 0 -> true
+    	| _ -> false
+"#,
+    ),
+    (
+        10,
+        r#"
+A match arm is missing its '->'
+
+Each arm of a 'match' has a pattern, then '->', then its result:
+
+    -- Error
+    isZero n = match n to
+    	| 0 true
+    	| _ -> false
+"#,
+    ),
+    (
+        11,
+        r#"
+A match is missing its 'to'
+
+The value that's matched is followed by 'to', then the arms:
+
+    -- Error
+    isZero n = match n
+    	| 0 -> true
+    	| _ -> false
+"#,
+    ),
+    (
+        12,
+        r#"
+An if is missing its 'then'
+
+The condition of an 'if' is followed by 'then' (or '?'):
+
+    -- Error
+    abs n = if n < 0 -n else n
+    -- Ok
+    abs n = if n < 0 then -n else n
+"#,
+    ),
+    (
+        13,
+        r#"
+An if is missing its 'else'
+
+Every 'if' has a result for when its condition is false, after 'else' (or ':'):
+
+    -- Error
+    abs n = if n < 0 then -n
+    -- Ok
+    abs n = if n < 0 then -n else n
+"#,
+    ),
+    (
+        14,
+        r#"
+A let is missing its '='
+
+    -- Error
+    a = let b 1 in b
+    -- Ok
+    a = let b = 1 in b
+"#,
+    ),
+    (
+        15,
+        r#"
+A let is missing its 'in'
+
+The bindings of a 'let' are followed by 'in', then the expression that uses them:
+
+    -- Error
+    a = let b = 1 b
+    -- Ok
+    a = let b = 1 in b
+"#,
+    ),
+    (
+        16,
+        r#"
+A delay is missing its '='
+
+    -- Error
+    a = delay b 1 in b
+    -- Ok
+    a = delay b = 1 in b
+"#,
+    ),
+    (
+        17,
+        r#"
+A delay is missing its 'in'
+
+    -- Error
+    a = delay b = 1 b
+    -- Ok
+    a = delay b = 1 in b
+"#,
+    ),
+    (
+        18,
+        r#"
+A declaration is missing its '='
+
+Declarations have a name and parameters, then '=', then their value:
+
+    -- Error
+    double n n * 2
+    -- Ok
+    double n = n * 2
+"#,
+    ),
+    (
+        19,
+        r#"
+A newline is missing
+
+Each declaration starts on a new line, so something was found after the end of
+a declaration on the same line.
+"#,
+    ),
+    (
+        20,
+        r#"
+The file ended too early
+
+The end of the file was reached before the last declaration or expression was
+finished (ex. an unclosed bracket, or an operator without a right side).
+"#,
+    ),
+    (
+        21,
+        r#"
+An unknown parse error
+
+The parser failed in a way that doesn't have a more specific error. Please
+report it, with the source that caused it.
+"#,
+    ),
+    (
+        22,
+        r#"
+An unhandled parse error
+
+The parser failed in a way that doesn't have a more specific error. Please
+report it, with the source that caused it.
+"#,
+    ),
+    (
+        23,
+        r#"
+An expression ended too early
+
+The line or file ended where the rest of an expression was expected:
+
+    -- Error
+    a = if
+    -- Ok
+    a = if true then 1 else 2
+"#,
+    ),
+    (
+        24,
+        r#"
+A bracket isn't closed
+
+Every '[' needs a ']' after it. The message also points to where the '[' is:
+
+    -- Error
+    a = [1, 2
+    -- Ok
+    a = [1, 2]
+"#,
+    ),
+    (
+        25,
+        r#"
+Incomplete input
+
+The parser needed more input than it was given.
+"#,
+    ),
+    (
+        26,
+        r#"
+A do is missing its 'in'
+
+'do' runs a Command, and binds its result after 'in':
+
+    main = do now in t then println t
+"#,
+    ),
+    (
+        27,
+        r#"
+A do is missing its 'then'
+
+The binding of a 'do' is followed by 'then', then the expression that uses it:
+
+    -- Error
+    main = do now in t println t
+    -- Ok
+    main = do now in t then println t
+"#,
+    ),
+    (
+        28,
+        r#"
+An import is missing what to import
+
+'import' is followed by a path in quotes, or the name of a module:
+
+    import "lib/math.tgo"
+    import Math
+"#,
+    ),
+    (
+        29,
+        r#"
+An export is missing its names
+
+'export' is followed by the names to export, in parentheses:
+
+    export (square, cube)
+"#,
+    ),
+    (
+        30,
+        r#"
+An import is missing its name after 'as'
+
+    -- Error
+    import "lib/numbers.tgo" as
+    -- Ok
+    import "lib/numbers.tgo" as N
+"#,
+    ),
+    (
+        31,
+        r#"
+An import has an invalid list of names
+
+The names to import are in parentheses, separated by commas:
+
+    import Math (pow, gcd)
+"#,
+    ),
+    (
+        32,
+        r#"
+A signature is missing its type
+
+    -- Error
+    double :
+    -- Ok
+    double : Int -> Int
+"#,
+    ),
+    (
+        33,
+        r#"
+The equations of a function have different numbers of parameters
+
+A function declared by several equations has the same number of parameters in
+each one:
+
+    -- Error
+    add 0 b = b
+    add a = a
+    -- Ok
+    add 0 b = b
+    add a b = a + b
+"#,
+    ),
+    (
+        34,
+        r#"
+A cond arm is missing its '->'
+
+Each arm of a 'cond' has a condition, then '->', then its result:
+
+    -- Error
+    sign x = cond
+    	| x < 0 "negative"
+    	| else -> "positive"
+"#,
+    ),
+    (
+        35,
+        r#"
+A cond is missing its 'else'
+
+The last arm of a 'cond' is '| else -> ...', which is the result when none of
+the conditions are true:
+
+    -- Error
+    sign x = cond
+    	| x < 0 -> "negative"
+    -- Ok
+    sign x = cond
+    	| x < 0 -> "negative"
+    	| else -> "positive"
+"#,
+    ),
+    // Errors and warnings found before a program is run
+    (
+        101,
+        r#"
+A value has the wrong type
+
+A value was used where a different type was expected:
+
+    -- Error ('true' isn't an 'Int')
+    a = 1 + true
+"#,
+    ),
+    (
+        102,
+        r#"
+A signature uses an unknown type
+
+The types are 'Int', 'Bool', 'Char', 'String', 'Any', functions (ex.
+'Int -> Int'), boxes (ex. '[Int]'), and type variables (ex. 'a'):
+
+    -- Error
+    double : Integer -> Integer
+    -- Ok
+    double : Int -> Int
+"#,
+    ),
+    (
+        103,
+        r#"
+A type has the wrong number of type arguments
+
+    -- Error ('Int' doesn't take a type argument)
+    a : Int Bool
+"#,
+    ),
+    (
+        104,
+        r#"
+A signature is more general than the declaration
+
+A signature can be less general than the type that's inferred, but not more:
+
+    -- Error (the result is always an 'Int')
+    double : a -> a
+    double n = n * 2
+"#,
+    ),
+    (
+        105,
+        r#"
+A signature doesn't have a declaration
+
+There's a signature for a name that isn't declared (ex. because of a typo):
+
+    -- Error
+    doubel : Int -> Int
+    double n = n * 2
+"#,
+    ),
+    (
+        106,
+        r#"
+A declaration has more than one signature
+
+    -- Error
+    double : Int -> Int
+    double : Int -> Int
+    double n = n * 2
+"#,
+    ),
+    (
+        107,
+        r#"
+A signature uses an unknown class
+
+The classes are 'Eq', 'Ord', and 'Num':
+
+    -- Error
+    max : Comparable a => a -> a -> a
+    -- Ok
+    max : Ord a => a -> a -> a
+"#,
+    ),
+    (
+        108,
+        r#"
+A constraint is on a type variable that isn't in the signature
+
+    -- Error
+    max : Ord b => a -> a -> a
+    -- Ok
+    max : Ord a => a -> a -> a
+"#,
+    ),
+    (
+        109,
+        r#"
+A type isn't an instance of a class
+
+An operator was used with a type that it doesn't work with:
+
+    -- Error ('Bool' isn't an instance of 'Num')
+    a = true + false
+"#,
+    ),
+    (
+        110,
+        r#"
+A signature is missing a constraint
+
+The declaration uses an operator on a type variable, so the signature needs
+the operator's class:
+
+    -- Error
+    max : a -> a -> a
+    max a b = if a > b then a else b
+    -- Ok
+    max : Ord a => a -> a -> a
+    max a b = if a > b then a else b
+"#,
+    ),
+    (
+        111,
+        r#"
+A match doesn't handle every value (warning)
+
+If none of the patterns match, the program fails when it's run:
+
+    -- Warning (there's no pattern for 'false')
+    f b = match b to
+    	| true -> 1
+
+Add a pattern for the values that are missing, or a '_' pattern.
+"#,
+    ),
+    (
+        112,
+        r#"
+A pattern is never reached (warning)
+
+Patterns before it already match every value that it matches:
+
+    -- Warning (the '_' matches 1)
+    f n = match n to
+    	| _ -> 0
+    	| 1 -> 1
+"#,
+    ),
+    (
+        113,
+        r#"
+A declaration is never used (warning)
+
+    -- Warning ('g' isn't used by 'main')
+    main = 1
+    g = 2
+
+Names that start with '_' aren't reported.
+"#,
+    ),
+    (
+        114,
+        r#"
+A parameter or local binding is never used (warning)
+
+    -- Warning ('b' isn't used)
+    first a b = a
+    -- Ok
+    first a _b = a
+"#,
+    ),
+    (
+        115,
+        r#"
+A binding's pattern can fail (warning)
+
+A parameter or 'let' pattern that doesn't match every value fails when the
+program runs with a value that it doesn't match:
+
+    -- Warning (fails for 'false')
+    flip true = false
+
+Use 'match' to handle the values that the pattern doesn't match.
+"#,
+    ),
+    (
+        116,
+        r#"
+A binding shadows another binding with the same name (warning)
+
+    -- Warning (the inner 'n' hides the parameter)
+    f n = let n = n + 1 in n
+
+It's an error with '-W error=shadowing', and isn't reported with
+'-W no-shadowing'.
+"#,
+    ),
+    (
+        117,
+        r#"
+A name isn't declared
+
+A name is used, but isn't declared in the file, imported, or a builtin:
+
+    -- Error
+    main = lenght "abc"
+    -- Ok
+    main = length "abc"
+"#,
+    ),
+    // Errors while a program is running
+    (
+        201,
+        r#"
+An error value
+
+The program (ex. with 'error'), or a builtin, resulted in an error, and the
+message says what went wrong.
+"#,
+    ),
+    (
+        202,
+        r#"
+A name that was called isn't declared
+
+A program that embeds tego called a name that isn't declared.
+"#,
+    ),
+    (
+        203,
+        r#"
+There's no 'main'
+
+A program that's run needs a 'main' declaration, which is its result:
+
+    main = println "Hello, world"
+"#,
+    ),
+    (
+        204,
+        r#"
+An import couldn't be loaded
+
+A file that's imported couldn't be found, read, or parsed, or a name that's
+imported isn't exported by it.
+"#,
+    ),
+    (
+        205,
+        r#"
+An arithmetic operation overflows
+
+The result doesn't fit in an 'Int' (between -2147483648 and 2147483647):
+
+    -- Error
+    main = 2147483647 + 1
+
+With 'tego run --wrapping', the result wraps around instead.
+"#,
+    ),
+    (
+        206,
+        r#"
+A division by zero
+
+    -- Error
+    main = 1 / 0
+"#,
+    ),
+    (
+        207,
+        r#"
+An index is out of bounds
+
+An 'Int' applied to a tuple is an index, which starts at 0 and has to be less
+than the tuple's length:
+
+    -- Error
+    main = 3 (1, 2)
+"#,
+    ),
+    (
+        208,
+        r#"
+A value depends on itself
+
+Only functions can refer to themselves:
+
+    -- Error
+    main = let rec x = x + 1 in x
+"#,
+    ),
+    (
+        209,
+        r#"
+A value that isn't a tuple is indexed
+
+    -- Error
+    main = 0 true
+"#,
+    ),
+    (
+        210,
+        r#"
+A function was given too many arguments
+
+    -- Error
+    add a b = a + b
+    main = add 1 2 3
+"#,
+    ),
+    (
+        211,
+        r#"
+Something went wrong inside tego
+
+This is a bug in tego, not in the program, and it's reported as an error
+instead of crashing. Please report it, with the program that caused it.
+"#,
+    ),
+    (
+        212,
+        r#"
+The program was stopped before it finished
+
+It was interrupted while it was running (ex. with Ctrl-C in the REPL, which
+stops what's being evaluated but keeps everything that's been declared).
+"#,
+    ),
+    (
+        213,
+        r#"
+A `break` was used after its `label` finished
+
+`break` can only exit its `label` while the `label` is being evaluated (or,
+if it results in a Command, while it's run), so it can't be kept for later.
+
+    -- Error
+    main = let exit = label (fn break -> break) in exit 1
+"#,
+    ),
+    (
+        214,
+        r#"
+The program exited with its own code
+
+`exit n` ends the program with the exit code `n`, and nothing after it is run.
+`tego run` exits with that code, and when tego is embedded, the program's
+result is this error, so the host can decide what to do with the code.
+
+    main = do println "Done" then exit 3
+"#,
+    ),
+    (
+        215,
+        r#"
+An assertion failed
+
+`assert`, `assertEq`, and `expectError` are errors when what they check isn't
+true, which says which assertion failed, the declaration it was in, and (for
+`assertEq` and `expectError`) what was expected and what was found.
+
+    -- Error
+    main = assertEq 4 (2 + 1)
+"#,
+    ),
+    (
+        216,
+        r#"
+The program recursed too deeply
+
+More expressions were being evaluated inside each other than the limit that
+the host set (see `EvalConfig::max_depth`), which is usually a function that
+calls itself without ever stopping.
+
+    -- Error
+    forever n = 1 + forever n
+    main = forever 0
+"#,
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tego_analysis::diagnostics::NUMBERS;
+
+    basic_test! {
+        number_test
+        number("E0007") => Some(7);
+        number("e117") => Some(117);
+        number("7") => Some(7);
+        number("E") => None;
+        number("E-1") => None;
+        number("unused-decl") => None
+    }
+
+    basic_test! {
+        explanation_test
+        code(7) => "E0007";
+        explanation(7).unwrap().trim().lines().next() => Some("A parenthesis isn't closed");
+        explanation(999) => None;
+        NUMBERS.iter().all(|(_, number)| explanation(*number).is_some()) => true;
+        (1..=35).chain(201..=216).all(|number| explanation(number).is_some()) => true
+    }
+}
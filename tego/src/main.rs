@@ -9,26 +9,493 @@ macro_rules! basic_test {
     };
 }
 
-use std::path::PathBuf;
+use manifest::Manifest;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::thread;
+use structopt::clap::ErrorKind;
 use structopt::StructOpt;
+use tego_analysis::diagnostics;
 
+mod bench;
 mod codefile;
+mod doc;
+mod explain;
+mod manifest;
+mod message;
+mod render;
 mod repl;
+mod test_runner;
+mod watch;
+
+const CACHE_DIR: &str = ".tego-cache";
 
 fn main() {
-    let cli = Cli::from_args();
+    let cli = match Cli::from_iter_safe(env::args_os()) {
+        Ok(cli) => cli,
+        // Showing the help or the version isn't an error
+        Err(error)
+            if matches!(
+                error.kind,
+                ErrorKind::HelpDisplayed | ErrorKind::VersionDisplayed
+            ) =>
+        {
+            error.exit()
+        }
+        Err(error) => {
+            eprintln!("{}", error.message);
+            process::exit(codefile::USAGE_ERROR)
+        }
+    };
+
+    message::set_format(cli.message_format);
+    let command = match (cli.eval, cli.command) {
+        (None, Some(command)) => command,
+        // `tego -e` is short for `tego run -e`
+        (Some(source), None) => Command::Run {
+            file_loc: None,
+            eval: Some(source),
+            watch: false,
+            seed: None,
+            allow_exec: false,
+            wrapping: false,
+            deterministic: false,
+            options: Options::default(),
+        },
+        (Some(_), Some(_)) => {
+            eprintln!("'-e' can't be used with a subcommand (use 'tego run -e' instead)");
+            process::exit(codefile::USAGE_ERROR)
+        }
+        (None, None) => {
+            // The help is only missing output if it can't be written
+            let _ = Cli::clap().print_help();
+            println!();
+            process::exit(codefile::USAGE_ERROR)
+        }
+    };
 
-    match cli {
-        Cli::Repl => repl::run().unwrap_or(()),
-        Cli::Run { file_loc } => codefile::run(file_loc).unwrap_or(()),
+    let code = match command {
+        Command::Repl { path, depth } => {
+            set_search_path(path);
+            repl::run(depth)
+        }
+        Command::Run {
+            file_loc,
+            eval,
+            watch,
+            seed,
+            allow_exec,
+            wrapping,
+            deterministic,
+            mut options,
+        } => {
+            // Source from the command line doesn't need a project
+            let file_loc = match eval {
+                Some(_) => file_loc,
+                None => Some(entry_point(file_loc, &mut options.path)),
+            };
+            set_search_path(options.path.clone());
+            // A seed takes precedence over the fixed one
+            tego_interpreter::capability::set_deterministic(deterministic);
+            if let Some(seed) = seed {
+                tego_interpreter::random::seed(seed);
+            }
+            tego_interpreter::capability::allow_exec(allow_exec);
+            tego_interpreter::capability::wrap_ints(wrapping);
+            // Only signatures are type checked, so they're checked at runtime instead
+            tego_interpreter::boundary::check_signatures(
+                options.typing == diagnostics::Typing::Gradual,
+            );
+            match (eval, file_loc) {
+                (Some(source), _) => codefile::eval(&source, &options.config()),
+                (None, Some(file_loc)) if watch => {
+                    if file_loc == Path::new("-") {
+                        eprintln!("'--watch' needs a file (stdin can't be watched)");
+                        process::exit(codefile::USAGE_ERROR)
+                    }
+                    watch::run(&file_loc, &options.config())
+                }
+                (None, file_loc) => codefile::run(file_loc.unwrap_or_default(), &options.config()),
+            }
+        }
+        Command::Check {
+            file_loc,
+            mut options,
+        } => {
+            let file_loc = entry_point(file_loc, &mut options.path);
+            set_search_path(options.path.clone());
+            codefile::check(file_loc, &options.config())
+        }
+        Command::Test {
+            file_loc,
+            filter,
+            jobs,
+            deterministic,
+            mut options,
+        } => {
+            let file_loc = entry_point(file_loc, &mut options.path);
+            set_search_path(options.path.clone());
+            tego_interpreter::capability::set_deterministic(deterministic);
+            let jobs = jobs
+                .unwrap_or_else(|| thread::available_parallelism().map_or(1, |jobs| jobs.get()));
+            test_runner::run(file_loc, &options.config(), filter.as_deref(), jobs)
+        }
+        Command::Bench {
+            file_loc,
+            filter,
+            warmup,
+            iterations,
+            mut options,
+        } => {
+            let file_loc = entry_point(file_loc, &mut options.path);
+            set_search_path(options.path.clone());
+            let settings = bench::Settings {
+                filter: filter.as_deref(),
+                warmup,
+                iterations,
+            };
+            bench::run(file_loc, &options.config(), &settings)
+        }
+        Command::Doc {
+            file_loc,
+            format,
+            out,
+            mut path,
+        } => {
+            let file_loc = entry_point(file_loc, &mut path);
+            set_search_path(path);
+            doc::run(&file_loc, format, &out)
+        }
+        Command::Tokens { file_loc } => codefile::tokens(file_loc),
+        Command::Explain { code } => explain::run(&code),
+        Command::Build { dir } => match load_project(&dir) {
+            Ok((entry, deps)) => {
+                set_search_path(deps);
+                codefile::check(entry, &diagnostics::Config::default())
+            }
+            Err(e) => {
+                eprintln!("Error loading project: {}", e);
+                Ok(codefile::LOAD_ERROR)
+            }
+        },
+    };
+
+    // Failing to write output is treated the same as a runtime error
+    process::exit(code.unwrap_or(codefile::RUNTIME_ERROR))
+}
+
+// Without a file, the project in the current directory is used, and its
+// dependencies are added to `path`
+fn entry_point(file_loc: Option<PathBuf>, path: &mut Vec<PathBuf>) -> PathBuf {
+    match file_loc {
+        Some(file_loc) => file_loc,
+        None => match load_project(Path::new(".")) {
+            Ok((entry, deps)) => {
+                path.extend(deps);
+                entry
+            }
+            Err(e) => {
+                eprintln!("Error loading project: {}", e);
+                process::exit(codefile::LOAD_ERROR)
+            }
+        },
     }
 }
 
+// Imports are searched for in the directories passed with `--path`, then in
+// the directories in the `TEGO_PATH` environment variable
+fn set_search_path(mut dirs: Vec<PathBuf>) {
+    if let Some(paths) = env::var_os("TEGO_PATH") {
+        dirs.extend(env::split_paths(&paths));
+    }
+    tego_interpreter::module::set_search_path(dirs);
+}
+
+// Results in the project's entry point, and the directories its dependencies
+// can be imported from
+// Projects cache the files they import in their `.tego-cache` directory
+fn load_project(dir: &Path) -> Result<(PathBuf, Vec<PathBuf>), String> {
+    let manifest = Manifest::load(dir)?;
+    let deps = manifest.resolve(dir)?;
+    tego_interpreter::module::set_cache_dir(Some(dir.join(CACHE_DIR)));
+    Ok((dir.join(manifest.entry), deps))
+}
+
 #[derive(StructOpt)]
-enum Cli {
-    Repl,
+#[structopt(
+    name = "tego",
+    about = "Runs and checks tego programs",
+    after_help = "EXIT CODES:
+    0     Success
+    1     Runtime error
+    2     A file couldn't be read, parsed, or type checked
+    64    The arguments couldn't be parsed
+    A program can also exit with its own code (see `exit`)"
+)]
+struct Cli {
+    #[structopt(
+        short = "e",
+        long = "eval",
+        value_name = "source",
+        help = "Runs an expression or a program given on the command line (same as 'run -e')"
+    )]
+    eval: Option<String>,
+    #[structopt(
+        long,
+        global = true,
+        default_value = "human",
+        value_name = "format",
+        help = "How errors and warnings are written: 'human', or 'json' (one object per line, on stderr)"
+    )]
+    message_format: message::Format,
+    #[structopt(subcommand)]
+    command: Option<Command>,
+}
+
+// There's no `fmt` subcommand yet: the parser drops comments, and the AST is
+// written back (see `Prog`'s `Display`) with each declaration on one line and
+// equations as `fn`s, so formatting a file would lose parts of it
+#[derive(StructOpt)]
+enum Command {
+    #[structopt(about = "Evaluates expressions and declarations interactively")]
+    Repl {
+        #[structopt(
+            long,
+            number_of_values = 1,
+            parse(from_os_str),
+            help = "Directory to search for imports (can be repeated)"
+        )]
+        path: Vec<PathBuf>,
+        #[structopt(
+            long,
+            default_value = "8",
+            help = "How many lists deep results are shown, before they're cut off with '...'"
+        )]
+        depth: usize,
+    },
+    #[structopt(about = "Checks a program, and then runs it")]
     Run {
-        #[structopt(name = "file-path", parse(from_os_str))]
+        #[structopt(
+            name = "file-path",
+            parse(from_os_str),
+            help = "Defaults to the entry point of the project in the current directory ('-' reads the program from stdin)"
+        )]
+        file_loc: Option<PathBuf>,
+        #[structopt(
+            short = "e",
+            long = "eval",
+            value_name = "source",
+            conflicts_with = "file-path",
+            help = "Runs an expression or a program given on the command line, instead of a file (imports are relative to the current directory)"
+        )]
+        eval: Option<String>,
+        #[structopt(
+            long,
+            conflicts_with = "eval",
+            help = "Runs the program again whenever it, or a file it imports, is saved"
+        )]
+        watch: bool,
+        #[structopt(
+            long,
+            help = "Seed for the random number generator (for reproducible runs)"
+        )]
+        seed: Option<u64>,
+        #[structopt(long, help = "Allows the program to run subprocesses with `exec`")]
+        allow_exec: bool,
+        #[structopt(
+            long,
+            help = "Int arithmetic that overflows wraps around, instead of being an error"
+        )]
+        wrapping: bool,
+        #[structopt(
+            long,
+            help = "Random builtins use the same seed, and 'now' reads a virtual clock, so every run has the same output"
+        )]
+        deterministic: bool,
+        #[structopt(flatten)]
+        options: Options,
+    },
+    #[structopt(
+        about = "Reports errors and warnings in a file and everything it imports, without running it"
+    )]
+    Check {
+        #[structopt(
+            name = "file-path",
+            parse(from_os_str),
+            help = "Defaults to the entry point of the project in the current directory ('-' reads the program from stdin)"
+        )]
+        file_loc: Option<PathBuf>,
+        #[structopt(flatten)]
+        options: Options,
+    },
+    #[structopt(
+        about = "Runs the tests in a file (the declarations whose names start with 'test')"
+    )]
+    Test {
+        #[structopt(
+            name = "file-path",
+            parse(from_os_str),
+            help = "Defaults to the entry point of the project in the current directory ('-' reads the program from stdin)"
+        )]
+        file_loc: Option<PathBuf>,
+        #[structopt(long, help = "Only runs the tests with this in their name")]
+        filter: Option<String>,
+        #[structopt(
+            short,
+            long,
+            help = "How many tests can run at once (defaults to the number of CPUs)"
+        )]
+        jobs: Option<usize>,
+        #[structopt(
+            long,
+            help = "Each test starts with the same random seed, and 'now' reads a virtual clock, so every run has the same output"
+        )]
+        deterministic: bool,
+        #[structopt(flatten)]
+        options: Options,
+    },
+    #[structopt(
+        about = "Times the benchmarks in a file (the declarations whose names start with 'bench')"
+    )]
+    Bench {
+        #[structopt(
+            name = "file-path",
+            parse(from_os_str),
+            help = "Defaults to the entry point of the project in the current directory ('-' reads the program from stdin)"
+        )]
+        file_loc: Option<PathBuf>,
+        #[structopt(long, help = "Only runs the benchmarks with this in their name")]
+        filter: Option<String>,
+        #[structopt(
+            long,
+            default_value = "10",
+            help = "How many times each benchmark runs before it's timed"
+        )]
+        warmup: usize,
+        #[structopt(
+            long,
+            default_value = "100",
+            help = "How many times each benchmark is timed"
+        )]
+        iterations: usize,
+        #[structopt(flatten)]
+        options: Options,
+    },
+    #[structopt(
+        about = "Writes documentation for a file and everything it imports, from the comments above their exported declarations"
+    )]
+    Doc {
+        #[structopt(
+            name = "file-path",
+            parse(from_os_str),
+            help = "Defaults to the entry point of the project in the current directory"
+        )]
+        file_loc: Option<PathBuf>,
+        #[structopt(
+            long,
+            default_value = "markdown",
+            help = "The format of the pages: 'markdown' or 'html'"
+        )]
+        format: doc::Format,
+        #[structopt(
+            long,
+            default_value = "doc",
+            parse(from_os_str),
+            help = "Directory the pages are written to (one page per module)"
+        )]
+        out: PathBuf,
+        #[structopt(
+            long,
+            number_of_values = 1,
+            parse(from_os_str),
+            help = "Directory to search for imports (can be repeated)"
+        )]
+        path: Vec<PathBuf>,
+    },
+    #[structopt(
+        about = "Prints the tokens in a file, with their line, column, and kind (for debugging the grammar)"
+    )]
+    Tokens {
+        #[structopt(
+            name = "file-path",
+            parse(from_os_str),
+            help = "'-' reads the source from stdin"
+        )]
         file_loc: PathBuf,
     },
+    #[structopt(about = "Explains an error or a warning, with examples")]
+    Explain {
+        #[structopt(help = "The error's code (ex. 'E0007')")]
+        code: String,
+    },
+    #[structopt(
+        about = "Fetches a project's dependencies and checks that it loads, without running it"
+    )]
+    Build {
+        #[structopt(
+            default_value = ".",
+            parse(from_os_str),
+            help = "Directory with a 'tego.toml'"
+        )]
+        dir: PathBuf,
+    },
+}
+
+// Flags for loading and checking a program, shared by `run` and `check`
+#[derive(StructOpt)]
+struct Options {
+    #[structopt(
+        long,
+        number_of_values = 1,
+        parse(from_os_str),
+        help = "Directory to search for imports (can be repeated)"
+    )]
+    path: Vec<PathBuf>,
+    #[structopt(
+        long,
+        default_value = "warn",
+        help = "How to report bindings that shadow another binding: 'allow', 'warn', or 'deny'"
+    )]
+    shadowing: diagnostics::Level,
+    #[structopt(
+        short = "W",
+        value_name = "warning",
+        number_of_values = 1,
+        help = "Turns a warning on ('-W unused') or off ('-W no-unused'), or makes it an error ('-W error=unused', or '-W error' for every warning); the warnings are 'all', 'unused', 'unreachable', and each warning's code (can be repeated, and later ones take precedence)"
+    )]
+    warnings: Vec<diagnostics::WarningFlag>,
+    #[structopt(
+        long,
+        default_value = "strict",
+        help = "Which declarations are type checked: 'strict' (all of them) or 'gradual' (only those with a signature, which are also checked at runtime)"
+    )]
+    typing: diagnostics::Typing,
+}
+
+// The same as passing none of the flags
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            path: vec![],
+            shadowing: diagnostics::Level::Warn,
+            warnings: vec![],
+            typing: diagnostics::Typing::Strict,
+        }
+    }
+}
+
+impl Options {
+    fn config(&self) -> diagnostics::Config {
+        // `--shadowing` is the same as a `-W` flag before the others
+        let shadowing = match self.shadowing {
+            diagnostics::Level::Allow => Some(diagnostics::WarningFlag::Disable("shadowing")),
+            diagnostics::Level::Warn => None,
+            diagnostics::Level::Deny => Some(diagnostics::WarningFlag::Error("shadowing")),
+        };
+        diagnostics::Config {
+            warnings: shadowing.into_iter().chain(self.warnings.clone()).collect(),
+            typing: self.typing,
+        }
+    }
 }
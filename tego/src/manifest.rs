@@ -0,0 +1,404 @@
+// `tego.toml`, the project manifest
+//
+// [package]
+// name = "app"
+// entry = "src/main.tgo"  # Optional, defaults to 'main.tgo'
+//
+// [dependencies]
+// utils = { path = "../utils" }
+// json = { git = "https://example.com/json.git", rev = "v1.0" }  # `rev` is optional
+//
+// Dependency names can only have letters, digits, and `_`, since they're also
+// the names of the directories that git dependencies are cloned to
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use toml::{Table, Value};
+
+pub const MANIFEST: &str = "tego.toml";
+const DEFAULT_ENTRY: &str = "main.tgo";
+// Where git dependencies are fetched to, relative to the project directory
+const DEPS_DIR: &str = ".tego/deps";
+
+#[derive(Debug, PartialEq)]
+pub struct Manifest {
+    pub name: String,
+    pub entry: PathBuf,
+    pub dependencies: Vec<(String, Dependency)>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Dependency {
+    Path(PathBuf),
+    Git { url: String, rev: Option<String> },
+}
+
+impl Manifest {
+    // Loads the manifest in `dir`
+    pub fn load(dir: &Path) -> Result<Manifest, String> {
+        let path = dir.join(MANIFEST);
+        let source = fs::read_to_string(&path)
+            .map_err(|e| format!("Can't read '{}': {}", path.display(), e))?;
+        Manifest::parse(&source).map_err(|e| format!("{}:{}", path.display(), e))
+    }
+
+    fn parse(source: &str) -> Result<Manifest, String> {
+        let toml = source
+            .parse::<Table>()
+            .map_err(|e| syntax_error(source, &e))?;
+        let mut name = None;
+        let mut entry = None;
+        let mut dependencies = vec![];
+        for (table, value) in toml {
+            let entries = match value {
+                Value::Table(entries) => entries,
+                _ => return Err(format!("'{}' has to be in a table", table)),
+            };
+            match table.as_str() {
+                "package" => {
+                    for (key, value) in entries {
+                        match (key.as_str(), value) {
+                            ("name", Value::String(value)) => name = Some(value),
+                            ("entry", Value::String(value)) => entry = Some(value.into()),
+                            (key, _) => {
+                                return Err(format!("unknown or invalid package key '{}'", key))
+                            }
+                        }
+                    }
+                }
+                "dependencies" => {
+                    for (key, value) in entries {
+                        let dependency = dependency(&key, value)?;
+                        dependencies.push((key, dependency));
+                    }
+                }
+                table => return Err(format!("unknown table '[{}]'", table)),
+            }
+        }
+        Ok(Manifest {
+            name: name.ok_or_else(|| "'[package]' is missing a 'name'".to_string())?,
+            entry: entry.unwrap_or_else(|| DEFAULT_ENTRY.into()),
+            dependencies,
+        })
+    }
+
+    // The directories that the project's dependencies (and their dependencies)
+    // can be imported from, fetching git dependencies if they haven't been yet
+    pub fn resolve(&self, dir: &Path) -> Result<Vec<PathBuf>, String> {
+        let mut dirs = vec![];
+        let mut seen = HashSet::new();
+        self.resolve_into(dir, dir, &mut dirs, &mut seen)?;
+        Ok(dirs)
+    }
+
+    // `root` is the top-level project's directory, where git dependencies go
+    fn resolve_into(
+        &self,
+        dir: &Path,
+        root: &Path,
+        dirs: &mut Vec<PathBuf>,
+        seen: &mut HashSet<PathBuf>,
+    ) -> Result<(), String> {
+        for (name, dependency) in &self.dependencies {
+            let dep_dir = match dependency {
+                Dependency::Path(path) => dir.join(path),
+                Dependency::Git { url, rev } => fetch(name, url, rev.as_deref(), root)?,
+            };
+            let dep_dir = fs::canonicalize(&dep_dir).map_err(|e| {
+                format!(
+                    "Can't find dependency '{}' at '{}': {}",
+                    name,
+                    dep_dir.display(),
+                    e
+                )
+            })?;
+            // Dependencies that are shared (or circular) are only resolved once
+            if !seen.insert(dep_dir.clone()) {
+                continue;
+            }
+            // A dependency without a manifest is just a directory of modules
+            if dep_dir.join(MANIFEST).is_file() {
+                let manifest = Manifest::load(&dep_dir)?;
+                let entry = dep_dir.join(&manifest.entry);
+                dirs.push(entry.parent().unwrap_or(&dep_dir).to_owned());
+                manifest.resolve_into(&dep_dir, root, dirs, seen)?;
+            } else {
+                dirs.push(dep_dir);
+            }
+        }
+        Ok(())
+    }
+}
+
+// `line: message`, without the source that the `toml` crate quotes
+fn syntax_error(source: &str, error: &toml::de::Error) -> String {
+    let message = error
+        .message()
+        .trim()
+        .lines()
+        .collect::<Vec<_>>()
+        .join(", ");
+    match error.span() {
+        Some(span) => format!(
+            "{}: {}",
+            source[..span.start].lines().count().max(1),
+            message
+        ),
+        None => message,
+    }
+}
+
+fn dependency(name: &str, value: Value) -> Result<Dependency, String> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(format!(
+            "dependency '{}' can only have letters, digits, and '_' in its name",
+            name
+        ));
+    }
+    let table = match value {
+        Value::Table(table) => table,
+        _ => {
+            return Err(format!(
+                "dependency '{}' has to be a table (ex. '{{ path = \"...\" }}')",
+                name
+            ))
+        }
+    };
+    let mut path = None;
+    let mut url = None;
+    let mut rev = None;
+    for (key, value) in table {
+        let value = match value {
+            Value::String(value) => value,
+            _ => {
+                return Err(format!(
+                    "dependency '{}' has to have a string '{}'",
+                    name, key
+                ))
+            }
+        };
+        match key.as_str() {
+            "path" => path = Some(value),
+            "git" => url = Some(value),
+            "rev" => rev = Some(value),
+            key => {
+                return Err(format!(
+                    "dependency '{}' has an unknown key '{}'",
+                    name, key
+                ))
+            }
+        }
+    }
+    // Anything that starts with '-' would be read by git as an option
+    for value in url.iter().chain(&rev) {
+        if value.starts_with('-') {
+            return Err(format!(
+                "dependency '{}' can't have a 'git' or 'rev' that starts with '-'",
+                name
+            ));
+        }
+    }
+    match (path, url) {
+        (Some(path), None) => Ok(Dependency::Path(path.into())),
+        (None, Some(url)) => Ok(Dependency::Git { url, rev }),
+        _ => Err(format!(
+            "dependency '{}' needs either a 'path' or a 'git'",
+            name
+        )),
+    }
+}
+
+// Clones a git dependency into the project's deps directory, unless it was
+// already cloned, and checks out its `rev` (which may have changed since it
+// was cloned)
+fn fetch(name: &str, url: &str, rev: Option<&str>, root: &Path) -> Result<PathBuf, String> {
+    let dir = root.join(DEPS_DIR).join(name);
+    let cloned = dir.is_dir();
+    if !cloned {
+        git(
+            &["clone", "--quiet", "--", url, &dir.to_string_lossy()],
+            root,
+        )
+        .map_err(|e| format!("Can't fetch dependency '{}' from '{}': {}", name, url, e))?;
+    }
+    if let Some(rev) = rev {
+        // A `rev` that isn't in an existing clone (ex. a new tag) is fetched
+        let commit = format!("{}^{{commit}}", rev);
+        if cloned && git(&["rev-parse", "--quiet", "--verify", &commit], &dir).is_err() {
+            git(&["fetch", "--quiet", "--tags", "origin"], &dir)
+                .map_err(|e| format!("Can't fetch dependency '{}' from '{}': {}", name, url, e))?;
+        }
+        git(&["checkout", "--quiet", "--detach", rev, "--"], &dir).map_err(|e| {
+            // Don't leave a new clone at the wrong revision behind
+            if !cloned {
+                let _ = fs::remove_dir_all(&dir);
+            }
+            format!("Can't check out '{}' of dependency '{}': {}", rev, name, e)
+        })?;
+    }
+    Ok(dir)
+}
+
+fn git(args: &[&str], dir: &Path) -> Result<(), String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .map_err(|e| e.to_string())?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    basic_test! {
+        parse_test
+        Manifest::parse("[package]\nname = \"app\"") => Ok(Manifest {
+            name: "app".into(),
+            entry: "main.tgo".into(),
+            dependencies: vec![],
+        });
+        Manifest::parse(
+            "# A project
+[package]
+name = \"app\" # The name
+entry = \"src/app.tgo\"
+
+[dependencies]
+utils = { path = \"../utils\" }
+json = { git = \"https://example.com/json.git\", rev = \"v1\" }
+\"odd_name\" = { path = \"a, b\" }"
+        ) => Ok(Manifest {
+            name: "app".into(),
+            entry: "src/app.tgo".into(),
+            dependencies: vec![
+                ("utils".into(), Dependency::Path("../utils".into())),
+                ("json".into(), Dependency::Git {
+                    url: "https://example.com/json.git".into(),
+                    rev: Some("v1".into()),
+                }),
+                // Commas in strings aren't table separators
+                ("odd_name".into(), Dependency::Path("a, b".into())),
+            ],
+        })
+    }
+
+    basic_test! {
+        parse_error_test
+        Manifest::parse("[package]") => Err("'[package]' is missing a 'name'".into());
+        Manifest::parse("[pkg]") => Err("unknown table '[pkg]'".into());
+        Manifest::parse("name = \"app\"") => Err("'name' has to be in a table".into());
+        Manifest::parse("[package]\nname = app") => Err("2: invalid string, expected `\"`, `'`".into());
+        Manifest::parse("[dependencies]\nutils = \"../utils\"")
+            => Err("dependency 'utils' has to be a table (ex. '{ path = \"...\" }')".into());
+        Manifest::parse("[dependencies]\nutils = { }")
+            => Err("dependency 'utils' needs either a 'path' or a 'git'".into());
+        Manifest::parse("[dependencies]\nutils = { path = \"a\", branch = \"b\" }")
+            => Err("dependency 'utils' has an unknown key 'branch'".into())
+    }
+
+    // Dependencies can't be cloned outside of the deps directory, or pass
+    // options to git
+    basic_test! {
+        unsafe_dependency_test
+        Manifest::parse("[package]\nname = \"app\"\n[dependencies]\n\"../../x\" = { git = \"a\" }")
+            => Err("dependency '../../x' can only have letters, digits, and '_' in its name".into());
+        Manifest::parse("[package]\nname = \"app\"\n[dependencies]\nx = { git = \"--upload-pack=touch y\" }")
+            => Err("dependency 'x' can't have a 'git' or 'rev' that starts with '-'".into());
+        Manifest::parse("[package]\nname = \"app\"\n[dependencies]\nx = { git = \"a\", rev = \"-b\" }")
+            => Err("dependency 'x' can't have a 'git' or 'rev' that starts with '-'".into())
+    }
+
+    #[test]
+    fn resolve_test() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = |name: &str| {
+            let dir = root.path().join(name);
+            fs::create_dir_all(&dir).unwrap();
+            dir
+        };
+        let app = dir("app");
+        let a = dir("a");
+        dir("a/src");
+        let b = dir("b");
+        fs::write(
+            a.join(MANIFEST),
+            "[package]\nname = \"a\"\nentry = \"src/a.tgo\"\n[dependencies]\nb = { path = \"../b\" }",
+        )
+        .unwrap();
+        let manifest = Manifest::parse(
+            "[package]\nname = \"app\"\n[dependencies]\na = { path = \"../a\" }\nb = { path = \"../b\" }",
+        )
+        .unwrap();
+
+        // 'b' is only included once, and 'a' is imported from its entry's directory
+        assert_eq!(
+            manifest.resolve(&app),
+            Ok(vec![
+                fs::canonicalize(a.join("src")).unwrap(),
+                fs::canonicalize(b).unwrap()
+            ])
+        );
+
+        let manifest =
+            Manifest::parse("[package]\nname = \"app\"\n[dependencies]\nc = { path = \"../c\" }")
+                .unwrap();
+        assert!(manifest
+            .resolve(&app)
+            .unwrap_err()
+            .starts_with("Can't find dependency 'c' at"));
+    }
+
+    // A changed `rev` is checked out in the existing clone
+    #[test]
+    fn fetch_test() {
+        let root = tempfile::tempdir().unwrap();
+        let repo = root.path().join("repo");
+        fs::create_dir(&repo).unwrap();
+        let commit = |version: &str| {
+            fs::write(repo.join("version.tgo"), version).unwrap();
+            for args in [
+                &["add", "."][..],
+                &[
+                    "-c",
+                    "user.name=t",
+                    "-c",
+                    "user.email=t@t",
+                    "commit",
+                    "--quiet",
+                    "-m",
+                    version,
+                ],
+                &["tag", version],
+            ] {
+                git(args, &repo).unwrap();
+            }
+        };
+        git(&["init", "--quiet"], &repo).unwrap();
+        commit("v1");
+        commit("v2");
+        let app = root.path().join("app");
+        fs::create_dir(&app).unwrap();
+        let version = |rev: &str| {
+            let manifest = Manifest::parse(&format!(
+                "[package]\nname = \"app\"\n[dependencies]\ndep = {{ git = \"{}\", rev = \"{}\" }}",
+                repo.display(),
+                rev
+            ))
+            .unwrap();
+            let dirs = manifest.resolve(&app).unwrap();
+            fs::read_to_string(dirs[0].join("version.tgo")).unwrap()
+        };
+        assert_eq!(version("v1"), "v1");
+        assert_eq!(version("v2"), "v2");
+        commit("v3");
+        assert_eq!(version("v3"), "v3");
+    }
+}
@@ -0,0 +1,218 @@
+// How errors and warnings are written (see `--message-format`): as text for
+// people, or as one JSON object per line for tools (ex. editors and CI bots)
+//
+//     {"severity":"warning","code":"unused-decl","id":"E0113","message":"Warning: 'g' is declared, but never used","file":"main.tgo","span":{"line":3,"column":1,"length":1},"suggestions":["Rename it to '_g' if it's unused on purpose"]}
+//
+// The id is the code that `tego explain` explains, which is also written after
+// the text for people, and it's `null` for errors that don't have one (ex. a
+// file that can't be read)
+// The span is where the declaration that a diagnostic is about is declared, or
+// where a parse error was found (which doesn't have a length), and it's `null`
+// if it isn't known
+// Both are written to stderr, so they don't mix with a program's output
+//
+// Every kind of error and warning is a `Message` (see `tego_analysis::message`),
+// and each format is a `Sink` that writes them
+
+use crate::explain;
+use crate::render::{self, Style};
+use std::cell::Cell;
+use std::io::{self, Write};
+use std::path::Path;
+use std::str::FromStr;
+use tego_analysis::diagnostics::{Diagnostic, Severity};
+use tego_analysis::message::{Message, Sink};
+use tego_parser::ParseError;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Format {
+    Human,
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(Format::Human),
+            "json" => Ok(Format::Json),
+            _ => Err(format!(
+                "Unknown message format '{}' (expected 'human' or 'json')",
+                s
+            )),
+        }
+    }
+}
+
+thread_local! {
+    static FORMAT: Cell<Format> = const { Cell::new(Format::Human) };
+}
+
+pub fn set_format(format: Format) {
+    FORMAT.with(|current| current.set(format))
+}
+
+fn format() -> Format {
+    FORMAT.with(Cell::get)
+}
+
+struct Human<'a, W: Write> {
+    out: &'a mut W,
+    style: Style,
+}
+
+impl<W: Write> Sink for Human<'_, W> {
+    fn emit(&mut self, message: &Message) -> io::Result<()> {
+        render::report(self.out, self.style, message)
+    }
+}
+
+struct Json<'a, W: Write> {
+    out: &'a mut W,
+}
+
+impl<W: Write> Sink for Json<'_, W> {
+    fn emit(&mut self, message: &Message) -> io::Result<()> {
+        writeln!(self.out, "{}", json(message))
+    }
+}
+
+// Writes messages to `out` in the format from `--message-format`
+pub fn sink<'a>(out: &'a mut impl Write) -> Box<dyn Sink + 'a> {
+    match format() {
+        Format::Human => Box::new(Human {
+            out,
+            style: Style::stderr(),
+        }),
+        Format::Json => Box::new(Json { out }),
+    }
+}
+
+// `diagnostics` were found in `source`, which was read from `file` (unless it
+// was given on the command line)
+pub fn diagnostics(
+    out: &mut impl Write,
+    file: Option<&Path>,
+    source: &str,
+    diagnostics: &[Diagnostic],
+) -> io::Result<()> {
+    let mut sink = sink(out);
+    Message::from_diagnostics(source, file, diagnostics)
+        .iter()
+        .try_for_each(|message| sink.emit(message))
+}
+
+pub fn parse_error(
+    out: &mut impl Write,
+    file: Option<&Path>,
+    source: &str,
+    error: &ParseError,
+) -> io::Result<()> {
+    sink(out).emit(&Message::from_parse_error(source, file, error))
+}
+
+// An error that isn't found by analyzing a program (ex. a file that can't be
+// read, or an error while it's running), which people see after `context`
+pub fn error(
+    out: &mut impl Write,
+    code: &'static str,
+    number: Option<u16>,
+    context: &'static str,
+    file: Option<&Path>,
+    error: &dyn std::fmt::Display,
+) -> io::Result<()> {
+    sink(out).emit(&Message::error(code, number, context, file, error))
+}
+
+fn json(message: &Message) -> String {
+    let severity = match message.severity {
+        Severity::Warning => "warning",
+        Severity::Error => "error",
+    };
+    let id = message
+        .number
+        .map_or("null".into(), |number| string(&explain::code(number)));
+    let file = message
+        .file
+        .as_ref()
+        .map_or("null".into(), |file| string(&file.to_string_lossy()));
+    let span = message.labels.first().map_or("null".into(), |label| {
+        format!(
+            "{{\"line\":{},\"column\":{},\"length\":{}}}",
+            label.line,
+            label.column,
+            label
+                .length
+                .map_or("null".into(), |length| length.to_string())
+        )
+    });
+    let suggestions = message
+        .suggestions
+        .iter()
+        .map(|suggestion| string(suggestion))
+        .collect::<Vec<_>>()
+        .join(",");
+    format!(
+        "{{\"severity\":\"{}\",\"code\":{},\"id\":{},\"message\":{},\"file\":{},\"span\":{},\"suggestions\":[{}]}}",
+        severity,
+        string(message.code),
+        id,
+        string(&message.text),
+        file,
+        span,
+        suggestions
+    )
+}
+
+// A JSON string
+fn string(text: &str) -> String {
+    let mut escaped = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use tego_analysis::message::Label;
+
+    fn warning() -> Message {
+        Message {
+            severity: Severity::Warning,
+            code: "unused-decl",
+            number: Some(113),
+            text: "'g' \"isn't\" used".into(),
+            context: None,
+            file: Some(PathBuf::from("a.tgo")),
+            labels: vec![Label {
+                line: 3,
+                column: 1,
+                length: Some(1),
+                text: String::new(),
+                source_line: "g = 2".into(),
+            }],
+            suggestions: vec!["Rename it".into()],
+        }
+    }
+
+    basic_test! {
+        json_test
+        json(&warning())
+            => "{\"severity\":\"warning\",\"code\":\"unused-decl\",\"id\":\"E0113\",\"message\":\"'g' \\\"isn't\\\" used\",\"file\":\"a.tgo\",\"span\":{\"line\":3,\"column\":1,\"length\":1},\"suggestions\":[\"Rename it\"]}";
+        json(&Message::error("read-error", None, "Error reading file", None, &"a\n\u{1}"))
+            => "{\"severity\":\"error\",\"code\":\"read-error\",\"id\":null,\"message\":\"a\\n\\u0001\",\"file\":null,\"span\":null,\"suggestions\":[]}"
+    }
+}
@@ -0,0 +1,239 @@
+// Messages written for people: what's wrong, then the lines of source it's
+// about with the spans underlined, then ways to fix it
+//
+//     Warning: 'g' is declared, but never used
+//      --> main.tgo:3:1
+//       |
+//     3 | g = 2
+//       | ^
+//       = help: Rename it to '_g' if it's unused on purpose
+//
+// Colors are only used when stderr is a terminal, and `NO_COLOR` isn't set
+// (the same styles color values in the REPL, when stdout is a terminal)
+
+use crate::explain;
+use std::env;
+use std::io::{self, IsTerminal, Write};
+use tego_analysis::diagnostics::Severity;
+use tego_analysis::message::Message;
+
+// How wide a tab in the source is shown, so the underlines line up
+const TAB_WIDTH: usize = 4;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Style {
+    color: bool,
+}
+
+impl Style {
+    pub fn new(color: bool) -> Self {
+        Style { color }
+    }
+
+    pub fn stderr() -> Self {
+        Style::new(io::stderr().is_terminal() && env::var_os("NO_COLOR").is_none())
+    }
+
+    // For what the REPL shows (see `repl::show`)
+    pub fn stdout() -> Self {
+        Style::new(io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none())
+    }
+
+    // `codes` are SGR codes (ex. `1;31` is bold and red)
+    pub fn paint(self, codes: &str, text: &str) -> String {
+        if self.color {
+            format!("\x1b[{}m{}\x1b[0m", codes, text)
+        } else {
+            text.into()
+        }
+    }
+
+    fn severity(self, severity: Severity, text: &str) -> String {
+        match severity {
+            Severity::Error => self.paint("1;31", text),
+            Severity::Warning => self.paint("1;33", text),
+        }
+    }
+
+    fn gutter(self, text: &str) -> String {
+        self.paint("1;34", text)
+    }
+}
+
+// A message without labels or suggestions is only its text (ex. a runtime
+// error, which isn't at a place in the source)
+pub fn report(out: &mut impl Write, style: Style, message: &Message) -> io::Result<()> {
+    let mut text = match message.context {
+        Some(context) => format!("{}: {}", context, message.text),
+        None => message.text.clone(),
+    };
+    // The code goes at the end of the first line (ex. before an assertion's
+    // values), unless the text already has it (ex. `error[E0007]: ...`)
+    if let Some(number) = message.number {
+        let code = explain::code(number);
+        if !text.contains(&code) {
+            let end = text.find('\n').unwrap_or(text.len());
+            text.insert_str(end, &format!(" [{}]", code));
+        }
+    }
+    writeln!(out, "{}", style.severity(message.severity, &text))?;
+    if message.labels.is_empty() && message.suggestions.is_empty() {
+        return Ok(());
+    }
+    let width = message
+        .labels
+        .iter()
+        .map(|label| label.line.to_string().len())
+        .max()
+        .unwrap_or(1);
+    let margin = " ".repeat(width);
+    if let Some(label) = message.labels.first() {
+        let file = message
+            .file
+            .as_ref()
+            .map_or("<command line>".into(), |file| file.display().to_string());
+        writeln!(
+            out,
+            "{}{} {}:{}:{}",
+            margin,
+            style.gutter("-->"),
+            file,
+            label.line,
+            label.column
+        )?;
+        writeln!(out, "{} {}", margin, style.gutter("|"))?;
+    }
+    for label in &message.labels {
+        let (shown, column) = shown_line(&label.source_line, label.column);
+        let number = format!("{:>1$}", label.line, width);
+        writeln!(
+            out,
+            "{} {} {}",
+            style.gutter(&number),
+            style.gutter("|"),
+            shown
+        )?;
+        let underline = "^".repeat(label.length.unwrap_or(1).max(1));
+        let text = if label.text.is_empty() {
+            String::new()
+        } else {
+            format!(" {}", label.text)
+        };
+        writeln!(
+            out,
+            "{} {} {}{}",
+            margin,
+            style.gutter("|"),
+            " ".repeat(column - 1),
+            style.severity(message.severity, &(underline + &text))
+        )?;
+    }
+    for suggestion in &message.suggestions {
+        writeln!(
+            out,
+            "{} {} {}",
+            margin,
+            style.gutter("="),
+            help_text(style, suggestion)
+        )?;
+    }
+    writeln!(out)
+}
+
+fn help_text(style: Style, help: &str) -> String {
+    format!("{} {}", style.paint("1", "help:"), help)
+}
+
+// The line with its tabs as spaces, and where `column` is in it
+fn shown_line(line: &str, column: usize) -> (String, usize) {
+    let mut shown = String::new();
+    let mut shown_column = column;
+    for (i, c) in line.chars().enumerate() {
+        if c == '\t' {
+            shown.push_str(&" ".repeat(TAB_WIDTH));
+            if i + 1 < column {
+                shown_column += TAB_WIDTH - 1;
+            }
+        } else {
+            shown.push(c);
+        }
+    }
+    (shown, shown_column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use tego_analysis::message::Label;
+
+    fn render(color: bool, source: &str, labels: Vec<Label>, help: &[String]) -> String {
+        render_message(color, "Warning: something", None, source, labels, help)
+    }
+
+    fn render_message(
+        color: bool,
+        text: &str,
+        number: Option<u16>,
+        source: &str,
+        labels: Vec<Label>,
+        help: &[String],
+    ) -> String {
+        let mut out = vec![];
+        let labels = labels
+            .into_iter()
+            .map(|label| Label {
+                source_line: source.lines().nth(label.line - 1).unwrap_or("").into(),
+                ..label
+            })
+            .collect();
+        let message = Message {
+            severity: Severity::Warning,
+            code: "warning",
+            number,
+            text: text.into(),
+            context: None,
+            file: Some(PathBuf::from("main.tgo")),
+            labels,
+            suggestions: help.to_vec(),
+        };
+        super::report(&mut out, Style { color }, &message).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn label(line: usize, column: usize, length: Option<usize>, text: &str) -> Label {
+        Label {
+            line,
+            column,
+            length,
+            text: text.into(),
+            source_line: String::new(),
+        }
+    }
+
+    basic_test! {
+        report_test
+        render(false, "", vec![], &[]) => "Warning: something\n";
+        render(false, "a = 1\nbb = 2", vec![label(2, 1, Some(2), "")], &["Rename it".into()])
+            => "Warning: something\n --> main.tgo:2:1\n  |\n2 | bb = 2\n  | ^^\n  = help: Rename it\n\n";
+        render(false, "main = (1\n\n\n\n\n\n\n\n\n", vec![label(10, 1, None, "here"), label(1, 8, None, "opened here")], &[])
+            => "Warning: something\n  --> main.tgo:10:1\n   |\n10 | \n   | ^ here\n 1 | main = (1\n   |        ^ opened here\n\n";
+        render(false, "f =\n\t\tx + y", vec![label(2, 7, Some(1), "")], &[])
+            => "Warning: something\n --> main.tgo:2:7\n  |\n2 |         x + y\n  |             ^\n\n"
+    }
+
+    basic_test! {
+        code_test
+        render_message(false, "Warning: something", Some(113), "", vec![], &[]) => "Warning: something [E0113]\n";
+        render_message(false, "Failed\n  found: 3", Some(201), "", vec![], &[]) => "Failed [E0201]\n  found: 3\n";
+        render_message(false, "error[E0007]: missing closing parenthesis", Some(7), "", vec![], &[])
+            => "error[E0007]: missing closing parenthesis\n"
+    }
+
+    basic_test! {
+        color_test
+        render(true, "a", vec![], &[]) => "\x1b[1;33mWarning: something\x1b[0m\n";
+        render(true, "a", vec![label(1, 1, None, "")], &[])
+            => "\x1b[1;33mWarning: something\x1b[0m\n \x1b[1;34m-->\x1b[0m main.tgo:1:1\n  \x1b[1;34m|\x1b[0m\n\x1b[1;34m1\x1b[0m \x1b[1;34m|\x1b[0m a\n  \x1b[1;34m|\x1b[0m \x1b[1;33m^\x1b[0m\n\n"
+    }
+}
@@ -1,10 +1,44 @@
-use std::io::{self, Write};
-use tego_interpreter as interpreter;
+use crate::codefile;
+use crate::render::Style;
+use editor::{Editor, Input};
+use show::Options;
+use std::env;
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tego_analysis::types;
+use tego_interpreter::capability;
+use tego_interpreter::value::Value;
+use tego_interpreter::{RuntimeError, Session, TegoError};
 use tego_parser as parser;
 use tego_parser::ast::Decl;
 
-pub fn run() -> io::Result<()> {
-    let mut stdout = io::BufWriter::new(io::stdout());
+mod editor;
+mod show;
+
+const PROMPT: &str = ">> ";
+// For the lines after the first line of an input that isn't finished
+const CONTINUATION_PROMPT: &str = ".. ";
+// What the expression is declared as for `:type` (which can't clash with a
+// name in the session)
+const TYPE_INPUT: &str = "<input>";
+// The last result that was shown, for the next inputs to use
+const LAST_RESULT: &str = "it";
+
+const HELP: &str = "Commands:
+  :load <file>   Declares everything in a file (':l')
+  :type <expr>   Shows the type of an expression, without evaluating it (':t')
+  :env           Lists everything that's been declared, with its type
+  :depth <n>     Shows results nested up to n lists deep, and '...' after that
+  :reset         Forgets everything that's been declared
+  :help          Shows this message (':h')
+  :quit          Exits the REPL (':q')";
+
+// Results in the exit code, which is only not `0` if the program used `exit`
+pub fn run(depth: usize) -> io::Result<i32> {
+    let mut stdout = io::stdout();
 
     writeln!(stdout, "Welcome to")?;
     writeln!(
@@ -15,58 +49,284 @@ pub fn run() -> io::Result<()> {
  //||\\\\  _   __   ___
    ||  ||_| / || // \\\\
    ||  ||_  \\_|| \\\\_//
-            \\_||       
+            \\_||
 "
     )?;
-    writeln!(stdout, "Type ':q' or ':quit' to exit\n")?;
-    stdout.flush()?;
-    repl_loop(
-        Some(interpreter::import_prelude(&interpreter::new_env())),
-        vec![],
+    writeln!(
         stdout,
-    )
-}
-
-fn repl_loop(
-    env: Option<interpreter::WrappedEnv>,
-    mut decls: Vec<Decl>,
-    mut stdout: io::BufWriter<io::Stdout>,
-) -> io::Result<()> {
-    write!(stdout, ">> ")?;
+        "Type ':q' or ':quit' to exit, or ':help' for more commands"
+    )?;
+    writeln!(
+        stdout,
+        "An unfinished line is continued on the next lines, until an empty line"
+    )?;
+    writeln!(stdout, "The last result is '{}'\n", LAST_RESULT)?;
     stdout.flush()?;
-    let mut code = String::new();
-    io::stdin().read_line(&mut code).unwrap();
-    let code = code.trim();
-
-    if code == ":quit" || code == ":q" {
-        return Ok(());
-    }
-    let (env, decls) = if let Ok((_, d)) = parser::decl(code.into()) {
-        decls.push(d);
-        (None, decls)
-    } else {
-        match parser::complete(parser::expr)(code.into()) {
-            Ok((_, e)) => {
-                let env = env.unwrap_or_else(|| {
-                    let decl_env = interpreter::env_from_decls(&decls);
-                    interpreter::import_prelude(&decl_env)
-                });
-                let result = interpreter::eval_expr(e, &env);
-                if result.is_error() {
-                    writeln!(stdout, "{}", result)?;
-                } else if let Err(()) = result.run() {
-                    writeln!(stdout, "{} : {}", result, result.type_())?;
-                } else {
-                    // Command was run
-                }
-                (Some(env), decls)
+
+    // Imports are relative to the current directory
+    let mut session = Session::new();
+    let mut options = Options {
+        depth,
+        style: Style::stdout(),
+    };
+    let mut editor = Editor::new(history_file());
+    if io::stdin().is_terminal() {
+        handle_interrupts(capability::interrupt_flag());
+    }
+    while let Some(source) = read_input(&mut editor)? {
+        let source = source.trim();
+        if source.starts_with(':') {
+            match command(source, &mut session, &mut options, &mut stdout)? {
+                Flow::Continue => {}
+                Flow::Quit => break,
             }
-            Err(error) => {
-                parser::ParseError::from(error).verbose_from_source(code, &mut stdout)?;
-                (env, decls)
+        } else if !source.is_empty() {
+            match session.eval(source) {
+                // Declarations and Commands without a result don't show anything
+                Ok(None) => {}
+                Ok(Some(value)) if value == Value::unit() => {}
+                Ok(Some(value)) => {
+                    writeln!(stdout, "{}", show::result(&value, options))?;
+                    if let Err(error) = session.engine().register_value(LAST_RESULT, value) {
+                        writeln!(stdout, "{}", error)?;
+                    }
+                }
+                Err(TegoError::Runtime(RuntimeError::Exit(code))) => return Ok(code),
+                Err(error) => report(&error, source, &mut stdout)?,
             }
         }
+        stdout.flush()?;
+    }
+    Ok(codefile::SUCCESS)
+}
+
+enum Flow {
+    Continue,
+    Quit,
+}
+
+// Runs a command that starts with ':'
+fn command(
+    source: &str,
+    session: &mut Session,
+    options: &mut Options,
+    out: &mut impl Write,
+) -> io::Result<Flow> {
+    let (name, arg) = match source.find(char::is_whitespace) {
+        Some(i) => (&source[..i], source[i..].trim()),
+        None => (source, ""),
     };
-    stdout.flush()?;
-    repl_loop(env, decls, stdout)
+    match name {
+        ":q" | ":quit" => return Ok(Flow::Quit),
+        ":l" | ":load" => load(arg, session, out)?,
+        ":t" | ":type" => type_of(arg, session, out)?,
+        ":env" => env(session, out)?,
+        ":depth" => depth(arg, options, out)?,
+        ":reset" => match session.clear() {
+            Ok(()) => writeln!(out, "Everything that was declared has been forgotten")?,
+            Err(error) => writeln!(out, "{}", error)?,
+        },
+        ":h" | ":help" => writeln!(out, "{}", HELP)?,
+        name => writeln!(
+            out,
+            "Unknown command '{}' (type ':help' for a list of commands)",
+            name
+        )?,
+    }
+    Ok(Flow::Continue)
+}
+
+fn load(path: &str, session: &mut Session, out: &mut impl Write) -> io::Result<()> {
+    if path.is_empty() {
+        return writeln!(out, "':load' needs a file (ex. ':load main.tgo')");
+    }
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(error) => return writeln!(out, "Can't load '{}': {}", path, error),
+    };
+    match session.load_source(&source) {
+        Ok(()) => writeln!(out, "Loaded '{}'", path),
+        Err(error) => report(&error, &source, out),
+    }
+}
+
+fn depth(arg: &str, options: &mut Options, out: &mut impl Write) -> io::Result<()> {
+    if arg.is_empty() {
+        return writeln!(out, "Results are shown {} lists deep", options.depth);
+    }
+    match arg.parse() {
+        Ok(depth) => {
+            options.depth = depth;
+            writeln!(out, "Results are shown {} lists deep", depth)
+        }
+        Err(_) => writeln!(out, "':depth' needs a number (ex. ':depth 3')"),
+    }
+}
+
+// The expression is type checked along with everything that's been declared
+fn type_of(source: &str, session: &Session, out: &mut impl Write) -> io::Result<()> {
+    let expr = match parser::complete(parser::expr)(source.into()) {
+        Ok((_, expr)) => expr,
+        Err(error) => return parser::ParseError::from(error).verbose_from_source(source, out),
+    };
+    let mut decls = session.decls().to_vec();
+    decls.push(Decl::Expression(TYPE_INPUT.into(), expr));
+    let inference = types::infer(&decls);
+    let errors = inference
+        .errors
+        .iter()
+        .filter(|error| error.decl() == TYPE_INPUT)
+        .collect::<Vec<_>>();
+    if !errors.is_empty() {
+        return errors
+            .iter()
+            .try_for_each(|error| writeln!(out, "{}", error));
+    }
+    match inference
+        .types
+        .iter()
+        .find(|(ident, _)| ident == TYPE_INPUT)
+    {
+        Some((_, scheme)) => writeln!(out, "{} : {}", source, scheme),
+        None => Ok(()),
+    }
+}
+
+// Imports, then declarations with their types
+fn env(session: &Session, out: &mut impl Write) -> io::Result<()> {
+    if session.decls().is_empty() {
+        return writeln!(out, "Nothing has been declared");
+    }
+    for decl in session.decls() {
+        if let Decl::Import(path, ..) = decl {
+            writeln!(out, "import \"{}\"", path)?;
+        }
+    }
+    types::infer(session.decls())
+        .types
+        .iter()
+        .try_for_each(|(ident, scheme)| writeln!(out, "{} : {}", ident, scheme))
+}
+
+fn report(error: &TegoError, source: &str, out: &mut impl Write) -> io::Result<()> {
+    match error {
+        TegoError::Parse(error) => error.verbose_from_source(source, out),
+        TegoError::Runtime(error) => writeln!(out, "{}", error),
+    }
+}
+
+// Ctrl-C while an input is being evaluated stops it (see
+// `capability::interrupt_flag`), instead of exiting and losing everything
+// that's been declared
+// While an input is being typed, Ctrl-C is read as a key by the editor
+fn handle_interrupts(flag: Arc<AtomicBool>) {
+    // Without a handler, Ctrl-C exits the REPL as usual
+    let _ = ctrlc::set_handler(move || flag.store(true, Ordering::SeqCst));
+}
+
+// An input that isn't finished after its first line continues until an empty
+// line, so that declarations can be written over several lines
+// Results in `None` at the end of the input
+fn read_input(editor: &mut Editor) -> io::Result<Option<String>> {
+    let mut source = match editor.read_line(PROMPT)? {
+        Input::Line(line) => line,
+        Input::Interrupted => return Ok(Some(String::new())),
+        Input::Eof => return Ok(None),
+    };
+    editor.add_history(&source);
+    // Commands are always one line
+    if source.trim_start().starts_with(':') || !is_unfinished(&source) {
+        return Ok(Some(source));
+    }
+    loop {
+        match editor.read_line(CONTINUATION_PROMPT)? {
+            Input::Line(line) if line.trim().is_empty() => return Ok(Some(source)),
+            Input::Line(line) => {
+                editor.add_history(&line);
+                source.push('\n');
+                source.push_str(&line);
+            }
+            // The whole input is thrown away
+            Input::Interrupted => return Ok(Some(String::new())),
+            Input::Eof => return Ok(Some(source)),
+        }
+    }
+}
+
+// Whether `source` is obviously missing something: a closing bracket or
+// quote, or whatever comes after its last token
+fn is_unfinished(source: &str) -> bool {
+    let mut depth = 0;
+    let mut quote = None;
+    let mut escaped = false;
+    // Names can have `'` in them (ex. `f'`), so a `'` right after a name's
+    // character doesn't start a char
+    let mut in_name = false;
+    for c in source.chars() {
+        match (quote, c) {
+            (Some(_), _) if escaped => escaped = false,
+            (Some(_), '\\') => escaped = true,
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'') if in_name => {}
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '(') | (None, '[') => depth += 1,
+            (None, ')') | (None, ']') => depth -= 1,
+            (None, _) => {}
+        }
+        in_name =
+            quote.is_none() && (c.is_ascii_alphanumeric() || c == '_' || (c == '\'' && in_name));
+    }
+    if quote.is_some() || depth > 0 {
+        return true;
+    }
+    // Comments can't tell whether the code before them is finished
+    let code = source.lines().last().unwrap_or("");
+    let code = code.split("--").next().unwrap_or(code).trim_end();
+    let last_word = code
+        .rsplit(|c: char| c.is_whitespace())
+        .next()
+        .unwrap_or("");
+    const KEYWORDS: &[&str] = &[
+        "do", "then", "in", "else", "if", "let", "rec", "delay", "match", "to", "fn", "and", "or",
+        "xor", "not", "cond",
+    ];
+    KEYWORDS.contains(&last_word)
+        || [
+            "=", "->", "|", ",", ";", "+", "-", "*", "/", "%", "<", ">", ":",
+        ]
+        .iter()
+        .any(|end| code.ends_with(end))
+}
+
+// The history is kept in the home directory, if there is one
+fn history_file() -> Option<PathBuf> {
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(".tego_history"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    basic_test! {
+        unfinished_test
+        is_unfinished("1 + 2") => false;
+        is_unfinished("double n = n * 2") => false;
+        is_unfinished("double n =") => true;
+        is_unfinished("f x = x +") => true;
+        is_unfinished("(1, 2") => true;
+        is_unfinished("[1, (2, 3)]") => false;
+        is_unfinished("\"a (") => true;
+        is_unfinished("'('") => false;
+        is_unfinished("f' x = x + 1") => false;
+        is_unfinished("f' 'a'") => false;
+        is_unfinished("f' '") => true;
+        is_unfinished("main =\n\tdo println 1 then") => true;
+        is_unfinished("main =\n\tprintln 1;") => true;
+        is_unfinished("f g = let rec") => true;
+        is_unfinished("f x = match x to") => true;
+        is_unfinished("thenable") => false;
+        is_unfinished("f x = -- a comment") => true;
+        is_unfinished("f : Int -> Int") => false
+    }
 }
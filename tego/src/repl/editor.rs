@@ -0,0 +1,95 @@
+// Reads lines for the REPL, with line editing and history (by `rustyline`)
+// when stdin is a terminal, and plain lines when it isn't (ex. when input is
+// piped in)
+//
+// Ctrl-C cancels the line, and Ctrl-D on an empty line ends the input
+
+use rustyline::config::Config;
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::PathBuf;
+
+// Only this many lines are kept in the history file
+const HISTORY_LEN: usize = 1000;
+
+pub enum Input {
+    Line(String),
+    // Ctrl-C
+    Interrupted,
+    // Ctrl-D, or the end of piped input
+    Eof,
+}
+
+pub struct Editor {
+    // Only when stdin is a terminal
+    editor: Option<DefaultEditor>,
+    // Where the history is kept between sessions, if anywhere
+    history_file: Option<PathBuf>,
+}
+
+impl Editor {
+    pub fn new(history_file: Option<PathBuf>) -> Self {
+        let mut editor = if io::stdin().is_terminal() {
+            terminal_editor()
+        } else {
+            None
+        };
+        if let (Some(editor), Some(file)) = (&mut editor, &history_file) {
+            // There's no history the first time
+            let _ = editor.load_history(file);
+        }
+        Editor {
+            editor,
+            history_file,
+        }
+    }
+
+    pub fn read_line(&mut self, prompt: &str) -> io::Result<Input> {
+        let editor = match &mut self.editor {
+            Some(editor) => editor,
+            None => return read_plain_line(prompt),
+        };
+        match editor.readline(prompt) {
+            Ok(line) => Ok(Input::Line(line)),
+            Err(ReadlineError::Interrupted) => Ok(Input::Interrupted),
+            Err(ReadlineError::Eof) => Ok(Input::Eof),
+            Err(ReadlineError::Io(error)) => Err(error),
+            Err(error) => Err(io::Error::other(error)),
+        }
+    }
+
+    // Adds a line to the history (unless it's empty, or the same as the last one)
+    pub fn add_history(&mut self, line: &str) {
+        if line.trim().is_empty() {
+            return;
+        }
+        // The history is only a convenience, so failing to save it is ignored
+        if let Some(editor) = &mut self.editor {
+            if let (Ok(true), Some(file)) = (editor.add_history_entry(line), &self.history_file) {
+                let _ = editor.append_history(file);
+            }
+        }
+    }
+}
+
+fn terminal_editor() -> Option<DefaultEditor> {
+    let config = Config::builder()
+        .max_history_size(HISTORY_LEN)
+        .ok()?
+        .auto_add_history(false)
+        .build();
+    DefaultEditor::with_config(config).ok()
+}
+
+// The prompt is still written, so piped input and its results can be told apart
+fn read_plain_line(prompt: &str) -> io::Result<Input> {
+    let mut stdout = io::stdout();
+    write!(stdout, "{}", prompt)?;
+    stdout.flush()?;
+    let mut line = String::new();
+    match io::stdin().lock().read_line(&mut line)? {
+        0 => Ok(Input::Eof),
+        _ => Ok(Input::Line(line.trim_end_matches(&['\r', '\n'][..]).into())),
+    }
+}
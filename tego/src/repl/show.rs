@@ -0,0 +1,126 @@
+// How the REPL shows a result: its value, then its type
+//
+//     >> (1, [true, false], "ab")
+//     (1, [(true, false)], "ab") : (Int, Boxed<(Bool, Bool)>, Boxed<String>)
+//
+// Lists (and other boxes) that are nested deeper than the depth (see `:depth`)
+// are shown as `...` (see `--depth`), so huge structures don't fill the terminal
+// Tuples can't be nested (`(1, (2, 3))` is `(1, 2, 3)`), so a list's brackets
+// and the tuple in it are one level
+//
+// Values are colored like source code, when stdout is a terminal (see `Style`)
+
+use crate::render::Style;
+use tego_interpreter::value::{Tuple, Value};
+use tego_interpreter::Type;
+
+const NUMBER: &str = "33";
+const BOOL: &str = "35";
+const TEXT: &str = "32";
+const FUNCTION: &str = "36";
+const ERROR: &str = "31";
+const TYPE: &str = "34";
+const CUT_OFF: &str = "2";
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Options {
+    pub depth: usize,
+    pub style: Style,
+}
+
+// `3 : Int`
+pub fn result(value: &Value, options: Options) -> String {
+    format!(
+        "{} : {}",
+        self::value(value, options.style, options.depth),
+        options
+            .style
+            .paint(TYPE, &type_(&value.type_(), options.depth))
+    )
+}
+
+fn value(value: &Value, style: Style, depth: usize) -> String {
+    match value {
+        Value::Int(_) => style.paint(NUMBER, &value.display_pretty()),
+        Value::Bool(_) => style.paint(BOOL, &value.display_pretty()),
+        Value::Char(_) | Value::Tuple(Tuple::String(_)) => {
+            style.paint(TEXT, &value.display_pretty())
+        }
+        Value::Tuple(Tuple::Generic(values)) => {
+            let values = values
+                .iter()
+                .map(|v| self::value(v, style, depth))
+                .collect::<Vec<_>>();
+            format!("({})", values.join(", "))
+        }
+        Value::Boxed(inner) => match (&**inner, value.as_string()) {
+            // The same strings as `Value::display_pretty`
+            (Value::Tuple(Tuple::String(_)), Some(_)) => style.paint(TEXT, &value.display_pretty()),
+            (_, Some(string)) if !string.is_empty() => style.paint(TEXT, &value.display_pretty()),
+            _ if depth == 0 => style.paint(CUT_OFF, "..."),
+            (inner, _) => format!("[{}]", self::value(inner, style, depth - 1)),
+        },
+        Value::Function(_) | Value::Command(_) => style.paint(FUNCTION, &value.display_pretty()),
+        Value::Delayed { .. } => self::value(&value.clone().eval(None), style, depth),
+        Value::Error(_) => style.paint(ERROR, &value.display_pretty()),
+    }
+}
+
+// Written the same way as `Type`'s `Display`, but cut off at the same depth as
+// the value
+fn type_(type_: &Type, depth: usize) -> String {
+    match type_ {
+        Type::Tuple(types) if !is_string(type_) => {
+            let types = types
+                .iter()
+                .map(|t| self::type_(t, depth))
+                .collect::<Vec<_>>();
+            format!("({})", types.join(", "))
+        }
+        Type::Boxed(inner) if is_string(inner) => type_.to_string(),
+        Type::Boxed(_) if depth == 0 => "...".into(),
+        Type::Boxed(inner) => format!("Boxed<{}>", self::type_(inner, depth - 1)),
+        type_ => type_.to_string(),
+    }
+}
+
+fn is_string(type_: &Type) -> bool {
+    matches!(type_, Type::Tuple(types) if types.iter().all(|t| matches!(t, Type::Char)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tego_interpreter::Engine;
+
+    fn show(source: &str, depth: usize, color: bool) -> String {
+        let value = Engine::new().eval_expr_str(source).unwrap();
+        result(
+            &value,
+            Options {
+                depth,
+                style: Style::new(color),
+            },
+        )
+    }
+
+    basic_test! {
+        show_test
+        show("1 + 2", 8, false) => "3 : Int";
+        show("(1, 'c', \"ab\", [()], [\"a\" ,, '\"'])", 8, false)
+            => "(1, 'c', \"ab\", [()], [\"a\" ,, '\"']) : (Int, Char, Boxed<String>, Boxed<String>, Boxed<String>)";
+        show("[1, [2, [3, [4]]]]", 8, false)
+            => "[(1, [(2, [(3, [4])])])] : Boxed<(Int, Boxed<(Int, Boxed<(Int, Boxed<Int>)>)>)>";
+        show("[1, [2, [3, [4]]]]", 2, false)
+            => "[(1, [(2, ...)])] : Boxed<(Int, Boxed<(Int, ...)>)>";
+        show("(true, [1], \"a\")", 0, false) => "(true, ..., \"a\") : (Bool, ..., Boxed<String>)";
+        show("fn x -> x", 0, false) => "<fn> : Fn"
+    }
+
+    basic_test! {
+        color_test
+        show("(1, \"a\")", 8, true)
+            => "(\x1b[33m1\x1b[0m, \x1b[32m\"a\"\x1b[0m) : \x1b[34m(Int, Boxed<String>)\x1b[0m";
+        show("[[true]]", 1, true) => "[\x1b[2m...\x1b[0m] : \x1b[34mBoxed<...>\x1b[0m"
+    }
+}
@@ -0,0 +1,171 @@
+// Runs the tests in a file (see `tego test`), which are the declarations whose
+// names start with `test`
+//
+//     testAdd = assertEq 3 (add 1 2)
+//
+// A test passes unless it results in an error or `false` (a Command is run
+// first), and each test's output is only shown if it fails
+
+use crate::codefile;
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::path::Path;
+use std::rc::Rc;
+use std::thread;
+use tego_analysis::diagnostics;
+use tego_interpreter as interpreter;
+use tego_interpreter::output::{self, Buffer};
+use tego_interpreter::value::Value;
+use tego_parser::ast::{Decl, Expr, Prog};
+use tego_parser::ExprOutput;
+
+const TEST_PREFIX: &str = "test";
+
+struct Outcome {
+    name: String,
+    // Why the test failed, if it did
+    failure: Option<String>,
+    output: String,
+}
+
+// Only tests with `filter` in their name are run, and they're split between
+// `jobs` threads
+pub fn run<P: AsRef<Path>>(
+    path: P,
+    config: &diagnostics::Config,
+    filter: Option<&str>,
+    jobs: usize,
+) -> io::Result<i32> {
+    let mut stdout = io::BufWriter::new(io::stdout());
+    let mut stderr = io::BufWriter::new(io::stderr());
+    let dir = codefile::dir_of(path.as_ref());
+    let decls = match codefile::load(path.as_ref(), config, &mut stderr)? {
+        Ok(Prog::Binary(_, decls)) | Ok(Prog::Library(decls)) => decls,
+        Err(code) => return codefile::wrap_up(stderr, stdout, code),
+    };
+    let tests = declarations(&decls, TEST_PREFIX, filter)
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>();
+    if tests.is_empty() {
+        writeln!(
+            stdout,
+            "No tests found (tests are declarations whose names start with '{}')",
+            TEST_PREFIX
+        )?;
+        return codefile::wrap_up(stderr, stdout, codefile::SUCCESS);
+    }
+
+    let outcomes = match run_tests(&decls, &dir, &tests, jobs) {
+        Ok(outcomes) => outcomes,
+        Err(e) => {
+            codefile::load_error(&mut stderr, Some(path.as_ref()), &e)?;
+            return codefile::wrap_up(stderr, stdout, codefile::RUNTIME_ERROR);
+        }
+    };
+    for outcome in &outcomes {
+        let result = if outcome.failure.is_some() {
+            "FAILED"
+        } else {
+            "ok"
+        };
+        writeln!(stdout, "test {} ... {}", outcome.name, result)?;
+    }
+    let failures = outcomes
+        .iter()
+        .filter_map(|outcome| outcome.failure.as_ref().map(|failure| (outcome, failure)))
+        .collect::<Vec<_>>();
+    if !failures.is_empty() {
+        writeln!(stdout, "\nfailures:")?;
+        for (outcome, failure) in &failures {
+            writeln!(stdout, "\n---- {} ----\n{}", outcome.name, failure)?;
+            if !outcome.output.is_empty() {
+                write!(stdout, "output:\n{}", outcome.output)?;
+            }
+        }
+    }
+    writeln!(
+        stdout,
+        "\ntest result: {}. {} passed; {} failed",
+        if failures.is_empty() { "ok" } else { "FAILED" },
+        outcomes.len() - failures.len(),
+        failures.len()
+    )?;
+    let code = if failures.is_empty() {
+        codefile::SUCCESS
+    } else {
+        codefile::RUNTIME_ERROR
+    };
+    codefile::wrap_up(stderr, stdout, code)
+}
+
+// The declarations whose names start with `prefix`, and have `filter` in them
+// (also used to find benchmarks)
+pub fn declarations(decls: &[Decl], prefix: &str, filter: Option<&str>) -> Vec<(String, Expr)> {
+    decls
+        .iter()
+        .filter_map(|decl| match decl {
+            Decl::Expression(name, expr) if name.starts_with(prefix) => {
+                Some((name.clone(), expr.clone()))
+            }
+            _ => None,
+        })
+        .filter(|(name, _)| filter.is_none_or(|filter| name.contains(filter)))
+        .collect()
+}
+
+// The outcomes are in the same order as `tests`
+// Values can't be shared between threads, so each thread loads the program
+// itself, with the same settings as this thread
+fn run_tests(
+    decls: &[Decl],
+    dir: &Path,
+    tests: &[String],
+    jobs: usize,
+) -> Result<Vec<Outcome>, String> {
+    let search_path = interpreter::module::search_path();
+    let cache_dir = interpreter::module::cache_dir();
+    let config = interpreter::capability::config();
+    let chunk_size = tests.len().div_ceil(jobs.max(1));
+    let chunks = thread::scope(|scope| {
+        let threads = tests
+            .chunks(chunk_size)
+            .map(|tests| {
+                let (search_path, cache_dir, config) =
+                    (search_path.clone(), cache_dir.clone(), config.clone());
+                scope.spawn(move || {
+                    interpreter::module::set_search_path(search_path);
+                    interpreter::module::set_cache_dir(cache_dir);
+                    interpreter::capability::set_config(config);
+                    let env = interpreter::program_env(decls, dir)?;
+                    Ok(tests.iter().map(|test| run_test(test, &env)).collect())
+                })
+            })
+            .collect::<Vec<_>>();
+        threads
+            .into_iter()
+            .map(|thread| thread.join().unwrap())
+            .collect::<Result<Vec<Vec<_>>, String>>()
+    })?;
+    Ok(chunks.into_iter().flatten().collect())
+}
+
+fn run_test(name: &str, env: &interpreter::WrappedEnv) -> Outcome {
+    // So a test's random numbers and times don't depend on the tests before it
+    interpreter::capability::restart();
+    let buffer = Buffer::new();
+    let previous = output::set_output(Some(Rc::new(RefCell::new(buffer.clone()))));
+    let value = interpreter::eval_expr(Expr::variable(name), env);
+    let value = value.run().unwrap_or(value);
+    output::set_output(previous);
+    let failure = match value {
+        Value::Error(_) => Some(value.to_string()),
+        Value::Bool(false) => Some(format!("'{}' is false", name)),
+        _ => None,
+    };
+    Outcome {
+        name: name.into(),
+        failure,
+        output: buffer.contents(),
+    }
+}
@@ -0,0 +1,101 @@
+// Runs a program again whenever it, or a file it imports, changes (see
+// `tego run --watch`)
+
+use crate::codefile;
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+use tego_analysis::diagnostics;
+use tego_interpreter as interpreter;
+use tego_parser as parser;
+use tego_parser::ast::Prog;
+
+// Saving a file can take several writes (or a write and a rename), which are
+// all waited for so the program is only run once
+const SETTLE_TIME: Duration = Duration::from_millis(100);
+
+// Only stops when it can't watch the files
+pub fn run(path: &Path, config: &diagnostics::Config) -> io::Result<i32> {
+    loop {
+        codefile::run(path, config)?;
+        let changed = match wait_for_change(path, &files(path)) {
+            Ok(changed) => changed,
+            Err(e) => {
+                eprintln!("Error watching files: {}", e);
+                return Ok(codefile::RUNTIME_ERROR);
+            }
+        };
+        // Files are shown relative to the current directory, if they're in it
+        let changed = env::current_dir()
+            .ok()
+            .and_then(|dir| dir.canonicalize().ok())
+            .and_then(|dir| changed.strip_prefix(dir).ok().map(Path::to_owned))
+            .unwrap_or(changed);
+        eprintln!("\n'{}' changed, running again\n", changed.display());
+    }
+}
+
+// The file and everything it imports, as they are now (so a file that can't
+// be parsed is watched without its imports)
+fn files(path: &Path) -> Vec<PathBuf> {
+    let mut files = vec![fs::canonicalize(path).unwrap_or_else(|_| path.to_owned())];
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(_) => return files,
+    };
+    let decls = match parser::prog(source.as_str().into()) {
+        Ok((_, Prog::Binary(_, decls))) | Ok((_, Prog::Library(decls))) => decls,
+        Err(_) => return files,
+    };
+    let dir = codefile::dir_of(path);
+    for file in interpreter::module::dependencies(&decls, Some(&dir)) {
+        if !files.contains(&file) {
+            files.push(file);
+        }
+    }
+    files
+}
+
+// Results in the first file that changed
+// `path` is the file that's run, which is the first of `files`
+// Directories are watched instead of the files themselves, since editors
+// often save by replacing the file
+fn wait_for_change(path: &Path, files: &[PathBuf]) -> notify::Result<PathBuf> {
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender)?;
+    let mut dirs = files
+        .iter()
+        .filter_map(|file| file.parent())
+        .collect::<Vec<_>>();
+    dirs.sort();
+    dirs.dedup();
+    for dir in dirs {
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    }
+    eprintln!(
+        "Watching {} for changes (Ctrl-C to stop)",
+        match files.len() {
+            1 => format!("'{}'", path.display()),
+            2 => format!("'{}' and 1 imported file", path.display()),
+            n => format!("'{}' and {} imported files", path.display(), n - 1),
+        }
+    );
+    let changed = loop {
+        let event = match receiver.recv() {
+            Ok(event) => event?,
+            Err(_) => return Err(notify::Error::generic("Stopped receiving changes")),
+        };
+        if matches!(event.kind, EventKind::Access(_)) {
+            continue;
+        }
+        if let Some(changed) = event.paths.into_iter().find(|path| files.contains(path)) {
+            break changed;
+        }
+    };
+    while receiver.recv_timeout(SETTLE_TIME).is_ok() {}
+    Ok(changed)
+}
@@ -1,7 +1,11 @@
 use assert_cmd::prelude::*;
 use std::fs;
+use std::io::{self, Write};
 use std::path;
-use std::process::Command;
+use std::process::{self, Command};
+use std::sync;
+use std::thread;
+use std::time;
 
 #[test]
 fn example_tests() -> Result<(), Box<dyn std::error::Error>> {
@@ -54,3 +58,844 @@ fn test_directory<P: AsRef<path::Path>>(path: P) -> Result<(), Box<dyn std::erro
         });
     Ok(())
 }
+
+#[test]
+fn exit_code_tests() -> Result<(), Box<dyn std::error::Error>> {
+    // (code, expected exit code)
+    let cases = [
+        ("main = 1 + 1", 0),
+        ("main = 1 / 0", 1),
+        ("main = 1 + true", 2),
+        ("main = do println 1 then exit 3", 3),
+        ("main = exit 0", 0),
+        ("main = do exit 4 then println 1", 4),
+        ("main = catch (exit 5) (fn _ -> return ())", 5),
+        ("main = (", 2),
+        ("id a = a", 1),
+        ("f : Int -> Bool\nf a = a + 1\nmain = f 1", 2),
+    ];
+    for (code, expected) in cases.iter() {
+        let mut file = tempfile::NamedTempFile::new()?;
+        write!(file, "{}", code)?;
+        Command::cargo_bin("tego")?
+            .arg("run")
+            .arg(file.path())
+            .assert()
+            .code(*expected);
+    }
+    Ok(())
+}
+
+#[test]
+fn seed_test() -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = tempfile::NamedTempFile::new()?;
+    write!(
+        file,
+        "main =\n\tdo random in a then\n\tdo randomInt 1 100 in b then\n\tprintln (a, b)"
+    )?;
+    let run = || -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let output = Command::cargo_bin("tego")?
+            .args(["run", "--seed", "42"])
+            .arg(file.path())
+            .output()?;
+        assert!(output.status.success());
+        Ok(output.stdout)
+    };
+    assert_eq!(run()?, run()?);
+    Ok(())
+}
+
+#[test]
+fn deterministic_test() -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = tempfile::NamedTempFile::new()?;
+    write!(
+        file,
+        "main =\n\tdo randomInt 1 100 in a then\n\tdo now in t then\n\tprintln (a, formatTime \"%Y-%m-%d\" t)\n\
+         testA = do randomInt 1 100 in a then return (a > 0)\n\
+         testB = do now in t then return (formatTime \"%S\" t == \"00\")"
+    )?;
+    let output = |args: &[&str]| -> Result<String, Box<dyn std::error::Error>> {
+        let output = Command::cargo_bin("tego")?
+            .args(args)
+            .arg(file.path())
+            .output()?;
+        Ok(String::from_utf8(output.stdout)?)
+    };
+    let run = output(&["run", "--deterministic"])?;
+    assert!(run.ends_with(", \"2000-01-01\")\n"), "{}", run);
+    assert_eq!(output(&["run", "--deterministic"])?, run);
+    // Each test starts over, so it doesn't matter which thread runs it
+    let test = output(&["test", "--deterministic", "-j", "1"])?;
+    assert!(test.contains("2 passed"), "{}", test);
+    assert_eq!(output(&["test", "--deterministic", "-j", "2"])?, test);
+    Ok(())
+}
+
+#[cfg(feature = "uuid")]
+#[test]
+fn uuid_test() -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = tempfile::NamedTempFile::new()?;
+    write!(
+        file,
+        "main =\n\tdo uuidV4 in a then\n\tdo uuidV4 in b then\n\tprintln (a, b, a == b)"
+    )?;
+    let run = || -> Result<String, Box<dyn std::error::Error>> {
+        let output = Command::cargo_bin("tego")?
+            .args(["run", "--seed", "42"])
+            .arg(file.path())
+            .output()?;
+        assert!(output.status.success());
+        Ok(String::from_utf8(output.stdout)?)
+    };
+    let first = run()?;
+    assert_eq!(first, run()?);
+    assert!(first.ends_with(", false)\n"));
+    assert_eq!(&first[16..17], "4");
+    Ok(())
+}
+
+#[test]
+fn exec_test() -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = tempfile::NamedTempFile::new()?;
+    write!(
+        file,
+        "main = do exec \"echo\" (\"a\", \"b\") in result then println result"
+    )?;
+    Command::cargo_bin("tego")?
+        .args(["run", "--allow-exec"])
+        .arg(file.path())
+        .assert()
+        .success()
+        .stdout("(0, \"a b\n\", \"\")\n");
+
+    // Subprocesses aren't allowed by default
+    let mut file = tempfile::NamedTempFile::new()?;
+    write!(file, "main = exec \"echo\" \"a\"")?;
+    Command::cargo_bin("tego")?
+        .arg("run")
+        .arg(file.path())
+        .assert()
+        .code(1);
+    Ok(())
+}
+
+#[test]
+fn wrapping_test() -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = tempfile::NamedTempFile::new()?;
+    write!(file, "main = 2147483647 + 1")?;
+    Command::cargo_bin("tego")?
+        .arg("run")
+        .arg(file.path())
+        .assert()
+        .code(1)
+        .stderr("Error running file: Error: '2147483647 + 1' overflows (the result doesn't fit in an 'Int') [E0205]\n");
+    Command::cargo_bin("tego")?
+        .args(["run", "--wrapping"])
+        .arg(file.path())
+        .assert()
+        .success()
+        .stdout("-2147483648\n");
+    Ok(())
+}
+
+#[test]
+fn assertion_test() -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = tempfile::NamedTempFile::new()?;
+    write!(file, "main = assertEq 4 (add 1 2)\nadd a b = a + b")?;
+    Command::cargo_bin("tego")?
+        .arg("run")
+        .arg(file.path())
+        .assert()
+        .code(1)
+        .stderr("Error running file: Error: Assertion failed: assertEq 4 (add 1 2) [E0215]\n  expected: 4\n  found: 3\n");
+    Ok(())
+}
+
+#[test]
+fn exhaustiveness_test() -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = tempfile::NamedTempFile::new()?;
+    write!(
+        file,
+        "main = println (f true)\nf b = match b to\n\t| true -> 1"
+    )?;
+    Command::cargo_bin("tego")?
+        .arg("run")
+        .arg(file.path())
+        .assert()
+        .success()
+        .stdout("1\n")
+        .stderr(format!(
+            "Warning in 'f': 'match b to' doesn't have a pattern for 'false' [E0111]\n --> {}:2:7\n  |\n2 | f b = match b to\n  |       ^^^^^^^^^^\n  = help: Add a pattern for 'false'\n\n",
+            file.path().display()
+        ));
+    Ok(())
+}
+
+#[test]
+fn shadowing_test() -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = tempfile::NamedTempFile::new()?;
+    write!(file, "main = f 1\nf a = let a = 2 in a")?;
+    Command::cargo_bin("tego")?
+        .arg("run")
+        .arg("--shadowing")
+        .arg("deny")
+        .arg(file.path())
+        .assert()
+        .code(2);
+    Command::cargo_bin("tego")?
+        .arg("run")
+        .arg("--shadowing")
+        .arg("allow")
+        .arg(file.path())
+        .assert()
+        .success()
+        .stdout("2\n");
+    Ok(())
+}
+
+#[test]
+fn warnings_test() -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = tempfile::NamedTempFile::new()?;
+    write!(file, "main = 1\ng = 2")?;
+    Command::cargo_bin("tego")?
+        .args(["check", "-Werror"])
+        .arg(file.path())
+        .assert()
+        .code(2);
+    Command::cargo_bin("tego")?
+        .args(["check", "-W", "no-unused", "-W", "error"])
+        .arg(file.path())
+        .assert()
+        .success()
+        .stderr("");
+    Command::cargo_bin("tego")?
+        .args(["check", "-W", "unused-everything"])
+        .arg(file.path())
+        .assert()
+        .code(64);
+    Ok(())
+}
+
+#[test]
+fn gradual_typing_test() -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = tempfile::NamedTempFile::new()?;
+    write!(file, "main = f true\nf a = if a then 1 else \"one\"")?;
+    Command::cargo_bin("tego")?
+        .arg("run")
+        .arg(file.path())
+        .assert()
+        .code(2);
+    Command::cargo_bin("tego")?
+        .arg("run")
+        .arg("--typing")
+        .arg("gradual")
+        .arg(file.path())
+        .assert()
+        .success()
+        .stdout("1\n");
+
+    // Values from unannotated code are checked when they're passed to `add`
+    let mut file = tempfile::NamedTempFile::new()?;
+    write!(
+        file,
+        "main = add 1 (g 0)\nadd : Int -> Int -> Int\nadd a b = a + b\ng n = n == 0"
+    )?;
+    Command::cargo_bin("tego")?
+        .arg("run")
+        .arg("--typing")
+        .arg("gradual")
+        .arg(file.path())
+        .assert()
+        .code(1)
+        .stderr("Error running file: Error: Type error in 'add': expected 'Int', found 'Bool' [E0201]\n");
+    Ok(())
+}
+
+#[test]
+fn check_test() -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = tempfile::NamedTempFile::new()?;
+    write!(file, "main = println \"hi\"")?;
+    Command::cargo_bin("tego")?
+        .arg("check")
+        .arg(file.path())
+        .assert()
+        .success()
+        .stdout("");
+
+    let mut file = tempfile::NamedTempFile::new()?;
+    write!(file, "main = println (f 1)\nf a = a + b")?;
+    Command::cargo_bin("tego")?
+        .arg("check")
+        .arg(file.path())
+        .assert()
+        .code(2)
+        .stderr(format!(
+            "Error in 'f': 'b' isn't declared [E0117]\n --> {}:2:1\n  |\n2 | f a = a + b\n  | ^\n\n",
+            file.path().display()
+        ));
+    Ok(())
+}
+
+#[test]
+fn message_format_test() -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = tempfile::NamedTempFile::new()?;
+    write!(file, "main = println (f 1)\nf a = a + b")?;
+    let path = file.path().display();
+    Command::cargo_bin("tego")?
+        .arg("check")
+        .arg("--message-format=json")
+        .arg(file.path())
+        .assert()
+        .code(2)
+        .stderr(format!(
+            "{{\"severity\":\"error\",\"code\":\"undeclared\",\"id\":\"E0117\",\"message\":\"Error in 'f': 'b' isn't declared\",\"file\":\"{}\",\"span\":{{\"line\":2,\"column\":1,\"length\":1}},\"suggestions\":[]}}\n",
+            path
+        ));
+
+    // A type error is at the expression that has the wrong type
+    let mut file = tempfile::NamedTempFile::new()?;
+    write!(
+        file,
+        "main = println (f 1)\nf a =\n\tlet b = a * 2 in\n\tb + true"
+    )?;
+    let path = file.path().display();
+    Command::cargo_bin("tego")?
+        .arg("check")
+        .arg("--message-format=json")
+        .arg(file.path())
+        .assert()
+        .code(2)
+        .stderr(format!(
+            "{{\"severity\":\"error\",\"code\":\"type-mismatch\",\"id\":\"E0101\",\"message\":\"Type error in 'f': expected 'Int', found 'Bool' in 'true'\",\"file\":\"{}\",\"span\":{{\"line\":4,\"column\":6,\"length\":4}},\"suggestions\":[]}}\n",
+            path
+        ));
+
+    let mut file = tempfile::NamedTempFile::new()?;
+    write!(file, "main = (1")?;
+    let output = Command::cargo_bin("tego")?
+        .arg("--message-format")
+        .arg("json")
+        .arg("run")
+        .arg(file.path())
+        .output()?;
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8(output.stderr)?
+        .starts_with("{\"severity\":\"error\",\"code\":\"parse-error\","));
+    Ok(())
+}
+
+#[test]
+fn missing_import_test() -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = tempfile::NamedTempFile::new()?;
+    write!(file, "import \"missing.tgo\"\nmain = 1")?;
+    let output = Command::cargo_bin("tego")?
+        .arg("run")
+        .arg(file.path())
+        .output()?;
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr)?.starts_with("Error running file: Can't import"));
+    Ok(())
+}
+
+#[test]
+fn search_path_test() -> Result<(), Box<dyn std::error::Error>> {
+    let lib = tempfile::tempdir()?;
+    let env_lib = tempfile::tempdir()?;
+    fs::write(lib.path().join("A.tgo"), "a = 1")?;
+    fs::write(env_lib.path().join("A.tgo"), "a = 2")?;
+    fs::write(env_lib.path().join("B.tgo"), "b = 3")?;
+    let mut file = tempfile::NamedTempFile::new()?;
+    write!(file, "import A\nimport B\nmain = A.a, B.b")?;
+
+    // `--path` directories are searched before `TEGO_PATH` directories
+    Command::cargo_bin("tego")?
+        .arg("run")
+        .arg("--path")
+        .arg(lib.path())
+        .arg(file.path())
+        .env("TEGO_PATH", env_lib.path())
+        .assert()
+        .success()
+        .stdout("(1, 3)\n");
+    Ok(())
+}
+
+#[test]
+fn project_test() -> Result<(), Box<dyn std::error::Error>> {
+    let root = tempfile::tempdir()?;
+    let app = root.path().join("app");
+    let utils = root.path().join("utils");
+    fs::create_dir_all(app.join("src"))?;
+    fs::create_dir_all(utils.join("lib"))?;
+    fs::write(
+        app.join("tego.toml"),
+        "[package]\nname = \"app\"\nentry = \"src/app.tgo\"\n\n[dependencies]\nutils = { path = \"../utils\" }\n",
+    )?;
+    fs::write(
+        app.join("src/app.tgo"),
+        "import Strings\nmain = Strings.greeting",
+    )?;
+    fs::write(
+        utils.join("tego.toml"),
+        "[package]\nname = \"utils\"\nentry = \"lib/Strings.tgo\"\n",
+    )?;
+    fs::write(utils.join("lib/Strings.tgo"), "greeting = \"hi\"")?;
+
+    Command::cargo_bin("tego")?
+        .arg("build")
+        .arg(&app)
+        .assert()
+        .success();
+    // Imported files are cached
+    assert_eq!(fs::read_dir(app.join(".tego-cache"))?.count(), 1);
+    // `tego run` without a file runs the project in the current directory
+    Command::cargo_bin("tego")?
+        .arg("run")
+        .current_dir(&app)
+        .assert()
+        .success()
+        .stdout("\"hi\"\n");
+
+    // Building loads imports without running `main`
+    fs::write(app.join("src/app.tgo"), "import Missing\nmain = 1")?;
+    let output = Command::cargo_bin("tego")?
+        .arg("build")
+        .arg(&app)
+        .output()?;
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr)?.starts_with("Error loading file: Can't import"));
+
+    let output = Command::cargo_bin("tego")?
+        .arg("build")
+        .arg(utils.join("lib"))
+        .output()?;
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8(output.stderr)?.starts_with("Error loading project: Can't read"));
+    Ok(())
+}
+
+#[cfg(feature = "crypto")]
+#[test]
+fn crypto_test() -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = tempfile::NamedTempFile::new()?;
+    write!(
+        file,
+        "main = (
+    sha256 \"abc\",
+    sha1 (97, 98, 99),
+    md5 \"\"
+)"
+    )?;
+    Command::cargo_bin("tego")?
+        .args(["run"])
+        .arg(file.path())
+        .assert()
+        .success()
+        .stdout(concat!(
+            "(\"ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad\", ",
+            "\"a9993e364706816aba3e25717850c26c9cd0d89d\", ",
+            "\"d41d8cd98f00b204e9800998ecf8427e\")\n"
+        ));
+    Ok(())
+}
+
+#[cfg(feature = "unicode")]
+#[test]
+fn unicode_test() -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = tempfile::NamedTempFile::new()?;
+    write!(
+        file,
+        "main = (
+    lengthGraphemes \"e\u{301}!\",
+    byteLength \"e\u{301}!\",
+    toGraphemes \"e\u{301}!\",
+    byteLength (toNFC \"e\u{301}\"),
+    byteLength (toNFD \"\u{e9}\"),
+    toNFKC \"\u{fb01}\"
+)"
+    )?;
+    Command::cargo_bin("tego")?
+        .args(["run"])
+        .arg(file.path())
+        .assert()
+        .success()
+        .stdout("(2, 4, \"e\u{301}\", \"!\", 2, 3, \"fi\")\n");
+    Ok(())
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn regex_test() -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = tempfile::NamedTempFile::new()?;
+    write!(
+        file,
+        "main = (
+    regexMatch \"[0-9]+\" \"abc 123\",
+    regexFind \"([a-z]+)=([0-9]+)?\" \"x: key=\",
+    [regexFind \"z\" \"abc\"],
+    regexReplace \"([a-z]+)=([0-9]+)\" \"$2=$1\" \"a=1, b=2\",
+    regexSplit \", *\" \"a, b,c\"
+)"
+    )?;
+    Command::cargo_bin("tego")?
+        .args(["run"])
+        .arg(file.path())
+        .assert()
+        .success()
+        .stdout(
+            "(true, [(\"key=\", 3, [([\"key\"], ())])], [()], \"1=a, 2=b\", \"a\", \"b\", \"c\")\n",
+        );
+    Ok(())
+}
+
+// Long inputs and patterns that backtrack a lot still finish quickly, and
+// invalid patterns are errors
+#[cfg(feature = "regex")]
+#[test]
+fn regex_limits_test() -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = tempfile::NamedTempFile::new()?;
+    write!(
+        file,
+        "main = (regexMatch \"a*b\" \"{a}\", regexMatch \"(a|aa)*c\" \"{a}\", regexFind \"b\" \"éb\")",
+        a = "a".repeat(20000)
+    )?;
+    Command::cargo_bin("tego")?
+        .args(["run"])
+        .arg(file.path())
+        .assert()
+        .success()
+        .stdout("(false, false, [(\"b\", 1, [()])])\n");
+    let mut file = tempfile::NamedTempFile::new()?;
+    write!(file, "main = regexMatch \"(ab\" \"ab\"")?;
+    Command::cargo_bin("tego")?
+        .args(["run"])
+        .arg(file.path())
+        .assert()
+        .code(1)
+        .stderr("Error running file: Error: Invalid regex '(ab': unclosed group [E0201]\n");
+    Ok(())
+}
+
+#[test]
+fn usage_test() -> Result<(), Box<dyn std::error::Error>> {
+    let output = Command::cargo_bin("tego")?.arg("--help").output()?;
+    assert!(output.status.success());
+    let help = String::from_utf8(output.stdout)?;
+    for subcommand in ["repl", "run", "check", "build"].iter() {
+        assert!(
+            help.contains(subcommand),
+            "missing '{}' in:\n{}",
+            subcommand,
+            help
+        );
+    }
+    Command::cargo_bin("tego")?
+        .args(["run", "--unknown-flag"])
+        .assert()
+        .code(64);
+    Command::cargo_bin("tego")?
+        .args(["run", "--typing", "loose", "file.tgo"])
+        .assert()
+        .code(64);
+    Ok(())
+}
+
+#[test]
+fn repl_test() -> Result<(), Box<dyn std::error::Error>> {
+    let output = assert_cmd::Command::cargo_bin("tego")?
+        .arg("repl")
+        .write_stdin("double n =\n\tn * 2\n\ndouble 21\nprintln \"a\"\n:q\n1 + 1\n")
+        .output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.contains("42 : Int\n"), "{}", stdout);
+    assert!(stdout.contains("a\n"), "{}", stdout);
+    // Nothing is evaluated after ':q'
+    assert!(!stdout.contains(">> 2 : Int"), "{}", stdout);
+    Ok(())
+}
+
+#[test]
+fn repl_command_test() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let lib = dir.path().join("lib.tgo");
+    fs::write(&lib, "triple n = n * 3\n")?;
+    let input = format!(
+        "f x = x + 1\n:type f\n:env\n:load {}\ntriple 2\n:reset\n:env\n:unknown\n",
+        lib.display()
+    );
+    let output = assert_cmd::Command::cargo_bin("tego")?
+        .arg("repl")
+        .write_stdin(input)
+        .output()?;
+    let stdout = String::from_utf8(output.stdout)?;
+    for expected in [
+        ">> f : Int -> Int\n",
+        ">> f : Int -> Int\n>> Loaded",
+        ">> 6 : Int\n",
+        ">> Nothing has been declared\n",
+        ">> Unknown command ':unknown'",
+    ]
+    .iter()
+    {
+        assert!(
+            stdout.contains(expected),
+            "missing {:?} in:\n{}",
+            expected,
+            stdout
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn repl_result_test() -> Result<(), Box<dyn std::error::Error>> {
+    let output = assert_cmd::Command::cargo_bin("tego")?
+        .args(["repl", "--depth", "1"])
+        .write_stdin("[1, [2]]\nit\n:depth 3\nit\n20 + 1\nit * 2\n:depth x\n")
+        .output()?;
+    let stdout = String::from_utf8(output.stdout)?;
+    for expected in [
+        ">> [(1, ...)] : Boxed<(Int, ...)>\n>> [(1, ...)]",
+        ">> Results are shown 3 lists deep\n>> [(1, [2])] : Boxed<(Int, Boxed<Int>)>\n",
+        ">> 42 : Int\n",
+        ">> ':depth' needs a number",
+    ]
+    .iter()
+    {
+        assert!(
+            stdout.contains(expected),
+            "missing {:?} in:\n{}",
+            expected,
+            stdout
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn eval_test() -> Result<(), Box<dyn std::error::Error>> {
+    Command::cargo_bin("tego")?
+        .args(["-e", "1 + 2"])
+        .assert()
+        .success()
+        .stdout("3\n");
+    Command::cargo_bin("tego")?
+        .args(["run", "-e", "main = double 21\ndouble n = n * 2"])
+        .assert()
+        .success()
+        .stdout("42\n");
+    Command::cargo_bin("tego")?
+        .args(["-e", "println \"hi\""])
+        .assert()
+        .success()
+        .stdout("hi\n");
+    Command::cargo_bin("tego")?
+        .args(["-e", "1 + \"a\""])
+        .assert()
+        .code(2);
+    Command::cargo_bin("tego")?
+        .args(["run", "-e", "1", "file.tgo"])
+        .assert()
+        .code(64);
+    Ok(())
+}
+
+#[test]
+fn stdin_test() -> Result<(), Box<dyn std::error::Error>> {
+    // The last declaration doesn't need a newline after it
+    assert_cmd::Command::cargo_bin("tego")?
+        .args(["run", "-"])
+        .write_stdin("main = double 21\ndouble n = n * 2")
+        .assert()
+        .success()
+        .stdout("42\n");
+    assert_cmd::Command::cargo_bin("tego")?
+        .args(["check", "-"])
+        .write_stdin("main = 1 + \"a\"\n")
+        .assert()
+        .code(2);
+    Ok(())
+}
+
+#[test]
+fn watch_test() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let main = dir.path().join("main.tgo");
+    let lib = dir.path().join("lib.tgo");
+    fs::write(&main, "import \"lib.tgo\"\nmain = b + 1\n")?;
+    fs::write(&lib, "b = 1\n")?;
+    let mut child = process::Command::new(assert_cmd::cargo::cargo_bin("tego"))
+        .args(["run", "--watch"])
+        .arg(&main)
+        .stdin(process::Stdio::null())
+        .stdout(process::Stdio::piped())
+        .stderr(process::Stdio::piped())
+        .spawn()?;
+    let stdout = lines(child.stdout.take().unwrap());
+    let stderr = lines(child.stderr.take().unwrap());
+    let timeout = time::Duration::from_secs(10);
+    let first = stdout.recv_timeout(timeout);
+    // The files are being watched once this is shown
+    let watching = stderr.recv_timeout(timeout);
+    // Changing an imported file runs the program again
+    fs::write(&lib, "b = 10\n")?;
+    let second = stdout.recv_timeout(timeout);
+    child.kill()?;
+    child.wait()?;
+    assert_eq!(first.as_deref(), Ok("2"));
+    assert!(watching?.starts_with("Watching"));
+    assert_eq!(second.as_deref(), Ok("11"));
+    Ok(())
+}
+
+// Each line of the output, as it's written
+fn lines<R: io::Read + Send + 'static>(output: R) -> sync::mpsc::Receiver<String> {
+    let (sender, receiver) = sync::mpsc::channel();
+    thread::spawn(move || {
+        for line in io::BufRead::lines(io::BufReader::new(output)) {
+            if sender.send(line.unwrap()).is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+#[test]
+fn test_runner_test() -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = tempfile::NamedTempFile::new()?;
+    write!(
+        file,
+        "add a b = a + b\n\
+         testAdd = assertEq 3 (add 1 2)\n\
+         testWrong = do println \"adding\" then return (assertEq 4 (add 1 2))\n\
+         testFalse = add 1 1 == 3\n\
+         helper = 1\n"
+    )?;
+    Command::cargo_bin("tego")?
+        .arg("test")
+        .arg(file.path())
+        .assert()
+        .code(1)
+        .stdout(
+            "test testAdd ... ok\n\
+             test testWrong ... FAILED\n\
+             test testFalse ... FAILED\n\
+             \n\
+             failures:\n\
+             \n\
+             ---- testWrong ----\n\
+             Error: Assertion failed: assertEq 4 (add 1 2)\n  expected: 4\n  found: 3\n\
+             output:\n\
+             adding\n\
+             \n\
+             ---- testFalse ----\n\
+             'testFalse' is false\n\
+             \n\
+             test result: FAILED. 1 passed; 2 failed\n",
+        );
+    Command::cargo_bin("tego")?
+        .args(["test", "--filter", "Add", "--jobs", "1"])
+        .arg(file.path())
+        .assert()
+        .success()
+        .stdout("test testAdd ... ok\n\ntest result: ok. 1 passed; 0 failed\n");
+    Ok(())
+}
+
+#[test]
+fn bench_test() -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = tempfile::NamedTempFile::new()?;
+    write!(
+        file,
+        "benchSum = 1 + 2\nbenchPrint = do println \"x\" then return 1\nbenchError = 1 / 0\n"
+    )?;
+    let output = Command::cargo_bin("tego")?
+        .args(["bench", "--warmup", "1", "--iterations", "5"])
+        .arg(file.path())
+        .output()?;
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout)?;
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 4, "{}", stdout);
+    // The benchmark's output isn't shown
+    for (line, name) in lines.iter().zip(["benchSum", "benchPrint"].iter()) {
+        assert!(
+            line.starts_with(&format!("bench {} ... mean ", name)),
+            "{}",
+            line
+        );
+        assert!(line.ends_with("(5 runs)"), "{}", line);
+    }
+    assert_eq!(
+        lines[2..],
+        [
+            "bench benchError ... FAILED",
+            "  Error: '1 / 0' divides by zero"
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn doc_test() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    fs::write(
+        dir.path().join("main.tgo"),
+        "import \"util.tgo\"\nmain = println (twice 2)\n",
+    )?;
+    fs::write(
+        dir.path().join("util.tgo"),
+        "-- Utilities\n\nexport (twice)\n\n-- Doubles `n`\ntwice n = n * 2\nhelper = 1\n",
+    )?;
+    Command::cargo_bin("tego")?
+        .current_dir(dir.path())
+        .args(["doc", "main.tgo", "--out", "pages"])
+        .assert()
+        .success();
+    assert_eq!(
+        fs::read_to_string(dir.path().join("pages/util.md"))?,
+        "# util\n\nUtilities\n\n## `twice`\n\n```\ntwice : Int -> Int\n```\n\nDoubles `n`\n"
+    );
+    assert_eq!(
+        fs::read_to_string(dir.path().join("pages/main.md"))?,
+        "# main\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn tokens_test() -> Result<(), Box<dyn std::error::Error>> {
+    assert_cmd::Command::cargo_bin("tego")?
+        .args(["tokens", "-"])
+        .write_stdin("main = \"a\" -- b\n")
+        .assert()
+        .success()
+        .stdout(
+            "1:1     identifier  \"main\"\n\
+             1:6     operator    \"=\"\n\
+             1:8     string      \"\\\"a\\\"\"\n\
+             1:12    comment     \"-- b\"\n\
+             1:16    newline     \"\\n\"\n",
+        );
+    Ok(())
+}
+
+#[test]
+fn explain_test() -> Result<(), Box<dyn std::error::Error>> {
+    let output = assert_cmd::Command::cargo_bin("tego")?
+        .args(["explain", "E0206"])
+        .output()?;
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout)?,
+        "E0206: A division by zero\n\n    -- Error\n    main = 1 / 0\n"
+    );
+    assert_cmd::Command::cargo_bin("tego")?
+        .args(["explain", "E9999"])
+        .assert()
+        .code(64)
+        .stderr("'E9999' isn't an error code (they look like 'E0007')\n");
+    Ok(())
+}
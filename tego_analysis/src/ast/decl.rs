@@ -1,6 +0,0 @@
-use crate::ast::{DeclId, ExprId};
-use std::rc::Rc;
-
-pub enum Decl {
-	Expression(String, ExprId)
-}
\ No newline at end of file
@@ -0,0 +1,167 @@
+// The names that could be written at a position in a file, for completion in
+// editors
+// The names in scope are the ones where the name being written is (which is a
+// name that doesn't refer to anything yet, as long as the file parses), or
+// just the file's declarations if there isn't a name there
+// If the file doesn't parse, the declarations are found by the lexer instead
+
+use crate::resolve::{self, BindingKind};
+use tego_parser::TokenKind;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum CompletionKind {
+    Decl,
+    Import,
+    // A name bound by `let`, `delay`, `match`, or `do`
+    Local,
+    Param,
+    Builtin,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Completion {
+    pub name: String,
+    pub kind: CompletionKind,
+}
+
+// The names that start with what's written before `offset` (in bytes), with
+// the innermost first and `builtins` last
+pub fn complete(source: &str, offset: usize, builtins: &[&str]) -> Vec<Completion> {
+    let before = source.get(..offset).unwrap_or(source);
+    let prefix_start = before.rfind(|c: char| !is_name_char(c)).map_or(0, |i| {
+        i + before[i..].chars().next().map_or(1, char::len_utf8)
+    });
+    let prefix = &before[prefix_start..];
+
+    let mut completions = match resolve::resolve(source) {
+        Ok(resolution) => {
+            // The declaration that `offset` is in starts before it
+            let decl = resolution.bindings.iter().rposition(|binding| {
+                binding.kind == BindingKind::Decl && binding.span.start <= offset
+            });
+            let scope = resolution
+                .references
+                .iter()
+                .rev()
+                .find(|reference| reference.decl == decl && reference.span.start <= offset)
+                .map(|reference| reference.scope.clone())
+                .unwrap_or_else(|| {
+                    (0..resolution.bindings.len())
+                        .filter(|&i| resolution.bindings[i].decl.is_none())
+                        .collect()
+                });
+            scope
+                .into_iter()
+                .rev()
+                .map(|i| {
+                    let binding = &resolution.bindings[i];
+                    let kind = match binding.kind {
+                        BindingKind::Decl => CompletionKind::Decl,
+                        BindingKind::Import => CompletionKind::Import,
+                        BindingKind::Param => CompletionKind::Param,
+                        BindingKind::Let
+                        | BindingKind::Delay
+                        | BindingKind::Match
+                        | BindingKind::Do => CompletionKind::Local,
+                    };
+                    Completion {
+                        name: binding.name.clone(),
+                        kind,
+                    }
+                })
+                .collect()
+        }
+        Err(_) => declared(source),
+    };
+    let mut builtins = builtins.to_vec();
+    builtins.sort_unstable();
+    completions.extend(builtins.into_iter().map(|name| Completion {
+        name: name.into(),
+        kind: CompletionKind::Builtin,
+    }));
+
+    let mut seen = vec![];
+    completions.retain(|completion| {
+        // Only the innermost of names that shadow each other is kept
+        if !completion.name.starts_with(prefix) || seen.contains(&completion.name) {
+            return false;
+        }
+        seen.push(completion.name.clone());
+        true
+    });
+    completions
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '\'' || c == '.'
+}
+
+// The identifiers at the start of a line (which are declarations, or their
+// signatures)
+fn declared(source: &str) -> Vec<Completion> {
+    let tokens = tego_parser::tokenize(source);
+    tokens
+        .iter()
+        .enumerate()
+        .filter(|&(i, token)| {
+            token.kind == TokenKind::Identifier
+                && token.span.column() == 1
+                && (i == 0 || tokens[i - 1].kind == TokenKind::Newline)
+        })
+        .map(|(_, token)| Completion {
+            name: token.span.to_str().into(),
+            kind: CompletionKind::Decl,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The completions where `|` is in `source`
+    fn names(source: &str) -> Vec<(String, CompletionKind)> {
+        let offset = source.find('|').unwrap();
+        let source = source.replacen('|', "", 1);
+        complete(&source, offset, &["println", "parseInt"])
+            .into_iter()
+            .map(|completion| (completion.name, completion.kind))
+            .collect()
+    }
+
+    fn names_of(completions: &[(&str, CompletionKind)]) -> Vec<(String, CompletionKind)> {
+        completions
+            .iter()
+            .map(|&(name, kind)| (name.into(), kind))
+            .collect()
+    }
+
+    basic_test! {
+        complete_test
+        names("import \"a.tgo\" (apply)\nadd a b = a + b\nmain = let abc = 1 in a|\n") => names_of(&[
+            ("abc", CompletionKind::Local),
+            ("add", CompletionKind::Decl),
+            ("apply", CompletionKind::Import),
+        ]);
+        names("f x = fn x -> p|\n") => names_of(&[
+            ("parseInt", CompletionKind::Builtin),
+            ("println", CompletionKind::Builtin),
+        ]);
+        // Shadowed names are only listed once
+        names("f x = fn x -> x|\n") => names_of(&[
+            ("x", CompletionKind::Param),
+        ]);
+        // Names in other declarations aren't in scope
+        names("f x = x\ng y = |y\n") => names_of(&[
+            ("y", CompletionKind::Param),
+            ("g", CompletionKind::Decl),
+            ("f", CompletionKind::Decl),
+            ("parseInt", CompletionKind::Builtin),
+            ("println", CompletionKind::Builtin),
+        ]);
+        // Code that doesn't parse still has its declarations
+        names("double n = n * 2\nmain = d|ouble (\n") => names_of(&[
+            ("double", CompletionKind::Decl),
+        ])
+    }
+}
@@ -0,0 +1,696 @@
+// Everything that's found by analyzing a program before it's run
+//
+// Errors stop the program from running, while warnings are only reported
+
+use crate::patterns;
+use crate::shadowing;
+use crate::spans::Place;
+use crate::types::deps::checked_free_vars;
+use crate::types::{self, MatchSite, Type, TypeError};
+use crate::unused;
+use std::fmt;
+use std::str::FromStr;
+use tego_parser::ast::{Decl, Match};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    // What kind of diagnostic it is (ex. `unused-local`), which doesn't change
+    // when the message does, for tools that read diagnostics
+    pub code: &'static str,
+    // The declaration that the diagnostic is about, and where it is in it
+    pub decl: String,
+    pub place: Place,
+    pub message: String,
+    // Ways to fix it, if there are any that are likely
+    pub suggestions: Vec<String>,
+}
+
+// How a lint that can be configured is reported
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Level {
+    Allow,
+    Warn,
+    Deny,
+}
+
+impl Level {
+    fn severity(self) -> Option<Severity> {
+        match self {
+            Level::Allow => None,
+            Level::Warn => Some(Severity::Warning),
+            Level::Deny => Some(Severity::Error),
+        }
+    }
+}
+
+impl FromStr for Level {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "allow" => Ok(Level::Allow),
+            "warn" => Ok(Level::Warn),
+            "deny" => Ok(Level::Deny),
+            _ => Err(format!(
+                "'{}' isn't a level, expected 'allow', 'warn', or 'deny'",
+                s
+            )),
+        }
+    }
+}
+
+// Which declarations are type checked before the program is run
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Typing {
+    Strict,
+    // Only declarations with a signature (see `types::infer_gradual`)
+    Gradual,
+}
+
+impl FromStr for Typing {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "strict" => Ok(Typing::Strict),
+            "gradual" => Ok(Typing::Gradual),
+            _ => Err(format!(
+                "'{}' isn't a typing mode, expected 'strict' or 'gradual'",
+                s
+            )),
+        }
+    }
+}
+
+// The warnings that can be configured, by their code, and the names of groups
+// of them
+const WARNINGS: [&str; 6] = [
+    "non-exhaustive-match",
+    "unreachable-pattern",
+    "unused-decl",
+    "unused-local",
+    "refutable-pattern",
+    "shadowing",
+];
+const WARNING_GROUPS: [(&str, &[&str]); 3] = [
+    ("all", &WARNINGS),
+    ("unused", &["unused-decl", "unused-local"]),
+    ("unreachable", &["unreachable-pattern"]),
+];
+
+// A `-W` flag, which changes how a warning, or a group of warnings, is reported
+// (ex. `-W no-unused` or `-W error=shadowing`)
+// Every warning is reported as a warning unless a flag changes it, and later
+// flags take precedence over earlier ones
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum WarningFlag {
+    // `-W unused`
+    Enable(&'static str),
+    // `-W no-unused`
+    Disable(&'static str),
+    // `-W error=unused`, or `-W error` for every warning
+    Error(&'static str),
+    // `-W no-error=unused`, or `-W no-error` for every warning
+    NoError(&'static str),
+}
+
+impl WarningFlag {
+    // Whether the flag is about the warning with `code`
+    fn includes(self, code: &str) -> bool {
+        let name = match self {
+            WarningFlag::Enable(name)
+            | WarningFlag::Disable(name)
+            | WarningFlag::Error(name)
+            | WarningFlag::NoError(name) => name,
+        };
+        name == code
+            || WARNING_GROUPS
+                .iter()
+                .any(|(group, codes)| *group == name && codes.contains(&code))
+    }
+}
+
+impl FromStr for WarningFlag {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (flag, name): (fn(&'static str) -> WarningFlag, &str) = match s {
+            "error" => return Ok(WarningFlag::Error("all")),
+            "no-error" => return Ok(WarningFlag::NoError("all")),
+            _ => match (s.strip_prefix("error="), s.strip_prefix("no-error=")) {
+                (Some(name), _) => (WarningFlag::Error, name),
+                (_, Some(name)) => (WarningFlag::NoError, name),
+                _ => match s.strip_prefix("no-") {
+                    Some(name) => (WarningFlag::Disable, name),
+                    None => (WarningFlag::Enable, s),
+                },
+            },
+        };
+        WARNINGS
+            .iter()
+            .chain(WARNING_GROUPS.iter().map(|(group, _)| group))
+            .find(|warning| **warning == name)
+            .map(|warning| flag(warning))
+            .ok_or_else(|| {
+                format!(
+                    "'{}' isn't a warning, expected one of 'all', 'unused', 'unreachable', or a warning's code (ex. 'shadowing')",
+                    name
+                )
+            })
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Config {
+    pub warnings: Vec<WarningFlag>,
+    pub typing: Typing,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            warnings: vec![],
+            typing: Typing::Strict,
+        }
+    }
+}
+
+impl Config {
+    // How the warning with `code` is reported
+    pub fn level(&self, code: &str) -> Level {
+        let (mut enabled, mut error) = (true, false);
+        for flag in self.warnings.iter().filter(|flag| flag.includes(code)) {
+            match flag {
+                WarningFlag::Enable(_) => enabled = true,
+                WarningFlag::Disable(_) => enabled = false,
+                WarningFlag::Error(_) => error = true,
+                WarningFlag::NoError(_) => error = false,
+            }
+        }
+        match (enabled, error) {
+            (false, _) => Level::Allow,
+            (true, false) => Level::Warn,
+            (true, true) => Level::Deny,
+        }
+    }
+
+    // The warning reported at its level, which is an error if it's denied
+    fn report(&self, diagnostic: Diagnostic) -> Option<Diagnostic> {
+        if diagnostic.severity != Severity::Warning {
+            return Some(diagnostic);
+        }
+        match self.level(diagnostic.code).severity()? {
+            Severity::Warning => Some(diagnostic),
+            Severity::Error => Some(Diagnostic {
+                severity: Severity::Error,
+                message: match diagnostic.message.strip_prefix("Warning") {
+                    Some(rest) => format!("Error{}", rest),
+                    None => diagnostic.message,
+                },
+                ..diagnostic
+            }),
+        }
+    }
+}
+
+// The number of each kind of diagnostic, which is written as `E0101` (see
+// `tego explain`), and never reused for a different kind
+// Parse errors are numbered from 1, and runtime errors from 201
+pub const NUMBERS: [(&str, u16); 17] = [
+    ("type-mismatch", 101),
+    ("unknown-type", 102),
+    ("type-args", 103),
+    ("too-general", 104),
+    ("unmatched-signature", 105),
+    ("duplicate-signature", 106),
+    ("unknown-class", 107),
+    ("constraint-var", 108),
+    ("no-instance", 109),
+    ("missing-constraint", 110),
+    ("non-exhaustive-match", 111),
+    ("unreachable-pattern", 112),
+    ("unused-decl", 113),
+    ("unused-local", 114),
+    ("refutable-pattern", 115),
+    ("shadowing", 116),
+    ("undeclared", 117),
+];
+
+impl Diagnostic {
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    pub fn number(&self) -> Option<u16> {
+        NUMBERS
+            .iter()
+            .find(|(code, _)| *code == self.code)
+            .map(|(_, number)| *number)
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+pub fn check(decls: &[Decl], config: &Config) -> Vec<Diagnostic> {
+    let inference = match config.typing {
+        Typing::Strict => types::infer(decls),
+        Typing::Gradual => types::infer_gradual(decls),
+    };
+    let mut diagnostics = inference
+        .errors
+        .iter()
+        .map(|error| Diagnostic {
+            severity: Severity::Error,
+            code: error.code(),
+            decl: error.decl().into(),
+            place: error.place(),
+            message: error.to_string(),
+            suggestions: match error {
+                TypeError::MissingConstraint { constraint, .. } => {
+                    vec![format!("Add '{}' to the signature", constraint)]
+                }
+                _ => vec![],
+            },
+        })
+        .collect::<Vec<_>>();
+    for site in &inference.matches {
+        if let Some((equations, type_)) = equations(site) {
+            let patterns = equations.iter().collect::<Vec<_>>();
+            if let Some(missing) = patterns::missing(&patterns, &type_) {
+                diagnostics.push(Diagnostic {
+                    severity: Severity::Warning,
+                    code: "non-exhaustive-match",
+                    decl: site.decl.clone(),
+                    place: Place::Decl,
+                    message: format!(
+                        "Warning in '{}': the equations of '{}' don't have a pattern for '{}'",
+                        site.decl, site.decl, missing
+                    ),
+                    suggestions: vec![format!("Add an equation for '{}'", missing)],
+                });
+            }
+            for i in patterns::unreachable(&patterns, &type_) {
+                let params = params_text(&equations[i]);
+                diagnostics.push(Diagnostic {
+                    severity: Severity::Warning,
+                    code: "unreachable-pattern",
+                    decl: site.decl.clone(),
+                    place: site.pattern_places[i],
+                    message: format!(
+                        "Warning in '{}': the equation for '{}' is never reached",
+                        site.decl, params
+                    ),
+                    suggestions: vec![format!("Remove the equation for '{}'", params)],
+                });
+            }
+            continue;
+        }
+        let patterns = site.patterns.iter().collect::<Vec<_>>();
+        if let Some(missing) = patterns::missing(&patterns, &site.type_) {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Warning,
+                code: "non-exhaustive-match",
+                decl: site.decl.clone(),
+                place: site.place,
+                message: format!(
+                    "Warning in '{}': 'match {} to' doesn't have a pattern for '{}'",
+                    site.decl, site.val, missing
+                ),
+                suggestions: vec![format!("Add a pattern for '{}'", missing)],
+            });
+        }
+        for i in patterns::unreachable(&patterns, &site.type_) {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Warning,
+                code: "unreachable-pattern",
+                decl: site.decl.clone(),
+                place: site.pattern_places[i],
+                message: format!(
+                    "Warning in '{}': the pattern '{}' in 'match {} to' is never reached",
+                    site.decl, site.patterns[i], site.val
+                ),
+                suggestions: vec![format!("Remove the pattern '{}'", site.patterns[i])],
+            });
+        }
+    }
+    for ident in unused::decls(decls) {
+        diagnostics.push(Diagnostic {
+            severity: Severity::Warning,
+            code: "unused-decl",
+            message: format!("Warning: '{}' is declared, but never used", ident),
+            suggestions: vec![unused_suggestion(&ident)],
+            decl: ident,
+            place: Place::Decl,
+        });
+    }
+    for decl in decls {
+        if let Decl::Expression(ident, body) = decl {
+            for (local, place) in unused::locals(body) {
+                diagnostics.push(Diagnostic {
+                    severity: Severity::Warning,
+                    code: "unused-local",
+                    decl: ident.clone(),
+                    place,
+                    message: format!("Warning in '{}': '{}' is never used", ident, local),
+                    suggestions: vec![unused_suggestion(&local)],
+                });
+            }
+        }
+    }
+    for decl in decls {
+        if let Decl::Expression(ident, body) = decl {
+            for (kind, pattern) in patterns::refutable_bindings(body) {
+                let pattern = match pattern {
+                    Match::Tuple(_) => format!("({})", pattern),
+                    pattern => pattern.to_string(),
+                };
+                diagnostics.push(Diagnostic {
+                    severity: Severity::Warning,
+                    code: "refutable-pattern",
+                    decl: ident.clone(),
+                    place: Place::Decl,
+                    message: format!(
+                        "Warning in '{}': the {} '{}' doesn't match every value, so it can fail when the program runs",
+                        ident, kind, pattern
+                    ),
+                    suggestions: vec![format!(
+                        "Use 'match' to handle the values that '{}' doesn't match",
+                        pattern
+                    )],
+                });
+            }
+        }
+    }
+    if config.level("shadowing") != Level::Allow {
+        for (decl, ident, place) in shadowing::shadowed(decls) {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Warning,
+                code: "shadowing",
+                message: format!(
+                    "Warning in '{}': '{}' shadows another binding with the same name",
+                    decl, ident
+                ),
+                suggestions: vec![format!("Rename one of the bindings called '{}'", ident)],
+                decl,
+                place,
+            });
+        }
+    }
+    diagnostics
+        .into_iter()
+        .filter_map(|diagnostic| config.report(diagnostic))
+        .collect()
+}
+
+// The parameters of a function declared by several equations are matched as
+// `match _0 to`, or `match [_0], [_1], ... to` (see `DeclOutput::clauses`), so
+// the patterns are unboxed to be each equation's parameters
+fn equations(site: &MatchSite) -> Option<(Vec<Match>, Type)> {
+    if site.val == "_0" {
+        return Some((site.patterns.clone(), site.type_.clone()));
+    }
+    if !site.val.starts_with("[_0], [_1]") {
+        return None;
+    }
+    let unboxed = |param: &Match| match param {
+        Match::Boxed(inner) => (**inner).clone(),
+        param => param.clone(),
+    };
+    let patterns = site
+        .patterns
+        .iter()
+        .map(|pattern| match pattern {
+            Match::Tuple(params) => Match::Tuple(params.iter().map(unboxed).collect()),
+            pattern => pattern.clone(),
+        })
+        .collect();
+    Some((patterns, Type::Any))
+}
+
+// An equation's parameters, as they're written
+fn params_text(pattern: &Match) -> String {
+    let param = |param: &Match| match param {
+        Match::Tuple(_) => format!("({})", param),
+        param => param.to_string(),
+    };
+    match pattern {
+        Match::Tuple(params) => params.iter().map(param).collect::<Vec<_>>().join(" "),
+        pattern => param(pattern),
+    }
+}
+
+// Names that start with `_` aren't reported when they're unused
+fn unused_suggestion(ident: &str) -> String {
+    format!("Rename it to '_{}' if it's unused on purpose", ident)
+}
+
+// Names that are used, but aren't declared anywhere (`is_declared` knows about
+// imports and builtins, which aren't in `decls`), and a declared name that's
+// `similar` to each one, if there is one
+// Like the type checker, the argument of `expectError` is skipped
+pub fn undeclared(
+    decls: &[Decl],
+    is_declared: impl Fn(&str) -> bool,
+    similar: impl Fn(&str) -> Option<String>,
+) -> Vec<Diagnostic> {
+    decls
+        .iter()
+        .filter_map(|decl| match decl {
+            Decl::Expression(ident, body) => Some((ident, body)),
+            _ => None,
+        })
+        .flat_map(|(ident, body)| {
+            checked_free_vars(body)
+                .into_iter()
+                .filter(|var| !is_declared(var))
+                .map(|var| Diagnostic {
+                    severity: Severity::Error,
+                    code: "undeclared",
+                    decl: ident.clone(),
+                    place: Place::Decl,
+                    message: format!("Error in '{}': '{}' isn't declared", ident, var),
+                    suggestions: similar(&var)
+                        .map(|similar| format!("Did you mean '{}'?", similar))
+                        .into_iter()
+                        .collect(),
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tego_parser::ast::Prog;
+    use tego_parser::Span;
+
+    fn messages(source: &str) -> Vec<String> {
+        messages_with(source, &Config::default())
+    }
+
+    fn messages_with(source: &str, config: &Config) -> Vec<String> {
+        match tego_parser::prog(Span::new(source)).unwrap().1 {
+            Prog::Binary(_, decls) | Prog::Library(decls) => check(&decls, config)
+                .iter()
+                .map(Diagnostic::to_string)
+                .collect(),
+        }
+    }
+
+    basic_test! {
+        exhaustiveness_test
+        messages("f b = match b to\n\t| true -> 1") =>
+            vec!["Warning in 'f': 'match b to' doesn't have a pattern for 'false'".to_string()];
+        messages("f n = match n + 1 to\n\t| 0 -> 1\n\t| 1 -> 2") =>
+            vec!["Warning in 'f': 'match n + 1 to' doesn't have a pattern for '_'".to_string()];
+        messages("f b = match [b] to\n\t| [true] -> 1\n\t| [false] -> 0") => Vec::<String>::new();
+        messages("f list = match list to\n\t| () -> 0\n\t| _, tail -> 1 + f tail") => Vec::<String>::new();
+        // Type errors are reported along with warnings
+        messages("a = 1 + true") =>
+            vec!["Type error in 'a': expected 'Int', found 'Bool' in 'true'".to_string()]
+    }
+
+    basic_test! {
+        equations_test
+        messages("isZero 0 = true\nisZero n = false") => vec!["Warning in 'isZero': 'n' is never used".to_string()];
+        messages("flip true = false") =>
+            vec!["Warning in 'flip': the parameter 'true' doesn't match every value, so it can fail when the program runs".to_string()];
+        messages("flip true = false\nflip true = true") =>
+            vec![
+                "Warning in 'flip': the equations of 'flip' don't have a pattern for 'false'".to_string(),
+                "Warning in 'flip': the equation for 'true' is never reached".to_string(),
+            ];
+        messages("zip () _ = ()\nzip _ () = ()\nzip (a, as) (b, bs) = ((a, b), zip as bs)") => Vec::<String>::new();
+        messages("f 0 _ = 0\nf _ 1 = 1") =>
+            vec!["Warning in 'f': the equations of 'f' don't have a pattern for '_'".to_string()];
+        messages("f _ _ = 0\nf (a, b) 1 = a + b") =>
+            vec!["Warning in 'f': the equation for '(a, b) 1' is never reached".to_string()]
+    }
+
+    basic_test! {
+        unreachable_test
+        messages("f n = match n to\n\t| _ -> 0\n\t| 1 -> 1") =>
+            vec!["Warning in 'f': the pattern '1' in 'match n to' is never reached".to_string()];
+        messages("f b = match b and true to\n\t| true -> 1\n\t| false -> 0\n\t| _ -> 2") =>
+            vec!["Warning in 'f': the pattern '_' in 'match b and true to' is never reached".to_string()]
+    }
+
+    basic_test! {
+        typing_test
+        messages_with("a = 1 + true", &Config { typing: Typing::Gradual, ..Config::default() }) =>
+            Vec::<String>::new();
+        "gradual".parse::<Typing>() => Ok(Typing::Gradual);
+        "loose".parse::<Typing>().is_err() => true
+    }
+
+    basic_test! {
+        unused_test
+        messages("main = f 1\nf a b = 1\ng = 2") => vec![
+            "Warning: 'g' is declared, but never used".to_string(),
+            "Warning in 'f': 'a' is never used".to_string(),
+            "Warning in 'f': 'b' is never used".to_string(),
+        ];
+        messages("main = f 1\nf _a = 1\n_g = 2") => Vec::<String>::new()
+    }
+
+    fn codes(source: &str) -> Vec<(&'static str, Vec<String>)> {
+        match tego_parser::prog(Span::new(source)).unwrap().1 {
+            Prog::Binary(_, decls) | Prog::Library(decls) => check(&decls, &Config::default())
+                .into_iter()
+                .map(|diagnostic| (diagnostic.code, diagnostic.suggestions))
+                .collect(),
+        }
+    }
+
+    fn numbers(source: &str) -> Vec<Option<u16>> {
+        match tego_parser::prog(Span::new(source)).unwrap().1 {
+            Prog::Binary(_, decls) | Prog::Library(decls) => check(&decls, &Config::default())
+                .iter()
+                .map(Diagnostic::number)
+                .collect(),
+        }
+    }
+
+    basic_test! {
+        number_test
+        numbers("main = 1\ng = 2") => vec![Some(113)];
+        numbers("main = 1 + true") => vec![Some(101)];
+        NUMBERS.iter().all(|(_, number)| NUMBERS.iter().filter(|(_, other)| other == number).count() == 1) => true
+    }
+
+    basic_test! {
+        code_test
+        codes("main = f true\nf b = match b to\n\t| true -> 1") =>
+            vec![("non-exhaustive-match", vec!["Add a pattern for 'false'".to_string()])];
+        codes("main = 1\ng = 2") =>
+            vec![("unused-decl", vec!["Rename it to '_g' if it's unused on purpose".to_string()])];
+        codes("main = 1 + true") => vec![("type-mismatch", vec![])]
+    }
+
+    basic_test! {
+        refutable_test
+        messages("main = f (1, 2)\nf (a, 1) = a") => vec![
+            "Warning in 'f': the parameter '(a, 1)' doesn't match every value, so it can fail when the program runs".to_string(),
+        ];
+        codes("main = let [a, 0] = [(1, 0)] in a") => vec![
+            ("refutable-pattern", vec!["Use 'match' to handle the values that '[a, 0]' doesn't match".to_string()]),
+        ];
+        messages("main = let [a] = [1] in match a to\n\t| 1 -> 1\n\t| _ -> 0") => Vec::<String>::new()
+    }
+
+    basic_test! {
+        undeclared_test
+        match tego_parser::prog(Span::new("main = f 1\nf a = g a b, expectError c\ng a = a")).unwrap().1 {
+            Prog::Binary(_, decls) | Prog::Library(decls) => undeclared(&decls, |ident| {
+                ["main", "f", "g", "expectError"].contains(&ident)
+            }, |_| None)
+            .iter()
+            .map(Diagnostic::to_string)
+            .collect::<Vec<_>>(),
+        } => vec!["Error in 'f': 'b' isn't declared".to_string()];
+        match tego_parser::prog(Span::new("main = f 1\nf a = gg a\ng a = a")).unwrap().1 {
+            Prog::Binary(_, decls) | Prog::Library(decls) => undeclared(&decls, |ident| {
+                ["main", "f", "g"].contains(&ident)
+            }, |_| Some("g".into()))
+            .into_iter()
+            .map(|diagnostic| diagnostic.suggestions)
+            .collect::<Vec<_>>(),
+        } => vec![vec!["Did you mean 'g'?".to_string()]]
+    }
+
+    basic_test! {
+        shadowing_test
+        messages("main = f 1\nf a = let a = 2 in a") => vec![
+            "Warning in 'f': 'a' is never used".to_string(),
+            "Warning in 'f': 'a' shadows another binding with the same name".to_string(),
+        ];
+        messages_with("main = f 1\nf a = let a = 2 in a", &Config { warnings: vec![WarningFlag::Disable("shadowing")], ..Config::default() }) =>
+            vec!["Warning in 'f': 'a' is never used".to_string()];
+        check(
+            &match tego_parser::prog(Span::new("main = f 1\nf _a = let _a = 2 in _a")).unwrap().1 {
+                Prog::Binary(_, decls) | Prog::Library(decls) => decls,
+            },
+            &Config { warnings: vec![WarningFlag::Error("shadowing")], ..Config::default() },
+        )
+        .iter()
+        .filter(|diagnostic| diagnostic.is_error())
+        .count() => 1;
+        "deny".parse::<Level>() => Ok(Level::Deny);
+        "error".parse::<Level>().is_err() => true
+    }
+
+    fn config(flags: &[&str]) -> Config {
+        Config {
+            warnings: flags.iter().map(|flag| flag.parse().unwrap()).collect(),
+            ..Config::default()
+        }
+    }
+
+    basic_test! {
+        warning_flag_test
+        "unused".parse::<WarningFlag>() => Ok(WarningFlag::Enable("unused"));
+        "no-shadowing".parse::<WarningFlag>() => Ok(WarningFlag::Disable("shadowing"));
+        "error".parse::<WarningFlag>() => Ok(WarningFlag::Error("all"));
+        "error=unreachable-pattern".parse::<WarningFlag>() => Ok(WarningFlag::Error("unreachable-pattern"));
+        "no-error=unused".parse::<WarningFlag>() => Ok(WarningFlag::NoError("unused"));
+        "no-type-mismatch".parse::<WarningFlag>().is_err() => true;
+        "error=".parse::<WarningFlag>().is_err() => true
+    }
+
+    basic_test! {
+        warning_level_test
+        config(&[]).level("unused-local") => Level::Warn;
+        config(&["no-unused"]).level("unused-local") => Level::Allow;
+        config(&["no-unused"]).level("shadowing") => Level::Warn;
+        config(&["no-all", "shadowing"]).level("shadowing") => Level::Warn;
+        config(&["no-all", "shadowing"]).level("unused-decl") => Level::Allow;
+        // Warnings that are disabled stay disabled with `-W error`
+        config(&["no-unused", "error"]).level("unused-decl") => Level::Allow;
+        config(&["error", "no-error=unreachable"]).level("unreachable-pattern") => Level::Warn;
+        config(&["error", "no-error=unreachable"]).level("non-exhaustive-match") => Level::Deny
+    }
+
+    basic_test! {
+        warnings_test
+        messages_with("main = f 1\nf a b = 1\ng = 2", &config(&["no-unused-local"])) =>
+            vec!["Warning: 'g' is declared, but never used".to_string()];
+        messages_with("main = f 1\nf a b = 1\ng = 2", &config(&["no-unused"])) => Vec::<String>::new();
+        messages_with("main = 1\ng = 2", &config(&["error"])) =>
+            vec!["Error: 'g' is declared, but never used".to_string()];
+        // Errors aren't warnings, so they can't be disabled
+        messages_with("main = 1 + true", &config(&["no-all"])) =>
+            vec!["Type error in 'main': expected 'Int', found 'Bool' in 'true'".to_string()]
+    }
+}
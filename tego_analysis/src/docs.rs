@@ -0,0 +1,176 @@
+// Documentation for a module, from the comments directly above its exported
+// declarations (see `tego doc`)
+//
+//     -- Checks if every value in `list` satisfies `pred`
+//     all pred list = ...
+//
+// Comments at the top of the file that are followed by an empty line
+// document the module itself
+
+use crate::types;
+use tego_parser::ast::Decl;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ModuleDoc {
+    pub doc: Option<String>,
+    pub items: Vec<ItemDoc>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ItemDoc {
+    pub name: String,
+    // The declaration's signature, or its inferred type if it doesn't have
+    // one (and it type checks)
+    pub type_: Option<String>,
+    pub doc: Option<String>,
+}
+
+// `decls` are the declarations parsed from `source`
+pub fn document(source: &str, decls: &[Decl]) -> ModuleDoc {
+    let lines = source.lines().collect::<Vec<_>>();
+    let inference = types::infer(decls);
+    let items = exported(decls)
+        .into_iter()
+        .map(|name| {
+            let signature = decls.iter().find_map(|decl| match decl {
+                Decl::Signature(ident, type_) if ident == name => Some(type_.to_string()),
+                _ => None,
+            });
+            let inferred = || {
+                if inference.errors.iter().any(|error| error.decl() == name) {
+                    return None;
+                }
+                inference
+                    .types
+                    .iter()
+                    .find(|(ident, _)| ident == name)
+                    .map(|(_, scheme)| scheme.to_string())
+            };
+            ItemDoc {
+                name: name.into(),
+                type_: signature.or_else(inferred),
+                doc: declared_at(&lines, name).and_then(|line| comment_above(&lines, line)),
+            }
+        })
+        .collect();
+    ModuleDoc {
+        doc: module_doc(&lines),
+        items,
+    }
+}
+
+// The same names that importing the module would (see `module::exports` in
+// the interpreter)
+fn exported(decls: &[Decl]) -> Vec<&str> {
+    let declared = decls
+        .iter()
+        .filter_map(|decl| match decl {
+            Decl::Expression(ident, _) if ident != "main" => Some(ident.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let exported = decls
+        .iter()
+        .filter_map(|decl| match decl {
+            Decl::Export(idents) => Some(idents),
+            _ => None,
+        })
+        .flatten()
+        .map(String::as_str)
+        .filter(|ident| declared.contains(ident))
+        .collect::<Vec<_>>();
+    if decls.iter().any(|decl| matches!(decl, Decl::Export(_))) {
+        exported
+    } else {
+        declared
+    }
+}
+
+// The first line that declares `name` (its signature, or its definition)
+fn declared_at(lines: &[&str], name: &str) -> Option<usize> {
+    lines.iter().position(|line| {
+        line.strip_prefix(name).is_some_and(|rest| {
+            rest.starts_with(|c: char| c.is_whitespace() || c == '=' || c == ':')
+        })
+    })
+}
+
+// The `--` comments on the lines directly above `line`
+fn comment_above(lines: &[&str], line: usize) -> Option<String> {
+    let start = lines[..line]
+        .iter()
+        .rposition(|line| !is_comment(line))
+        .map_or(0, |i| i + 1);
+    comment_text(&lines[start..line])
+}
+
+fn module_doc(lines: &[&str]) -> Option<String> {
+    let end = lines
+        .iter()
+        .position(|line| !is_comment(line))
+        .unwrap_or(lines.len());
+    // Otherwise the comment belongs to the first declaration
+    if lines.get(end).is_some_and(|line| !line.trim().is_empty()) {
+        return None;
+    }
+    comment_text(&lines[..end])
+}
+
+fn is_comment(line: &&str) -> bool {
+    line.starts_with("--")
+}
+
+fn comment_text(lines: &[&str]) -> Option<String> {
+    if lines.is_empty() {
+        return None;
+    }
+    Some(
+        lines
+            .iter()
+            .map(|line| {
+                let text = line.trim_start_matches("--");
+                text.strip_prefix(' ').unwrap_or(text).trim_end()
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tego_parser::ast::Prog;
+
+    fn document_source(source: &str) -> ModuleDoc {
+        let decls = match tego_parser::prog(source.into()).unwrap().1 {
+            Prog::Binary(_, decls) | Prog::Library(decls) => decls,
+        };
+        document(source, &decls)
+    }
+
+    fn item(name: &str, type_: Option<&str>, doc: Option<&str>) -> ItemDoc {
+        ItemDoc {
+            name: name.into(),
+            type_: type_.map(String::from),
+            doc: doc.map(String::from),
+        }
+    }
+
+    basic_test! {
+        document_test
+        document_source("-- Numbers\n-- (and more)\n\n-- Doubles `n`\ndouble n = n * 2\n\nid x = x\n") => ModuleDoc {
+            doc: Some("Numbers\n(and more)".into()),
+            items: vec![
+                item("double", Some("Int -> Int"), Some("Doubles `n`")),
+                item("id", Some("a -> a"), None),
+            ],
+        };
+        // A comment at the top that's attached to a declaration documents it
+        document_source("-- The answer\nanswer = 42\n").doc => None;
+        document_source("-- The answer\n-- Always 42\nanswer : Int\nanswer = 42\n").items => vec![
+            item("answer", Some("Int"), Some("The answer\nAlways 42")),
+        ];
+        document_source("export (a)\n-- A\na = 1\nb = 2\nmain = a\n").items => vec![item("a", Some("Int"), Some("A"))];
+        document_source("bad = 1 + \"a\"\n").items => vec![item("bad", None, None)]
+    }
+}
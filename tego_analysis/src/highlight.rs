@@ -0,0 +1,213 @@
+// Classifies each token in a file for syntax highlighting (for editors, and
+// external highlighters)
+// The lexer (see `tego_parser::tokenize`) decides what each token is, and
+// identifiers are refined by where they are: module names, the types in a
+// signature, and the names of functions declared in the file (if it parses)
+
+use tego_parser::ast::{Decl, Expr, Prog};
+use tego_parser::{Token, TokenKind};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum HighlightKind {
+    Keyword,
+    Function,
+    Variable,
+    // Module names (ex. `List` in `import List` and `List.map`)
+    Namespace,
+    Type,
+    Number,
+    String,
+    Char,
+    Boolean,
+    Operator,
+    Punctuation,
+    Comment,
+    Unknown,
+}
+
+// Positions are in characters, and lines and columns start at 1 (a tab is 4
+// columns wide)
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Highlight {
+    pub kind: HighlightKind,
+    pub line: usize,
+    pub column: usize,
+    pub offset: usize,
+    pub len: usize,
+}
+
+// The highlights are in the order they're found in the source, and newlines
+// aren't highlighted
+pub fn highlight(source: &str) -> Vec<Highlight> {
+    let functions = functions(source);
+    let tokens = tego_parser::tokenize(source);
+    let mut highlights = vec![];
+    let mut in_signature = false;
+    let mut in_import = false;
+    for (i, token) in tokens.iter().enumerate() {
+        let lexeme = token.span.to_str();
+        let starts_line = i == 0 || tokens[i - 1].kind == TokenKind::Newline;
+        if starts_line {
+            in_signature = token.kind == TokenKind::Identifier
+                && tokens
+                    .get(i + 1)
+                    .is_some_and(|next| next.span.to_str() == ":");
+            in_import = lexeme == "import" || lexeme == "export";
+        }
+        let kind = match token.kind {
+            TokenKind::Newline => continue,
+            TokenKind::Keyword if lexeme == "true" || lexeme == "false" => HighlightKind::Boolean,
+            TokenKind::Keyword => HighlightKind::Keyword,
+            // `as` is only a keyword in imports
+            TokenKind::Identifier if in_import && lexeme == "as" => HighlightKind::Keyword,
+            // `rec` is only a keyword between `let` and a name
+            TokenKind::Identifier
+                if lexeme == "rec"
+                    && i > 0
+                    && tokens[i - 1].span.to_str() == "let"
+                    && tokens
+                        .get(i + 1)
+                        .is_some_and(|next| next.kind == TokenKind::Identifier) =>
+            {
+                HighlightKind::Keyword
+            }
+            TokenKind::Identifier if in_import && lexeme.starts_with(char::is_uppercase) => {
+                HighlightKind::Namespace
+            }
+            TokenKind::Identifier if in_signature && !starts_line => HighlightKind::Type,
+            TokenKind::Identifier => match lexeme.find('.') {
+                // `List.map` is split into the module and the name
+                Some(dot) => {
+                    highlights.push(at(token, HighlightKind::Namespace, 0, dot));
+                    let name = dot + 1;
+                    highlights.push(at(
+                        token,
+                        HighlightKind::Variable,
+                        name,
+                        lexeme.len() - name,
+                    ));
+                    continue;
+                }
+                None if functions.iter().any(|function| function == lexeme) => {
+                    HighlightKind::Function
+                }
+                None => HighlightKind::Variable,
+            },
+            TokenKind::Number => HighlightKind::Number,
+            TokenKind::String => HighlightKind::String,
+            TokenKind::Char => HighlightKind::Char,
+            TokenKind::Operator => HighlightKind::Operator,
+            TokenKind::Punctuation => HighlightKind::Punctuation,
+            TokenKind::Comment => HighlightKind::Comment,
+            TokenKind::Unknown => HighlightKind::Unknown,
+        };
+        highlights.push(at(token, kind, 0, lexeme.len()));
+    }
+    highlights
+}
+
+// Part of a token, from `start` for `len` bytes (which are ASCII, since only
+// identifiers are split)
+fn at(token: &Token, kind: HighlightKind, start: usize, len: usize) -> Highlight {
+    let lexeme = token.span.to_str();
+    Highlight {
+        kind,
+        line: token.span.line(),
+        column: token.span.column() + start,
+        offset: token.span.offset() + start,
+        len: lexeme[start..start + len].chars().count(),
+    }
+}
+
+// The names of the functions declared in the file
+fn functions(source: &str) -> Vec<String> {
+    let decls = match tego_parser::prog(source.into()) {
+        Ok((_, Prog::Binary(_, decls))) | Ok((_, Prog::Library(decls))) => decls,
+        Err(_) => return vec![],
+    };
+    decls
+        .into_iter()
+        .filter_map(|decl| match decl {
+            Decl::Expression(name, Expr::Fn_(..)) => Some(name),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(source: &str) -> Vec<(HighlightKind, &str)> {
+        let chars = source.chars().collect::<Vec<_>>();
+        highlight(source)
+            .into_iter()
+            .map(|highlight| {
+                let start = chars[..highlight.offset]
+                    .iter()
+                    .map(|c| c.len_utf8())
+                    .sum::<usize>();
+                let len = chars[highlight.offset..highlight.offset + highlight.len]
+                    .iter()
+                    .map(|c| c.len_utf8())
+                    .sum::<usize>();
+                (highlight.kind, &source[start..start + len])
+            })
+            .collect()
+    }
+
+    basic_test! {
+        highlight_test
+        kinds("import List as L\ndouble : Int -> Int\ndouble n = List.sum (n, n) -- twice\nmain = double 2 == 4 or false") => vec![
+            (HighlightKind::Keyword, "import"),
+            (HighlightKind::Namespace, "List"),
+            (HighlightKind::Keyword, "as"),
+            (HighlightKind::Namespace, "L"),
+            (HighlightKind::Function, "double"),
+            (HighlightKind::Operator, ":"),
+            (HighlightKind::Type, "Int"),
+            (HighlightKind::Operator, "->"),
+            (HighlightKind::Type, "Int"),
+            (HighlightKind::Function, "double"),
+            (HighlightKind::Variable, "n"),
+            (HighlightKind::Operator, "="),
+            (HighlightKind::Namespace, "List"),
+            (HighlightKind::Variable, "sum"),
+            (HighlightKind::Punctuation, "("),
+            (HighlightKind::Variable, "n"),
+            (HighlightKind::Operator, ","),
+            (HighlightKind::Variable, "n"),
+            (HighlightKind::Punctuation, ")"),
+            (HighlightKind::Comment, "-- twice"),
+            (HighlightKind::Variable, "main"),
+            (HighlightKind::Operator, "="),
+            (HighlightKind::Function, "double"),
+            (HighlightKind::Number, "2"),
+            (HighlightKind::Operator, "=="),
+            (HighlightKind::Number, "4"),
+            (HighlightKind::Keyword, "or"),
+            (HighlightKind::Boolean, "false"),
+        ];
+        // `rec` is only a keyword after `let`
+        kinds("f rec = let rec g = rec in g") => vec![
+            (HighlightKind::Function, "f"),
+            (HighlightKind::Variable, "rec"),
+            (HighlightKind::Operator, "="),
+            (HighlightKind::Keyword, "let"),
+            (HighlightKind::Keyword, "rec"),
+            (HighlightKind::Variable, "g"),
+            (HighlightKind::Operator, "="),
+            (HighlightKind::Variable, "rec"),
+            (HighlightKind::Keyword, "in"),
+            (HighlightKind::Variable, "g"),
+        ];
+        // Code that doesn't parse is still highlighted
+        kinds("f x = \"é\" ~") => vec![
+            (HighlightKind::Variable, "f"),
+            (HighlightKind::Variable, "x"),
+            (HighlightKind::Operator, "="),
+            (HighlightKind::String, "\"é\""),
+            (HighlightKind::Unknown, "~"),
+        ]
+    }
+}
@@ -0,0 +1,146 @@
+// The type of the name at a position in a file, for tooling (ex. showing a
+// type when the cursor is over a name in an editor)
+// Declarations have their inferred type (or their signature), and every other
+// name has its type where it's bound or used (ex. `id` in `id 1` is
+// `Int -> Int`, even though the declaration is `a -> a`)
+// The types come from type inference, which goes through each declaration in
+// the same order as name resolution, so each name that's found by resolution
+// is matched with the type that inference gave it
+
+use crate::resolve::{self, BindingKind, Resolution};
+use crate::types::{self, Inference};
+use std::ops::Range;
+use tego_parser::ast::Prog;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Hover {
+    pub name: String,
+    pub type_: String,
+    // A byte range of the source
+    pub span: Range<usize>,
+}
+
+// The name at `offset` (in bytes), if there is one and the file parses
+pub fn hover(source: &str, offset: usize) -> Option<Hover> {
+    let resolution = resolve::resolve(source).ok()?;
+    let decls = match tego_parser::prog(source.into()) {
+        Ok((_, Prog::Binary(_, decls))) | Ok((_, Prog::Library(decls))) => decls,
+        Err(_) => return None,
+    };
+    let inference = types::infer(&decls);
+
+    if let Some(index) = resolution
+        .bindings
+        .iter()
+        .position(|binding| binding.span.contains(&offset))
+    {
+        let binding = &resolution.bindings[index];
+        let type_ = match binding.kind {
+            BindingKind::Decl => decl_type(&inference, &binding.name)?,
+            BindingKind::Import => return None,
+            _ => {
+                let decl = binding.decl?;
+                let nth = resolution.bindings[..index]
+                    .iter()
+                    .filter(|other| other.decl == Some(decl))
+                    .count();
+                site_type(&resolution, &inference, decl, &binding.name, true, nth)?
+            }
+        };
+        return Some(Hover {
+            name: binding.name.clone(),
+            type_,
+            span: binding.span.clone(),
+        });
+    }
+
+    let index = resolution
+        .references
+        .iter()
+        .position(|reference| reference.span.contains(&offset))?;
+    let reference = &resolution.references[index];
+    let name = source.get(reference.span.clone())?;
+    let type_ = match reference.decl {
+        Some(decl) => {
+            let nth = resolution.references[..index]
+                .iter()
+                .filter(|other| other.decl == Some(decl))
+                .count();
+            site_type(&resolution, &inference, decl, name, false, nth)?
+        }
+        // A name in a signature or an export list
+        None => {
+            let binding = &resolution.bindings[reference.binding?];
+            decl_type(&inference, &binding.name)?
+        }
+    };
+    Some(Hover {
+        name: name.into(),
+        type_,
+        span: reference.span.clone(),
+    })
+}
+
+fn decl_type(inference: &Inference, name: &str) -> Option<String> {
+    inference
+        .types
+        .iter()
+        .find(|(ident, _)| ident == name)
+        .map(|(_, scheme)| scheme.to_string())
+}
+
+// The type of the `nth` name that's bound (or used) in the declaration `decl`
+fn site_type(
+    resolution: &Resolution,
+    inference: &Inference,
+    decl: usize,
+    name: &str,
+    bound: bool,
+    nth: usize,
+) -> Option<String> {
+    let decl = &resolution.bindings[decl].name;
+    inference
+        .names
+        .iter()
+        .filter(|site| &site.decl == decl && site.bound == bound)
+        .nth(nth)
+        // In case the declaration wasn't inferred the same way
+        .filter(|site| site.name == name)
+        .map(|site| site.type_.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str =
+        "double n = n * 2\nmain = let xs = [double 1] in match xs to | [x] -> x == println\n";
+
+    // The type of the name that's at the start of `text` in `SOURCE`
+    fn type_at(text: &str) -> Option<String> {
+        let offset = SOURCE.find(text).unwrap();
+        hover(SOURCE, offset).map(|hover| hover.type_)
+    }
+
+    basic_test! {
+        hover_test
+        type_at("double n") => Some("Int -> Int".into());
+        type_at("n =") => Some("Int".into());
+        type_at("n * 2") => Some("Int".into());
+        type_at("xs =") => Some("[Int]".into());
+        type_at("double 1") => Some("Int -> Int".into());
+        type_at("xs to") => Some("[Int]".into());
+        type_at("x]") => Some("Int".into());
+        type_at("x ==") => Some("Int".into());
+        // Builtins can be anything
+        type_at("println") => Some("Any".into());
+        type_at("==") => None;
+        hover(SOURCE, 1) => Some(Hover { name: "double".into(), type_: "Int -> Int".into(), span: 0..6 });
+        hover("main = 1 +", 0) => None
+    }
+
+    basic_test! {
+        signature_hover_test
+        hover("id : a -> a\nid x = x\n", 0).map(|hover| hover.type_) => Some("a -> a".into())
+    }
+}
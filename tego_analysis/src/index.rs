@@ -0,0 +1,308 @@
+// An in-memory index of every module in a project, for tooling that looks
+// across files (ex. finding every use of a declaration, or searching for a
+// declaration by name)
+// Modules are added (and updated) with their source, so editors can index
+// files that haven't been saved. A module's imports are linked to the modules
+// in the index by their path, relative to the importing module like the
+// interpreter does (imports from the search path or the standard library
+// aren't linked unless they're in the index with that path).
+
+use crate::resolve::{self, BindingKind, Resolution};
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+use tego_parser::ast::{Decl, Prog};
+use tego_parser::ParseError;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Location {
+    pub path: PathBuf,
+    // A byte range of the module's source
+    pub span: Range<usize>,
+}
+
+// A declaration
+#[derive(Debug, PartialEq, Clone)]
+pub struct Symbol {
+    pub name: String,
+    pub location: Location,
+}
+
+#[derive(Debug, Default)]
+pub struct Index {
+    modules: Vec<Module>,
+}
+
+#[derive(Debug)]
+struct Module {
+    path: PathBuf,
+    resolution: Resolution,
+    source: String,
+    imports: Vec<Import>,
+}
+
+#[derive(Debug)]
+struct Import {
+    path: PathBuf,
+    namespace: Option<String>,
+    names: Option<Vec<String>>,
+}
+
+impl Index {
+    pub fn new() -> Self {
+        Index::default()
+    }
+
+    // Adds the module at `path`, or replaces it if it's already in the index
+    // A module that doesn't parse is removed until it does
+    pub fn update(&mut self, path: impl AsRef<Path>, source: String) -> Result<(), ParseError> {
+        let path = normalize(path.as_ref());
+        self.remove(&path);
+        let resolution = resolve::resolve(&source)?;
+        let decls = match tego_parser::prog(source.as_str().into()) {
+            Ok((_, Prog::Binary(_, decls))) | Ok((_, Prog::Library(decls))) => decls,
+            Err(error) => return Err(ParseError::from(error)),
+        };
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let imports = decls
+            .into_iter()
+            .filter_map(|decl| match decl {
+                Decl::Import(import, namespace, names, _) => Some(Import {
+                    path: normalize(&dir.join(import)),
+                    namespace,
+                    names,
+                }),
+                _ => None,
+            })
+            .collect();
+        self.modules.push(Module {
+            path,
+            resolution,
+            source,
+            imports,
+        });
+        Ok(())
+    }
+
+    pub fn remove(&mut self, path: impl AsRef<Path>) {
+        let path = normalize(path.as_ref());
+        self.modules.retain(|module| module.path != path);
+    }
+
+    // The declarations whose names contain `query` (ignoring case), in the
+    // order their modules were added
+    pub fn symbols(&self, query: &str) -> Vec<Symbol> {
+        let query = query.to_lowercase();
+        self.modules
+            .iter()
+            .flat_map(|module| {
+                module
+                    .resolution
+                    .bindings
+                    .iter()
+                    .filter(|binding| {
+                        binding.kind == BindingKind::Decl
+                            && binding.name.to_lowercase().contains(&query)
+                    })
+                    .map(move |binding| Symbol {
+                        name: binding.name.clone(),
+                        location: Location {
+                            path: module.path.clone(),
+                            span: binding.span.clone(),
+                        },
+                    })
+            })
+            .collect()
+    }
+
+    // Every use of the declaration that the name at `offset` (in bytes) in the
+    // module at `path` is, or refers to, in every module (including where
+    // it's imported by name)
+    pub fn references(&self, path: impl AsRef<Path>, offset: usize) -> Vec<Location> {
+        let (module, name) = match self.declaration_at(&normalize(path.as_ref()), offset) {
+            Some(declaration) => declaration,
+            None => return vec![],
+        };
+        let mut locations = vec![];
+        for other in &self.modules {
+            let resolution = &other.resolution;
+            let mut add = |span: Range<usize>| {
+                locations.push(Location {
+                    path: other.path.clone(),
+                    span,
+                })
+            };
+            if other.path == module.path {
+                if let Some(decl) = resolution.decl(name) {
+                    resolution
+                        .references_of(decl)
+                        .into_iter()
+                        .for_each(&mut add);
+                }
+                continue;
+            }
+            for import in other
+                .imports
+                .iter()
+                .filter(|import| import.path == module.path)
+            {
+                let qualified = match &import.namespace {
+                    Some(namespace) => format!("{}.{}", namespace, name),
+                    None => name.to_string(),
+                };
+                match &import.names {
+                    // Names in an import's list are bindings in the importing module
+                    Some(names) if names.iter().any(|imported| imported == name) => {
+                        let binding = resolution.bindings.iter().find(|binding| {
+                            binding.kind == BindingKind::Import && binding.name == qualified
+                        });
+                        if let Some(binding) = binding {
+                            add(binding.span.clone());
+                            resolution
+                                .references_of(binding)
+                                .into_iter()
+                                .for_each(&mut add);
+                        }
+                    }
+                    Some(_) => {}
+                    // Otherwise they're used without being declared (only the
+                    // name is included, not the module's name)
+                    None => {
+                        for reference in &resolution.references {
+                            if reference.binding.is_none()
+                                && other.source.get(reference.span.clone()) == Some(&qualified)
+                            {
+                                add(reference.span.end - name.len()..reference.span.end);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        locations
+    }
+
+    // The module that declares the name at `offset`, and the name
+    fn declaration_at(&self, path: &Path, offset: usize) -> Option<(&Module, &str)> {
+        let module = self.modules.iter().find(|module| module.path == path)?;
+        let resolution = &module.resolution;
+        let binding = match resolution.definition_of(offset) {
+            Some(binding) => binding,
+            // A name from an import without a list
+            None => {
+                let reference = resolution
+                    .references
+                    .iter()
+                    .find(|reference| reference.span.contains(&offset))?;
+                let text = module.source.get(reference.span.clone())?;
+                return module.imports.iter().find_map(|import| {
+                    let name = match &import.namespace {
+                        Some(namespace) => text.strip_prefix(namespace)?.strip_prefix('.')?,
+                        None => text,
+                    };
+                    self.declared_in(&import.path, name)
+                });
+            }
+        };
+        match binding.kind {
+            BindingKind::Decl => Some((module, binding.name.as_str())),
+            // The last part of a qualified name
+            BindingKind::Import => {
+                let name = binding.name.rsplit('.').next()?;
+                module
+                    .imports
+                    .iter()
+                    .filter(|import| {
+                        import
+                            .names
+                            .as_ref()
+                            .is_some_and(|names| names.iter().any(|imported| imported == name))
+                    })
+                    .find_map(|import| self.declared_in(&import.path, name))
+            }
+            _ => None,
+        }
+    }
+
+    fn declared_in<'a>(&'a self, path: &Path, name: &str) -> Option<(&'a Module, &'a str)> {
+        let module = self.modules.iter().find(|module| module.path == path)?;
+        let decl = module.resolution.decl(name)?;
+        Some((module, decl.name.as_str()))
+    }
+}
+
+// Removes `.` and `..` from a path without reading the file system, so
+// modules that haven't been saved can be found
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if normalized.file_name().is_some() => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index() -> Index {
+        let mut index = Index::new();
+        index
+            .update("p/math.tgo", "double n = n * 2\nhalf n = n / 2\n".into())
+            .unwrap();
+        index
+            .update(
+                "p/main.tgo",
+                "import \"math.tgo\" (double)\nmain = double (double 1)\n".into(),
+            )
+            .unwrap();
+        index
+            .update(
+                "p/sub/other.tgo",
+                "import \"../math.tgo\"\nimport \"./../math.tgo\" as M\nquad n = double (M.double n)\n".into(),
+            )
+            .unwrap();
+        index
+    }
+
+    // Each location as its path and the start of its span
+    fn starts(locations: Vec<Location>) -> Vec<(String, usize)> {
+        locations
+            .into_iter()
+            .map(|location| (location.path.display().to_string(), location.span.start))
+            .collect()
+    }
+
+    basic_test! {
+        references_test
+        starts(index().references("p/math.tgo", 0)) => vec![
+            ("p/main.tgo".to_string(), 19),
+            ("p/main.tgo".to_string(), 34),
+            ("p/main.tgo".to_string(), 42),
+            ("p/sub/other.tgo".to_string(), 58),
+            ("p/sub/other.tgo".to_string(), 68),
+        ];
+        // From a use in another module
+        index().references("p/sub/other.tgo", 69).len() => 5;
+        index().references("p/main.tgo", 35).len() => 5;
+        index().references("p/math.tgo", 17).is_empty() => true;
+        index().references("p/main.tgo", 28).is_empty() => true
+    }
+
+    basic_test! {
+        symbols_test
+        index().symbols("DOUB").into_iter().map(|symbol| symbol.location.path.display().to_string()).collect::<Vec<_>>() => vec!["p/math.tgo".to_string()];
+        index().symbols("").len() => 4;
+        {
+            let mut index = index();
+            index.remove("./p/math.tgo");
+            index.symbols("").into_iter().map(|symbol| symbol.name).collect::<Vec<_>>()
+        } => vec!["main".to_string(), "quad".to_string()];
+        Index::new().update("a.tgo", "main = (".into()).is_err() => true
+    }
+}
@@ -1,2 +1,25 @@
-mod ast;
-mod transform;
+#[allow(unused_macros)]
+macro_rules! basic_test {
+    ( $name:ident $( $actual:expr => $expected:expr );+) => {
+        #[allow(clippy::eq_op)]
+        #[test]
+        fn $name() {
+            $( assert_eq!($expected, $actual); )+
+        }
+    };
+}
+
+pub mod completion;
+pub mod diagnostics;
+pub mod docs;
+pub mod highlight;
+pub mod hover;
+pub mod index;
+pub mod message;
+pub mod patterns;
+pub mod rename;
+pub mod resolve;
+pub mod shadowing;
+pub mod spans;
+pub mod types;
+pub mod unused;
@@ -0,0 +1,258 @@
+// Errors and warnings from every part of loading and running a program
+// (parsing, analysis, imports, and runtime errors) as one kind of message, so
+// everything that shows them (ex. a terminal, `--message-format=json`, or an
+// editor) is given the same ones, through a `Sink`
+//
+// Diagnostics are given the span of the place in their declaration that
+// they're about (see `spans`)
+
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::spans::Spans;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use tego_parser::ParseError;
+
+// A place in a file (positions start at 1), and what to say about it
+#[derive(Debug, PartialEq, Clone)]
+pub struct Label {
+    pub line: usize,
+    pub column: usize,
+    // In `Char`s, and a parse error's isn't known
+    pub length: Option<usize>,
+    pub text: String,
+    // The line that the place is on, so it can be shown without the file
+    pub source_line: String,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Message {
+    pub severity: Severity,
+    // What kind of message it is (ex. `unused-decl`, or `parse-error`), for
+    // tools that read messages
+    pub code: &'static str,
+    // The number that `tego explain` explains, if it has one
+    pub number: Option<u16>,
+    pub text: String,
+    // What was being done when an error that isn't in the source happened (ex.
+    // `Error running file`), which people see before the text
+    pub context: Option<&'static str>,
+    // Where the source was read from, unless it was given on the command line
+    pub file: Option<PathBuf>,
+    // The first one is where the message is, if it's at a place in the file
+    pub labels: Vec<Label>,
+    // Ways to fix it
+    pub suggestions: Vec<String>,
+}
+
+// Where messages go (ex. stderr, or an editor)
+pub trait Sink {
+    fn emit(&mut self, message: &Message) -> io::Result<()>;
+}
+
+// Messages can be collected, to show them all at once
+impl Sink for Vec<Message> {
+    fn emit(&mut self, message: &Message) -> io::Result<()> {
+        self.push(message.clone());
+        Ok(())
+    }
+}
+
+impl Message {
+    // `diagnostics` were found in `source`
+    pub fn from_diagnostics(
+        source: &str,
+        file: Option<&Path>,
+        diagnostics: &[Diagnostic],
+    ) -> Vec<Message> {
+        let spans = Spans::of(source).ok();
+        diagnostics
+            .iter()
+            .map(|diagnostic| {
+                let label = spans
+                    .as_ref()
+                    .and_then(|spans| spans.span(&diagnostic.decl, diagnostic.place))
+                    .map(|span| label_of(source, span));
+                Message {
+                    severity: diagnostic.severity,
+                    code: diagnostic.code,
+                    number: diagnostic.number(),
+                    text: diagnostic.message.clone(),
+                    context: None,
+                    file: file.map(Path::to_owned),
+                    labels: label.into_iter().collect(),
+                    suggestions: diagnostic.suggestions.clone(),
+                }
+            })
+            .collect()
+    }
+
+    // A parse error in `source`, which is also about where a parenthesis or a
+    // bracket that isn't closed was opened
+    pub fn from_parse_error(source: &str, file: Option<&Path>, error: &ParseError) -> Message {
+        let mut labels = vec![label_at(
+            source,
+            error.line(),
+            error.column(),
+            "error found here",
+        )];
+        if let Some((line, column, opening)) = error.opening() {
+            let text = format!("opening {} found here", opening);
+            labels.push(label_at(source, line, column, &text));
+        }
+        Message {
+            severity: Severity::Error,
+            code: "parse-error",
+            number: Some(error.number()),
+            text: error.to_string(),
+            context: None,
+            file: file.map(Path::to_owned),
+            labels,
+            suggestions: vec![],
+        }
+    }
+
+    // An error that isn't at a place in the source (ex. a file that can't be
+    // read, or an error while a program is running)
+    pub fn error(
+        code: &'static str,
+        number: Option<u16>,
+        context: &'static str,
+        file: Option<&Path>,
+        error: &dyn std::fmt::Display,
+    ) -> Message {
+        Message {
+            severity: Severity::Error,
+            code,
+            number,
+            text: error.to_string(),
+            context: Some(context),
+            file: file.map(Path::to_owned),
+            labels: vec![],
+            suggestions: vec![],
+        }
+    }
+}
+
+fn source_line(source: &str, line: usize) -> String {
+    source.lines().nth(line - 1).unwrap_or("").into()
+}
+
+fn label_at(source: &str, line: usize, column: usize, text: &str) -> Label {
+    Label {
+        line,
+        column,
+        length: None,
+        text: text.into(),
+        source_line: source_line(source, line),
+    }
+}
+
+// `range` is a range of bytes in `source`
+// A span that's on more than one line (ex. a `match` and its arms) is only
+// underlined on its first line
+fn label_of(source: &str, range: Range<usize>) -> Label {
+    let before = &source[..range.start];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let line = before.matches('\n').count() + 1;
+    let text = source[range].split('\n').next().unwrap_or_default();
+    Label {
+        line,
+        column: before[line_start..].chars().count() + 1,
+        length: Some(text.chars().count()),
+        text: String::new(),
+        source_line: source_line(source, line),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::{self, Config};
+    use tego_parser::ast::Prog;
+    use tego_parser::Span;
+
+    fn position(label: &Label) -> (usize, usize, Option<usize>, &str) {
+        (label.line, label.column, label.length, &label.source_line)
+    }
+
+    basic_test! {
+        label_test
+        position(&label_of("a = 1\nbé = 2\n", 6..9)) => (2, 1, Some(2), "bé = 2");
+        position(&label_of("a = 1\nb = é + c\n", 15..16)) => (2, 9, Some(1), "b = é + c");
+        position(&label_of("a = if b\n\tthen 1\n\telse 2", 4..24)) => (1, 5, Some(4), "a = if b")
+    }
+
+    fn from_source(source: &str) -> Vec<Message> {
+        match tego_parser::prog(Span::new(source)) {
+            Ok((_, Prog::Binary(_, decls))) | Ok((_, Prog::Library(decls))) => {
+                let diagnostics = diagnostics::check(&decls, &Config::default());
+                Message::from_diagnostics(source, None, &diagnostics)
+            }
+            Err(error) => vec![Message::from_parse_error(source, None, &error.into())],
+        }
+    }
+
+    basic_test! {
+        message_test
+        from_source("main = 1\ng = 2")
+            .iter()
+            .map(|message| (message.code, message.number, message.labels.iter().map(position).collect()))
+            .collect::<Vec<_>>()
+            => vec![("unused-decl", Some(113), vec![(2, 1, Some(1), "g = 2")])];
+        // Type errors are at the expression, or the signature, that they're about
+        from_source("a = 1 + true\nb : Number\nb = 1")
+            .iter()
+            .map(|message| (message.code, message.number, message.labels.iter().map(position).collect()))
+            .collect::<Vec<_>>()
+            => vec![
+                ("unknown-type", Some(102), vec![(2, 1, Some(1), "b : Number")]),
+                ("type-mismatch", Some(101), vec![(1, 9, Some(4), "a = 1 + true")]),
+            ];
+        // Patterns are at the arm, and missing patterns at the `match`
+        from_source("main = 1\nexport (f, g)\nf a = match a to\n\t| 0 -> 1\n\t| 0 -> 2\ng 0 = 1\ng 0 = 2")
+            .iter()
+            .map(|message| (message.code, message.number, message.labels.iter().map(position).collect()))
+            .collect::<Vec<_>>()
+            => vec![
+                ("non-exhaustive-match", Some(111), vec![(3, 7, Some(10), "f a = match a to")]),
+                ("unreachable-pattern", Some(112), vec![(5, 4, Some(1), "\t| 0 -> 2")]),
+                ("non-exhaustive-match", Some(111), vec![(6, 1, Some(1), "g 0 = 1")]),
+                ("unreachable-pattern", Some(112), vec![(7, 3, Some(1), "g 0 = 2")]),
+            ];
+        // Unused and shadowing bindings are at the binding
+        from_source("main = 1\nexport (f)\nf a = catch (1 / 0) (fn (m, a) -> a)")
+            .iter()
+            .map(|message| (message.code, message.number, message.labels.iter().map(position).collect()))
+            .collect::<Vec<_>>()
+            => vec![
+                ("unused-local", Some(114), vec![(3, 3, Some(1), "f a = catch (1 / 0) (fn (m, a) -> a)")]),
+                ("unused-local", Some(114), vec![(3, 26, Some(1), "f a = catch (1 / 0) (fn (m, a) -> a)")]),
+                ("shadowing", Some(116), vec![(3, 29, Some(1), "f a = catch (1 / 0) (fn (m, a) -> a)")]),
+            ];
+        from_source("main = (1\n")
+            .iter()
+            .map(|message| (message.code, message.number, message.labels.iter().map(position).collect()))
+            .collect::<Vec<_>>()
+            => vec![("parse-error", Some(7), vec![(2, 1, None, ""), (1, 8, None, "main = (1")])]
+    }
+
+    basic_test! {
+        sink_test
+        {
+            let mut messages = vec![];
+            let message = Message::error("runtime-error", Some(206), "Error running file", None, &"'1 / 0' divides by zero");
+            messages.emit(&message).unwrap();
+            messages
+        } => vec![Message {
+            severity: Severity::Error,
+            code: "runtime-error",
+            number: Some(206),
+            text: "'1 / 0' divides by zero".into(),
+            context: Some("Error running file"),
+            file: None,
+            labels: vec![],
+            suggestions: vec![],
+        }]
+    }
+}
@@ -0,0 +1,199 @@
+// Checks whether the arms of a `match` cover every value of the scrutinee's
+// type, and whether every arm can be reached
+//
+// The check is conservative: the only types whose values can be listed are
+// `Bool` and boxed values, so for every other type (including tuples, which
+// have the type `Any`) the match needs a pattern that matches anything, other
+// than for optional values.
+
+use crate::types::Type;
+use tego_parser::ast::{Expr, Match, MatchVal};
+
+// A pattern for a value that none of the patterns match, if there is one
+pub fn missing(patterns: &[&Match], type_: &Type) -> Option<String> {
+    if patterns.iter().any(|pattern| irrefutable(pattern)) {
+        return None;
+    }
+    match type_ {
+        Type::Bool => [true, false]
+            .iter()
+            .find(|b| {
+                !patterns
+                    .iter()
+                    .any(|pattern| **pattern == Match::Value(MatchVal::Bool(**b)))
+            })
+            .map(|b| b.to_string()),
+        Type::Boxed(inner) => {
+            let inner_patterns = patterns
+                .iter()
+                .filter_map(|pattern| match pattern {
+                    Match::Boxed(inner) => Some(&**inner),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            missing(&inner_patterns, inner).map(|missing| format!("[{}]", missing))
+        }
+        // Optional values (ex. the result of `parseInt`) are either `[value]` or `()`,
+        // but they're tuples, so nothing else is known about them
+        Type::Any | Type::Var(_) if optional(patterns) => None,
+        _ => Some("_".into()),
+    }
+}
+
+// The arms that can't be reached, because the arms before them already match
+// everything that they do (ex. `n` before `0`, or `true` and `false` before `_`)
+pub fn unreachable(patterns: &[&Match], type_: &Type) -> Vec<usize> {
+    (1..patterns.len())
+        .filter(|&i| {
+            let earlier = &patterns[..i];
+            earlier.iter().any(|pattern| subsumes(pattern, patterns[i]))
+                || (known(type_) && missing(earlier, type_).is_none())
+        })
+        .collect()
+}
+
+// Whether every value that `later` matches is also matched by `pattern`
+fn subsumes(pattern: &Match, later: &Match) -> bool {
+    match (pattern, later) {
+        (pattern, _) if irrefutable(pattern) => true,
+        (Match::Boxed(a), Match::Boxed(b)) => subsumes(a, b),
+        (Match::Tuple(a), Match::Tuple(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| subsumes(a, b))
+        }
+        (Match::Value(a), Match::Value(b)) => a == b,
+        (Match::Unit, Match::Unit) => true,
+        _ => false,
+    }
+}
+
+// Types whose values are all known, so `missing` isn't just guessing
+fn known(type_: &Type) -> bool {
+    match type_ {
+        Type::Boxed(inner) => known(inner),
+        Type::Any | Type::Var(_) => false,
+        _ => true,
+    }
+}
+
+fn optional(patterns: &[&Match]) -> bool {
+    patterns.contains(&&Match::Unit)
+        && patterns.iter().any(|pattern| match pattern {
+            Match::Boxed(inner) => irrefutable(inner),
+            _ => false,
+        })
+}
+
+// A pattern that matches every value
+// A tuple pattern matches any value, as long as each of its parts does, since
+// missing parts are matched against `()`
+pub fn irrefutable(pattern: &Match) -> bool {
+    match pattern {
+        Match::Ident(_) | Match::Ignore => true,
+        Match::Tuple(patterns) => patterns.iter().all(irrefutable),
+        Match::Boxed(_) | Match::Value(_) | Match::Unit => false,
+    }
+}
+
+// The patterns of `let`s, parameters, and `do`s (with what kind of pattern
+// each one is) that some values of the right type don't match, which is an
+// error when the program runs, since only a `match` can try another pattern
+// Boxed patterns (ex. `let [a] = list in`) are how boxed values are unwrapped,
+// and the type checker makes sure that they're given one, so only literals
+// and `()` are reported
+pub fn refutable_bindings(expr: &Expr) -> Vec<(&'static str, &Match)> {
+    let mut found = vec![];
+    collect_refutable(expr, &mut found);
+    found
+}
+
+fn collect_refutable<'a>(expr: &'a Expr, found: &mut Vec<(&'static str, &'a Match)>) {
+    let mut binding = |kind, pattern: &'a Match| {
+        if has_literal(pattern) {
+            found.push((kind, pattern));
+        }
+    };
+    match expr {
+        Expr::Let(pattern, _, _) => binding("'let' pattern", pattern),
+        Expr::Fn_(pattern, _) => binding("parameter", pattern),
+        Expr::Do(_, pattern, _) => binding("'do' pattern", pattern),
+        _ => (),
+    }
+    match expr {
+        Expr::Variable(_) | Expr::Literal(_) => (),
+        Expr::Boxed(a) | Expr::Unary(_, a) | Expr::Fn_(_, a) => collect_refutable(a, found),
+        Expr::FnApp(a, b)
+        | Expr::Binary(a, _, b)
+        | Expr::Let(_, a, b)
+        | Expr::Do(a, _, b)
+        | Expr::Delayed(_, a, b) => {
+            collect_refutable(a, found);
+            collect_refutable(b, found);
+        }
+        Expr::If(cond, a, b) => {
+            collect_refutable(cond, found);
+            collect_refutable(a, found);
+            collect_refutable(b, found);
+        }
+        Expr::Match(val, arms) => {
+            collect_refutable(val, found);
+            for (_, body) in arms {
+                collect_refutable(body, found);
+            }
+        }
+    }
+}
+
+fn has_literal(pattern: &Match) -> bool {
+    match pattern {
+        Match::Ident(_) | Match::Ignore => false,
+        Match::Tuple(patterns) => patterns.iter().any(has_literal),
+        Match::Boxed(inner) => has_literal(inner),
+        Match::Value(_) | Match::Unit => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tego_parser::MatchOutput;
+
+    fn check(patterns: Vec<Match>, type_: Type) -> Option<String> {
+        missing(&patterns.iter().collect::<Vec<_>>(), &type_)
+    }
+
+    basic_test! {
+        missing_test
+        check(vec![Match::bool(true)], Type::Bool) => Some("false".into());
+        check(vec![Match::bool(false), Match::bool(true)], Type::Bool) => None;
+        check(vec![Match::int(0), Match::int(1)], Type::Int) => Some("_".into());
+        check(vec![Match::int(0), Match::ident("n")], Type::Int) => None;
+        check(vec![Match::unit(), Match::tuple(Match::ident("h"), Match::ident("t"))], Type::Any) => None;
+        check(vec![Match::unit(), Match::tuple(Match::ident("h"), Match::unit())], Type::Any) => Some("_".into());
+        check(vec![Match::boxed(Match::ident("n")), Match::unit()], Type::Any) => None;
+        check(vec![Match::boxed(Match::int(1)), Match::unit()], Type::Any) => Some("_".into());
+        check(vec![Match::boxed(Match::bool(true))], Type::boxed(Type::Bool)) => Some("[false]".into());
+        check(vec![Match::boxed(Match::ignore())], Type::boxed(Type::Int)) => None
+    }
+
+    fn check_unreachable(patterns: Vec<Match>, type_: Type) -> Vec<usize> {
+        unreachable(&patterns.iter().collect::<Vec<_>>(), &type_)
+    }
+
+    basic_test! {
+        unreachable_test
+        check_unreachable(vec![Match::ident("n"), Match::int(0)], Type::Int) => vec![1];
+        check_unreachable(vec![Match::int(0), Match::int(1), Match::int(0)], Type::Int) => vec![2];
+        check_unreachable(vec![Match::bool(true), Match::bool(false), Match::ignore()], Type::Bool) => vec![2];
+        check_unreachable(vec![Match::boxed(Match::ignore()), Match::boxed(Match::int(1))], Type::Any) => vec![1];
+        check_unreachable(
+            vec![Match::tuple(Match::int(1), Match::ident("t")), Match::tuple(Match::int(1), Match::unit())],
+            Type::Any
+        ) => vec![1];
+        check_unreachable(vec![Match::int(0), Match::ident("n")], Type::Int) => Vec::<usize>::new();
+        // Optional values might be neither `()` nor a boxed value
+        check_unreachable(
+            vec![Match::unit(), Match::boxed(Match::ident("a")), Match::tuple(Match::ident("h"), Match::ident("t"))],
+            Type::Any
+        ) => Vec::<usize>::new()
+    }
+}
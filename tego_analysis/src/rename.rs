@@ -0,0 +1,194 @@
+// Renames a declaration or a local binding everywhere it's used (including
+// signatures and export lists), for tooling
+// A rename is refused if it would change what any name refers to, or make a
+// binding shadow another one (see `shadowing`), since then the program
+// wouldn't mean the same thing anymore
+
+use crate::resolve::{self, BindingKind};
+use crate::shadowing;
+use std::fmt;
+use std::ops::Range;
+use tego_parser::ast::{Decl, Prog};
+use tego_parser::TokenKind;
+
+// Replaces a range of bytes in the source with `text`
+#[derive(Debug, PartialEq, Clone)]
+pub struct Edit {
+    pub span: Range<usize>,
+    pub text: String,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum RenameError {
+    // The file has to parse to find every use of the name
+    Parse,
+    // There isn't a name at the position that's declared in the file
+    NotFound,
+    // Names from an import are declared in another module
+    Imported(String),
+    Main,
+    Keyword(String),
+    InvalidName(String),
+    // Another binding with the same name would be used instead, or the
+    // renamed binding would be used instead of another one
+    Conflict { name: String, new_name: String },
+    Shadowing { name: String, new_name: String },
+}
+
+impl fmt::Display for RenameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RenameError::Parse => write!(f, "Can't rename in a file that doesn't parse"),
+            RenameError::NotFound => write!(f, "There isn't a name here that can be renamed"),
+            RenameError::Imported(name) => write!(
+                f,
+                "'{}' is imported, so it has to be renamed in the module that declares it",
+                name
+            ),
+            RenameError::Main => write!(f, "'main' can't be renamed, since it's where the program starts"),
+            RenameError::Keyword(name) => write!(f, "'{}' is a keyword", name),
+            RenameError::InvalidName(name) => write!(f, "'{}' isn't a valid name", name),
+            RenameError::Conflict { name, new_name } => write!(
+                f,
+                "Renaming '{}' to '{}' would change what a name refers to, since '{}' is already declared",
+                name, new_name, new_name
+            ),
+            RenameError::Shadowing { name, new_name } => write!(
+                f,
+                "Renaming '{}' to '{}' would make it shadow another binding (or be shadowed)",
+                name, new_name
+            ),
+        }
+    }
+}
+
+// The edits that rename the name at `offset` (in bytes) to `new_name`, in the
+// order they're found in the source
+pub fn rename(source: &str, offset: usize, new_name: &str) -> Result<Vec<Edit>, RenameError> {
+    let resolution = resolve::resolve(source).map_err(|_| RenameError::Parse)?;
+    let binding = resolution
+        .definition_of(offset)
+        .ok_or(RenameError::NotFound)?;
+    match binding.kind {
+        BindingKind::Import => return Err(RenameError::Imported(binding.name.clone())),
+        BindingKind::Decl if binding.name == "main" => return Err(RenameError::Main),
+        _ => {}
+    }
+    let tokens = tego_parser::tokenize(new_name);
+    match tokens.as_slice() {
+        [token] if token.kind == TokenKind::Keyword => {
+            return Err(RenameError::Keyword(new_name.into()))
+        }
+        [token] if token.kind == TokenKind::Identifier && !new_name.contains('.') => {}
+        _ => return Err(RenameError::InvalidName(new_name.into())),
+    }
+    if new_name == binding.name {
+        return Ok(vec![]);
+    }
+
+    let mut edits = std::iter::once(binding.span.clone())
+        .chain(resolution.references_of(binding))
+        .map(|span| Edit {
+            span,
+            text: new_name.into(),
+        })
+        .collect::<Vec<_>>();
+    edits.sort_by_key(|edit| edit.span.start);
+
+    // The renamed program has to resolve the same way, and not shadow more
+    let renamed = apply(source, &edits);
+    let conflict = || RenameError::Conflict {
+        name: binding.name.clone(),
+        new_name: new_name.into(),
+    };
+    let after = resolve::resolve(&renamed).map_err(|_| conflict())?;
+    let same_references = resolution
+        .references
+        .iter()
+        .zip(&after.references)
+        .all(|(before, after)| before.binding == after.binding);
+    let duplicate_decl = binding.decl.is_none()
+        && resolution
+            .bindings
+            .iter()
+            .any(|other| other.decl.is_none() && other.name == new_name);
+    if !same_references || duplicate_decl {
+        return Err(conflict());
+    }
+    let shadows = |source: &str| {
+        decls(source)
+            .map(|decls| {
+                shadowing::shadowed(&decls)
+                    .into_iter()
+                    .filter(|(_, ident, _)| ident == new_name)
+                    .count()
+            })
+            .unwrap_or(0)
+    };
+    if shadows(&renamed) > shadows(source) {
+        return Err(RenameError::Shadowing {
+            name: binding.name.clone(),
+            new_name: new_name.into(),
+        });
+    }
+    Ok(edits)
+}
+
+// `edits` can't overlap, and have to be in order
+pub fn apply(source: &str, edits: &[Edit]) -> String {
+    let mut result = String::new();
+    let mut end = 0;
+    for edit in edits {
+        result.push_str(&source[end..edit.span.start]);
+        result.push_str(&edit.text);
+        end = edit.span.end;
+    }
+    result.push_str(&source[end..]);
+    result
+}
+
+fn decls(source: &str) -> Option<Vec<Decl>> {
+    match tego_parser::prog(source.into()) {
+        Ok((_, Prog::Binary(_, decls))) | Ok((_, Prog::Library(decls))) => Some(decls),
+        Err(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Renames the name that's at the start of `at` in `source`
+    fn renamed(source: &str, at: &str, new_name: &str) -> Result<String, RenameError> {
+        let offset = source.find(at).unwrap();
+        rename(source, offset, new_name).map(|edits| apply(source, &edits))
+    }
+
+    const SOURCE: &str = "export (double)\ndouble : Int -> Int\ndouble n = n * 2\nmain = let x = double 1 in x + 1\n";
+
+    basic_test! {
+        rename_test
+        renamed(SOURCE, "double 1", "twice") => Ok("export (twice)\ntwice : Int -> Int\ntwice n = n * 2\nmain = let x = twice 1 in x + 1\n".into());
+        renamed(SOURCE, "n =", "m") => Ok("export (double)\ndouble : Int -> Int\ndouble m = m * 2\nmain = let x = double 1 in x + 1\n".into());
+        renamed(SOURCE, "x +", "y") => Ok("export (double)\ndouble : Int -> Int\ndouble n = n * 2\nmain = let y = double 1 in y + 1\n".into());
+        // Every equation of a function is renamed
+        renamed("fact 0 = 1\nfact n = n * fact (n - 1)\n", "fact n", "factorial")
+            => Ok("factorial 0 = 1\nfactorial n = n * factorial (n - 1)\n".into())
+    }
+
+    basic_test! {
+        rename_error_test
+        renamed(SOURCE, "main", "start") => Err(RenameError::Main);
+        renamed(SOURCE, "x =", "then") => Err(RenameError::Keyword("then".into()));
+        renamed(SOURCE, "x =", "A.b") => Err(RenameError::InvalidName("A.b".into()));
+        renamed(SOURCE, "x =", "x y") => Err(RenameError::InvalidName("x y".into()));
+        renamed("main = println 1\n", "println", "p") => Err(RenameError::NotFound);
+        renamed(SOURCE, "x =", "double") => Err(RenameError::Shadowing { name: "x".into(), new_name: "double".into() });
+        renamed(SOURCE, "double n", "main") => Err(RenameError::Conflict { name: "double".into(), new_name: "main".into() });
+        renamed("import \"a.tgo\" (a)\nmain = a\n", "a\n", "b") => Err(RenameError::Imported("a".into()));
+        // `x` in the body would refer to the parameter instead
+        renamed("f x = fn y -> x + y\nmain = f 1 2\n", "x =", "y") => Err(RenameError::Conflict { name: "x".into(), new_name: "y".into() });
+        renamed("f x = let y = 1 in y\nmain = f 1\n", "y =", "x") => Err(RenameError::Shadowing { name: "y".into(), new_name: "x".into() });
+        renamed("main = (", "main", "a") => Err(RenameError::Parse)
+    }
+}
@@ -0,0 +1,524 @@
+// Finds what every name in a file refers to, for tooling (ex. going to a
+// definition, or finding everywhere a declaration is used)
+//
+//     double n = n * 2
+//     main = double 21
+//
+// Both `n`s after `=` refer to the parameter, and `double` in `main` refers to
+// the declaration. Names that aren't declared in the file (builtins, or names
+// from a qualified import like `List.map`) don't refer to a binding.
+//
+// The file is parsed into its own tree (see the output traits in the parser),
+// where each name is a slice of the source, so it knows where every name is.
+
+use std::ops::Range;
+use tego_parser::{Clause, DeclOutput, ExprOutput, MatchOutput, ParseError, ProgOutput};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum BindingKind {
+    Decl,
+    // A name in an import's list (ex. `a` in `import "a.tgo" (a)`)
+    Import,
+    Let,
+    Delay,
+    // A function's parameter (declarations with parameters are functions)
+    Param,
+    // A name in one of a `match` expression's patterns
+    Match,
+    // The result of a Command (ex. `x` in `do readLine in x then ...`)
+    Do,
+}
+
+// Spans are byte ranges of the source
+#[derive(Debug, PartialEq, Clone)]
+pub struct Binding {
+    pub name: String,
+    pub kind: BindingKind,
+    pub span: Range<usize>,
+    // The declaration that it's in (an index into `Resolution::bindings`),
+    // unless it's at the top level
+    pub decl: Option<usize>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Reference {
+    pub span: Range<usize>,
+    // An index into `Resolution::bindings`, unless it isn't declared in the file
+    pub binding: Option<usize>,
+    // The same as `Binding::decl`
+    pub decl: Option<usize>,
+    // The bindings that are in scope where it is, innermost last
+    pub scope: Vec<usize>,
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Resolution {
+    // In the order they're found in the source
+    pub bindings: Vec<Binding>,
+    pub references: Vec<Reference>,
+}
+
+impl Resolution {
+    // The binding that the name at `offset` refers to, or is
+    pub fn definition_of(&self, offset: usize) -> Option<&Binding> {
+        if let Some(binding) = self
+            .bindings
+            .iter()
+            .find(|binding| binding.span.contains(&offset))
+        {
+            return Some(binding);
+        }
+        self.references
+            .iter()
+            .find(|reference| reference.span.contains(&offset))
+            .and_then(|reference| reference.binding)
+            .map(|binding| &self.bindings[binding])
+    }
+
+    // Everywhere `binding` is used, not including the binding itself
+    pub fn references_of(&self, binding: &Binding) -> Vec<Range<usize>> {
+        let index = self.bindings.iter().position(|other| other == binding);
+        self.references
+            .iter()
+            .filter(|reference| index.is_some() && reference.binding == index)
+            .map(|reference| reference.span.clone())
+            .collect()
+    }
+
+    // The top level declaration called `name`
+    pub fn decl(&self, name: &str) -> Option<&Binding> {
+        self.bindings
+            .iter()
+            .find(|binding| binding.kind == BindingKind::Decl && binding.name == name)
+    }
+}
+
+pub fn resolve(source: &str) -> Result<Resolution, ParseError> {
+    let decls = match tego_parser::prog::<Prog>(source.into()) {
+        Ok((_, prog)) => prog.0,
+        Err(error) => return Err(ParseError::from(error)),
+    };
+    let mut resolver = Resolver {
+        source,
+        resolution: Resolution::default(),
+        scope: vec![],
+        decl: None,
+    };
+    // Every declaration can use every other one
+    for decl in &decls {
+        match decl {
+            Decl::Expression(name, _) => resolver.bind(name, BindingKind::Decl),
+            Decl::Import(names) => {
+                for name in names {
+                    resolver.bind(name, BindingKind::Import);
+                }
+            }
+            Decl::Names(_) | Decl::Other => {}
+        }
+    }
+    let mut bound = 0;
+    for decl in decls {
+        match decl {
+            Decl::Expression(_, body) => {
+                resolver.decl = Some(bound);
+                resolver.resolve(body);
+                resolver.decl = None;
+                bound += 1;
+            }
+            // Signatures and export lists refer to declarations
+            Decl::Names(names) => {
+                for name in names {
+                    resolver.resolve(Node::Var(name));
+                }
+            }
+            Decl::Import(names) => bound += names.len(),
+            Decl::Other => {}
+        }
+    }
+    Ok(resolver.resolution)
+}
+
+struct Resolver<'a> {
+    source: &'a str,
+    resolution: Resolution,
+    // The bindings that are in scope, innermost last
+    scope: Vec<usize>,
+    // The declaration that's being resolved
+    decl: Option<usize>,
+}
+
+impl Resolver<'_> {
+    fn bind(&mut self, name: &Name, kind: BindingKind) {
+        self.resolution.bindings.push(Binding {
+            name: name.name.clone(),
+            kind,
+            span: name.span(self.source),
+            decl: self.decl,
+        });
+        self.scope.push(self.resolution.bindings.len() - 1);
+    }
+
+    fn resolve(&mut self, node: Node) {
+        match node {
+            Node::Var(name) => {
+                let bindings = &self.resolution.bindings;
+                let binding = self
+                    .scope
+                    .iter()
+                    .rev()
+                    .find(|binding| bindings[**binding].name == name.name)
+                    .copied();
+                self.resolution.references.push(Reference {
+                    span: name.span(self.source),
+                    binding,
+                    decl: self.decl,
+                    scope: self.scope.clone(),
+                });
+            }
+            Node::Seq(nodes) => nodes.into_iter().for_each(|node| self.resolve(node)),
+            Node::Bind(kind, names, body) => {
+                let depth = self.scope.len();
+                for name in &names {
+                    self.bind(name, kind);
+                }
+                self.resolve(*body);
+                self.scope.truncate(depth);
+            }
+        }
+    }
+}
+
+// A name, and where its text is in memory (which is in the source that was
+// parsed, so it can be turned into a span)
+#[derive(Debug, Clone)]
+pub(crate) struct Name {
+    pub(crate) name: String,
+    address: usize,
+    // The length of its text (which is shorter than the name if it's qualified)
+    len: usize,
+}
+
+impl Name {
+    pub(crate) fn new(name: &str) -> Self {
+        Name {
+            name: name.into(),
+            address: name.as_ptr() as usize,
+            len: name.len(),
+        }
+    }
+
+    pub(crate) fn span(&self, source: &str) -> Range<usize> {
+        let start = self.address.saturating_sub(source.as_ptr() as usize);
+        start..start + self.len
+    }
+}
+
+// Only what matters for scoping is kept
+#[derive(Debug, Clone)]
+enum Node {
+    Var(Name),
+    // Evaluated in the same scope
+    Seq(Vec<Node>),
+    // The names are only in scope in the body
+    Bind(BindingKind, Vec<Name>, Box<Node>),
+}
+
+impl Node {
+    fn empty() -> Self {
+        Node::Seq(vec![])
+    }
+
+    fn bind(kind: BindingKind, pattern: Pattern, body: Node) -> Self {
+        Node::Bind(kind, pattern.0, Box::new(body))
+    }
+}
+
+// The names a pattern binds
+#[derive(Debug, Clone, Default)]
+struct Pattern(Vec<Name>);
+
+impl MatchOutput for Pattern {
+    fn tuple(mut a: Self, b: Self) -> Self {
+        a.0.extend(b.0);
+        a
+    }
+    fn unit() -> Self {
+        Pattern::default()
+    }
+    fn boxed(a: Self) -> Self {
+        a
+    }
+    fn ignore() -> Self {
+        Pattern::default()
+    }
+    fn bool(_: bool) -> Self {
+        Pattern::default()
+    }
+    fn int(_: i32) -> Self {
+        Pattern::default()
+    }
+    fn ident(s: &str) -> Self {
+        Pattern(vec![Name::new(s)])
+    }
+    fn string(_: &str) -> Self {
+        Pattern::default()
+    }
+    fn char(_: char) -> Self {
+        Pattern::default()
+    }
+}
+
+impl ExprOutput for Node {
+    type Match = Pattern;
+
+    fn binary(a: Self, _: &str, b: Self) -> Self {
+        Node::Seq(vec![a, b])
+    }
+    fn unary(_: &str, a: Self) -> Self {
+        a
+    }
+    fn let_expr(pattern: Pattern, value: Self, body: Self) -> Self {
+        Node::Seq(vec![value, Node::bind(BindingKind::Let, pattern, body)])
+    }
+    // The value can refer to the name it's bound to
+    fn delayed(pattern: Pattern, value: Self, body: Self) -> Self {
+        Node::bind(BindingKind::Delay, pattern, Node::Seq(vec![value, body]))
+    }
+    fn if_expr(cond: Self, t: Self, f: Self) -> Self {
+        Node::Seq(vec![cond, t, f])
+    }
+    fn match_(val: Self, patterns: Vec<(Pattern, Self)>) -> Self {
+        let arms = patterns
+            .into_iter()
+            .map(|(pattern, body)| Node::bind(BindingKind::Match, pattern, body));
+        Node::Seq(std::iter::once(val).chain(arms).collect())
+    }
+    fn fn_expr(param: Pattern, body: Self) -> Self {
+        Node::bind(BindingKind::Param, param, body)
+    }
+    fn fn_app(function: Self, arg: Self) -> Self {
+        Node::Seq(vec![function, arg])
+    }
+    fn unit() -> Self {
+        Node::empty()
+    }
+    fn boxed(inner: Self) -> Self {
+        inner
+    }
+    fn bool(_: bool) -> Self {
+        Node::empty()
+    }
+    fn int(_: i32) -> Self {
+        Node::empty()
+    }
+    fn variable(ident: &str) -> Self {
+        Node::Var(Name::new(ident))
+    }
+    fn string(_: &str) -> Self {
+        Node::empty()
+    }
+    fn char(_: char) -> Self {
+        Node::empty()
+    }
+    fn do_expr(command: Self, pattern: Pattern, body: Self) -> Self {
+        Node::Seq(vec![command, Node::bind(BindingKind::Do, pattern, body)])
+    }
+    // The parts without defaults are in scope in the defaults, and each part
+    // with a default is in scope in the defaults after it
+    fn fn_defaults(params: Vec<(Pattern, Option<Self>)>, body: Self) -> Self {
+        let (plain, defaults): (Vec<_>, Vec<_>) = params
+            .into_iter()
+            .partition(|(_, default)| default.is_none());
+        let body = defaults
+            .into_iter()
+            .rev()
+            .filter_map(|(pattern, default)| Some((pattern, default?)))
+            .fold(body, |body, (pattern, default)| {
+                Node::Seq(vec![default, Node::bind(BindingKind::Param, pattern, body)])
+            });
+        let plain = plain
+            .into_iter()
+            .map(|(pattern, _)| pattern)
+            .fold(Pattern::default(), Pattern::tuple);
+        Node::bind(BindingKind::Param, plain, body)
+    }
+    // The missing arguments aren't names, so only the others are resolved
+    fn holes(function: Self, args: Vec<Option<Self>>) -> Self {
+        Node::Seq(
+            std::iter::once(function)
+                .chain(args.into_iter().flatten())
+                .collect(),
+        )
+    }
+}
+
+enum Decl {
+    Expression(Name, Node),
+    // Names that are bound by an import
+    Import(Vec<Name>),
+    // Names that refer to declarations (in a signature or an export list)
+    Names(Vec<Name>),
+    Other,
+}
+
+impl DeclOutput for Decl {
+    type Expr = Node;
+    type Type = ();
+
+    fn expression(ident: &str, body: Node) -> Self {
+        Decl::Expression(Name::new(ident), body)
+    }
+    fn import(_: &str) -> Self {
+        Decl::Other
+    }
+    fn qualified_import(_: &str) -> Self {
+        Decl::Other
+    }
+    // Names imported with an alias are qualified with it (ex. `L.a`), but
+    // their span is still only the name in the list
+    fn import_with(_: &str, namespace: Option<&str>, names: Option<Vec<&str>>) -> Self {
+        let names = names
+            .unwrap_or_default()
+            .into_iter()
+            .map(|name| {
+                let mut qualified = Name::new(name);
+                if let Some(namespace) = namespace {
+                    qualified.name = format!("{}.{}", namespace, name);
+                }
+                qualified
+            })
+            .collect();
+        Decl::Import(names)
+    }
+    fn export(idents: Vec<&str>) -> Self {
+        Decl::Names(idents.into_iter().map(Name::new).collect())
+    }
+    fn reexport(import: Self) -> Self {
+        import
+    }
+    fn signature(ident: &str, _: ()) -> Self {
+        Decl::Names(vec![Name::new(ident)])
+    }
+    // Each equation's parameters are in scope in its body, and the names of
+    // the equations after the first refer to the declaration
+    fn clauses(clauses: Vec<Clause<Node>>) -> Self {
+        let ident = Name::new(clauses[0].0);
+        let nodes = clauses
+            .into_iter()
+            .enumerate()
+            .flat_map(|(i, (name, params, body))| {
+                let params = params.into_iter().fold(Pattern::default(), Pattern::tuple);
+                let name = Some(Node::Var(Name::new(name))).filter(|_| i > 0);
+                name.into_iter()
+                    .chain(Some(Node::bind(BindingKind::Param, params, body)))
+            })
+            .collect();
+        Decl::Expression(ident, Node::Seq(nodes))
+    }
+}
+
+struct Prog(Vec<Decl>);
+
+impl ProgOutput for Prog {
+    type Decl = Decl;
+
+    fn binary(_: Node, decls: Vec<Decl>) -> Self {
+        Prog(decls)
+    }
+    fn library(decls: Vec<Decl>) -> Self {
+        Prog(decls)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The text of each binding's references
+    fn references<'a>(source: &'a str, resolution: &Resolution) -> Vec<(&'a str, Vec<usize>)> {
+        resolution
+            .bindings
+            .iter()
+            .map(|binding| {
+                (
+                    &source[binding.span.clone()],
+                    resolution
+                        .references_of(binding)
+                        .into_iter()
+                        .map(|span| span.start)
+                        .collect(),
+                )
+            })
+            .collect()
+    }
+
+    fn kinds(resolution: &Resolution) -> Vec<BindingKind> {
+        resolution
+            .bindings
+            .iter()
+            .map(|binding| binding.kind)
+            .collect()
+    }
+
+    const SOURCE: &str =
+        "double : Int -> Int\ndouble n = n * 2\nmain = let n = double 1 in match n to | x -> x + n\n";
+
+    basic_test! {
+        resolve_test
+        references(SOURCE, &resolve(SOURCE).unwrap()) => vec![
+            ("double", vec![0, 52]),
+            ("main", vec![]),
+            ("n", vec![31]),
+            ("n", vec![70, 86]),
+            ("x", vec![82]),
+        ];
+        kinds(&resolve(SOURCE).unwrap()) => vec![
+            BindingKind::Decl,
+            BindingKind::Decl,
+            BindingKind::Param,
+            BindingKind::Let,
+            BindingKind::Match,
+        ];
+        // Names imported with an alias are used qualified
+        references("import \"a.tgo\" as A (a)\nmain = (A.a, a)\n", &resolve("import \"a.tgo\" as A (a)\nmain = (A.a, a)\n").unwrap())
+            => vec![("a", vec![32]), ("main", vec![])]
+    }
+
+    const CLAUSES_SOURCE: &str = "fact 0 = 1\nfact n = n * fact (n - 1)\n";
+
+    basic_test! {
+        clauses_test
+        // The equations after the first refer to the declaration
+        references(CLAUSES_SOURCE, &resolve(CLAUSES_SOURCE).unwrap()) => vec![
+            ("fact", vec![11, 24]),
+            ("n", vec![20, 30]),
+        ];
+        kinds(&resolve(CLAUSES_SOURCE).unwrap()) => vec![BindingKind::Decl, BindingKind::Param]
+    }
+
+    fn definition(source: &str, name: &str) -> Option<BindingKind> {
+        let resolution = resolve(source).unwrap();
+        let offset = source.rfind(name).unwrap();
+        resolution.definition_of(offset).map(|binding| binding.kind)
+    }
+
+    const DO_SOURCE: &str =
+        "import \"lib.tgo\" (helper)\nmain = do readLine in line then println (helper line, missing)\n";
+
+    basic_test! {
+        definition_test
+        definition(DO_SOURCE, "helper") => Some(BindingKind::Import);
+        definition(DO_SOURCE, "line") => Some(BindingKind::Do);
+        definition(DO_SOURCE, "missing") => None;
+        definition(DO_SOURCE, "println") => None;
+        definition(SOURCE, "double n") => Some(BindingKind::Decl);
+        definition("main = delay xs = (1, xs) in xs\n", "xs)") => Some(BindingKind::Delay);
+        // A default can use the parts of the parameter before it
+        definition("f = fn (a, b = a) -> b\n", "a)") => Some(BindingKind::Param);
+        kinds(&resolve("f = fn (a, b = a) -> b\n").unwrap()) => vec![BindingKind::Decl, BindingKind::Param, BindingKind::Param];
+        // `_` isn't a name, so only the other arguments are resolved
+        definition("f x = map (add x _) x\n", "x _") => Some(BindingKind::Param);
+        kinds(&resolve("f x = map (add x _) x\n").unwrap()) => vec![BindingKind::Decl, BindingKind::Param];
+        resolve(DO_SOURCE).unwrap().decl("main").map(|main| main.span.clone()) => Some(26..30)
+    }
+}
@@ -0,0 +1,138 @@
+// Finds bindings that shadow another binding with the same name
+//
+// Every scope can see the scopes around it, so reusing a name hides the outer
+// binding for the rest of the inner scope, which is easy to do by accident
+// (ex. `f list = map (fn list -> list + 1) list`)
+
+use crate::spans::{self, Node, Place};
+use crate::types::deps::pattern_bindings;
+use tego_parser::ast::{Decl, Expr, Match};
+
+// The declaration, name and place of every binding that shadows another one,
+// including the file's own declarations
+pub fn shadowed(decls: &[Decl]) -> Vec<(String, String, Place)> {
+    let mut scope = decls
+        .iter()
+        .filter_map(|decl| match decl {
+            Decl::Expression(ident, _) => Some(ident.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let mut shadowed = vec![];
+    for decl in decls {
+        if let Decl::Expression(ident, body) = decl {
+            let mut found = vec![];
+            collect(body, &mut scope, &mut found);
+            shadowed.extend(found.into_iter().map(|(name, pattern)| {
+                let place =
+                    spans::position(body, Node::Match(pattern)).map_or(Place::Decl, Place::Node);
+                (ident.clone(), name.to_string(), place)
+            }));
+        }
+    }
+    shadowed
+}
+
+type Found<'a> = Vec<(&'a str, &'a Match)>;
+
+fn collect<'a>(expr: &'a Expr, scope: &mut Vec<&'a str>, found: &mut Found<'a>) {
+    match expr {
+        Expr::Variable(_) | Expr::Literal(_) => (),
+        Expr::Boxed(a) | Expr::Unary(_, a) => collect(a, scope, found),
+        Expr::FnApp(a, b) | Expr::Binary(a, _, b) => {
+            collect(a, scope, found);
+            collect(b, scope, found);
+        }
+        Expr::If(cond, a, b) => {
+            collect(cond, scope, found);
+            collect(a, scope, found);
+            collect(b, scope, found);
+        }
+        Expr::Fn_(pattern, body) => bind(pattern, scope, found, |scope, found| {
+            collect(body, scope, found)
+        }),
+        // `delay` declarations can refer to themselves
+        Expr::Delayed(pattern, val, body) => bind(pattern, scope, found, |scope, found| {
+            collect(val, scope, found);
+            collect(body, scope, found);
+        }),
+        Expr::Let(pattern, val, body) | Expr::Do(val, pattern, body) => {
+            collect(val, scope, found);
+            bind(pattern, scope, found, |scope, found| {
+                collect(body, scope, found)
+            });
+        }
+        Expr::Match(val, arms) => {
+            collect(val, scope, found);
+            for (pattern, body) in arms {
+                match (&**val, pattern) {
+                    // `match n to | n -> ...` binds the same value to the same name
+                    (Expr::Variable(var), Match::Ident(ident)) if var == ident => {
+                        collect(body, scope, found)
+                    }
+                    _ => bind(pattern, scope, found, |scope, found| {
+                        collect(body, scope, found)
+                    }),
+                }
+            }
+        }
+    }
+}
+
+fn bind<'a>(
+    pattern: &'a Match,
+    scope: &mut Vec<&'a str>,
+    found: &mut Found<'a>,
+    f: impl FnOnce(&mut Vec<&'a str>, &mut Found<'a>),
+) {
+    let bindings = pattern_bindings(pattern);
+    found.extend(
+        bindings
+            .iter()
+            .filter(|(ident, _)| scope.contains(ident) && !is_generated(ident)),
+    );
+    let len = scope.len();
+    scope.extend(bindings.iter().map(|(ident, _)| ident));
+    f(scope, found);
+    scope.truncate(len);
+}
+
+// Parameters that aren't written (ex. `_0` for a parameter with a default,
+// see `ExprOutput::fn_defaults`) have a digit after the `_`, which names can't
+fn is_generated(ident: &str) -> bool {
+    ident.starts_with('_') && ident[1..].chars().all(|c| c.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tego_parser::ast::Prog;
+    use tego_parser::Span;
+
+    fn check(source: &str) -> Vec<(String, String)> {
+        match tego_parser::prog(Span::new(source)).unwrap().1 {
+            Prog::Binary(_, decls) | Prog::Library(decls) => shadowed(&decls)
+                .into_iter()
+                .map(|(decl, ident, _)| (decl, ident))
+                .collect(),
+        }
+    }
+
+    fn pair(decl: &str, ident: &str) -> (String, String) {
+        (decl.into(), ident.into())
+    }
+
+    basic_test! {
+        shadowed_test
+        check("f a = let a = 1 in a") => vec![pair("f", "a")];
+        check("f list = map (fn list -> list + 1) list") => vec![pair("f", "list")];
+        check("f a = 1\ng f = f") => vec![pair("g", "f")];
+        check("f a = match a to\n\t| [b] -> match b to\n\t\t| [b] -> b") => vec![pair("f", "b")];
+        check("f n = match n to\n\t| 0 -> 1\n\t| n -> n") => Vec::<(String, String)>::new();
+        // Arms of the same `match` don't shadow each other
+        check("f a = match a to\n\t| [b] -> b\n\t| b -> b") => Vec::<(String, String)>::new();
+        check("f a = 1\ng a = 2") => Vec::<(String, String)>::new();
+        // Parameters with defaults are bound to names that can't be written
+        check("f = fn (a = 1) -> fn (b = 2) -> a + b") => Vec::<(String, String)>::new()
+    }
+}
@@ -0,0 +1,442 @@
+// Where a diagnostic is in the source, when it's about part of a declaration
+// (ex. the expression with a type error, or a binding that's never used)
+//
+// The syntax tree that's analyzed doesn't know where anything is, so every
+// expression and pattern in a declaration's body is a node, numbered in the
+// order they're written (a node comes before the nodes in it), and the source
+// is parsed again into a tree of spans with the same nodes
+//
+//     f x = x + 1
+//
+// The body is `fn x -> x + 1`, so `x` is node 1, `x + 1` is node 2, and `1`
+// is node 4. Desugared nodes (ex. the parameters of a function declared by
+// several equations) are numbered too, and are where the nodes around them are.
+
+use crate::resolve::Name;
+use std::iter;
+use std::ops::Range;
+use tego_parser::ast::{Expr, Match};
+use tego_parser::{DeclOutput, ExprOutput, MatchOutput, ParseError, ProgOutput};
+
+// Where a diagnostic is in its declaration
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Place {
+    // The declaration's name
+    Decl,
+    // One of the declaration's signatures, by its position (a declaration
+    // with more than one signature is an error)
+    Signature(usize),
+    // A node in the declaration's body, by its position (see `position`)
+    Node(usize),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Node<'a> {
+    Expr(&'a Expr),
+    Match(&'a Match),
+}
+
+impl<'a> Node<'a> {
+    fn is(self, other: Node) -> bool {
+        match (self, other) {
+            (Node::Expr(a), Node::Expr(b)) => std::ptr::eq(a, b),
+            (Node::Match(a), Node::Match(b)) => std::ptr::eq(a, b),
+            _ => false,
+        }
+    }
+
+    // In the order they're written, which is the same order as `Tree`'s
+    fn children(self) -> Vec<Node<'a>> {
+        match self {
+            Node::Expr(expr) => {
+                match expr {
+                    Expr::Literal(_) | Expr::Variable(_) => vec![],
+                    Expr::Boxed(a) | Expr::Unary(_, a) => vec![Node::Expr(a)],
+                    Expr::FnApp(a, b) | Expr::Binary(a, _, b) => vec![Node::Expr(a), Node::Expr(b)],
+                    Expr::If(cond, a, b) => vec![Node::Expr(cond), Node::Expr(a), Node::Expr(b)],
+                    Expr::Fn_(pattern, body) => vec![Node::Match(pattern), Node::Expr(body)],
+                    Expr::Let(pattern, val, body) | Expr::Delayed(pattern, val, body) => {
+                        vec![Node::Match(pattern), Node::Expr(val), Node::Expr(body)]
+                    }
+                    Expr::Do(command, pattern, body) => {
+                        vec![Node::Expr(command), Node::Match(pattern), Node::Expr(body)]
+                    }
+                    Expr::Match(val, arms) => iter::once(Node::Expr(val))
+                        .chain(arms.iter().flat_map(|(pattern, body)| {
+                            vec![Node::Match(pattern), Node::Expr(body)]
+                        }))
+                        .collect(),
+                }
+            }
+            Node::Match(pattern) => match pattern {
+                Match::Tuple(patterns) => patterns.iter().map(Node::Match).collect(),
+                Match::Boxed(inner) => vec![Node::Match(inner)],
+                Match::Ident(_) | Match::Value(_) | Match::Unit | Match::Ignore => vec![],
+            },
+        }
+    }
+}
+
+// The position of `node` in `body` (`body` itself is 0), if it's in it
+pub fn position(body: &Expr, node: Node) -> Option<usize> {
+    let mut stack = vec![Node::Expr(body)];
+    let mut i = 0;
+    while let Some(next) = stack.pop() {
+        if next.is(node) {
+            return Some(i);
+        }
+        i += 1;
+        stack.extend(next.children().into_iter().rev());
+    }
+    None
+}
+
+// The spans of the declarations in a file, and of the nodes in them
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Spans {
+    decls: Vec<DeclSpans>,
+    // The name of each signature
+    signatures: Vec<(String, Range<usize>)>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+struct DeclSpans {
+    name: String,
+    span: Range<usize>,
+    // In the order of their positions
+    nodes: Vec<Option<Range<usize>>>,
+}
+
+impl Spans {
+    pub fn of(source: &str) -> Result<Spans, ParseError> {
+        let decls = match tego_parser::prog::<Prog>(source.into()) {
+            Ok((_, prog)) => prog.0,
+            Err(error) => return Err(ParseError::from(error)),
+        };
+        let mut spans = Spans::default();
+        for decl in decls {
+            match decl {
+                Decl::Expression(name, body) => spans.decls.push(DeclSpans {
+                    span: name.span(source),
+                    name: name.name,
+                    nodes: body.spans(source),
+                }),
+                Decl::Signature(name) => spans
+                    .signatures
+                    .push((name.name.clone(), name.span(source))),
+                Decl::Other => {}
+            }
+        }
+        Ok(spans)
+    }
+
+    // Where `place` is in the declaration `decl`, which is the declaration's
+    // name if the place isn't known
+    pub fn span(&self, decl: &str, place: Place) -> Option<Range<usize>> {
+        let found = self.decls.iter().find(|found| found.name == decl);
+        let place = match place {
+            Place::Decl => None,
+            Place::Signature(i) => self
+                .signatures
+                .iter()
+                .filter(|(name, _)| name == decl)
+                .nth(i)
+                .map(|(_, span)| span.clone()),
+            Place::Node(i) => found.and_then(|found| found.nodes.get(i).cloned().flatten()),
+        };
+        place.or_else(|| found.map(|found| found.span.clone()))
+    }
+}
+
+// A node, and where it was parsed from (see `ExprOutput::located`), which
+// isn't known for a desugared node
+#[derive(Debug, Clone, Default)]
+struct Tree {
+    // Where its text is in memory, which is in the source that was parsed
+    // (like `resolve::Name`)
+    text: Option<Range<usize>>,
+    children: Vec<Tree>,
+    // Whether it's a tuple pattern, which is joined with the tuples next to
+    // it, like `Match::tuple` does
+    tuple: bool,
+}
+
+impl Tree {
+    fn new(children: Vec<Tree>) -> Self {
+        Tree {
+            children,
+            ..Tree::default()
+        }
+    }
+
+    // The span of each node, in order, without the spaces around it
+    // A node that wasn't parsed is where the nodes in it are, or else where
+    // the node around it is
+    fn spans(&self, source: &str) -> Vec<Option<Range<usize>>> {
+        let mut spans = vec![];
+        self.collect(source, None, &mut spans);
+        for i in 0..spans.len() {
+            if let (Some(parent), None) = spans[i] {
+                spans[i].1 = spans[parent].1.clone();
+            }
+        }
+        spans.into_iter().map(|(_, span)| span).collect()
+    }
+
+    // Adds each node's parent and span to `spans`
+    fn collect(
+        &self,
+        source: &str,
+        parent: Option<usize>,
+        spans: &mut Vec<(Option<usize>, Option<Range<usize>>)>,
+    ) {
+        let i = spans.len();
+        spans.push((parent, None));
+        for child in &self.children {
+            child.collect(source, Some(i), spans);
+        }
+        let inner = spans[i + 1..]
+            .iter()
+            .filter_map(|(_, span)| span.as_ref())
+            .fold(None, |joined: Option<Range<usize>>, span| match joined {
+                Some(joined) => Some(joined.start.min(span.start)..joined.end.max(span.end)),
+                None => Some(span.clone()),
+            });
+        let start = source.as_ptr() as usize;
+        let own = self.text.as_ref().map(|text| {
+            trim(
+                source,
+                text.start.saturating_sub(start)..text.end.saturating_sub(start),
+            )
+        });
+        spans[i].1 = own.or(inner);
+    }
+}
+
+fn trim(source: &str, span: Range<usize>) -> Range<usize> {
+    let text = &source[span.clone()];
+    let start = span.start + (text.len() - text.trim_start().len());
+    let end = span.end - (text.len() - text.trim_end().len());
+    start..end.max(start)
+}
+
+#[derive(Debug, Clone, Default)]
+struct Pattern(Tree);
+
+impl MatchOutput for Pattern {
+    fn tuple(a: Self, b: Self) -> Self {
+        let parts = |pattern: Pattern| match pattern.0 {
+            tree if tree.tuple => tree.children,
+            tree => vec![tree],
+        };
+        let mut children = parts(a);
+        children.extend(parts(b));
+        Pattern(Tree {
+            tuple: true,
+            ..Tree::new(children)
+        })
+    }
+    fn unit() -> Self {
+        Pattern::default()
+    }
+    fn boxed(a: Self) -> Self {
+        Pattern(Tree::new(vec![a.0]))
+    }
+    fn ignore() -> Self {
+        Pattern::default()
+    }
+    fn bool(_: bool) -> Self {
+        Pattern::default()
+    }
+    fn int(_: i32) -> Self {
+        Pattern::default()
+    }
+    fn ident(_: &str) -> Self {
+        Pattern::default()
+    }
+    // A string pattern is a boxed tuple of `Char`s (see `Match::string`)
+    fn string(_: &str) -> Self {
+        Pattern::boxed(Pattern::default())
+    }
+    fn char(_: char) -> Self {
+        Pattern::default()
+    }
+    fn located(self, text: &str) -> Self {
+        Pattern(self.0.located(text))
+    }
+}
+
+impl ExprOutput for Tree {
+    type Match = Pattern;
+
+    fn binary(a: Self, _: &str, b: Self) -> Self {
+        Tree::new(vec![a, b])
+    }
+    fn unary(_: &str, a: Self) -> Self {
+        Tree::new(vec![a])
+    }
+    fn let_expr(pattern: Pattern, value: Self, body: Self) -> Self {
+        Tree::new(vec![pattern.0, value, body])
+    }
+    fn delayed(pattern: Pattern, value: Self, body: Self) -> Self {
+        Tree::new(vec![pattern.0, value, body])
+    }
+    fn if_expr(cond: Self, t: Self, f: Self) -> Self {
+        Tree::new(vec![cond, t, f])
+    }
+    fn match_(val: Self, patterns: Vec<(Pattern, Self)>) -> Self {
+        let arms = patterns
+            .into_iter()
+            .flat_map(|(pattern, body)| vec![pattern.0, body]);
+        Tree::new(iter::once(val).chain(arms).collect())
+    }
+    fn fn_expr(param: Pattern, body: Self) -> Self {
+        Tree::new(vec![param.0, body])
+    }
+    fn fn_app(function: Self, arg: Self) -> Self {
+        Tree::new(vec![function, arg])
+    }
+    fn unit() -> Self {
+        Tree::default()
+    }
+    fn boxed(inner: Self) -> Self {
+        Tree::new(vec![inner])
+    }
+    fn bool(_: bool) -> Self {
+        Tree::default()
+    }
+    fn int(_: i32) -> Self {
+        Tree::default()
+    }
+    fn variable(_: &str) -> Self {
+        Tree::default()
+    }
+    fn string(_: &str) -> Self {
+        Tree::default()
+    }
+    fn char(_: char) -> Self {
+        Tree::default()
+    }
+    fn do_expr(command: Self, pattern: Pattern, body: Self) -> Self {
+        Tree::new(vec![command, pattern.0, body])
+    }
+    fn located(self, text: &str) -> Self {
+        let start = text.as_ptr() as usize;
+        Tree {
+            text: Some(start..start + text.len()),
+            ..self
+        }
+    }
+}
+
+// Functions declared by several equations are desugared the same way as in the
+// syntax tree, so their nodes are the same
+enum Decl {
+    Expression(Name, Tree),
+    Signature(Name),
+    Other,
+}
+
+impl DeclOutput for Decl {
+    type Expr = Tree;
+    type Type = ();
+
+    fn expression(ident: &str, body: Tree) -> Self {
+        Decl::Expression(Name::new(ident), body)
+    }
+    fn import(_: &str) -> Self {
+        Decl::Other
+    }
+    fn qualified_import(_: &str) -> Self {
+        Decl::Other
+    }
+    fn import_with(_: &str, _: Option<&str>, _: Option<Vec<&str>>) -> Self {
+        Decl::Other
+    }
+    fn export(_: Vec<&str>) -> Self {
+        Decl::Other
+    }
+    fn reexport(import: Self) -> Self {
+        import
+    }
+    fn signature(ident: &str, _: ()) -> Self {
+        Decl::Signature(Name::new(ident))
+    }
+}
+
+struct Prog(Vec<Decl>);
+
+impl ProgOutput for Prog {
+    type Decl = Decl;
+
+    fn binary(_: Tree, decls: Vec<Decl>) -> Self {
+        Prog(decls)
+    }
+    fn library(decls: Vec<Decl>) -> Self {
+        Prog(decls)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tego_parser::ast::Prog;
+    use tego_parser::Span;
+
+    // The text of every node in the body of the first declaration, which
+    // has to be the same nodes as in the syntax tree
+    fn nodes(source: &str) -> Vec<&str> {
+        let body = match tego_parser::prog(Span::new(source)).unwrap().1 {
+            Prog::Binary(_, decls) | Prog::Library(decls) => match decls.into_iter().next() {
+                Some(tego_parser::ast::Decl::Expression(_, body)) => body,
+                _ => panic!("expected a declaration"),
+            },
+        };
+        let mut count = 0;
+        let mut stack = vec![Node::Expr(&body)];
+        while let Some(node) = stack.pop() {
+            assert_eq!(position(&body, node), Some(count));
+            count += 1;
+            stack.extend(node.children().into_iter().rev());
+        }
+        let spans = Spans::of(source).unwrap();
+        let decl = &spans.decls[0];
+        assert_eq!(decl.nodes.len(), count);
+        (0..count)
+            .map(|i| &source[spans.span(&decl.name, Place::Node(i)).unwrap()])
+            .collect()
+    }
+
+    basic_test! {
+        nodes_test
+        nodes("f x = x + 1") => vec!["x = x + 1", "x", "x + 1", "x", "1"];
+        nodes("a = if f 'c' then [\"s\"] else -2") => vec![
+            "if f 'c' then [\"s\"] else -2", "f 'c'", "f", "'c'", "[\"s\"]", "\"s\"", "-2", "2",
+        ];
+        nodes("a = let (x, [y]) = (1, 2) in do x in () then y") => vec![
+            "let (x, [y]) = (1, 2) in do x in () then y",
+            "(x, [y])", "x", "[y]", "y",
+            "(1, 2)", "1", "2",
+            "do x in () then y", "x", "()", "y",
+        ];
+        nodes("a = match 1 to\n\t| 0 -> \"zero\"\n\t| n -> fn _ -> n") => vec![
+            "match 1 to\n\t| 0 -> \"zero\"\n\t| n -> fn _ -> n",
+            "1", "0", "\"zero\"", "n", "fn _ -> n", "_", "n",
+        ];
+        // The parameters of the equations are matched as `match [_0], [_1] to`,
+        // which is desugared
+        nodes("f 0 \"a\" = 1\nf n s = n")[10..] => vec![
+            "0 \"a\"", "0", "0", "\"a\"", "\"a\"", "\"a\"", "1", "n s", "n", "n", "s", "s", "n",
+        ];
+        nodes("f 0 \"a\" = 1\nf n s = n")[..10] => vec!["0 \"a\" = 1\nf n s = n"; 10]
+    }
+
+    basic_test! {
+        span_test
+        Spans::of("f : Int\nf : Int\nf x = x").unwrap().span("f", Place::Signature(1)) => Some(8..9);
+        Spans::of("f : Int\nf x = x").unwrap().span("f", Place::Decl) => Some(8..9);
+        // Places that aren't known are the declaration's name
+        Spans::of("f x = x").unwrap().span("f", Place::Node(10)) => Some(0..1);
+        Spans::of("f x = x").unwrap().span("g", Place::Node(1)) => None
+    }
+}
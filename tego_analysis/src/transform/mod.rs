@@ -1 +0,0 @@
-mod match_;
\ No newline at end of file
@@ -0,0 +1,174 @@
+// Declarations are inferred in dependency order, so that a declaration's type
+// is generalized before the declarations that use it are inferred
+
+use tego_parser::ast::{Expr, Match};
+
+// Names used in `expr` that it doesn't bind itself
+pub fn free_vars(expr: &Expr) -> Vec<String> {
+    let mut free = vec![];
+    collect(expr, false, &mut vec![], &mut free);
+    free
+}
+
+// Names used in `expr` that it doesn't bind itself, other than in the argument
+// of `expectError`
+pub fn checked_free_vars(expr: &Expr) -> Vec<String> {
+    let mut free = vec![];
+    collect(expr, true, &mut vec![], &mut free);
+    free
+}
+
+fn collect(expr: &Expr, checked: bool, bound: &mut Vec<String>, free: &mut Vec<String>) {
+    match expr {
+        Expr::Variable(ident) => {
+            if !bound.contains(ident) && !free.contains(ident) {
+                free.push(ident.clone());
+            }
+        }
+        Expr::Literal(_) => (),
+        // The argument of `expectError` is expected to fail, so it isn't checked
+        Expr::FnApp(a, _) if checked && **a == Expr::Variable("expectError".into()) => {
+            collect(a, checked, bound, free)
+        }
+        Expr::Boxed(a) | Expr::Unary(_, a) => collect(a, checked, bound, free),
+        Expr::FnApp(a, b) | Expr::Binary(a, _, b) => {
+            collect(a, checked, bound, free);
+            collect(b, checked, bound, free);
+        }
+        Expr::If(cond, a, b) => {
+            collect(cond, checked, bound, free);
+            collect(a, checked, bound, free);
+            collect(b, checked, bound, free);
+        }
+        Expr::Fn_(pattern, body) => {
+            with_bindings(pattern, bound, |bound| collect(body, checked, bound, free))
+        }
+        // `delay` declarations can refer to themselves
+        Expr::Delayed(pattern, val, body) => with_bindings(pattern, bound, |bound| {
+            collect(val, checked, bound, free);
+            collect(body, checked, bound, free);
+        }),
+        Expr::Let(pattern, val, body) | Expr::Do(val, pattern, body) => {
+            collect(val, checked, bound, free);
+            with_bindings(pattern, bound, |bound| collect(body, checked, bound, free));
+        }
+        Expr::Match(val, arms) => {
+            collect(val, checked, bound, free);
+            for (pattern, body) in arms {
+                with_bindings(pattern, bound, |bound| collect(body, checked, bound, free));
+            }
+        }
+    }
+}
+
+fn with_bindings(pattern: &Match, bound: &mut Vec<String>, f: impl FnOnce(&mut Vec<String>)) {
+    let len = bound.len();
+    bound.extend(pattern_idents(pattern));
+    f(bound);
+    bound.truncate(len);
+}
+
+pub fn pattern_idents(pattern: &Match) -> Vec<String> {
+    match pattern {
+        Match::Ident(ident) => vec![ident.clone()],
+        Match::Tuple(patterns) => patterns.iter().flat_map(pattern_idents).collect(),
+        Match::Boxed(pattern) => pattern_idents(pattern),
+        Match::Value(_) | Match::Unit | Match::Ignore => vec![],
+    }
+}
+
+// The same names as `pattern_idents`, with the pattern that binds each one
+pub fn pattern_bindings(pattern: &Match) -> Vec<(&str, &Match)> {
+    match pattern {
+        Match::Ident(ident) => vec![(ident, pattern)],
+        Match::Tuple(patterns) => patterns.iter().flat_map(pattern_bindings).collect(),
+        Match::Boxed(inner) => pattern_bindings(inner),
+        Match::Value(_) | Match::Unit | Match::Ignore => vec![],
+    }
+}
+
+// Tarjan's algorithm, where `edges[i]` are the nodes that node `i` depends on
+// Each group only depends on itself and the groups before it
+pub fn components(edges: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    struct State<'a> {
+        edges: &'a [Vec<usize>],
+        index: Vec<Option<usize>>,
+        low: Vec<usize>,
+        stack: Vec<usize>,
+        on_stack: Vec<bool>,
+        next: usize,
+        components: Vec<Vec<usize>>,
+    }
+
+    fn visit(state: &mut State, node: usize) {
+        state.index[node] = Some(state.next);
+        state.low[node] = state.next;
+        state.next += 1;
+        state.stack.push(node);
+        state.on_stack[node] = true;
+        for &dep in &state.edges[node] {
+            match state.index[dep] {
+                None => {
+                    visit(state, dep);
+                    state.low[node] = state.low[node].min(state.low[dep]);
+                }
+                Some(index) if state.on_stack[dep] => state.low[node] = state.low[node].min(index),
+                Some(_) => (),
+            }
+        }
+        if Some(state.low[node]) == state.index[node] {
+            let mut component = vec![];
+            while let Some(member) = state.stack.pop() {
+                state.on_stack[member] = false;
+                component.push(member);
+                if member == node {
+                    break;
+                }
+            }
+            component.sort_unstable();
+            state.components.push(component);
+        }
+    }
+
+    let mut state = State {
+        edges,
+        index: vec![None; edges.len()],
+        low: vec![0; edges.len()],
+        stack: vec![],
+        on_stack: vec![false; edges.len()],
+        next: 0,
+        components: vec![],
+    };
+    for node in 0..edges.len() {
+        if state.index[node].is_none() {
+            visit(&mut state, node);
+        }
+    }
+    state.components
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tego_parser::{ExprOutput, MatchOutput};
+
+    basic_test! {
+        free_vars_test
+        free_vars(&Expr::fn_expr(
+            Match::ident("a"),
+            Expr::fn_app(Expr::variable("f"), Expr::variable("a"))
+        )) => vec!["f".to_string()];
+        free_vars(&Expr::let_expr(
+            Match::ident("x"),
+            Expr::variable("x"),
+            Expr::variable("x")
+        )) => vec!["x".to_string()]
+    }
+
+    basic_test! {
+        components_test
+        // 0 -> 1 -> 2 -> 1, 3 on its own
+        components(&[vec![1], vec![2], vec![1], vec![]]) => vec![vec![1, 2], vec![0], vec![3]];
+        components(&[vec![0]]) => vec![vec![0]]
+    }
+}
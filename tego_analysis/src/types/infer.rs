@@ -0,0 +1,826 @@
+use crate::spans::{self, Node, Place};
+use crate::types::deps::{components, free_vars};
+use crate::types::type_::display_together;
+use crate::types::{Class, Inference, MatchSite, NameSite, Scheme, Type, TypeError};
+use std::collections::HashMap;
+use tego_parser::ast::{BinaryOp, Decl, Expr, ExprValue, Match, MatchVal, TypeExpr, UnaryOp};
+
+pub struct Inferer<'a> {
+    // What each type variable has been unified with, if anything
+    subst: Vec<Option<Type>>,
+    globals: HashMap<String, Scheme>,
+    // The types in declarations' signatures
+    signatures: HashMap<String, Scheme>,
+    locals: Vec<(String, Scheme)>,
+    // The declaration that's being inferred, and its body, for errors
+    decl: String,
+    body: Option<&'a Expr>,
+    // The position of the signature that's being read, among the
+    // declaration's signatures
+    signature: usize,
+    errors: Vec<TypeError>,
+    matches: Vec<MatchSite>,
+    names: Vec<NameSite>,
+    // Types that have to be instances of a class, which are checked once
+    // they're known
+    wanted: Vec<Wanted>,
+    // Declarations without a signature are `Any`, and their bodies aren't checked
+    gradual: bool,
+}
+
+struct Wanted {
+    class: Class,
+    type_: Type,
+    // Where the constraint came from, for errors
+    decl: String,
+    expr: String,
+    place: Place,
+}
+
+impl<'a> Inferer<'a> {
+    pub fn new() -> Self {
+        Inferer {
+            subst: vec![],
+            globals: HashMap::new(),
+            signatures: HashMap::new(),
+            locals: vec![],
+            decl: String::new(),
+            body: None,
+            signature: 0,
+            errors: vec![],
+            matches: vec![],
+            names: vec![],
+            wanted: vec![],
+            gradual: false,
+        }
+    }
+
+    pub fn gradual() -> Self {
+        Inferer {
+            gradual: true,
+            ..Inferer::new()
+        }
+    }
+
+    pub fn infer_decls(mut self, decls: &'a [Decl]) -> Inference {
+        let exprs = decls
+            .iter()
+            .filter_map(|decl| match decl {
+                Decl::Expression(ident, body) => Some((ident.as_str(), body)),
+                Decl::Import(..) | Decl::Export(_) | Decl::Signature(..) => None,
+            })
+            .collect::<Vec<_>>();
+        self.read_signatures(decls, &exprs);
+        let decls = exprs;
+        let edges = decls
+            .iter()
+            .map(|(_, body)| {
+                free_vars(body)
+                    .iter()
+                    .filter_map(|var| decls.iter().position(|(ident, _)| ident == var))
+                    .collect()
+            })
+            .collect::<Vec<_>>();
+
+        for group in components(&edges) {
+            // Declarations in a group can refer to each other, so they're
+            // monomorphic until the whole group is inferred (unless they have
+            // a signature)
+            // The signature's type variables are instantiated without their
+            // constraints, since the body can rely on them
+            let vars = group
+                .iter()
+                .map(|&i| {
+                    let ident = decls[i].0.to_string();
+                    match self.signatures.get(&ident).cloned() {
+                        Some(signature) => {
+                            self.globals.insert(ident, signature.clone());
+                            let fresh = self.fresh_vars(&signature);
+                            (substitute(&signature.type_, &fresh), fresh)
+                        }
+                        None if self.gradual => {
+                            self.globals.insert(ident, Scheme::mono(Type::Any));
+                            (Type::Any, HashMap::new())
+                        }
+                        None => {
+                            let var = self.fresh();
+                            self.globals.insert(ident, Scheme::mono(var.clone()));
+                            (var, HashMap::new())
+                        }
+                    }
+                })
+                .collect::<Vec<_>>();
+            for (&i, (var, fresh)) in group.iter().zip(&vars) {
+                let (ident, body) = decls[i];
+                self.decl = ident.to_string();
+                self.body = Some(body);
+                let errors = self.errors.len();
+                let type_ = self.infer(body);
+                match self.signatures.get(ident).cloned() {
+                    Some(signature) => self.check_signature(ident, &signature, fresh, var, &type_),
+                    // Only the `match` expressions in the body are kept
+                    None if self.gradual => {
+                        self.errors.truncate(errors);
+                        self.wanted.retain(|wanted| wanted.decl != ident);
+                    }
+                    None => self.expect(var, &type_, body),
+                }
+            }
+            for (&i, (var, _)) in group.iter().zip(vars) {
+                let ident = decls[i].0;
+                let scheme = match self.signatures.get(ident) {
+                    Some(signature) => signature.clone(),
+                    None if self.gradual => Scheme::mono(Type::Any),
+                    None => self.generalize(&var),
+                };
+                self.globals.insert(ident.to_string(), scheme);
+            }
+        }
+
+        // Constraints on types that were never generalized are only checked if
+        // the type is known
+        self.solve();
+        let types = decls
+            .iter()
+            .map(|(ident, _)| (ident.to_string(), self.globals[*ident].clone()))
+            .collect();
+        // Matches are only checked once everything has been inferred
+        let matches = self
+            .matches
+            .iter()
+            .map(|site| MatchSite {
+                type_: self.apply(&site.type_),
+                ..site.clone()
+            })
+            .collect();
+        let names = self
+            .names
+            .iter()
+            .map(|site| NameSite {
+                type_: self.apply(&site.type_),
+                ..site.clone()
+            })
+            .collect();
+        Inference {
+            types,
+            errors: self.errors,
+            matches,
+            names,
+        }
+    }
+
+    // Every signature has to belong to a declaration, and a declaration can
+    // only have one signature
+    fn read_signatures(&mut self, decls: &[Decl], exprs: &[(&str, &Expr)]) {
+        let mut read = HashMap::new();
+        for (ident, type_) in decls.iter().filter_map(|decl| match decl {
+            Decl::Signature(ident, type_) => Some((ident, type_)),
+            _ => None,
+        }) {
+            self.decl = ident.clone();
+            let count = read.entry(ident).or_insert(0);
+            self.signature = *count;
+            *count += 1;
+            let place = Place::Signature(self.signature);
+            if self.signatures.contains_key(ident) {
+                self.errors.push(TypeError::DuplicateSignature {
+                    decl: ident.clone(),
+                    place,
+                });
+            } else if !exprs.iter().any(|(other, _)| other == ident) {
+                self.errors.push(TypeError::UnmatchedSignature {
+                    decl: ident.clone(),
+                    place,
+                });
+            } else if let Some(scheme) = self.signature_scheme(type_) {
+                self.signatures.insert(ident.clone(), scheme);
+            }
+        }
+    }
+
+    fn signature_scheme(&mut self, type_: &TypeExpr) -> Option<Scheme> {
+        let mut names = HashMap::new();
+        let (constraints, type_) = match type_ {
+            TypeExpr::Constrained(constraints, type_) => (constraints.as_slice(), &**type_),
+            type_ => (&[][..], type_),
+        };
+        let type_ = self.signature_type(type_, &mut names)?;
+        let mut vars = vec![];
+        type_.vars(&mut vars);
+        let mut scheme_constraints = vec![];
+        for (class_name, var) in constraints {
+            let class = match Class::from_name(class_name) {
+                Some(class) => class,
+                None => {
+                    self.errors.push(TypeError::UnknownClass {
+                        decl: self.decl.clone(),
+                        name: class_name.clone(),
+                        place: Place::Signature(self.signature),
+                    });
+                    return None;
+                }
+            };
+            match names.get(var) {
+                Some(Type::Var(var)) => scheme_constraints.push((class, *var)),
+                _ => {
+                    self.errors.push(TypeError::ConstraintVar {
+                        decl: self.decl.clone(),
+                        var: var.clone(),
+                        place: Place::Signature(self.signature),
+                    });
+                    return None;
+                }
+            }
+        }
+        Some(Scheme {
+            vars,
+            constraints: scheme_constraints,
+            type_,
+        })
+    }
+
+    // Type variables with the same name are the same type variable
+    fn signature_type(
+        &mut self,
+        type_: &TypeExpr,
+        vars: &mut HashMap<String, Type>,
+    ) -> Option<Type> {
+        match type_ {
+            TypeExpr::Named(name, args) => {
+                let expected = match name.as_str() {
+                    "Int" | "Bool" | "Char" | "String" | "Any" | "Dynamic" => 0,
+                    "Command" => 1,
+                    _ => {
+                        self.errors.push(TypeError::UnknownType {
+                            decl: self.decl.clone(),
+                            name: name.clone(),
+                            place: Place::Signature(self.signature),
+                        });
+                        return None;
+                    }
+                };
+                if args.len() != expected {
+                    self.errors.push(TypeError::TypeArgs {
+                        decl: self.decl.clone(),
+                        name: name.clone(),
+                        expected,
+                        found: args.len(),
+                        place: Place::Signature(self.signature),
+                    });
+                    return None;
+                }
+                Some(match name.as_str() {
+                    "Int" => Type::Int,
+                    "Bool" => Type::Bool,
+                    "Char" => Type::Char,
+                    "String" => Type::String,
+                    "Any" | "Dynamic" => Type::Any,
+                    _ => Type::command(self.signature_type(&args[0], vars)?),
+                })
+            }
+            TypeExpr::Var(ident) => match vars.get(ident) {
+                Some(var) => Some(var.clone()),
+                None => {
+                    let var = self.fresh();
+                    vars.insert(ident.clone(), var.clone());
+                    Some(var)
+                }
+            },
+            TypeExpr::Boxed(inner) => Some(Type::boxed(self.signature_type(inner, vars)?)),
+            TypeExpr::Fn_(param, result) => Some(Type::fn_(
+                self.signature_type(param, vars)?,
+                self.signature_type(result, vars)?,
+            )),
+            // Constraints are only parsed at the start of a signature
+            TypeExpr::Constrained(_, type_) => self.signature_type(type_, vars),
+        }
+    }
+
+    // `expected` is the signature's type, with the fresh type variables in `fresh`
+    // Each of the signature's type variables has to be able to be any type, so
+    // they can't be unified with anything other than a fresh type variable (or `Any`)
+    fn check_signature(
+        &mut self,
+        ident: &str,
+        signature: &Scheme,
+        fresh: &HashMap<usize, Type>,
+        expected: &Type,
+        found: &Type,
+    ) {
+        let errors = self.errors.len();
+        self.expect(expected, found, &Expr::Variable(ident.to_string()));
+        if self.errors.len() > errors {
+            return;
+        }
+        // The signature's type variables, and what they were unified with
+        let mut unified = vec![];
+        for &var in &signature.vars {
+            match self.apply(&fresh[&var]) {
+                Type::Any => (),
+                Type::Var(other) if !unified.iter().any(|(_, u)| *u == other) => {
+                    unified.push((var, other))
+                }
+                _ => {
+                    self.errors.push(TypeError::TooGeneral {
+                        decl: ident.to_string(),
+                        signature: signature.to_string(),
+                        found: Scheme::mono(self.apply(found)).to_string(),
+                        place: Place::Signature(0),
+                    });
+                    return;
+                }
+            }
+        }
+
+        // Constraints on the signature's type variables have to be in the signature
+        self.solve();
+        let mut wanted = std::mem::take(&mut self.wanted);
+        wanted.retain(|wanted| {
+            let var = match self.apply(&wanted.type_) {
+                Type::Var(other) => match unified.iter().find(|(_, u)| *u == other) {
+                    Some(&(var, _)) => var,
+                    None => return true,
+                },
+                _ => return true,
+            };
+            let given = signature
+                .constraints
+                .iter()
+                .any(|&(class, given)| given == var && class.implies(wanted.class));
+            if !given {
+                let name = display_together(&[&signature.type_, &Type::Var(var)]).remove(1);
+                let error = TypeError::MissingConstraint {
+                    decl: ident.to_string(),
+                    signature: signature.to_string(),
+                    constraint: format!("{} {}", wanted.class, name),
+                    place: Place::Signature(0),
+                };
+                if !self.errors.contains(&error) {
+                    self.errors.push(error);
+                }
+            }
+            false
+        });
+        self.wanted = wanted;
+    }
+
+    fn want(&mut self, class: Class, type_: &Type, expr: &Expr) {
+        self.wanted.push(Wanted {
+            class,
+            type_: type_.clone(),
+            decl: self.decl.clone(),
+            expr: expr.to_string(),
+            place: self.place(expr),
+        });
+    }
+
+    // Where `expr` is in the declaration that's being inferred
+    fn place(&self, expr: &Expr) -> Place {
+        self.body
+            .and_then(|body| spans::position(body, Node::Expr(expr)))
+            .map_or(Place::Decl, Place::Node)
+    }
+
+    fn pattern_place(&self, pattern: &Match) -> Place {
+        self.body
+            .and_then(|body| spans::position(body, Node::Match(pattern)))
+            .map_or(Place::Decl, Place::Node)
+    }
+
+    // Checks the constraints on types that are known, and reduces the others
+    // to constraints on type variables (ex. `Eq [a]` to `Eq a`)
+    fn solve(&mut self) {
+        let mut solved: Vec<Wanted> = vec![];
+        for wanted in std::mem::take(&mut self.wanted) {
+            let type_ = self.apply(&wanted.type_);
+            match wanted.class.instance(&type_) {
+                Some(constraints) => {
+                    for (class, type_) in constraints {
+                        if !solved
+                            .iter()
+                            .any(|other| other.class == class && other.type_ == type_)
+                        {
+                            solved.push(Wanted {
+                                class,
+                                type_,
+                                decl: wanted.decl.clone(),
+                                expr: wanted.expr.clone(),
+                                place: wanted.place,
+                            });
+                        }
+                    }
+                }
+                None => self.errors.push(TypeError::NoInstance {
+                    decl: wanted.decl,
+                    expr: wanted.expr,
+                    class: wanted.class.to_string(),
+                    type_: Scheme::mono(type_).to_string(),
+                    place: wanted.place,
+                }),
+            }
+        }
+        self.wanted = solved;
+    }
+
+    fn fresh(&mut self) -> Type {
+        self.subst.push(None);
+        Type::Var(self.subst.len() - 1)
+    }
+
+    // Replaces every unified type variable in `type_`
+    fn apply(&self, type_: &Type) -> Type {
+        match type_ {
+            Type::Var(var) => match &self.subst[*var] {
+                Some(type_) => self.apply(type_),
+                None => type_.clone(),
+            },
+            Type::Boxed(inner) => Type::boxed(self.apply(inner)),
+            Type::Command(result) => Type::command(self.apply(result)),
+            Type::Fn_(param, result) => Type::fn_(self.apply(param), self.apply(result)),
+            type_ => type_.clone(),
+        }
+    }
+
+    fn occurs(&self, var: usize, type_: &Type) -> bool {
+        let mut vars = vec![];
+        self.apply(type_).vars(&mut vars);
+        vars.contains(&var)
+    }
+
+    fn unify(&mut self, a: &Type, b: &Type) -> Result<(), ()> {
+        match (self.apply(a), self.apply(b)) {
+            (Type::Var(a), Type::Var(b)) if a == b => Ok(()),
+            // A type variable unified with `Any` is `Any` from then on
+            (Type::Var(var), type_) | (type_, Type::Var(var)) => {
+                if self.occurs(var, &type_) {
+                    return Err(());
+                }
+                self.subst[var] = Some(type_);
+                Ok(())
+            }
+            (Type::Any, _) | (_, Type::Any) => Ok(()),
+            (Type::Int, Type::Int)
+            | (Type::Bool, Type::Bool)
+            | (Type::Char, Type::Char)
+            | (Type::String, Type::String) => Ok(()),
+            // An `Int` can be applied to a tuple to index it
+            (Type::Int, Type::Fn_(_, result)) | (Type::Fn_(_, result), Type::Int) => {
+                self.unify(&result, &Type::Any)
+            }
+            // A string is a boxed tuple of `Char`s
+            (Type::Boxed(_), Type::String) | (Type::String, Type::Boxed(_)) => Ok(()),
+            (Type::Boxed(a), Type::Boxed(b)) | (Type::Command(a), Type::Command(b)) => {
+                self.unify(&a, &b)
+            }
+            (Type::Fn_(a_param, a_result), Type::Fn_(b_param, b_result)) => {
+                self.unify(&a_param, &b_param)?;
+                self.unify(&a_result, &b_result)
+            }
+            _ => Err(()),
+        }
+    }
+
+    // Unifies, recording an error for `expr` if the types don't match
+    fn expect(&mut self, expected: &Type, found: &Type, expr: &Expr) {
+        if self.unify(expected, found).is_err() {
+            let (expected, found) = (self.apply(expected), self.apply(found));
+            // Both types are shown with the same names for their type variables
+            let mut names = display_together(&[&expected, &found]).into_iter();
+            let (expected, found) = (names.next().unwrap(), names.next().unwrap());
+            self.errors.push(TypeError::Mismatch {
+                decl: self.decl.clone(),
+                expr: expr.to_string(),
+                expected,
+                found,
+                place: self.place(expr),
+            });
+        }
+    }
+
+    fn fresh_vars(&mut self, scheme: &Scheme) -> HashMap<usize, Type> {
+        scheme.vars.iter().map(|&var| (var, self.fresh())).collect()
+    }
+
+    // Type variables that aren't used by any local can be anything
+    // Constraints on those type variables become part of the scheme
+    fn generalize(&mut self, type_: &Type) -> Scheme {
+        self.solve();
+        let type_ = self.apply(type_);
+        let mut env_vars = vec![];
+        for (_, scheme) in &self.locals {
+            let mut vars = vec![];
+            self.apply(&scheme.type_).vars(&mut vars);
+            env_vars.extend(vars.into_iter().filter(|var| !scheme.vars.contains(var)));
+        }
+        let mut vars = vec![];
+        type_.vars(&mut vars);
+        let vars = vars
+            .into_iter()
+            .filter(|var| !env_vars.contains(var))
+            .collect::<Vec<_>>();
+        let mut constraints = vec![];
+        for &var in &vars {
+            let mut classes = vec![];
+            self.wanted.retain(|wanted| match wanted.type_ {
+                Type::Var(other) if other == var => {
+                    classes.push(wanted.class);
+                    false
+                }
+                _ => true,
+            });
+            // `Ord a` already means `Eq a`
+            for &class in &classes {
+                let implied = classes
+                    .iter()
+                    .any(|&other| other != class && other.implies(class));
+                if !implied && !constraints.contains(&(class, var)) {
+                    constraints.push((class, var));
+                }
+            }
+        }
+        Scheme {
+            vars,
+            constraints,
+            type_,
+        }
+    }
+
+    fn lookup(&mut self, expr: &Expr, ident: &str) -> Type {
+        let scheme = self
+            .locals
+            .iter()
+            .rev()
+            .find(|(local, _)| local == ident)
+            .map(|(_, scheme)| scheme.clone())
+            .or_else(|| self.globals.get(ident).cloned());
+        match scheme {
+            Some(scheme) => {
+                let fresh = self.fresh_vars(&scheme);
+                for &(class, var) in &scheme.constraints {
+                    self.want(class, &fresh[&var], expr);
+                }
+                substitute(&scheme.type_, &fresh)
+            }
+            // Builtins and imported names
+            None => Type::Any,
+        }
+    }
+
+    fn name(&mut self, name: &str, bound: bool, type_: &Type) {
+        self.names.push(NameSite {
+            decl: self.decl.clone(),
+            name: name.into(),
+            bound,
+            type_: type_.clone(),
+        });
+    }
+
+    fn with_locals<T>(
+        &mut self,
+        locals: Vec<(String, Scheme)>,
+        f: impl FnOnce(&mut Self) -> T,
+    ) -> T {
+        let len = self.locals.len();
+        self.locals.extend(locals);
+        let result = f(self);
+        self.locals.truncate(len);
+        result
+    }
+
+    // The names that `pattern` binds when it matches a value of type `type_`
+    fn bind(&mut self, pattern: &Match, type_: &Type) -> Vec<(String, Scheme)> {
+        match pattern {
+            Match::Ident(ident) => {
+                self.name(ident, true, type_);
+                vec![(ident.clone(), Scheme::mono(type_.clone()))]
+            }
+            Match::Ignore | Match::Unit => vec![],
+            // A tuple pattern matches any number of values, so nothing is known
+            // about the types of its parts
+            Match::Tuple(patterns) => patterns
+                .iter()
+                .flat_map(|pattern| self.bind(pattern, &Type::Any))
+                .collect(),
+            Match::Boxed(inner) => {
+                let inner_type = self.fresh();
+                if self.unify(&Type::boxed(inner_type.clone()), type_).is_err() {
+                    return self.bind(inner, &Type::Any);
+                }
+                self.bind(inner, &inner_type)
+            }
+            Match::Value(val) => {
+                let val_type = match val {
+                    MatchVal::Int(_) => Type::Int,
+                    MatchVal::Bool(_) => Type::Bool,
+                    MatchVal::Char(_) => Type::Char,
+                    MatchVal::String(_) => Type::String,
+                };
+                // A pattern that can't match isn't an error, it's just skipped
+                let _ = self.unify(&val_type, type_);
+                vec![]
+            }
+        }
+    }
+
+    fn infer(&mut self, expr: &Expr) -> Type {
+        match expr {
+            Expr::Literal(val) => match val {
+                ExprValue::Int(_) => Type::Int,
+                ExprValue::Bool(_) => Type::Bool,
+                ExprValue::Char(_) => Type::Char,
+                ExprValue::String(_) => Type::String,
+                // `()` is the empty tuple, and also a missing optional value
+                ExprValue::Unit => Type::Any,
+            },
+            Expr::Variable(ident) => {
+                let type_ = self.lookup(expr, ident);
+                self.name(ident, false, &type_);
+                type_
+            }
+            Expr::Boxed(inner) => Type::boxed(self.infer(inner)),
+            Expr::Unary(UnaryOp::Negate, a) => {
+                let a_type = self.infer(a);
+                self.want(Class::Num, &a_type, expr);
+                a_type
+            }
+            Expr::Unary(UnaryOp::Not, a) => {
+                let a_type = self.infer(a);
+                self.expect(&Type::Bool, &a_type, a);
+                Type::Bool
+            }
+            Expr::Binary(a, op, b) => self.infer_binary(expr, a, op, b),
+            Expr::FnApp(function, arg) => {
+                let function_type = self.infer(function);
+                let arg_type = match **function {
+                    // The argument of the `expectError` builtin is supposed to be an error
+                    Expr::Variable(ref ident) if ident == "expectError" => {
+                        let errors = self.errors.len();
+                        self.infer(arg);
+                        self.errors.truncate(errors);
+                        Type::Any
+                    }
+                    _ => self.infer(arg),
+                };
+                // Arguments given by name can be for any number of parameters
+                if arg.named_args().is_some() {
+                    return Type::Any;
+                }
+                match self.apply(&function_type) {
+                    Type::Any => return Type::Any,
+                    // Applying an `Int` to a tuple indexes it
+                    Type::Int => return Type::Any,
+                    _ => (),
+                }
+                let result = self.fresh();
+                self.expect(
+                    &Type::fn_(arg_type, result.clone()),
+                    &function_type,
+                    function,
+                );
+                result
+            }
+            Expr::Fn_(pattern, body) => {
+                let param = self.fresh();
+                let locals = self.bind(pattern, &param);
+                let result = self.with_locals(locals, |inferer| inferer.infer(body));
+                Type::fn_(param, result)
+            }
+            Expr::If(cond, a, b) => {
+                let cond_type = self.infer(cond);
+                self.expect(&Type::Bool, &cond_type, cond);
+                let a_type = self.infer(a);
+                let b_type = self.infer(b);
+                self.expect(&a_type, &b_type, b);
+                a_type
+            }
+            Expr::Match(val, arms) => {
+                let val_type = self.infer(val);
+                let result = self.fresh();
+                for (pattern, body) in arms {
+                    let locals = self.bind(pattern, &val_type);
+                    let body_type = self.with_locals(locals, |inferer| inferer.infer(body));
+                    self.expect(&result, &body_type, body);
+                }
+                self.matches.push(MatchSite {
+                    decl: self.decl.clone(),
+                    val: val.to_string(),
+                    type_: val_type,
+                    patterns: arms.iter().map(|(pattern, _)| pattern.clone()).collect(),
+                    place: self.place(expr),
+                    pattern_places: arms
+                        .iter()
+                        .map(|(pattern, _)| self.pattern_place(pattern))
+                        .collect(),
+                });
+                result
+            }
+            Expr::Let(pattern, val, body) => {
+                let val_type = self.infer(val);
+                let locals = match pattern {
+                    // Let-bound names are polymorphic
+                    Match::Ident(ident) => {
+                        self.name(ident, true, &val_type);
+                        vec![(ident.clone(), self.generalize(&val_type))]
+                    }
+                    pattern => self.bind(pattern, &val_type),
+                };
+                self.with_locals(locals, |inferer| inferer.infer(body))
+            }
+            // The value can refer to the name it's bound to
+            Expr::Delayed(pattern, val, body) => {
+                let var = self.fresh();
+                let locals = self.bind(pattern, &var);
+                self.with_locals(locals, |inferer| {
+                    let val_type = inferer.infer(val);
+                    inferer.expect(&var, &val_type, val);
+                    inferer.infer(body)
+                })
+            }
+            Expr::Do(command, pattern, body) => {
+                let command_type = self.infer(command);
+                let value = self.fresh();
+                self.expect(&Type::command(value.clone()), &command_type, command);
+                let locals = self.bind(pattern, &value);
+                let body_type = self.with_locals(locals, |inferer| inferer.infer(body));
+                let result = self.fresh();
+                self.expect(&Type::command(result.clone()), &body_type, body);
+                Type::command(result)
+            }
+        }
+    }
+
+    fn infer_binary(&mut self, expr: &Expr, a: &Expr, op: &BinaryOp, b: &Expr) -> Type {
+        let a_type = self.infer(a);
+        let b_type = self.infer(b);
+        match op {
+            BinaryOp::Plus
+            | BinaryOp::Minus
+            | BinaryOp::Multiply
+            | BinaryOp::Divide
+            | BinaryOp::Modulo => {
+                self.expect(&a_type, &b_type, b);
+                self.want(Class::Num, &a_type, expr);
+                a_type
+            }
+            // Works on both `Int`s and `Bool`s
+            BinaryOp::And | BinaryOp::Or | BinaryOp::Xor => {
+                self.expect(&a_type, &b_type, b);
+                a_type
+            }
+            BinaryOp::Equal | BinaryOp::NotEqual => {
+                self.expect(&a_type, &b_type, b);
+                self.want(Class::Eq, &a_type, expr);
+                Type::Bool
+            }
+            BinaryOp::LessThan
+            | BinaryOp::GreaterThan
+            | BinaryOp::LessThanEqual
+            | BinaryOp::GreaterThanEqual => {
+                self.expect(&a_type, &b_type, b);
+                self.want(Class::Ord, &a_type, expr);
+                Type::Bool
+            }
+            // Works on both lists and `String`s (which unify with lists)
+            BinaryOp::Concat => {
+                self.expect(&a_type, &b_type, b);
+                let list = Type::boxed(self.fresh());
+                self.expect(&list, &a_type, a);
+                a_type
+            }
+            // Tuples can hold values of any type, and can have any length
+            BinaryOp::Join | BinaryOp::FlatJoin => Type::Any,
+            // Only a function can be given an argument by name
+            BinaryOp::Named => Type::Any,
+            // `a` is run before `b` if it's a Command (like in `do`), and
+            // otherwise it has to be `()`, since its value is discarded
+            BinaryOp::Sequence => match self.apply(&a_type) {
+                Type::Command(_) => {
+                    let result = self.fresh();
+                    self.expect(&Type::command(result.clone()), &b_type, b);
+                    Type::command(result)
+                }
+                Type::Any | Type::Var(_) => b_type,
+                found => {
+                    self.errors.push(TypeError::Mismatch {
+                        decl: self.decl.clone(),
+                        expr: a.to_string(),
+                        expected: "()".into(),
+                        found: display_together(&[&found]).remove(0),
+                        place: self.place(a),
+                    });
+                    b_type
+                }
+            },
+        }
+    }
+}
+
+fn substitute(type_: &Type, vars: &HashMap<usize, Type>) -> Type {
+    match type_ {
+        Type::Var(var) => vars.get(var).cloned().unwrap_or_else(|| type_.clone()),
+        Type::Boxed(inner) => Type::boxed(substitute(inner, vars)),
+        Type::Command(result) => Type::command(substitute(result, vars)),
+        Type::Fn_(param, result) => Type::fn_(substitute(param, vars), substitute(result, vars)),
+        type_ => type_.clone(),
+    }
+}
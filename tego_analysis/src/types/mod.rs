@@ -0,0 +1,453 @@
+// Hindley-Milner type inference
+//
+// Tuples can have any length and hold values of any type, and a tuple of one
+// value is that value, so tuples (and `()`) have the type `Any`, which is
+// compatible with every type. Builtins and imported names are also `Any`.
+// Everything else gets its principal type (ex. `id a = a` is `a -> a`).
+//
+// A declaration with a signature (ex. `add : Int -> Int -> Int`) has the type
+// in its signature, as long as the type that's inferred for it is the same or
+// more general. Type variables in a signature (ex. `a` in `a -> a`) can be any
+// type, unless the signature constrains them to a class (ex. `Eq a => a -> a -> Bool`).
+//
+// Operators work on any instance of their class, so `add a b = a + b` is
+// `Num a => a -> a -> a`, and `==` can't compare functions.
+//
+// Gradual typing (see `infer_gradual`) only checks declarations with a
+// signature: everything else is `Dynamic` (the same as `Any`), and the
+// interpreter checks the values that cross into a signature at runtime.
+
+pub(crate) mod deps;
+mod infer;
+mod type_;
+
+use crate::spans::Place;
+use infer::Inferer;
+use std::fmt;
+use tego_parser::ast::{Decl, Match};
+
+pub use type_::{Class, Scheme, Type};
+
+#[derive(Debug, PartialEq)]
+pub struct Inference {
+    // The type of each declaration, in the order they were declared
+    pub types: Vec<(String, Scheme)>,
+    pub errors: Vec<TypeError>,
+    // Every `match` expression, in the order they were inferred
+    pub matches: Vec<MatchSite>,
+    // Every name that's bound or used in a declaration, in the order they
+    // were inferred (which is the order they're in the declaration)
+    pub names: Vec<NameSite>,
+}
+
+// A `match` expression, with the type of the value that it matches
+#[derive(Debug, PartialEq, Clone)]
+pub struct MatchSite {
+    // The declaration that the `match` is in
+    pub decl: String,
+    pub val: String,
+    pub type_: Type,
+    pub patterns: Vec<Match>,
+    // Where the `match` is, and where each of its patterns is
+    pub place: Place,
+    pub pattern_places: Vec<Place>,
+}
+
+// A name in a declaration's body, with its type there (ex. `x` in `f x = x + 1`
+// is an `Int` both where it's bound and where it's used)
+#[derive(Debug, PartialEq, Clone)]
+pub struct NameSite {
+    // The declaration that the name is in
+    pub decl: String,
+    pub name: String,
+    // Whether this is where the name is bound (by a pattern)
+    pub bound: bool,
+    pub type_: Type,
+}
+
+// Every error has the place in its declaration that it's about (see `Place`)
+#[derive(Debug, PartialEq, Clone)]
+pub enum TypeError {
+    // `expr` (in the declaration `decl`) doesn't have the type it's supposed to
+    Mismatch {
+        decl: String,
+        expr: String,
+        expected: String,
+        found: String,
+        place: Place,
+    },
+    // A signature uses a type that doesn't exist
+    UnknownType {
+        decl: String,
+        name: String,
+        place: Place,
+    },
+    // A signature gives a type the wrong number of arguments (ex. `Int Bool`)
+    TypeArgs {
+        decl: String,
+        name: String,
+        expected: usize,
+        found: usize,
+        place: Place,
+    },
+    // A signature allows more types than the declaration does (ex. `a -> a` for `Int -> Int`)
+    TooGeneral {
+        decl: String,
+        signature: String,
+        found: String,
+        place: Place,
+    },
+    UnmatchedSignature {
+        decl: String,
+        place: Place,
+    },
+    DuplicateSignature {
+        decl: String,
+        place: Place,
+    },
+    // A signature uses a class that doesn't exist
+    UnknownClass {
+        decl: String,
+        name: String,
+        place: Place,
+    },
+    // A signature constrains a type variable that it doesn't use (ex. `Eq b => a -> a`)
+    ConstraintVar {
+        decl: String,
+        var: String,
+        place: Place,
+    },
+    // `expr` uses a type that isn't an instance of `class` (ex. comparing functions with `==`)
+    NoInstance {
+        decl: String,
+        expr: String,
+        class: String,
+        type_: String,
+        place: Place,
+    },
+    // The declaration needs a constraint that its signature doesn't have
+    MissingConstraint {
+        decl: String,
+        signature: String,
+        constraint: String,
+        place: Place,
+    },
+}
+
+impl TypeError {
+    // The declaration that the error is in
+    pub fn decl(&self) -> &str {
+        match self {
+            TypeError::Mismatch { decl, .. }
+            | TypeError::UnknownType { decl, .. }
+            | TypeError::TypeArgs { decl, .. }
+            | TypeError::TooGeneral { decl, .. }
+            | TypeError::UnmatchedSignature { decl, .. }
+            | TypeError::DuplicateSignature { decl, .. }
+            | TypeError::UnknownClass { decl, .. }
+            | TypeError::ConstraintVar { decl, .. }
+            | TypeError::NoInstance { decl, .. }
+            | TypeError::MissingConstraint { decl, .. } => decl,
+        }
+    }
+
+    pub fn place(&self) -> Place {
+        match self {
+            TypeError::Mismatch { place, .. }
+            | TypeError::UnknownType { place, .. }
+            | TypeError::TypeArgs { place, .. }
+            | TypeError::TooGeneral { place, .. }
+            | TypeError::UnmatchedSignature { place, .. }
+            | TypeError::DuplicateSignature { place, .. }
+            | TypeError::UnknownClass { place, .. }
+            | TypeError::ConstraintVar { place, .. }
+            | TypeError::NoInstance { place, .. }
+            | TypeError::MissingConstraint { place, .. } => *place,
+        }
+    }
+
+    // A name for the kind of error (see `Diagnostic::code`)
+    pub fn code(&self) -> &'static str {
+        match self {
+            TypeError::Mismatch { .. } => "type-mismatch",
+            TypeError::UnknownType { .. } => "unknown-type",
+            TypeError::TypeArgs { .. } => "type-args",
+            TypeError::TooGeneral { .. } => "too-general",
+            TypeError::UnmatchedSignature { .. } => "unmatched-signature",
+            TypeError::DuplicateSignature { .. } => "duplicate-signature",
+            TypeError::UnknownClass { .. } => "unknown-class",
+            TypeError::ConstraintVar { .. } => "constraint-var",
+            TypeError::NoInstance { .. } => "no-instance",
+            TypeError::MissingConstraint { .. } => "missing-constraint",
+        }
+    }
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TypeError::Mismatch {
+                decl,
+                expr,
+                expected,
+                found,
+                ..
+            } => write!(
+                f,
+                "Type error in '{}': expected '{}', found '{}' in '{}'",
+                decl, expected, found, expr
+            ),
+            TypeError::UnknownType { decl, name, .. } => {
+                write!(f, "Type error in '{}': unknown type '{}'", decl, name)
+            }
+            TypeError::TypeArgs {
+                decl,
+                name,
+                expected,
+                found,
+                ..
+            } => write!(
+                f,
+                "Type error in '{}': '{}' takes {} type argument{}, found {}",
+                decl,
+                name,
+                expected,
+                if *expected == 1 { "" } else { "s" },
+                found
+            ),
+            TypeError::TooGeneral {
+                decl,
+                signature,
+                found,
+                ..
+            } => write!(
+                f,
+                "Type error in '{}': the signature '{}' is more general than the type '{}'",
+                decl, signature, found
+            ),
+            TypeError::UnmatchedSignature { decl, .. } => {
+                write!(
+                    f,
+                    "Type error: '{}' has a signature, but isn't declared",
+                    decl
+                )
+            }
+            TypeError::DuplicateSignature { decl, .. } => {
+                write!(f, "Type error: '{}' has more than one signature", decl)
+            }
+            TypeError::UnknownClass { decl, name, .. } => {
+                write!(f, "Type error in '{}': unknown class '{}'", decl, name)
+            }
+            TypeError::ConstraintVar { decl, var, .. } => write!(
+                f,
+                "Type error in '{}': '{}' has a constraint, but isn't in the signature",
+                decl, var
+            ),
+            TypeError::NoInstance {
+                decl,
+                expr,
+                class,
+                type_,
+                ..
+            } => write!(
+                f,
+                "Type error in '{}': '{}' isn't an instance of '{}' in '{}'",
+                decl, type_, class, expr
+            ),
+            TypeError::MissingConstraint {
+                decl,
+                signature,
+                constraint,
+                ..
+            } => write!(
+                f,
+                "Type error in '{}': the signature '{}' needs the constraint '{}'",
+                decl, signature, constraint
+            ),
+        }
+    }
+}
+
+pub fn infer(decls: &[Decl]) -> Inference {
+    Inferer::new().infer_decls(decls)
+}
+
+pub fn infer_gradual(decls: &[Decl]) -> Inference {
+    Inferer::gradual().infer_decls(decls)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tego_parser::ast::Prog;
+    use tego_parser::Span;
+
+    fn infer_source(source: &str, infer: fn(&[Decl]) -> Inference) -> Inference {
+        match tego_parser::prog(Span::new(source)).unwrap().1 {
+            Prog::Binary(_, decls) | Prog::Library(decls) => infer(&decls),
+        }
+    }
+
+    // The type of each declaration, or the errors
+    fn types(source: &str) -> Result<Vec<String>, Vec<String>> {
+        types_with(source, infer)
+    }
+
+    fn types_with(
+        source: &str,
+        infer: fn(&[Decl]) -> Inference,
+    ) -> Result<Vec<String>, Vec<String>> {
+        let inference = infer_source(source, infer);
+        if inference.errors.is_empty() {
+            Ok(inference
+                .types
+                .iter()
+                .map(|(ident, scheme)| format!("{} : {}", ident, scheme))
+                .collect())
+        } else {
+            Err(inference.errors.iter().map(TypeError::to_string).collect())
+        }
+    }
+
+    basic_test! {
+        literal_test
+        types("a = 1\nb = true\nc = 'c'\nd = \"s\"\ne = [1]\nf = 1, true")
+            => Ok(vec![
+                "a : Int".into(),
+                "b : Bool".into(),
+                "c : Char".into(),
+                "d : String".into(),
+                "e : [Int]".into(),
+                "f : Any".into(),
+            ])
+    }
+
+    basic_test! {
+        function_test
+        types("id a = a") => Ok(vec!["id : a -> a".into()]);
+        types("const a b = a") => Ok(vec!["const : a -> b -> a".into()]);
+        types("compose f g x = f (g x)") => Ok(vec!["compose : (a -> b) -> (c -> a) -> c -> b".into()]);
+        types("addOne n = n + 1") => Ok(vec!["addOne : Int -> Int".into()]);
+        types("isZero n = if n == 0 then true else false") => Ok(vec!["isZero : Int -> Bool".into()]);
+        types("unbox [a] = a") => Ok(vec!["unbox : [a] -> a".into()]);
+        types("greet name = \"Hi, \" ++ name") => Ok(vec!["greet : String -> String".into()]);
+        types("snoc xs x = xs ++ [x]") => Ok(vec!["snoc : [a] -> a -> [a]".into()]);
+        types("append a b = a ++ b") => Ok(vec!["append : [a] -> [a] -> [a]".into()]);
+        types("twice c = c; c") => Ok(vec!["twice : a -> a".into()])
+    }
+
+    basic_test! {
+        polymorphism_test
+        // `id` is generalized before `main` is inferred
+        types("main = id 1, id true\nid a = a") => Ok(vec!["main : Any".into(), "id : a -> a".into()]);
+        types("main = let f = fn a -> a in (f 1) == 1 and f true") => Ok(vec!["main : Bool".into()])
+    }
+
+    basic_test! {
+        recursion_test
+        types("sumTo n = match n to\n\t| 0 -> 0\n\t| n -> n + sumTo (n - 1)") => Ok(vec!["sumTo : Int -> Int".into()]);
+        types("isEven n = if n == 0 then true else isOdd (n - 1)\nisOdd n = if n == 0 then false else isEven (n - 1)")
+            => Ok(vec!["isEven : Int -> Bool".into(), "isOdd : Int -> Bool".into()])
+    }
+
+    basic_test! {
+        any_test
+        // Builtins and tuples can be anything
+        types("a = println 1") => Ok(vec!["a : Any".into()]);
+        types("head list = match list to\n\t| h, _ -> h\n\t| () -> 0") => Ok(vec!["head : a -> Any".into()]);
+        // Applying an `Int` indexes a tuple
+        types("second = 1 (1, 2)") => Ok(vec!["second : Any".into()]);
+        types("get tuple index = index tuple\nmain = get (1, 2) 0") => Ok(vec!["get : a -> (a -> b) -> b".into(), "main : Any".into()]);
+        types("main = expectError (1 + true)") => Ok(vec!["main : Any".into()])
+    }
+
+    basic_test! {
+        error_test
+        types("a = 1 + true") => Err(vec!["Type error in 'a': expected 'Int', found 'Bool' in 'true'".into()]);
+        types("a = if 1 then 2 else 3") => Err(vec!["Type error in 'a': expected 'Bool', found 'Int' in '1'".into()]);
+        types("a = if true then 2 else 'c'") => Err(vec!["Type error in 'a': expected 'Int', found 'Char' in ''c''".into()]);
+        types("a = true 1") => Err(vec!["Type error in 'a': expected 'Int -> a', found 'Bool' in 'true'".into()]);
+        types("f n = n + 1\nmain = f 'c'") => Err(vec!["Type error in 'main': expected 'Char -> a', found 'Int -> Int' in 'f'".into()]);
+        types("f x = x x") => Err(vec!["Type error in 'f': expected 'a -> b', found 'a' in 'x'".into()]);
+        types("a = [1] ++ [true]") => Err(vec!["Type error in 'a': expected '[Int]', found '[Bool]' in '[true]'".into()]);
+        types("a = 1 ++ 2") => Err(vec!["Type error in 'a': expected '[a]', found 'Int' in '1'".into()]);
+        types("a = 1; ()") => Err(vec!["Type error in 'a': expected '()', found 'Int' in '1'".into()]);
+        types("c : Command Int\nc = return 1\na = c; 2") => Err(vec!["Type error in 'a': expected 'Command a', found 'Int' in '2'".into()])
+    }
+
+    basic_test! {
+        signature_test
+        types("add : Int -> Int -> Int\nadd a b = a + b") => Ok(vec!["add : Int -> Int -> Int".into()]);
+        // A signature can be less general than the declaration's type
+        types("id : Int -> Int\nid a = a") => Ok(vec!["id : Int -> Int".into()]);
+        types("main = id true\nid : Int -> Int\nid a = a")
+            => Err(vec!["Type error in 'main': expected 'Bool -> a', found 'Int -> Int' in 'id'".into()]);
+        types("f : (a -> b) -> [a] -> [b]\nf g [a] = [g a]") => Ok(vec!["f : (a -> b) -> [a] -> [b]".into()]);
+        types("read : Command String\nread = readLine") => Ok(vec!["read : Command String".into()]);
+        // Declarations with signatures can be used at different types in their own group
+        types("f : a -> a\nf a = let b = g 1 in a\ng a = f a, f true")
+            => Ok(vec!["f : a -> a".into(), "g : Int -> Any".into()])
+    }
+
+    basic_test! {
+        signature_error_test
+        types("add : Int -> Int -> Bool\nadd a b = a + b")
+            => Err(vec!["Type error in 'add': expected 'Int -> Int -> Bool', found 'Int -> Int -> Int' in 'add'".into()]);
+        types("id : a -> a\nid a = a + 1")
+            => Err(vec!["Type error in 'id': the signature 'a -> a' is more general than the type 'Int -> Int'".into()]);
+        types("const : a -> b -> a\nconst a b = b")
+            => Err(vec!["Type error in 'const': the signature 'a -> b -> a' is more general than the type 'a -> a -> a'".into()]);
+        types("a : Number\na = 1") => Err(vec!["Type error in 'a': unknown type 'Number'".into()]);
+        types("a : Command\na = readLine") => Err(vec!["Type error in 'a': 'Command' takes 1 type argument, found 0".into()]);
+        types("a : Int") => Err(vec!["Type error: 'a' has a signature, but isn't declared".into()]);
+        types("a : Int\na : Int\na = 1") => Err(vec!["Type error: 'a' has more than one signature".into()])
+    }
+
+    basic_test! {
+        class_test
+        types("add a b = a + b") => Ok(vec!["add : Num a => a -> a -> a".into()]);
+        types("same a b = a == b") => Ok(vec!["same : Eq a => a -> a -> Bool".into()]);
+        // `Ord a` already means `Eq a`
+        types("f a b = a == b or a < b") => Ok(vec!["f : Ord a => a -> a -> Bool".into()]);
+        types("f a b = [a] == [b]") => Ok(vec!["f : Eq a => a -> a -> Bool".into()]);
+        types("f a b c = a < b and c == 1") => Ok(vec!["f : Ord a => a -> a -> Int -> Bool".into()]);
+        // Constraints are checked when the type is known
+        types("same a b = a == b\nmain = same 1 2, same \"a\" \"b\"") =>
+            Ok(vec!["same : Eq a => a -> a -> Bool".into(), "main : Any".into()]);
+        types("id a = a\nmain = id == id") =>
+            Err(vec!["Type error in 'main': 'a -> a' isn't an instance of 'Eq' in 'id == id'".into()]);
+        types("same a b = a == b\nmain = same [id] [id]\nid a = a") =>
+            Err(vec!["Type error in 'main': '[a -> a]' isn't an instance of 'Eq' in 'same'".into()]);
+        types("a = true + false") =>
+            Err(vec!["Type error in 'a': 'Bool' isn't an instance of 'Num' in 'true + false'".into()])
+    }
+
+    basic_test! {
+        class_signature_test
+        types("max : Ord a => a -> a -> a\nmax a b = if a > b then a else b")
+            => Ok(vec!["max : Ord a => a -> a -> a".into()]);
+        types("same : Ord a => a -> a -> Bool\nsame a b = a == b")
+            => Ok(vec!["same : Ord a => a -> a -> Bool".into()]);
+        types("same : (Eq a, Show b) => a -> a -> b -> b\nsame a b c = if a == b then c else c")
+            => Ok(vec!["same : (Eq a, Show b) => a -> a -> b -> b".into()]);
+        types("same : a -> a -> Bool\nsame a b = a == b")
+            => Err(vec!["Type error in 'same': the signature 'a -> a -> Bool' needs the constraint 'Eq a'".into()]);
+        types("f : Eq b => a -> a\nf a = a")
+            => Err(vec!["Type error in 'f': 'b' has a constraint, but isn't in the signature".into()]);
+        types("f : Hash a => a -> a\nf a = a")
+            => Err(vec!["Type error in 'f': unknown class 'Hash'".into()])
+    }
+
+    basic_test! {
+        gradual_test
+        types_with("a = 1 + true\nb = a 1", infer_gradual) => Ok(vec!["a : Any".into(), "b : Any".into()]);
+        types_with("add : Int -> Int -> Int\nadd a b = a + b\nmain = add true", infer_gradual)
+            => Ok(vec!["add : Int -> Int -> Int".into(), "main : Any".into()]);
+        types_with("add : Int -> Int -> Bool\nadd a b = a + b", infer_gradual)
+            => Err(vec!["Type error in 'add': expected 'Int -> Int -> Bool', found 'Int -> Int -> Int' in 'add'".into()]);
+        // Unannotated declarations are `Dynamic` in signatures
+        types_with("f : Int -> Dynamic\nf n = g n\ng n = n + true", infer_gradual)
+            => Ok(vec!["f : Int -> Any".into(), "g : Any".into()]);
+        types("f : Dynamic -> Int\nf a = a") => Ok(vec!["f : Any -> Int".into()])
+    }
+}
@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Type {
+    Int,
+    Bool,
+    Char,
+    String,
+    Boxed(Box<Type>),
+    Fn_(Box<Type>, Box<Type>),
+    Command(Box<Type>),
+    Var(usize),
+    // A value whose type can't be inferred (ex. a tuple made with `,`), which is
+    // compatible with every type
+    Any,
+}
+
+impl Type {
+    pub fn fn_(param: Type, result: Type) -> Self {
+        Type::Fn_(Box::new(param), Box::new(result))
+    }
+
+    pub fn boxed(inner: Type) -> Self {
+        Type::Boxed(Box::new(inner))
+    }
+
+    pub fn command(result: Type) -> Self {
+        Type::Command(Box::new(result))
+    }
+
+    // Type variables in the order they appear
+    pub(crate) fn vars(&self, vars: &mut Vec<usize>) {
+        match self {
+            Type::Var(var) if !vars.contains(var) => vars.push(*var),
+            Type::Boxed(inner) | Type::Command(inner) => inner.vars(vars),
+            Type::Fn_(param, result) => {
+                param.vars(vars);
+                result.vars(vars);
+            }
+            _ => (),
+        }
+    }
+
+    fn fmt_with(&self, f: &mut fmt::Formatter, names: &HashMap<usize, String>) -> fmt::Result {
+        match self {
+            Type::Int => write!(f, "Int"),
+            Type::Bool => write!(f, "Bool"),
+            Type::Char => write!(f, "Char"),
+            Type::String => write!(f, "String"),
+            Type::Any => write!(f, "Any"),
+            Type::Var(var) => match names.get(var) {
+                Some(name) => write!(f, "{}", name),
+                None => write!(f, "{}", var_name(*var)),
+            },
+            Type::Boxed(inner) => {
+                write!(f, "[")?;
+                inner.fmt_with(f, names)?;
+                write!(f, "]")
+            }
+            Type::Fn_(param, result) => {
+                // `->` is right associative
+                if let Type::Fn_(..) = **param {
+                    write!(f, "(")?;
+                    param.fmt_with(f, names)?;
+                    write!(f, ")")?;
+                } else {
+                    param.fmt_with(f, names)?;
+                }
+                write!(f, " -> ")?;
+                result.fmt_with(f, names)
+            }
+            Type::Command(result) => {
+                write!(f, "Command ")?;
+                match **result {
+                    Type::Fn_(..) | Type::Command(_) => {
+                        write!(f, "(")?;
+                        result.fmt_with(f, names)?;
+                        write!(f, ")")
+                    }
+                    _ => result.fmt_with(f, names),
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_with(f, &HashMap::new())
+    }
+}
+
+// 'a' through 'z', then 'a1', 'b1', ...
+fn var_name(index: usize) -> String {
+    let letter = (b'a' + (index % 26) as u8) as char;
+    match index / 26 {
+        0 => letter.to_string(),
+        n => format!("{}{}", letter, n),
+    }
+}
+
+// A group of types that share operations
+// Only these classes exist, and only the builtin types are instances (there
+// are no class or instance declarations), so the type checker only uses them
+// to check constraints: nothing is passed to functions at runtime, since the
+// interpreter already works out what operators do from the values they're
+// applied to
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Class {
+    // `==` and `!=`
+    Eq,
+    // `<`, `>`, `<=`, and `>=`
+    Ord,
+    // Values with a readable representation
+    Show,
+    // `+`, `-`, `*`, `/`, and `%`
+    Num,
+}
+
+impl Class {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Eq" => Some(Class::Eq),
+            "Ord" => Some(Class::Ord),
+            "Show" => Some(Class::Show),
+            "Num" => Some(Class::Num),
+            _ => None,
+        }
+    }
+
+    // What has to be true for `type_` to be an instance (ex. `[a]` is `Eq` if
+    // `a` is), or `None` if it can't be one
+    pub(crate) fn instance(self, type_: &Type) -> Option<Vec<(Class, Type)>> {
+        match (self, type_) {
+            (_, Type::Any) | (_, Type::Int) => Some(vec![]),
+            (class, Type::Var(_)) => Some(vec![(class, type_.clone())]),
+            (Class::Num, _) => None,
+            (_, Type::Bool) | (_, Type::Char) | (_, Type::String) => Some(vec![]),
+            (class, Type::Boxed(inner)) => class.instance(inner),
+            (_, Type::Fn_(..)) | (_, Type::Command(_)) => None,
+        }
+    }
+
+    // Whether knowing that a type is an instance of `self` means that it's an instance of `other`
+    pub(crate) fn implies(self, other: Class) -> bool {
+        self == other || (self, other) == (Class::Ord, Class::Eq)
+    }
+}
+
+impl fmt::Display for Class {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Class::Eq => "Eq",
+            Class::Ord => "Ord",
+            Class::Show => "Show",
+            Class::Num => "Num",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// A type that's polymorphic over `vars` (ex. `a -> a`), where some of them
+// have to be instances of a class (ex. `Eq a => a -> a -> Bool`)
+#[derive(Debug, PartialEq, Clone)]
+pub struct Scheme {
+    pub vars: Vec<usize>,
+    pub constraints: Vec<(Class, usize)>,
+    pub type_: Type,
+}
+
+impl Scheme {
+    pub fn mono(type_: Type) -> Self {
+        Scheme {
+            vars: vec![],
+            constraints: vec![],
+            type_,
+        }
+    }
+}
+
+// Type variables are named in the order they appear, so that `a -> b` is
+// always shown the same way, no matter how the variables are numbered
+impl fmt::Display for Scheme {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut types = vec![self.type_.clone()];
+        types.extend(self.constraints.iter().map(|(_, var)| Type::Var(*var)));
+        let names = display_together(&types.iter().collect::<Vec<_>>());
+        let constraints = self
+            .constraints
+            .iter()
+            .zip(&names[1..])
+            .map(|((class, _), var)| format!("{} {}", class, var))
+            .collect::<Vec<_>>();
+        match constraints.as_slice() {
+            [] => write!(f, "{}", names[0]),
+            [constraint] => write!(f, "{} => {}", constraint, names[0]),
+            _ => write!(f, "({}) => {}", constraints.join(", "), names[0]),
+        }
+    }
+}
+
+// Shows the types with the same names for their type variables
+pub(crate) fn display_together(types: &[&Type]) -> Vec<String> {
+    struct Named<'a>(&'a Type, &'a HashMap<usize, String>);
+
+    impl fmt::Display for Named<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            self.0.fmt_with(f, self.1)
+        }
+    }
+
+    let mut vars = vec![];
+    for type_ in types {
+        type_.vars(&mut vars);
+    }
+    let names = vars
+        .into_iter()
+        .enumerate()
+        .map(|(i, var)| (var, var_name(i)))
+        .collect();
+    types
+        .iter()
+        .map(|type_| Named(type_, &names).to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    basic_test! {
+        display_test
+        Type::fn_(Type::Int, Type::fn_(Type::Bool, Type::Char)).to_string() => "Int -> Bool -> Char";
+        Type::fn_(Type::fn_(Type::Int, Type::Int), Type::Int).to_string() => "(Int -> Int) -> Int";
+        Type::command(Type::boxed(Type::String)).to_string() => "Command [String]";
+        Type::command(Type::fn_(Type::Int, Type::Any)).to_string() => "Command (Int -> Any)";
+        Scheme { vars: vec![7, 3], constraints: vec![], type_: Type::fn_(Type::Var(7), Type::Var(3)) }.to_string() => "a -> b";
+        Scheme {
+            vars: vec![7, 3],
+            constraints: vec![(Class::Eq, 3), (Class::Show, 7)],
+            type_: Type::fn_(Type::Var(7), Type::Var(3))
+        }.to_string() => "(Eq b, Show a) => a -> b";
+        Scheme::mono(Type::Var(27)).to_string() => "a"
+    }
+}
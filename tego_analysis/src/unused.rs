@@ -0,0 +1,166 @@
+// Finds bindings that are never used
+//
+// Names that start with `_` (ex. `_unused`) are never reported, so a binding
+// can be kept on purpose, like a parameter that a callback has to take.
+
+use crate::spans::{self, Node, Place};
+use crate::types::deps::{free_vars, pattern_bindings};
+use std::collections::HashSet;
+use tego_parser::ast::{Decl, Expr, Match};
+
+// Declarations that can't be reached from `main` or from what the file exports
+// A file without `main` or `export` exports every declaration, so all of them are used
+pub fn decls(decls: &[Decl]) -> Vec<String> {
+    let bodies = decls
+        .iter()
+        .filter_map(|decl| match decl {
+            Decl::Expression(ident, body) => Some((ident.as_str(), body)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let mut roots = decls
+        .iter()
+        .filter_map(|decl| match decl {
+            Decl::Export(idents) => Some(idents.iter().map(String::as_str)),
+            _ => None,
+        })
+        .flatten()
+        .collect::<Vec<_>>();
+    let has_main = bodies.iter().any(|(ident, _)| *ident == "main");
+    if roots.is_empty() && !has_main {
+        return vec![];
+    }
+    if has_main {
+        roots.push("main");
+    }
+
+    let mut used = HashSet::new();
+    while let Some(ident) = roots.pop() {
+        if !used.insert(ident.to_string()) {
+            continue;
+        }
+        for (_, body) in bodies.iter().filter(|(other, _)| *other == ident) {
+            for var in free_vars(body) {
+                if let Some((dep, _)) = bodies.iter().find(|(other, _)| *other == var) {
+                    roots.push(dep);
+                }
+            }
+        }
+    }
+    bodies
+        .iter()
+        .map(|(ident, _)| *ident)
+        .filter(|ident| !used.contains(*ident) && !ident.starts_with('_'))
+        .map(String::from)
+        .collect()
+}
+
+// Parameters and local bindings in `expr` that its body never uses, and where
+// each one is bound
+pub fn locals(expr: &Expr) -> Vec<(String, Place)> {
+    let mut unused = vec![];
+    collect(expr, &mut unused);
+    unused
+        .into_iter()
+        .map(|(ident, pattern)| {
+            let place =
+                spans::position(expr, Node::Match(pattern)).map_or(Place::Decl, Place::Node);
+            (ident.to_string(), place)
+        })
+        .collect()
+}
+
+fn collect<'a>(expr: &'a Expr, unused: &mut Vec<(&'a str, &'a Match)>) {
+    match expr {
+        Expr::Variable(_) | Expr::Literal(_) => (),
+        Expr::Boxed(a) | Expr::Unary(_, a) => collect(a, unused),
+        Expr::FnApp(a, b) | Expr::Binary(a, _, b) => {
+            collect(a, unused);
+            collect(b, unused);
+        }
+        Expr::If(cond, a, b) => {
+            collect(cond, unused);
+            collect(a, unused);
+            collect(b, unused);
+        }
+        Expr::Fn_(pattern, body) => {
+            check(pattern, &[body], unused);
+            collect(body, unused);
+        }
+        // `delay` declarations can refer to themselves
+        Expr::Delayed(pattern, val, body) => {
+            check(pattern, &[val, body], unused);
+            collect(val, unused);
+            collect(body, unused);
+        }
+        Expr::Let(pattern, val, body) | Expr::Do(val, pattern, body) => {
+            collect(val, unused);
+            check(pattern, &[body], unused);
+            collect(body, unused);
+        }
+        Expr::Match(val, arms) => {
+            collect(val, unused);
+            for (pattern, body) in arms {
+                check(pattern, &[body], unused);
+                collect(body, unused);
+            }
+        }
+    }
+}
+
+fn check<'a>(pattern: &'a Match, scope: &[&Expr], unused: &mut Vec<(&'a str, &'a Match)>) {
+    let used = scope
+        .iter()
+        .flat_map(|expr| free_vars(expr))
+        .collect::<Vec<_>>();
+    unused.extend(
+        pattern_bindings(pattern)
+            .into_iter()
+            .filter(|(ident, _)| !ident.starts_with('_') && !used.iter().any(|var| var == ident)),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tego_parser::ast::Prog;
+    use tego_parser::Span;
+
+    fn parse(source: &str) -> Vec<Decl> {
+        match tego_parser::prog(Span::new(source)).unwrap().1 {
+            Prog::Binary(_, decls) | Prog::Library(decls) => decls,
+        }
+    }
+
+    fn unused_locals(source: &str) -> Vec<String> {
+        parse(source)
+            .iter()
+            .flat_map(|decl| match decl {
+                Decl::Expression(_, body) => {
+                    locals(body).into_iter().map(|(ident, _)| ident).collect()
+                }
+                _ => vec![],
+            })
+            .collect()
+    }
+
+    basic_test! {
+        decls_test
+        decls(&parse("main = f 1\nf a = g a\ng a = a\nh = 2")) => vec!["h".to_string()];
+        decls(&parse("main = 1\n_h = 2")) => Vec::<String>::new();
+        decls(&parse("export (f)\nf = 1\ng = 2")) => vec!["g".to_string()];
+        // Every declaration in a library without `export` is exported
+        decls(&parse("f = 1\ng = 2")) => Vec::<String>::new()
+    }
+
+    basic_test! {
+        locals_test
+        unused_locals("f a b = a") => vec!["b".to_string()];
+        unused_locals("f a _b = let c = a in 1") => vec!["c".to_string()];
+        unused_locals("f list = match list to\n\t| () -> 0\n\t| head, tail -> 1 + f tail") =>
+            vec!["head".to_string()];
+        // A binding that's shadowed before it's used is unused
+        unused_locals("f a = let a = 1 in a") => vec!["a".to_string()];
+        unused_locals("f = delay xs = 1, xs in xs") => Vec::<String>::new()
+    }
+}
@@ -0,0 +1,59 @@
+// Compares the native `map`, `filter`, `foldl`, and `zip` with the same
+// functions written in tego (the way they used to be declared in the prelude)
+// Run with `cargo bench -p tego_interpreter`
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use tego_interpreter::Engine;
+
+const SOURCE: &str = "
+xs = range 0 200
+
+slowMap f list =
+	match list to
+	| () -> ()
+	| head, tail -> f head, slowMap f tail
+
+slowFilter pred list =
+	match list to
+	| () -> ()
+	| head, tail ->
+		if pred head then
+			head, slowFilter pred tail
+		else
+			slowFilter pred tail
+
+slowFoldl f acc list =
+	match list to
+	| () -> acc
+	| head, tail -> slowFoldl f (f acc head) tail
+
+slowZip listA listB =
+	match [listA], [listB] to
+	| [()], _ -> ()
+	| _, [()] -> ()
+	| [headA, tailA], [headB, tailB] -> [headA, headB], slowZip tailA tailB
+";
+
+fn list_benchmark(c: &mut Criterion) {
+    let mut engine = Engine::new();
+    engine.load_source(SOURCE).unwrap();
+    let cases = [
+        ("map", "map (fn x -> x * 2) xs"),
+        ("filter", "filter (fn x -> x % 2 == 0) xs"),
+        ("foldl", "foldl (fn acc -> fn x -> acc + x) 0 xs"),
+        ("zip", "zip xs xs"),
+    ];
+    for (name, expr) in cases.iter() {
+        let mut group = c.benchmark_group(*name);
+        group.bench_function("native", |b| b.iter(|| engine.eval_expr_str(expr).unwrap()));
+        let slow = format!("slow{}{}", name[..1].to_uppercase(), &name[1..]);
+        let slow_expr = expr.replacen(name, &slow, 1);
+        group.bench_function("tego", |b| {
+            b.iter(|| engine.eval_expr_str(&slow_expr).unwrap())
+        });
+        group.finish();
+    }
+}
+
+criterion_group!(benches, list_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,111 @@
+// Runtime checks for declarations with a signature, when the type checker only
+// checks those declarations (gradual typing)
+//
+// Values can come from code that wasn't type checked, so every argument is
+// checked against the signature when the function is called, and the result
+// is checked when it's returned. Functions that are passed in or returned are
+// checked in the same way when they're called.
+
+use crate::value::function::Function;
+use crate::value::Value;
+use std::cell::Cell;
+use tego_parser::ast::TypeExpr;
+
+thread_local! {
+    static CHECKED: Cell<bool> = const { Cell::new(false) };
+}
+
+pub fn check_signatures(check: bool) {
+    CHECKED.with(|checked| checked.set(check))
+}
+
+pub fn signatures_checked() -> bool {
+    CHECKED.with(|checked| checked.get())
+}
+
+// A function declaration, checked against its signature
+pub(crate) fn wrap(decl: &str, signature: &TypeExpr, value: Value) -> Value {
+    match signature {
+        TypeExpr::Constrained(_, type_) => wrap(decl, type_, value),
+        TypeExpr::Fn_(..) => check(decl, signature, value),
+        _ => value,
+    }
+}
+
+// `value`, if it has the type `type_`, with functions wrapped so they're
+// checked when they're called
+pub(crate) fn check(decl: &str, type_: &TypeExpr, value: Value) -> Value {
+    match (type_, value) {
+        (_, Value::Error(error)) => Value::Error(error),
+        (TypeExpr::Constrained(_, type_), value) => check(decl, type_, value),
+        (TypeExpr::Var(_), value) => value,
+        (TypeExpr::Named(name, _), value) if name == "Any" || name == "Dynamic" => value,
+        (TypeExpr::Named(name, _), value) if name == "String" && is_string(&value) => value,
+        (TypeExpr::Named(name, _), value)
+            if matches!(
+                (name.as_str(), &value),
+                ("Int", Value::Int(_))
+                    | ("Bool", Value::Bool(_))
+                    | ("Char", Value::Char(_))
+                    | ("Command", Value::Command(_))
+            ) =>
+        {
+            value
+        }
+        (TypeExpr::Boxed(inner), Value::Boxed(value)) => match check(decl, inner, *value) {
+            Value::Error(error) => Value::Error(error),
+            value => Value::Boxed(Box::new(value)),
+        },
+        (TypeExpr::Fn_(..), Value::Function(function)) => Value::Function(Function::Checked(
+            decl.to_string(),
+            type_.clone(),
+            Box::new(Value::Function(function)),
+        )),
+        (type_, value) => Value::Error(
+            format!(
+                "Type error in '{}': expected '{}', found '{}'",
+                decl,
+                type_,
+                value.type_()
+            )
+            .into(),
+        ),
+    }
+}
+
+// Strings are boxed tuples of `Char`s, and a string of one `Char` is that `Char`
+fn is_string(value: &Value) -> bool {
+    match value {
+        Value::Boxed(inner) => matches!(**inner, Value::Char(_)) || value.as_string().is_some(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tego_parser::TypeOutput;
+
+    fn int_to_int() -> TypeExpr {
+        TypeExpr::function(
+            TypeExpr::named("Int", vec![]),
+            TypeExpr::named("Int", vec![]),
+        )
+    }
+
+    basic_test! {
+        check_test
+        check("f", &TypeExpr::named("Int", vec![]), Value::Int(1)) => Value::Int(1);
+        check("f", &TypeExpr::named("Dynamic", vec![]), Value::Bool(true)) => Value::Bool(true);
+        check("f", &TypeExpr::boxed(TypeExpr::var("a")), Value::Boxed(Box::new(Value::Int(1))))
+            => Value::Boxed(Box::new(Value::Int(1)));
+        check("f", &TypeExpr::named("Int", vec![]), Value::Bool(true))
+            => Value::Error("Type error in 'f': expected 'Int', found 'Bool'".into());
+        check("f", &TypeExpr::named("String", vec![]), Value::Boxed(Box::new(Value::Char('a')))).is_error() => false;
+        wrap("f", &int_to_int(), Value::internal_fn(|_| Value::Bool(true))).apply(Value::Int(1))
+            => Value::Error("Type error in 'f': expected 'Int', found 'Bool'".into());
+        wrap("f", &int_to_int(), Value::internal_fn(|n| n)).apply(Value::Char('c'))
+            => Value::Error("Type error in 'f': expected 'Int', found 'Char'".into());
+        wrap("f", &int_to_int(), Value::internal_fn(|n| n)).apply(Value::Int(2)) => Value::Int(2)
+    }
+}
@@ -0,0 +1,277 @@
+// What a program is allowed to do, so untrusted programs can be run safely
+// (see `EvalConfig::sandboxed`)
+
+use crate::error::RuntimeError;
+use crate::label;
+use crate::random;
+use crate::time;
+use std::cell::{Cell, RefCell};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct EvalConfig {
+    // Running subprocesses with `exec`
+    pub exec: bool,
+    // HTTP requests (with the `http` feature)
+    pub network: bool,
+    // Importing files (the standard library can always be imported)
+    pub files: bool,
+    // Reading input with `readLine` and `readInt`
+    pub stdin: bool,
+    // Reading environment variables with `getEnv`
+    pub env_vars: bool,
+    // Ending the program with `exit` (the host is given `RuntimeError::Exit`,
+    // which a server might not expect)
+    pub exit: bool,
+    // How many expressions can be evaluated before the program is stopped
+    pub max_steps: Option<u64>,
+    // How many expressions can be evaluated inside each other (ex. by a
+    // function that calls itself) before the program is stopped
+    pub max_depth: Option<usize>,
+    // How many values a tuple (or characters a string) can have, since
+    // growing tuples is how programs use up memory
+    pub max_tuple_len: Option<usize>,
+    // `Int` arithmetic that overflows wraps around, instead of being an error
+    pub wrapping: bool,
+    // Random builtins start from the same seed, and the clock is virtual (see
+    // `time::unix_millis`), so every run of a program has the same output
+    pub deterministic: bool,
+}
+
+impl EvalConfig {
+    // Nothing outside of the program can be used, and it can only run for so long
+    pub fn sandboxed() -> Self {
+        EvalConfig {
+            exec: false,
+            network: false,
+            files: false,
+            stdin: false,
+            env_vars: false,
+            exit: false,
+            max_steps: Some(10_000_000),
+            max_depth: Some(10_000),
+            max_tuple_len: Some(1_000_000),
+            wrapping: false,
+            deterministic: false,
+        }
+    }
+}
+
+// Subprocesses can't be run unless the host explicitly allows it, and a
+// program that recurses forever is stopped before it uses up the memory
+impl Default for EvalConfig {
+    fn default() -> Self {
+        EvalConfig {
+            exec: false,
+            network: true,
+            files: true,
+            stdin: true,
+            env_vars: true,
+            exit: true,
+            max_steps: None,
+            max_depth: Some(100_000),
+            max_tuple_len: None,
+            wrapping: false,
+            deterministic: false,
+        }
+    }
+}
+
+thread_local! {
+    static CONFIG: RefCell<EvalConfig> = RefCell::new(EvalConfig::default());
+    static STEPS: Cell<u64> = const { Cell::new(0) };
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+    static INTERRUPTED: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
+}
+
+// Results in the configuration that was used before, and starts over (see `restart`)
+pub fn set_config(config: EvalConfig) -> EvalConfig {
+    let previous = CONFIG.with(|current| current.replace(config));
+    restart();
+    previous
+}
+
+// Starts counting steps again, forgets an interrupt (and a `break`, see
+// `label.rs`), and if evaluation is deterministic, starts the random numbers
+// and the virtual clock over (ex. before each test, so they don't depend on
+// which tests ran before)
+pub fn restart() {
+    STEPS.with(|steps| steps.set(0));
+    INTERRUPTED.with(|interrupted| interrupted.store(false, Ordering::SeqCst));
+    label::reset();
+    if deterministic() {
+        random::seed(random::FIXED_SEED);
+        time::reset_clock();
+    }
+}
+
+pub fn config() -> EvalConfig {
+    CONFIG.with(|config| config.borrow().clone())
+}
+
+pub fn allow_exec(allow: bool) {
+    CONFIG.with(|config| config.borrow_mut().exec = allow)
+}
+
+pub fn exec_allowed() -> bool {
+    CONFIG.with(|config| config.borrow().exec)
+}
+
+pub fn wrap_ints(wrap: bool) {
+    CONFIG.with(|config| config.borrow_mut().wrapping = wrap)
+}
+
+pub fn wrapping() -> bool {
+    CONFIG.with(|config| config.borrow().wrapping)
+}
+
+pub fn set_deterministic(deterministic: bool) {
+    CONFIG.with(|config| config.borrow_mut().deterministic = deterministic);
+    restart();
+}
+
+pub fn deterministic() -> bool {
+    CONFIG.with(|config| config.borrow().deterministic)
+}
+
+pub fn network_allowed() -> bool {
+    CONFIG.with(|config| config.borrow().network)
+}
+
+pub fn files_allowed() -> bool {
+    CONFIG.with(|config| config.borrow().files)
+}
+
+pub fn stdin_allowed() -> bool {
+    CONFIG.with(|config| config.borrow().stdin)
+}
+
+pub fn env_vars_allowed() -> bool {
+    CONFIG.with(|config| config.borrow().env_vars)
+}
+
+pub fn exit_allowed() -> bool {
+    CONFIG.with(|config| config.borrow().exit)
+}
+
+// Setting this flag (ex. from another thread, or a signal handler) stops what
+// this thread is evaluating at its next step, with `RuntimeError::Interrupted`
+// It stays set until evaluation starts over (see `restart`), so the error
+// isn't lost while the program stops
+pub fn interrupt_flag() -> Arc<AtomicBool> {
+    INTERRUPTED.with(Arc::clone)
+}
+
+// Counts an expression being evaluated
+pub(crate) fn step() -> Result<(), RuntimeError> {
+    steps(1)
+}
+
+// Counts work that builtins do without evaluating expressions (ex. comparing
+// values while sorting), so it can't get around the step limit
+pub(crate) fn steps(count: u64) -> Result<(), RuntimeError> {
+    if INTERRUPTED.with(|interrupted| interrupted.load(Ordering::Relaxed)) {
+        return Err(RuntimeError::Interrupted);
+    }
+    let max_steps = CONFIG.with(|config| config.borrow().max_steps);
+    let steps = STEPS.with(|steps| {
+        steps.set(steps.get().saturating_add(count));
+        steps.get()
+    });
+    match max_steps {
+        Some(max_steps) if steps > max_steps => Err(format!(
+            "Stopped after {} steps (the program can't run for any longer)",
+            max_steps
+        )
+        .into()),
+        _ => Ok(()),
+    }
+}
+
+// An expression that's being evaluated, inside the ones that were entered
+// before it, until it's dropped
+pub(crate) struct Depth(());
+
+pub(crate) fn enter() -> Result<Depth, RuntimeError> {
+    let max_depth = CONFIG.with(|config| config.borrow().max_depth);
+    let depth = DEPTH.with(|depth| depth.get());
+    match max_depth {
+        Some(max_depth) if depth >= max_depth => Err(RuntimeError::TooDeep(max_depth)),
+        _ => {
+            DEPTH.with(|current| current.set(depth + 1));
+            Ok(Depth(()))
+        }
+    }
+}
+
+impl Drop for Depth {
+    fn drop(&mut self) {
+        DEPTH.with(|depth| depth.set(depth.get().saturating_sub(1)));
+    }
+}
+
+pub(crate) fn check_tuple_len(len: usize) -> Result<(), String> {
+    match CONFIG.with(|config| config.borrow().max_tuple_len) {
+        Some(max_len) if len > max_len => {
+            Err(format!("A tuple can't have more than {} values", max_len))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_test() {
+        let previous = set_config(EvalConfig {
+            max_steps: Some(2),
+            ..EvalConfig::default()
+        });
+        assert_eq!(step(), Ok(()));
+        assert_eq!(step(), Ok(()));
+        assert!(step().is_err());
+        set_config(previous);
+        assert_eq!(step(), Ok(()));
+    }
+
+    #[test]
+    fn depth_test() {
+        let previous = set_config(EvalConfig {
+            max_depth: Some(2),
+            ..EvalConfig::default()
+        });
+        let outer = enter().unwrap();
+        let inner = enter().unwrap();
+        assert_eq!(enter().err(), Some(RuntimeError::TooDeep(2)));
+        drop(inner);
+        assert!(enter().is_ok());
+        drop(outer);
+        set_config(previous);
+    }
+
+    #[test]
+    fn interrupt_test() {
+        restart();
+        assert_eq!(step(), Ok(()));
+        interrupt_flag().store(true, Ordering::SeqCst);
+        assert_eq!(step(), Err(RuntimeError::Interrupted));
+        assert_eq!(step(), Err(RuntimeError::Interrupted));
+        restart();
+        assert_eq!(step(), Ok(()));
+    }
+
+    #[test]
+    fn deterministic_test() {
+        let run = || (random::int(), time::unix_millis(), time::unix_millis());
+        set_deterministic(true);
+        let first = run();
+        restart();
+        assert_eq!(run(), first);
+        assert_eq!(first.1, 946_684_800_000);
+        assert_eq!(first.2 - first.1, 1000);
+        set_deterministic(false);
+    }
+}
@@ -0,0 +1,104 @@
+// Base64 (the standard alphabet, with `=` padding) and hexadecimal encodings
+// of bytes, used by the 'base64Encode', 'base64Decode', 'hexEncode', and
+// 'hexDecode' builtins
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub fn base64_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (i, &byte)| {
+            group | u32::from(byte) << (16 - 8 * i)
+        });
+        // A chunk of n bytes is n + 1 characters, and the rest is padding
+        for i in 0..4 {
+            if i <= chunk.len() {
+                let index = (group >> (18 - 6 * i)) & 0x3f;
+                encoded.push(BASE64_ALPHABET[index as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+pub fn base64_decode(s: &str) -> Result<Vec<u8>, String> {
+    let s = s.trim_end_matches('=');
+    if s.len() % 4 == 1 {
+        return Err("'base64Decode' expects a Base64 string with a valid length".into());
+    }
+    let mut bytes = Vec::with_capacity(s.len() * 3 / 4);
+    let digits = s
+        .chars()
+        .map(|c| {
+            BASE64_ALPHABET
+                .iter()
+                .position(|&d| d as char == c)
+                .map(|d| d as u32)
+                .ok_or_else(|| format!("'base64Decode' found '{}', which isn't Base64", c))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    for chunk in digits.chunks(4) {
+        let group = chunk
+            .iter()
+            .enumerate()
+            .fold(0, |group, (i, digit)| group | digit << (18 - 6 * i));
+        // A chunk of n characters is n - 1 bytes
+        for i in 0..chunk.len() - 1 {
+            bytes.push((group >> (16 - 8 * i)) as u8);
+        }
+    }
+    Ok(bytes)
+}
+
+pub fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub fn hex_decode(s: &str) -> Result<Vec<u8>, String> {
+    let digits = s
+        .chars()
+        .map(|c| {
+            c.to_digit(16)
+                .ok_or_else(|| format!("'hexDecode' found '{}', which isn't a hex digit", c))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if digits.len() % 2 != 0 {
+        return Err("'hexDecode' expects an even number of hex digits".into());
+    }
+    Ok(digits
+        .chunks(2)
+        .map(|pair| (pair[0] * 16 + pair[1]) as u8)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    basic_test! {
+        base64_test
+        base64_encode(b"") => "";
+        base64_encode(b"f") => "Zg==";
+        base64_encode(b"fo") => "Zm8=";
+        base64_encode(b"foo") => "Zm9v";
+        base64_encode(b"foobar") => "Zm9vYmFy";
+        base64_encode(&[0xff, 0xfe]) => "//4=";
+        base64_decode("Zm9vYmE=") => Ok(b"fooba".to_vec());
+        base64_decode("Zm9vYg") => Ok(b"foob".to_vec());
+        base64_decode("//4=") => Ok(vec![0xff, 0xfe]);
+        base64_decode("Zm9vY") => Err("'base64Decode' expects a Base64 string with a valid length".into());
+        base64_decode("Zm9!") => Err("'base64Decode' found '!', which isn't Base64".into())
+    }
+
+    basic_test! {
+        hex_test
+        hex_encode(b"") => "";
+        hex_encode(&[0, 15, 255]) => "000fff";
+        hex_decode("000fFF") => Ok(vec![0, 15, 255]);
+        hex_decode("abc") => Err("'hexDecode' expects an even number of hex digits".into());
+        hex_decode("0g") => Err("'hexDecode' found 'g', which isn't a hex digit".into())
+    }
+}
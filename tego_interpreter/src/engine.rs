@@ -0,0 +1,474 @@
+// Runs tego code from a Rust program, without using the parser or the
+// environment directly
+//
+//     let mut engine = Engine::new();
+//     engine.load_source("main = double 21\ndouble n = n * 2")?;
+//     assert_eq!(engine.run()?, Value::Int(42));
+//     assert_eq!(engine.eval_expr_str("double 4")?, Value::Int(8));
+//
+// Functions written in Rust can be called from tego, once they're registered:
+//
+//     engine.register_fn("half", 1, |n| match n {
+//         Value::Int(n) => Ok(Value::Int(n / 2)),
+//         n => Err(format!("'half' expects an 'Int', found '{}'", n.type_()).into()),
+//     })?;
+//
+// Declarations can also be called directly, with values from Rust:
+//
+//     assert_eq!(engine.call("double", &[Value::Int(5)])?, Value::Int(10));
+//
+// Rust closures can be passed to them as callbacks, with `Value::native` for a
+// function, or `Value::command_fn` for a Command that calls the closure when
+// it's run:
+//
+//     let log = Value::command_fn(1, |message| {
+//         eprintln!("{}", message.to_plain_string());
+//         Ok(Value::unit())
+//     });
+//     engine.call("process", &[log])?;
+//
+// Untrusted programs can be run with a sandbox, which stops them from using
+// anything outside of the program and limits how long they can run for:
+//
+//     engine.set_config(EvalConfig::sandboxed());
+//
+// And they can be made to give the same output every time they're run, with
+// the same random numbers and times (ex. for tests):
+//
+//     engine.set_config(EvalConfig { deterministic: true, ..EvalConfig::default() });
+//
+// A program that runs for too long can be stopped from another thread, with
+// the flag from `capability::interrupt_flag` (which is for the thread that's
+// running it), and it results in `RuntimeError::Interrupted`
+//
+// Programs aren't type checked (see `tego_analysis` for that), so type errors
+// are only found when the code that has them is run.
+
+use crate::capability::{self, EvalConfig};
+use crate::error::{RuntimeError, TegoError};
+use crate::interpreter::{eval_expr, host_env, VarEnv, WrappedEnv};
+use crate::output::{self, Sink};
+use crate::value::Value;
+use std::cell::RefCell;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use tego_parser::ast::{Decl, Expr, Prog};
+use tego_parser::ParseError;
+
+pub struct Engine {
+    // Imports are relative to this directory
+    dir: PathBuf,
+    env: WrappedEnv,
+    decls: Vec<Decl>,
+    main: Option<Expr>,
+    // Functions from the host, which can be used by everything that's loaded
+    natives: Vec<(String, Value)>,
+    // Where `println` writes to, if it isn't stdout
+    output: Option<Sink>,
+    // What the engine's programs are allowed to do
+    config: EvalConfig,
+}
+
+impl Engine {
+    // An engine with only the prelude, which imports relative to the current directory
+    pub fn new() -> Self {
+        Engine::with_dir(".")
+    }
+
+    pub fn with_dir<P: AsRef<Path>>(dir: P) -> Self {
+        let dir = dir.as_ref().to_owned();
+        Engine {
+            env: host_env(&[], &dir, &[]).expect("Nothing is imported"),
+            dir,
+            decls: vec![],
+            main: None,
+            natives: vec![],
+            output: None,
+            config: EvalConfig::default(),
+        }
+    }
+
+    // Replaces whatever was loaded before with the declarations in `source`
+    // (and everything they import)
+    pub fn load_source(&mut self, source: &str) -> Result<(), TegoError> {
+        let (main, decls) = match tego_parser::prog(source.into()) {
+            Ok((_, Prog::Binary(main, decls))) => (Some(main), decls),
+            Ok((_, Prog::Library(decls))) => (None, decls),
+            Err(error) => return Err(ParseError::from(error).into()),
+        };
+        Ok(self.load_decls(decls, main)?)
+    }
+
+    pub(crate) fn load_decls(
+        &mut self,
+        decls: Vec<Decl>,
+        main: Option<Expr>,
+    ) -> Result<(), RuntimeError> {
+        self.env = self
+            .with_config(|| host_env(&decls, &self.dir, &self.natives))
+            .map_err(RuntimeError::Import)?;
+        self.decls = decls;
+        self.main = main;
+        Ok(())
+    }
+
+    // Makes `f` available as `name`, which takes `arity` arguments, and is
+    // called with them as a tuple (so a function with one argument gets it as
+    // it is)
+    // The program's declarations and imports take precedence over it, but it
+    // takes precedence over builtins
+    pub fn register_fn<F>(&mut self, name: &str, arity: usize, f: F) -> Result<(), RuntimeError>
+    where
+        F: Fn(Value) -> Result<Value, RuntimeError> + 'static,
+    {
+        self.register_value(name, Value::native(arity, f))
+    }
+
+    // Makes `value` available as `name`, the same way as `register_fn` (ex. the
+    // REPL's `it`), replacing whatever was registered as `name` before
+    pub fn register_value(&mut self, name: &str, value: Value) -> Result<(), RuntimeError> {
+        self.natives.retain(|(native, _)| native != name);
+        // It can outlive the declarations it was made with
+        self.natives.push((name.into(), value.into_exported()));
+        self.env = self
+            .with_config(|| host_env(&self.decls, &self.dir, &self.natives))
+            .map_err(RuntimeError::Import)?;
+        Ok(())
+    }
+
+    // Sends the output of the Commands that the engine runs to `sink`, instead
+    // of stdout (see `output::Buffer` to read it afterwards)
+    pub fn set_output<W: Write + 'static>(&mut self, sink: W) {
+        self.output = Some(Rc::new(RefCell::new(sink)));
+    }
+
+    // Limits what programs can do from now on (imports that were already
+    // loaded aren't affected)
+    // The step limit applies to each call to `run`, `eval_expr_str`, and `call`
+    pub fn set_config(&mut self, config: EvalConfig) {
+        self.config = config;
+    }
+
+    // Evaluates the loaded program's `main`, and runs it if it's a Command
+    pub fn run(&self) -> Result<Value, RuntimeError> {
+        match &self.main {
+            Some(main) => self.result(|| eval_expr(main.clone(), &self.env)),
+            None => Err(RuntimeError::NoMain),
+        }
+    }
+
+    // Evaluates an expression that can use everything that's been loaded, and
+    // runs it if it's a Command
+    pub fn eval_expr_str(&self, source: &str) -> Result<Value, TegoError> {
+        match tego_parser::complete(tego_parser::expr)(source.into()) {
+            Ok((_, expr)) => Ok(self.eval(expr)?),
+            Err(error) => Err(ParseError::from(error).into()),
+        }
+    }
+
+    pub(crate) fn eval(&self, expr: Expr) -> Result<Value, RuntimeError> {
+        self.result(|| eval_expr(expr, &self.env))
+    }
+
+    // Applies the declaration (or builtin) `name` to each of `args` in turn, and
+    // runs the result if it's a Command
+    pub fn call(&self, name: &str, args: &[Value]) -> Result<Value, RuntimeError> {
+        let function = match VarEnv::get(&self.env, name) {
+            Some(function) => function,
+            None => return Err(RuntimeError::Undeclared(name.into())),
+        };
+        self.result(|| {
+            args.iter().fold(
+                function.eval(Some(Rc::clone(&self.env))),
+                |function, arg| function.apply(arg.clone()),
+            )
+        })
+    }
+
+    // Evaluates with the engine's configuration, and runs Commands with the
+    // engine's output
+    fn result(&self, eval: impl FnOnce() -> Value) -> Result<Value, RuntimeError> {
+        let value = self.with_config(|| {
+            let value = eval();
+            match &self.output {
+                Some(sink) => {
+                    let previous = output::set_output(Some(Rc::clone(sink)));
+                    let result = value.run();
+                    output::set_output(previous);
+                    result.unwrap_or(value)
+                }
+                None => value.run().unwrap_or(value),
+            }
+        });
+        match value {
+            Value::Error(error) => Err(error),
+            value => Ok(value),
+        }
+    }
+
+    // Installs the engine's configuration while `f` runs, which also starts
+    // counting steps again
+    fn with_config<T>(&self, f: impl FnOnce() -> T) -> T {
+        let previous = capability::set_config(self.config.clone());
+        let result = f();
+        capability::set_config(previous);
+        result
+    }
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Engine::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryFrom;
+    use std::sync::atomic::Ordering;
+
+    fn run(source: &str) -> Result<Value, TegoError> {
+        let mut engine = Engine::new();
+        engine.load_source(source)?;
+        Ok(engine.run()?)
+    }
+
+    fn eval(source: &str, expr: &str) -> Result<Value, TegoError> {
+        let mut engine = Engine::new();
+        engine.load_source(source)?;
+        engine.eval_expr_str(expr)
+    }
+
+    basic_test! {
+        engine_test
+        run("main = double 21\ndouble n = n * 2") => Ok(Value::Int(42));
+        run("main = return (1 + 2)") => Ok(Value::Int(3));
+        run("main = 1 + true").is_err() => true;
+        run("double n = n * 2") => Err(RuntimeError::NoMain.into());
+        // The host decides what `exit` does
+        run("main = do exit 3 then println 1") => Err(RuntimeError::Exit(3).into());
+        eval("double n = n * 2", "double 4") => Ok(Value::Int(8));
+        // The prelude is always available
+        Engine::new().eval_expr_str("length (1, 2, 3)") => Ok(Value::Int(3));
+        matches!(Engine::new().eval_expr_str("1 +"), Err(TegoError::Parse(_))) => true;
+        matches!(Engine::new().load_source("main = ("), Err(TegoError::Parse(_))) => true
+    }
+
+    fn with_natives() -> Engine {
+        let mut engine = Engine::new();
+        engine
+            .register_fn("half", 1, |n| match n {
+                Value::Int(n) => Ok(Value::Int(n / 2)),
+                n => Err(format!("'half' expects an 'Int', found '{}'", n.type_()).into()),
+            })
+            .unwrap();
+        engine
+            .register_fn("sub", 2, |args| {
+                let (a, b) = <(i32, i32)>::try_from(args)?;
+                Ok(Value::Int(a - b))
+            })
+            .unwrap();
+        engine
+    }
+
+    basic_test! {
+        native_test
+        with_natives().eval_expr_str("half 10") => Ok(Value::Int(5));
+        with_natives().eval_expr_str("sub 5 2") => Ok(Value::Int(3));
+        with_natives().eval_expr_str("map (sub 10) (1, 2)").map(|v| v.to_string()) => Ok("(9, 8)".into());
+        with_natives().eval_expr_str("half true")
+            => Err(RuntimeError::Value("'half' expects an 'Int', found 'Bool'".into()).into());
+        // Errors are passed on without calling the function
+        with_natives().eval_expr_str("half (1 + true)").is_err() => true;
+        {
+            let mut engine = with_natives();
+            engine.load_source("main = quarter 20\nquarter n = half (half n)").unwrap();
+            engine.run()
+        } => Ok(Value::Int(5))
+    }
+
+    // `show` renders values the way they're written, so its result parses
+    // back to the same value
+    #[test]
+    fn show_test() {
+        let engine = Engine::new();
+        for source in &[
+            "(1, -2, true, 'c')",
+            "\"\", \"text\", [1, [\"nested\"]]",
+            "[()], ['a'], [(1, 2)]",
+            "[\"a \" ,, '\"' ,, \"quote\"]",
+        ] {
+            let value = engine.eval_expr_str(source).unwrap();
+            let shown = engine.eval_expr_str(&format!("show ({})", source)).unwrap();
+            let shown = shown.as_string().unwrap();
+            assert_eq!(shown, value.display_pretty());
+            assert_eq!(engine.eval_expr_str(&shown).unwrap(), value, "{}", shown);
+        }
+    }
+
+    #[test]
+    fn register_value_test() {
+        let mut engine = Engine::new();
+        engine.load_source("double n = n * 2").unwrap();
+        engine.register_value("it", Value::Int(1)).unwrap();
+        let double = engine.eval_expr_str("double").unwrap();
+        engine.register_value("it", double).unwrap();
+        // The function still works once its declarations are replaced
+        engine.load_source("main = it 21").unwrap();
+        assert_eq!(engine.run(), Ok(Value::Int(42)));
+    }
+
+    #[test]
+    fn interrupt_test() {
+        let mut engine = Engine::new();
+        engine.load_source("double n = n * 2").unwrap();
+        engine
+            .register_fn("interrupt", 1, |value| {
+                capability::interrupt_flag().store(true, Ordering::SeqCst);
+                Ok(value)
+            })
+            .unwrap();
+        assert_eq!(
+            engine.eval_expr_str("double (interrupt 1)"),
+            Err(RuntimeError::Interrupted.into())
+        );
+        // It can't be caught
+        assert_eq!(
+            engine.eval_expr_str("catch (double (interrupt 1)) (fn _ -> 0)"),
+            Err(RuntimeError::Interrupted.into())
+        );
+        // The next evaluation isn't interrupted, and nothing is forgotten
+        assert_eq!(engine.eval_expr_str("double 2"), Ok(Value::Int(4)));
+    }
+
+    basic_test! {
+        unused_break_test
+        // The next evaluation doesn't see a `break` that wasn't used by its `label`
+        {
+            let engine = Engine::new();
+            engine.eval_expr_str("let exit = label (fn break -> break) in exit 1").unwrap_err();
+            engine.eval_expr_str("1 + 1")
+        } => Ok(Value::Int(2))
+    }
+
+    fn call(source: &str, name: &str, args: &[Value]) -> Result<Value, RuntimeError> {
+        let mut engine = with_natives();
+        engine.load_source(source).unwrap();
+        engine.call(name, args)
+    }
+
+    basic_test! {
+        output_test
+        {
+            let buffer = output::Buffer::new();
+            let mut engine = Engine::new();
+            engine.set_output(buffer.clone());
+            engine.load_source("main = do println \"a\" then println 1").unwrap();
+            engine.run().unwrap();
+            engine.eval_expr_str("println (1, 2)").unwrap();
+            buffer.contents()
+        } => "a\n1\n(1, 2)\n"
+    }
+
+    basic_test! {
+        call_test
+        call("add a b = a + b", "add", &[Value::Int(1), Value::Int(2)]) => Ok(Value::Int(3));
+        call("add a b = a + b", "add", &[Value::Int(1)]).map(|add| add.apply(Value::Int(2))) => Ok(Value::Int(3));
+        call("answer = 42", "answer", &[]) => Ok(Value::Int(42));
+        // Builtins and natives can be called, too
+        call("answer = 42", "length", &[Value::from(vec![Value::Int(1), Value::Int(2)])]) => Ok(Value::Int(2));
+        call("answer = 42", "half", &[Value::Int(8)]) => Ok(Value::Int(4));
+        call("add a b = a + b", "add", &[Value::Int(1), Value::Bool(true)]).is_err() => true;
+        call("answer = 42", "missing", &[]) => Err(RuntimeError::Undeclared("missing".into()))
+    }
+
+    fn sandboxed(config: EvalConfig, source: &str) -> Result<Value, TegoError> {
+        let mut engine = Engine::new();
+        engine.set_config(config);
+        engine.load_source(source)?;
+        Ok(engine.run()?)
+    }
+
+    fn limits(max_steps: Option<u64>, max_tuple_len: Option<usize>) -> EvalConfig {
+        EvalConfig {
+            max_steps,
+            max_tuple_len,
+            ..EvalConfig::sandboxed()
+        }
+    }
+
+    basic_test! {
+        config_test
+        sandboxed(EvalConfig::sandboxed(), "main = 1 + 2") => Ok(Value::Int(3));
+        sandboxed(EvalConfig::sandboxed(), "main = exec \"ls\" ()")
+            => Err(RuntimeError::Value("Running 'ls' isn't allowed (subprocesses are disabled)".into()).into());
+        sandboxed(EvalConfig::sandboxed(), "main = readLine") => Err(RuntimeError::Value("Reading input isn't allowed".into()).into());
+        sandboxed(EvalConfig::sandboxed(), "main = getEnv \"HOME\"")
+            => Err(RuntimeError::Value("Reading 'HOME' isn't allowed (environment variables are disabled)".into()).into());
+        sandboxed(EvalConfig::sandboxed(), "import \"missing.tgo\"\nmain = 1")
+            => Err(RuntimeError::Import("Can't import 'missing.tgo': reading files isn't allowed (only the standard library can be imported)".into()).into());
+        // The standard library is part of the interpreter
+        sandboxed(EvalConfig::sandboxed(), "import \"Math.tgo\"\nmain = 1") => Ok(Value::Int(1));
+        sandboxed(limits(Some(1000), None), "main = loop 0\nloop n = loop (n + 1)")
+            => Err(RuntimeError::Value("Stopped after 1000 steps (the program can't run for any longer)".into()).into());
+        // Native builtins count their steps too
+        sandboxed(limits(Some(1000), None), &format!("main = sort ({})", vec!["2, 1"; 1000].join(", ")))
+            => Err(RuntimeError::Value("Stopped after 1000 steps (the program can't run for any longer)".into()).into());
+        sandboxed(EvalConfig::sandboxed(), "main = exit 3")
+            => Err(RuntimeError::Value("Exiting isn't allowed".into()).into());
+        sandboxed(EvalConfig { max_depth: Some(500), ..EvalConfig::sandboxed() }, "main = go 1000\ngo n = if n == 0 then 0 else 1 + go (n - 1)")
+            => Err(RuntimeError::TooDeep(500).into());
+        sandboxed(EvalConfig { max_depth: Some(500), ..EvalConfig::sandboxed() }, "main = go 10\ngo n = if n == 0 then 0 else 1 + go (n - 1)")
+            => Ok(Value::Int(10));
+        // Deep recursion doesn't overflow the stack (which is small for tests)
+        sandboxed(EvalConfig::default(), "main = go 5000\ngo n = match n to\n\t| 0 -> 0\n\t| _ -> go (n - 1)")
+            => Ok(Value::Int(0));
+        sandboxed(limits(None, Some(3)), "main = (1, 2) ,, (3, 4)")
+            => Err(RuntimeError::Value("A tuple can't have more than 3 values".into()).into());
+        sandboxed(limits(None, Some(3)), "main = (1, 2), 3") => Ok(Value::from((1, 2, 3)));
+        sandboxed(EvalConfig::default(), "main = 7 % (1 - 1)")
+            => Err(RuntimeError::DivisionByZero("7 % 0".into()).into());
+        sandboxed(EvalConfig::default(), "main = 2147483647 * 2")
+            => Err(RuntimeError::Overflow("2147483647 * 2".into()).into());
+        sandboxed(EvalConfig { wrapping: true, ..EvalConfig::default() }, "main = 2147483647 * 2") => Ok(Value::Int(-2));
+        // Steps are counted again for every evaluation
+        {
+            let mut engine = Engine::new();
+            engine.set_config(limits(Some(20), None));
+            engine.load_source("double n = n * 2").unwrap();
+            (engine.eval_expr_str("double 1"), engine.eval_expr_str("double 2"))
+        } => (Ok(Value::Int(2)), Ok(Value::Int(4)))
+    }
+
+    // Collects everything the callback is called with
+    fn logger() -> (Value, Rc<RefCell<Vec<Value>>>) {
+        let log = Rc::new(RefCell::new(vec![]));
+        let messages = Rc::clone(&log);
+        let callback = Value::command_fn(1, move |message| {
+            messages.borrow_mut().push(message);
+            Ok(Value::unit())
+        });
+        (callback, log)
+    }
+
+    basic_test! {
+        callback_test
+        call("twice f x = f (f x)", "twice", &[Value::native(1, |n| Ok(n * Value::Int(3))), Value::Int(2)])
+            => Ok(Value::Int(18));
+        {
+            let (log, messages) = logger();
+            call("process log = do log 1 then do log 2 then return 3", "process", &[log]).unwrap();
+            messages.take()
+        } => vec![Value::Int(1), Value::Int(2)];
+        // Callbacks aren't called until their Command is run
+        {
+            let (log, messages) = logger();
+            call("ignore log = let unused = log 1 in 2", "ignore", &[log]).unwrap();
+            messages.take()
+        } => Vec::<Value>::new();
+        call("process log = log 1", "process", &[Value::command_fn(1, |_| Err("failed".into()))])
+            => Err(RuntimeError::Value("failed".into()))
+    }
+}
@@ -31,12 +31,20 @@ where
         value: V,
         parent: &EnvWrapper<Self>,
     ) -> Result<EnvWrapper<Self>, String> {
-        let parent = Rc::clone(parent);
-        value.unwrap_matches(&pattern).map(|matches| {
-            matches.into_iter().fold(parent, |parent, (ident, value)| {
+        value
+            .unwrap_matches(&pattern)
+            .map(|matches| Env::associate_matches(matches, parent))
+    }
+    // Binds the names that a pattern matched (see `EnvVal::unwrap_matches`)
+    pub fn associate_matches(
+        matches: Vec<(String, V)>,
+        parent: &EnvWrapper<Self>,
+    ) -> EnvWrapper<Self> {
+        matches
+            .into_iter()
+            .fold(Rc::clone(parent), |parent, (ident, value)| {
                 Env::associate_ident(ident, value, parent)
             })
-        })
     }
     pub fn associate_ident(ident: String, value: V, parent: EnvWrapper<Self>) -> EnvWrapper<Self> {
         Rc::new(RefCell::new(Env::Entry {
@@ -61,6 +69,26 @@ where
             }
         }
     }
+    // Every name in the environment, innermost first (a name that's shadowed
+    // is in it more than once)
+    pub fn idents(env: &EnvWrapper<Self>) -> Vec<String> {
+        let mut idents = vec![];
+        let mut env = Rc::clone(env);
+        loop {
+            let parent = match *env.borrow() {
+                Env::Empty => return idents,
+                Env::Entry {
+                    ref ident,
+                    ref parent,
+                    ..
+                } => {
+                    idents.push(ident.clone());
+                    Rc::clone(parent)
+                }
+            };
+            env = parent;
+        }
+    }
     pub fn get_evaluated_value(env: &EnvWrapper<Self>) -> Result<V, String> {
         match *env.borrow() {
             Env::Empty => Err("No variables are declared".into()),
@@ -165,6 +193,13 @@ mod tests {
             &Env::associate(Match::ident("b"), DummyValue::int("b", 2), &Env::empty()).unwrap())
             .unwrap(), "c") => None
     }
+    basic_test! {
+        idents_test
+        Env::idents(&Env::associate(Match::ident("a"), DummyValue::int("a", 1),
+            &Env::associate(Match::ident("b"), DummyValue::int("b", 2), &Env::empty()).unwrap())
+            .unwrap()) => vec!["a".to_string(), "b".to_string()];
+        Env::<DummyValue>::idents(&Env::empty()) => Vec::<String>::new()
+    }
     basic_test! {
         with_tuple_test
         Env::get(&Env::associate(
@@ -347,10 +382,7 @@ mod tests {
             }
         }
         fn is_evaluated(&self) -> bool {
-            match self {
-                DummyValue::Delayed(_) => false,
-                _ => true,
-            }
+            !matches!(self, DummyValue::Delayed(_))
         }
     }
 }
@@ -0,0 +1,251 @@
+use std::fmt;
+use tego_parser::ParseError;
+
+// Why a program couldn't be run, or failed while it was running
+#[derive(Debug, PartialEq, Clone)]
+pub enum RuntimeError {
+    // An error value from the program, or from a native function (see
+    // `Engine::register_fn`), where it becomes an error value in the program
+    Value(String),
+    // A name that was called but isn't declared (see `Engine::call`)
+    Undeclared(String),
+    // A program without a `main` was run
+    NoMain,
+    // Something the program imports couldn't be loaded
+    Import(String),
+    // `Int` arithmetic whose result doesn't fit in an `Int` (ex. `2147483647 + 1`),
+    // unless wrapping arithmetic is enabled (see `EvalConfig::wrapping`)
+    Overflow(String),
+    // `/` or `%` with 0 on the right (ex. `1 / 0`)
+    // Expressions don't have spans yet (see TODO.md), so the operation is
+    // named instead of pointed at
+    DivisionByZero(String),
+    // An `Int` applied to a tuple that doesn't have a value at that index
+    // (ex. `3 (1, 2)`), including negative indices
+    // `expr` is the application, with the index as a value
+    IndexOutOfBounds {
+        index: i32,
+        len: usize,
+        expr: String,
+    },
+    // A declaration, `let rec`, or `delay` whose value depends on itself (ex.
+    // `let rec a = a + 1`), unlike a function that calls itself
+    Cycle(String),
+    // An `Int` applied to something that isn't a tuple (ex. `0 true`)
+    NotIndexable {
+        type_: String,
+        expr: String,
+    },
+    // A function that was given more arguments than it has parameters (ex.
+    // `add 1 2 3` when `add x y = x + y`), since its result can't be applied
+    // `function` is the expression that was applied
+    Arity {
+        function: String,
+        expected: usize,
+        given: usize,
+    },
+    // Something that tego should never do (ex. a closure that outlived the
+    // declarations it was made in), which is reported instead of crashing
+    Internal(String),
+    // The host stopped the evaluation (ex. Ctrl-C in the REPL), see
+    // `capability::interrupt_flag`
+    Interrupted,
+    // A `break` that was used after its `label` finished (see `label.rs`)
+    Break,
+    // The program asked to end with an exit code (see the `exit` builtin),
+    // which whatever is running it is responsible for (ex. `tego run` exits
+    // the process with it)
+    Exit(i32),
+    // An `assert`, `assertEq`, or `expectError` that failed
+    // `expr` is the assertion (which the interpreter adds, see
+    // `interpreter::with_source`), `location` is the declaration that was being
+    // evaluated, and `details` is what was expected and what was found (boxed,
+    // so errors don't make every `Value` bigger)
+    Assertion {
+        expr: Option<String>,
+        location: Option<String>,
+        details: Option<Box<(String, String)>>,
+    },
+    // More expressions were being evaluated inside each other than the limit
+    // (see `EvalConfig::max_depth`), usually from a function that calls itself
+    // without stopping
+    TooDeep(usize),
+}
+
+impl RuntimeError {
+    // The kind of error, which is written as `E0201` (see `tego explain`)
+    pub fn number(&self) -> u16 {
+        match self {
+            RuntimeError::Value(_) => 201,
+            RuntimeError::Undeclared(_) => 202,
+            RuntimeError::NoMain => 203,
+            RuntimeError::Import(_) => 204,
+            RuntimeError::Overflow(_) => 205,
+            RuntimeError::DivisionByZero(_) => 206,
+            RuntimeError::IndexOutOfBounds { .. } => 207,
+            RuntimeError::Cycle(_) => 208,
+            RuntimeError::NotIndexable { .. } => 209,
+            RuntimeError::Arity { .. } => 210,
+            RuntimeError::Internal(_) => 211,
+            RuntimeError::Interrupted => 212,
+            RuntimeError::Break => 213,
+            RuntimeError::Exit(_) => 214,
+            RuntimeError::Assertion { .. } => 215,
+            RuntimeError::TooDeep(_) => 216,
+        }
+    }
+
+    // The kind of error, which programs are given when they catch it (see the
+    // `catch` builtin)
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RuntimeError::Value(_) => "error",
+            RuntimeError::Undeclared(_) => "undeclared",
+            RuntimeError::NoMain => "no-main",
+            RuntimeError::Import(_) => "import",
+            RuntimeError::Overflow(_) => "overflow",
+            RuntimeError::DivisionByZero(_) => "division-by-zero",
+            RuntimeError::IndexOutOfBounds { .. } => "index-out-of-bounds",
+            RuntimeError::Cycle(_) => "cycle",
+            RuntimeError::NotIndexable { .. } => "not-indexable",
+            RuntimeError::Arity { .. } => "arity",
+            RuntimeError::Internal(_) => "internal",
+            RuntimeError::Interrupted => "interrupted",
+            RuntimeError::Break => "break",
+            RuntimeError::Exit(_) => "exit",
+            RuntimeError::Assertion { .. } => "assertion",
+            RuntimeError::TooDeep(_) => "too-deep",
+        }
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RuntimeError::Value(message) | RuntimeError::Import(message) => {
+                write!(f, "{}", message)
+            }
+            RuntimeError::Undeclared(name) => write!(f, "'{}' isn't declared", name),
+            RuntimeError::NoMain => write!(f, "No 'main' found"),
+            RuntimeError::IndexOutOfBounds { index, len, expr } => write!(
+                f,
+                "Index {} is out of bounds in '{}', since the tuple's length is {}",
+                index, expr, len
+            ),
+            RuntimeError::NotIndexable { type_, expr } => write!(
+                f,
+                "Can't index a '{}' in '{}', since only tuples can be indexed",
+                type_, expr
+            ),
+            RuntimeError::Cycle(name) => write!(
+                f,
+                "'{}' depends on its own value (only functions can refer to themselves)",
+                name
+            ),
+            RuntimeError::Arity {
+                function,
+                expected,
+                given,
+            } => write!(
+                f,
+                "'{}' expects {} argument{}, but {} were given",
+                function,
+                expected,
+                if *expected == 1 { "" } else { "s" },
+                given
+            ),
+            RuntimeError::DivisionByZero(operation) => {
+                write!(f, "'{}' divides by zero", operation)
+            }
+            RuntimeError::Overflow(operation) => {
+                write!(
+                    f,
+                    "'{}' overflows (the result doesn't fit in an 'Int')",
+                    operation
+                )
+            }
+            RuntimeError::Internal(message) => {
+                write!(f, "Internal error: {} (this is a bug in tego)", message)
+            }
+            RuntimeError::Interrupted => write!(f, "Interrupted"),
+            RuntimeError::Break => write!(f, "'break' was used after its 'label' finished"),
+            RuntimeError::Exit(code) => write!(f, "Exited with code {}", code),
+            RuntimeError::TooDeep(max_depth) => write!(
+                f,
+                "Stopped {} expressions deep (the program can't recurse any deeper)",
+                max_depth
+            ),
+            RuntimeError::Assertion {
+                expr,
+                location,
+                details,
+            } => {
+                write!(f, "Assertion failed")?;
+                if let Some(location) = location {
+                    write!(f, " in '{}'", location)?;
+                }
+                if let Some(expr) = expr {
+                    write!(f, ": {}", expr)?;
+                }
+                match details.as_deref() {
+                    Some((expected, found)) => {
+                        write!(f, "\n  expected: {}\n  found: {}", expected, found)
+                    }
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+impl std::error::Error for RuntimeError {}
+
+impl From<String> for RuntimeError {
+    fn from(message: String) -> Self {
+        RuntimeError::Value(message)
+    }
+}
+
+impl From<&str> for RuntimeError {
+    fn from(message: &str) -> Self {
+        RuntimeError::Value(message.into())
+    }
+}
+
+// Anything that can go wrong when running tego code, so that parsing and
+// running can both use `?`
+#[derive(Debug, PartialEq, Clone)]
+pub enum TegoError {
+    Parse(ParseError),
+    Runtime(RuntimeError),
+}
+
+impl fmt::Display for TegoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TegoError::Parse(error) => write!(f, "{}", error),
+            TegoError::Runtime(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for TegoError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TegoError::Parse(error) => Some(error),
+            TegoError::Runtime(error) => Some(error),
+        }
+    }
+}
+
+impl From<ParseError> for TegoError {
+    fn from(error: ParseError) -> Self {
+        TegoError::Parse(error)
+    }
+}
+
+impl From<RuntimeError> for TegoError {
+    fn from(error: RuntimeError) -> Self {
+        TegoError::Runtime(error)
+    }
+}
@@ -0,0 +1,311 @@
+// printf-style formatting, used by the 'format' builtin
+//
+// A placeholder is `{}` or `{:spec}`, where `spec` is `[[fill]align][0][width][.precision][type]`:
+// * `align` is `<` (left), `>` (right), or `^` (center)
+// * `0` pads `Int`s with zeros after the sign
+// * `precision` is the most characters of a string to show
+// * `type` is one of:
+//   * nothing: any value, with strings shown as they are
+//   * `d`: an `Int`
+//   * `x`: an `Int` in hexadecimal
+//   * `c`: a `Char`
+//   * `s`: a `String`
+//   * `b`: a `Bool`
+//   * `?`: any value, shown the same way the REPL does
+//
+// `{{` and `}}` are a literal `{` and `}`
+
+use crate::value::Value;
+use std::iter::Peekable;
+use std::str::Chars;
+
+#[derive(Debug, PartialEq, Default)]
+struct Spec {
+    fill: Option<char>,
+    align: Option<Align>,
+    zero: bool,
+    width: usize,
+    precision: Option<usize>,
+    kind: Kind,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+enum Kind {
+    #[default]
+    Plain,
+    Int,
+    Hex,
+    Char,
+    String,
+    Bool,
+    Debug,
+}
+
+enum Piece {
+    Text(String),
+    Placeholder(String, Spec),
+}
+
+pub fn format(template: &str, args: Vec<Value>) -> Result<String, String> {
+    let pieces = parse(template)?;
+    let placeholders = pieces
+        .iter()
+        .filter(|piece| matches!(piece, Piece::Placeholder(..)))
+        .count();
+    if placeholders != args.len() {
+        return Err(format!(
+            "'format' has {} placeholder{}, but was given {} value{}",
+            placeholders,
+            plural(placeholders),
+            args.len(),
+            plural(args.len())
+        ));
+    }
+    let mut args = args.into_iter().enumerate();
+    let mut result = String::new();
+    for piece in pieces {
+        match piece {
+            Piece::Text(text) => result.push_str(&text),
+            Piece::Placeholder(source, spec) => {
+                // There's one for each placeholder, since they were counted
+                let (index, arg) = args
+                    .next()
+                    .ok_or_else(|| "'format' ran out of values".to_string())?;
+                let arg = match arg {
+                    arg @ Value::Delayed { .. } => arg.eval(None),
+                    arg => arg,
+                };
+                if let Value::Error(error) = arg {
+                    return Err(error.to_string());
+                }
+                let text = render(&spec, &arg).ok_or_else(|| {
+                    format!(
+                        "'format' placeholder {} ('{}') expects {}, found '{}'",
+                        index + 1,
+                        source,
+                        spec.kind.expected(),
+                        arg.type_()
+                    )
+                })?;
+                result.push_str(&pad(&spec, text, matches!(arg, Value::Int(_))));
+            }
+        }
+    }
+    Ok(result)
+}
+
+fn plural(n: usize) -> &'static str {
+    if n == 1 {
+        ""
+    } else {
+        "s"
+    }
+}
+
+fn parse(template: &str) -> Result<Vec<Piece>, String> {
+    let mut pieces = vec![];
+    let mut text = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('{', Some('{')) | ('}', Some('}')) => {
+                chars.next();
+                text.push(c);
+            }
+            ('{', _) => {
+                let mut source = String::from("{");
+                for c in chars.by_ref() {
+                    source.push(c);
+                    if c == '}' {
+                        break;
+                    }
+                }
+                if !source.ends_with('}') {
+                    return Err(format!(
+                        "'format' placeholder '{}' is missing a '}}'",
+                        source
+                    ));
+                }
+                let spec = parse_spec(&source[1..source.len() - 1])
+                    .map_err(|e| format!("'format' placeholder '{}' {}", source, e))?;
+                pieces.push(Piece::Text(std::mem::take(&mut text)));
+                pieces.push(Piece::Placeholder(source, spec));
+            }
+            ('}', _) => return Err("'format' has a '}' without a matching '{'".into()),
+            (c, _) => text.push(c),
+        }
+    }
+    pieces.push(Piece::Text(text));
+    Ok(pieces)
+}
+
+fn parse_spec(spec: &str) -> Result<Spec, String> {
+    let mut chars = match spec.strip_prefix(':') {
+        Some(spec) => spec.chars().peekable(),
+        None if spec.is_empty() => return Ok(Spec::default()),
+        None => return Err("must start with ':'".into()),
+    };
+    let mut result = Spec::default();
+
+    // A fill character is only a fill character if it's followed by an alignment
+    let mut lookahead = chars.clone();
+    match (lookahead.next(), lookahead.next().and_then(align)) {
+        (Some(fill), Some(alignment)) => {
+            result.fill = Some(fill);
+            result.align = Some(alignment);
+            chars = lookahead;
+        }
+        (Some(c), None) if align(c).is_some() => {
+            result.align = align(c);
+            chars.next();
+        }
+        _ => (),
+    }
+    if chars.peek() == Some(&'0') {
+        result.zero = true;
+        chars.next();
+    }
+    result.width = number(&mut chars).unwrap_or(0);
+    if chars.peek() == Some(&'.') {
+        chars.next();
+        result.precision =
+            Some(number(&mut chars).ok_or_else(|| "is missing a precision after '.'".to_string())?);
+    }
+    result.kind = match chars.next() {
+        None => Kind::Plain,
+        Some('d') => Kind::Int,
+        Some('x') => Kind::Hex,
+        Some('c') => Kind::Char,
+        Some('s') => Kind::String,
+        Some('b') => Kind::Bool,
+        Some('?') => Kind::Debug,
+        Some(c) => return Err(format!("has an unknown type '{}'", c)),
+    };
+    match (chars.next(), result.kind, result.precision) {
+        (Some(c), _, _) => Err(format!("has an unexpected '{}'", c)),
+        (None, Kind::Plain, _) | (None, Kind::String, _) | (None, _, None) => Ok(result),
+        (None, _, Some(_)) => Err("can only have a precision for strings".into()),
+    }
+}
+
+fn align(c: char) -> Option<Align> {
+    match c {
+        '<' => Some(Align::Left),
+        '>' => Some(Align::Right),
+        '^' => Some(Align::Center),
+        _ => None,
+    }
+}
+
+fn number(chars: &mut Peekable<Chars>) -> Option<usize> {
+    let mut digits = String::new();
+    while let Some(c) = chars.peek().filter(|c| c.is_ascii_digit()) {
+        digits.push(*c);
+        chars.next();
+    }
+    digits.parse().ok()
+}
+
+// Results in `None` if the value doesn't match the placeholder's type
+fn render(spec: &Spec, val: &Value) -> Option<String> {
+    let text = match (spec.kind, val) {
+        (Kind::Plain, val) => val.to_plain_string(),
+        (Kind::Debug, val) => val.to_string(),
+        (Kind::Int, Value::Int(i)) => i.to_string(),
+        (Kind::Hex, Value::Int(i)) => format!("{:x}", i),
+        (Kind::Char, Value::Char(c)) => c.to_string(),
+        (Kind::Bool, Value::Bool(b)) => b.to_string(),
+        (Kind::String, val) => val.as_string()?,
+        _ => return None,
+    };
+    Some(match spec.precision {
+        Some(precision) => text.chars().take(precision).collect(),
+        None => text,
+    })
+}
+
+fn pad(spec: &Spec, text: String, numeric: bool) -> String {
+    let len = text.chars().count();
+    if len >= spec.width {
+        return text;
+    }
+    let padding = spec.width - len;
+    if spec.zero && numeric && spec.align.is_none() {
+        return match text.strip_prefix('-') {
+            Some(digits) => format!("-{}{}", "0".repeat(padding), digits),
+            None => format!("{}{}", "0".repeat(padding), text),
+        };
+    }
+    let fill = spec.fill.unwrap_or(' ').to_string();
+    // Numbers are right-aligned by default, everything else is left-aligned
+    let (before, after) = match spec.align {
+        Some(Align::Left) => (0, padding),
+        Some(Align::Right) => (padding, 0),
+        Some(Align::Center) => (padding / 2, padding - padding / 2),
+        None if numeric => (padding, 0),
+        None => (0, padding),
+    };
+    format!("{}{}{}", fill.repeat(before), text, fill.repeat(after))
+}
+
+impl Kind {
+    fn expected(self) -> &'static str {
+        match self {
+            Kind::Int | Kind::Hex => "an 'Int'",
+            Kind::Char => "a 'Char'",
+            Kind::String => "a 'String'",
+            Kind::Bool => "a 'Bool'",
+            Kind::Plain | Kind::Debug => "a value",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fmt(template: &str, args: Vec<Value>) -> Result<String, String> {
+        format(template, args)
+    }
+
+    basic_test! {
+        placeholder_test
+        fmt("x={} y={}", vec![Value::Int(1), "two".into()]) => Ok("x=1 y=two".into());
+        fmt("{:d} {:c} {:s} {:b}", vec![Value::Int(-4), Value::Char('a'), "s".into(), Value::Bool(true)])
+            => Ok("-4 a s true".into());
+        fmt("{:?} {:?}", vec!["s".into(), Value::Char('a')]) => Ok("\"s\" 'a'".into());
+        fmt("{:x}", vec![Value::Int(255)]) => Ok("ff".into());
+        fmt("{{}} {{{}}}", vec![Value::Int(1)]) => Ok("{} {1}".into());
+        fmt("none", vec![]) => Ok("none".into())
+    }
+
+    basic_test! {
+        width_test
+        fmt("[{:5}]", vec!["ab".into()]) => Ok("[ab   ]".into());
+        fmt("[{:5d}]", vec![Value::Int(42)]) => Ok("[   42]".into());
+        fmt("[{:>5}]", vec!["ab".into()]) => Ok("[   ab]".into());
+        fmt("[{:*^6}]", vec!["ab".into()]) => Ok("[**ab**]".into());
+        fmt("[{:05d}]", vec![Value::Int(-42)]) => Ok("[-0042]".into());
+        fmt("[{:03}]", vec![Value::Int(7)]) => Ok("[007]".into());
+        fmt("[{:.2}]", vec!["abcd".into()]) => Ok("[ab]".into());
+        fmt("[{:1}]", vec!["abc".into()]) => Ok("[abc]".into())
+    }
+
+    basic_test! {
+        error_test
+        fmt("{} {}", vec![Value::Int(1)]) => Err("'format' has 2 placeholders, but was given 1 value".into());
+        fmt("{:d}", vec!["a".into()]) => Err("'format' placeholder 1 ('{:d}') expects an 'Int', found 'Boxed<String>'".into());
+        fmt("{:.2d}", vec![Value::Int(1)]) => Err("'format' placeholder '{:.2d}' can only have a precision for strings".into());
+        fmt("{:q}", vec![Value::Int(1)]) => Err("'format' placeholder '{:q}' has an unknown type 'q'".into());
+        fmt("{", vec![]) => Err("'format' placeholder '{' is missing a '}'".into());
+        fmt("}", vec![]) => Err("'format' has a '}' without a matching '{'".into());
+        fmt("{}", vec![Value::Error("oops".into())]) => Err("oops".into())
+    }
+}
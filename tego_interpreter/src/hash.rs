@@ -0,0 +1,105 @@
+// Hashes used by the 'hash' builtin, and (with the `crypto` feature) the
+// 'sha256', 'sha1', and 'md5' builtins
+
+use crate::error::RuntimeError;
+use crate::value::Value;
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+// A non-cryptographic hash (FNV-1a) of a value, which is the same for values
+// that are `==` (ex. `"ab"` and `['a', 'b']`), and on every run
+// It's never negative, so it can be used with `%` to pick a bucket
+// Functions and Commands can't be hashed, since they can't be compared
+pub fn hash(val: &Value) -> Result<i32, RuntimeError> {
+    let mut hasher = Fnv(FNV_OFFSET);
+    hasher.value(val)?;
+    Ok((hasher.0 & 0x7fff_ffff) as i32)
+}
+
+struct Fnv(u64);
+
+impl Fnv {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    // Each kind of value starts with a different byte, and tuples are
+    // written as their values, since tuples are flattened (a single value is
+    // the same as a tuple of one value)
+    fn value(&mut self, val: &Value) -> Result<(), RuntimeError> {
+        match val {
+            Value::Error(error) => return Err(error.clone()),
+            Value::Int(i) => {
+                self.write(&[0]);
+                self.write(&i.to_le_bytes());
+            }
+            Value::Char(c) => {
+                self.write(&[1]);
+                self.write(&u32::from(*c).to_le_bytes());
+            }
+            Value::Bool(b) => self.write(&[2, *b as u8]),
+            Value::Tuple(tuple) => {
+                for val in tuple {
+                    self.value(&val)?;
+                }
+            }
+            // The end is marked, so `[1], 2` and `[1, 2]` are different
+            Value::Boxed(inner) => {
+                self.write(&[3]);
+                self.value(inner)?;
+                self.write(&[4]);
+            }
+            Value::Delayed { .. } => self.value(&val.clone().eval(None))?,
+            Value::Function(_) | Value::Command(_) => {
+                return Err(format!("'hash' can't hash '{}'", val.type_()).into())
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "crypto")]
+pub fn sha256(bytes: &[u8]) -> String {
+    use sha2::Digest;
+    crate::encoding::hex_encode(&sha2::Sha256::digest(bytes))
+}
+
+#[cfg(feature = "crypto")]
+pub fn sha1(bytes: &[u8]) -> String {
+    use sha1::Digest;
+    crate::encoding::hex_encode(&sha1::Sha1::digest(bytes))
+}
+
+#[cfg(feature = "crypto")]
+pub fn md5(bytes: &[u8]) -> String {
+    use md5::Digest;
+    crate::encoding::hex_encode(&md5::Md5::digest(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    basic_test! {
+        hash_test
+        hash(&Value::from("ab"))
+            == hash(&Value::Boxed(Box::new(vec![Value::Char('a'), Value::Char('b')].into()))) => true;
+        hash(&Value::Int(1)) == hash(&vec![Value::Int(1)].into()) => true;
+        hash(&Value::Int(1)) == hash(&Value::Int(2)) => false;
+        hash(&Value::Int(1)) == hash(&Value::Boxed(Box::new(Value::Int(1)))) => false;
+        hash(&Value::from("abc")).unwrap() >= 0 => true;
+        hash(&Value::internal_fn(|val| val)).map_err(|error| error.to_string())
+            => Err("'hash' can't hash 'Fn'".into())
+    }
+
+    #[cfg(feature = "crypto")]
+    basic_test! {
+        crypto_test
+        sha256(b"abc") => "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        sha1(b"abc") => "a9993e364706816aba3e25717850c26c9cd0d89d";
+        md5(b"abc") => "900150983cd24fb0d6963f7d28e17f72"
+    }
+}
@@ -0,0 +1,128 @@
+use crate::capability;
+use std::io::{self, Read};
+use std::time::Duration;
+
+// A request that takes longer than this fails, instead of hanging the program
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const TIMEOUT: Duration = Duration::from_secs(30);
+// Bodies bigger than this (10 MiB) are an error, since the whole body is kept
+// in a string
+const MAX_BODY: u64 = 10 * 1024 * 1024;
+
+#[derive(Debug, PartialEq)]
+pub struct Response {
+    pub status: i32,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+pub fn get(url: &str) -> Result<Response, String> {
+    request("GET", url, None)
+}
+
+pub fn post(url: &str, body: &str) -> Result<Response, String> {
+    request("POST", url, Some(body))
+}
+
+// Both `http://` and `https://` urls can be requested, and responses with an
+// error status (ex. 404) are results like any other
+fn request(method: &str, url: &str, body: Option<&str>) -> Result<Response, String> {
+    if !capability::network_allowed() {
+        return Err(format!(
+            "Can't request '{}': network access isn't allowed",
+            url
+        ));
+    }
+    let request = ureq::AgentBuilder::new()
+        .timeout_connect(CONNECT_TIMEOUT)
+        .timeout(TIMEOUT)
+        .user_agent("tego")
+        .build()
+        .request(method, url);
+    let response = match body {
+        Some(body) => request.send_string(body),
+        None => request.call(),
+    };
+    let response = match response {
+        Ok(response) | Err(ureq::Error::Status(_, response)) => response,
+        Err(error) => return Err(format!("Can't request '{}': {}", url, error)),
+    };
+    let status = i32::from(response.status());
+    let headers = response
+        .headers_names()
+        .into_iter()
+        .filter_map(|name| {
+            let value = response.header(&name)?.to_string();
+            Some((name, value))
+        })
+        .collect();
+    let body = read_body(response.into_reader(), MAX_BODY)
+        .map_err(|error| format!("Can't request '{}': {}", url, error))?;
+    Ok(Response {
+        status,
+        headers,
+        body,
+    })
+}
+
+fn read_body(reader: impl Read, limit: u64) -> io::Result<String> {
+    let mut body = vec![];
+    // One byte past the limit is read, to tell if there's more
+    reader.take(limit + 1).read_to_end(&mut body)?;
+    if body.len() as u64 > limit {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("the body is bigger than {} bytes", limit),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&body).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::net::TcpListener;
+    use std::thread;
+
+    basic_test! {
+        read_body_test
+        read_body(&b"hello"[..], 5).unwrap() => "hello".to_string();
+        read_body(&b"hello!"[..], 5).map_err(|error| error.to_string())
+            => Err("the body is bigger than 5 bytes".into())
+    }
+
+    #[test]
+    fn request_test() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = vec![];
+            let mut buffer = [0; 1024];
+            // The request is done once its body has been sent
+            while !request.ends_with(b"body") {
+                let len = stream.read(&mut buffer).unwrap();
+                request.extend_from_slice(&buffer[..len]);
+            }
+            stream
+                .write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 2\r\n\r\nno")
+                .unwrap();
+            String::from_utf8_lossy(&request).to_string()
+        });
+        let response = post(&format!("http://127.0.0.1:{}/items", port), "body").unwrap();
+        let request = server.join().unwrap();
+        assert!(request.starts_with("POST /items HTTP/1.1\r\n"));
+        assert!(request.ends_with("\r\n\r\nbody"));
+        assert_eq!(response.status, 404);
+        assert_eq!(response.body, "no");
+        assert!(response
+            .headers
+            .contains(&("content-length".into(), "2".into())));
+    }
+
+    #[test]
+    fn unsupported_url_test() {
+        assert!(get("ftp://example.com").is_err());
+    }
+}
@@ -1,19 +1,65 @@
-use crate::environment::{Env, EnvWrapper};
-use crate::prelude::prelude;
-use crate::value::Value;
+use crate::boundary;
+use crate::capability;
+use crate::environment::{Env, EnvVal, EnvWrapper};
+use crate::error::RuntimeError;
+use crate::label;
+use crate::module::Modules;
+use crate::prelude::{prelude, ASSERTIONS};
+use crate::suggest;
+use crate::value::{pattern_text, Value};
+use std::convert::TryFrom;
+use std::path::Path;
 use std::rc::Rc;
-use tego_parser::ast::{BinaryOp, Decl, Expr, Prog, UnaryOp};
+use tego_parser::ast::{BinaryOp, Decl, Expr, ExprValue, Match, Prog, UnaryOp};
 
 pub type VarEnv = Env<Value>;
 pub type WrappedEnv = EnvWrapper<VarEnv>;
 
-pub fn run_prog(prog: Prog) -> Result<Value, String> {
+// `dir` is the directory of the program's file (imports are relative to it)
+// The result can still be an error value (or a Command that hasn't been run)
+pub fn run_prog(prog: Prog, dir: &Path) -> Result<Value, RuntimeError> {
     match prog {
-        Prog::Binary(main, decls) => Ok(eval_expr(main, &import_prelude(&env_from_decls(&decls)))),
-        Prog::Library(_) => Err("No 'main' found in file".into()),
+        Prog::Binary(main, decls) => {
+            let env = program_env(&decls, dir).map_err(RuntimeError::Import)?;
+            Ok(eval_expr(main, &env))
+        }
+        Prog::Library(_) => Err(RuntimeError::NoMain),
     }
 }
 
+// An environment with the declarations, everything they import, and the prelude
+pub fn program_env(decls: &[Decl], dir: &Path) -> Result<WrappedEnv, String> {
+    host_env(decls, dir, &[])
+}
+
+// Like `program_env`, with values from the host (see `Engine::register_fn`),
+// which are found before the prelude
+pub fn host_env(
+    decls: &[Decl],
+    dir: &Path,
+    host: &[(String, Value)],
+) -> Result<WrappedEnv, String> {
+    module_env(decls, Some(dir), &mut Modules::default(), host)
+}
+
+pub(crate) fn module_env(
+    decls: &[Decl],
+    dir: Option<&Path>,
+    modules: &mut Modules,
+    host: &[(String, Value)],
+) -> Result<WrappedEnv, String> {
+    let (imported, private) = modules.import(decls, dir)?;
+    // Private names are only found if nothing else has that name
+    let prelude = VarEnv::add_parent(&prelude(), &private);
+    let prelude = host.iter().fold(prelude, |parent, (ident, value)| {
+        VarEnv::associate_ident(ident.clone(), value.clone(), parent)
+    });
+    Ok(VarEnv::add_parent(
+        &env_from_decls(decls),
+        &VarEnv::add_parent(&imported, &prelude),
+    ))
+}
+
 pub fn import_prelude(env: &WrappedEnv) -> WrappedEnv {
     VarEnv::add_parent(env, &prelude())
 }
@@ -23,43 +69,74 @@ pub fn new_env() -> WrappedEnv {
 }
 
 pub fn env_from_decls(decls: &[Decl]) -> WrappedEnv {
-    let (env, decl_ptrs) = unfilled_env(decls);
-    fill_decl_env(decls, &decl_ptrs, env)
+    // Imports are loaded separately (see `module_env`), and signatures are only
+    // used by the type checker, unless they're checked at runtime (see `boundary`)
+    let exprs = decls
+        .iter()
+        .filter_map(|decl| match decl {
+            Decl::Expression(ident, expr) => Some((ident.as_str(), expr)),
+            Decl::Import(..) | Decl::Export(_) | Decl::Signature(..) => None,
+        })
+        .collect::<Vec<_>>();
+    let (env, decl_ptrs) = unfilled_env(&exprs);
+    let env = fill_decl_env(&exprs, &decl_ptrs, env);
+    if boundary::signatures_checked() {
+        check_signatures(decls, &exprs, &decl_ptrs);
+    }
+    env
 }
 
-fn unfilled_env(decls: &[Decl]) -> (WrappedEnv, Vec<WrappedEnv>) {
+// Functions with a signature are checked against it when they're called
+fn check_signatures(signatures: &[Decl], decls: &[(&str, &Expr)], decl_ptrs: &[WrappedEnv]) {
+    for (ident, type_) in signatures.iter().filter_map(|decl| match decl {
+        Decl::Signature(ident, type_) => Some((ident, type_)),
+        _ => None,
+    }) {
+        if let Some(decl_ptr) = decls
+            .iter()
+            .position(|(other, _)| other == ident)
+            .map(|i| &decl_ptrs[i])
+        {
+            if let Ok(value) = Env::get_evaluated_value(decl_ptr) {
+                Env::set_value(decl_ptr, boundary::wrap(ident, type_, value));
+            }
+        }
+    }
+}
+
+fn unfilled_env(decls: &[(&str, &Expr)]) -> (WrappedEnv, Vec<WrappedEnv>) {
     decls
         .iter()
-        .map(|decl| match decl {
-            Decl::Expression(ident, _) => (
+        .map(|(ident, _)| {
+            (
                 ident,
-                Value::Error(format!("'{}' has not been initialized", ident)),
-            ),
+                Value::Error(format!("'{}' has not been initialized", ident).into()),
+            )
         })
         .fold(
             (new_env(), Vec::with_capacity(decls.len())),
             |(parent, mut decl_ptrs), (ident, val)| {
-                let new_env = Env::associate_ident(ident.into(), val, parent);
+                let new_env = Env::associate_ident(ident.to_string(), val, parent);
                 decl_ptrs.push(Rc::clone(&new_env));
                 (new_env, decl_ptrs)
             },
         )
 }
 
-fn fill_decl_env(decls: &[Decl], decl_ptrs: &[WrappedEnv], env: WrappedEnv) -> WrappedEnv {
+fn fill_decl_env(decls: &[(&str, &Expr)], decl_ptrs: &[WrappedEnv], env: WrappedEnv) -> WrappedEnv {
     decls
         .iter()
         .zip(decl_ptrs.iter())
-        .for_each(|(decl, decl_ptr)| match decl {
-            Decl::Expression(_, Expr::Fn_(param, body)) => Env::set_value(
+        .for_each(|((_, expr), decl_ptr)| match expr {
+            Expr::Fn_(param, body) => Env::set_value(
                 decl_ptr,
                 Value::decl_function(param.clone(), body.clone(), Rc::downgrade(&env)),
             ),
-            Decl::Expression(_, expr) => Env::set_value(
+            expr => Env::set_value(
                 decl_ptr,
                 Value::delayed_decl(
-                    expr.clone(), // Could probably fix this so it doesn't clone...
-                    Rc::downgrade(&decl_ptr),
+                    (*expr).clone(), // Could probably fix this so it doesn't clone...
+                    Rc::downgrade(decl_ptr),
                     Rc::downgrade(&env),
                 ),
             ),
@@ -67,42 +144,68 @@ fn fill_decl_env(decls: &[Decl], decl_ptrs: &[WrappedEnv], env: WrappedEnv) -> W
     env
 }
 
+// Evaluating an expression can take a lot of the stack before the next one
+// is evaluated (ex. applying a function), so the stack is grown onto the heap
+// when less than `RED_ZONE` is left, and a program that recurses too deeply is
+// stopped by `EvalConfig::max_depth` instead of overflowing the stack
+// Values are cloned and compared recursively, so the red zone also leaves room
+// for values that are nested inside each other (ex. `[[[1]]]`)
+const RED_ZONE: usize = 1024 * 1024;
+const STACK_GROWTH: usize = 16 * 1024 * 1024;
+
 pub fn eval_expr(expr: Expr, env: &WrappedEnv) -> Value {
+    if let Err(error) = capability::step() {
+        return Value::Error(error);
+    }
+    if label::breaking() {
+        return Value::Error(RuntimeError::Break);
+    }
+    let _depth = match capability::enter() {
+        Ok(depth) => depth,
+        Err(error) => return Value::Error(error),
+    };
+    stacker::maybe_grow(RED_ZONE, STACK_GROWTH, || eval(expr, env))
+}
+
+fn eval(expr: Expr, env: &WrappedEnv) -> Value {
     match expr {
         Expr::Unary(op, a) => eval_unary(op, eval_expr(*a, env)),
-        Expr::Binary(a, op, b) => eval_binary(op, eval_expr(*a, env), eval_expr(*b, env)),
+        Expr::Binary(a, op, b) => {
+            let a = eval_expr(*a, env);
+            match (&op, &a) {
+                // The right side is only evaluated if it can change the result
+                // (`and` and `or` on `Int`s are bitwise, so both sides are needed)
+                (BinaryOp::And, Value::Bool(false)) | (BinaryOp::Or, Value::Bool(true)) => a,
+                (BinaryOp::And | BinaryOp::Or | BinaryOp::Xor, Value::Error(_)) => a,
+                _ => eval_binary(op, a, eval_expr(*b, env)),
+            }
+        }
         Expr::Literal(val) => val.into(),
         Expr::If(cond, a, b) => match eval_expr(*cond, env) {
             Value::Bool(true) => eval_expr(*a, env),
             Value::Bool(false) => eval_expr(*b, env),
+            error @ Value::Error(_) => error,
             _ => error("If condition must return a boolean"),
         },
         Expr::Variable(ident) => match Env::get(env, &ident) {
             Some(val) => val.eval(Some(Rc::clone(env))),
-            None => error(&format!("Variable '{}' is not declared", ident)),
+            None => undeclared_error(&ident, env),
         },
         Expr::Let(ident, value, inner) => {
-            match VarEnv::associate(ident, eval_expr(*value, env), env) {
+            match bind("The 'let' pattern", &ident, eval_expr(*value, env), env) {
                 Ok(env) => eval_expr(*inner, &env),
-                Err(error) => Value::Error(error),
+                Err(error) => error,
             }
         }
         Expr::Fn_(param, body) => Value::function(param, body, Rc::clone(env)),
-        Expr::FnApp(function, arg) => {
-            let function = eval_expr(*function, env);
-            match function {
-                Value::Function(function) => function.eval(eval_expr(*arg, env)),
-                Value::Int(index) if index >= 0 => match eval_expr(*arg, env) {
-                    Value::Tuple(tuple) => tuple.get(index as usize),
-                    arg => error(&format!("Can't index type '{}'", arg.type_())),
-                },
-                Value::Int(_) => error("Cannot have a negative index of a tuple"),
-                _ => error(&format!(
-                    "Can't apply argument to type '{}'",
-                    function.type_()
-                )),
+        Expr::FnApp(function, arg) if is_assertion(&function) => {
+            let source = Expr::FnApp(function.clone(), arg.clone());
+            match eval_fn_app(*function, *arg, env) {
+                Value::Error(error) => Value::Error(with_source(error, &source)),
+                val => val,
             }
         }
+        Expr::FnApp(function, arg) => eval_fn_app(*function, *arg, env),
         Expr::Match(val, patterns) => {
             let val = eval_expr(*val, env);
             match patterns.into_iter().find_map(|(pattern, expr)| {
@@ -115,12 +218,24 @@ pub fn eval_expr(expr: Expr, env: &WrappedEnv) -> Value {
             }
         }
         Expr::Delayed(ident, value, inner) => {
-            let new_env =
-                VarEnv::associate(ident, Value::Error("Value not yet initialized".into()), env)
-                    .unwrap(); // This will never fail because the ident is always a variable identifier
+            // The parser only makes these with a name
+            let ident = match ident {
+                Match::Ident(ident) => ident,
+                pattern => {
+                    return Value::Error(RuntimeError::Internal(format!(
+                        "'{}' can't be delayed, since it isn't a name",
+                        pattern
+                    )))
+                }
+            };
+            let new_env = VarEnv::associate_ident(
+                ident,
+                Value::Error("Value not yet initialized".into()),
+                Rc::clone(env),
+            );
             VarEnv::set_value(
                 &new_env,
-                Value::delayed(*value, Rc::downgrade(&new_env), Rc::clone(&env)),
+                Value::delayed(*value, Rc::downgrade(&new_env), Rc::clone(env)),
             );
             eval_expr(*inner, &new_env)
         }
@@ -128,19 +243,153 @@ pub fn eval_expr(expr: Expr, env: &WrappedEnv) -> Value {
         Expr::Do(command, result_match, body) => {
             let env = env.clone();
             match eval_expr(*command, &env) {
-                Value::Command(command) => Value::Command(command.bind(
-                    move |value| {
-                        let env = VarEnv::associate(result_match.clone(), value, &env).map_err(Value::Error)?;
-                        let result = eval_expr(*body.clone(), &env);
-                        match result {
-                            Value::Command(command) => Ok(command),
-                            _ => Err(error("'do' expressions must evaluate to a Command"))
-                        }
+                Value::Command(command) => Value::Command(command.bind(move |value| {
+                    let env = bind("The 'do' pattern", &result_match, value, &env)?;
+                    let result = eval_expr(*body.clone(), &env);
+                    match result {
+                        Value::Command(command) => Ok(command),
+                        _ => Err(error("'do' expressions must evaluate to a Command")),
                     }
-                )),
-                _ => error("'do' expressions only accept Commands")
+                })),
+                _ => error("'do' expressions only accept Commands"),
+            }
+        }
+    }
+}
+
+// Unlike the patterns of a `match`, `let`, parameter, and `do` patterns have
+// to match, so the error names the whole pattern (after `context`) and the
+// part of it that didn't match
+pub(crate) fn bind(
+    context: &str,
+    pattern: &Match,
+    value: Value,
+    env: &WrappedEnv,
+) -> Result<WrappedEnv, Value> {
+    match value.unwrap_matches(pattern) {
+        Ok(matches) => Ok(VarEnv::associate_matches(matches, env)),
+        Err(reason) => {
+            let pattern = pattern_text(pattern);
+            let message = format!("{} '{}' doesn't match '{}'", context, pattern, value);
+            // The part is only named if it isn't the whole pattern
+            if reason.starts_with(&format!("'{}' ", pattern)) {
+                Err(error(&message))
+            } else {
+                Err(error(&format!("{}, since {}", message, reason)))
+            }
+        }
+    }
+}
+
+// `f a b c` applies the arguments one at a time to the value of `f`, so if
+// `f` is a function whose result can't be applied to the rest of them, it was
+// given too many arguments
+fn eval_fn_app(function: Expr, arg: Expr, env: &WrappedEnv) -> Value {
+    let mut args = vec![arg];
+    let mut head = function;
+    while let Expr::FnApp(function, arg) = head {
+        args.push(*arg);
+        head = *function;
+    }
+    args.reverse();
+    let given = args.len();
+    let mut value = eval_expr(head.clone(), env);
+    let calls_function = matches!(value, Value::Function(_));
+    for (applied, arg) in args.into_iter().enumerate() {
+        let too_many = applied > 0 && calls_function;
+        value = match value {
+            Value::Function(_) | Value::Error(_) => apply(value, arg, env),
+            // The result can still be an index into a tuple (ex. `f x (1, 2)`)
+            Value::Int(_) if too_many => match apply(value, arg, env) {
+                Value::Error(RuntimeError::NotIndexable { .. }) => {
+                    arity_error(&head, applied, given)
+                }
+                value => value,
+            },
+            _ if too_many => arity_error(&head, applied, given),
+            _ => apply(value, arg, env),
+        };
+    }
+    value
+}
+
+fn arity_error(function: &Expr, expected: usize, given: usize) -> Value {
+    Value::Error(RuntimeError::Arity {
+        function: function.to_string(),
+        expected,
+        given,
+    })
+}
+
+fn apply(function: Value, arg: Expr, env: &WrappedEnv) -> Value {
+    match function {
+        Value::Function(function) => match arg.named_args() {
+            Some(args) => function.apply_named(
+                args.into_iter()
+                    .map(|(name, value)| (name.to_string(), eval_expr(value.clone(), env)))
+                    .collect(),
+            ),
+            None => function.eval(eval_expr(arg, env)),
+        },
+        Value::Int(index) => {
+            let source = arg.clone();
+            match eval_expr(arg, env) {
+                Value::Tuple(tuple) => usize::try_from(index)
+                    .ok()
+                    .and_then(|i| tuple.get(i))
+                    .unwrap_or_else(|| {
+                        Value::Error(RuntimeError::IndexOutOfBounds {
+                            index,
+                            len: tuple.len(),
+                            expr: index_expr(index, source),
+                        })
+                    }),
+                arg @ Value::Error(_) => arg,
+                arg => Value::Error(RuntimeError::NotIndexable {
+                    type_: arg.type_().to_string(),
+                    expr: index_expr(index, source),
+                }),
             }
         }
+        Value::Error(_) => function,
+        _ => error(&format!(
+            "Can't apply argument to type '{}'",
+            function.type_()
+        )),
+    }
+}
+
+// How an index was applied to `tuple`, for errors (ex. `3 (1, 2)`)
+fn index_expr(index: i32, tuple: Expr) -> String {
+    let index = match index.checked_neg() {
+        Some(negated) if index < 0 => Expr::negate(Expr::Literal(ExprValue::Int(negated))),
+        _ => Expr::Literal(ExprValue::Int(index)),
+    };
+    Expr::FnApp(Box::new(index), Box::new(tuple)).to_string()
+}
+
+// Adds the expression that failed to a failed assertion (unless it was already added)
+fn with_source(error: RuntimeError, source: &Expr) -> RuntimeError {
+    match error {
+        RuntimeError::Assertion {
+            expr: None,
+            location,
+            details,
+        } => RuntimeError::Assertion {
+            expr: Some(source.to_string()),
+            location,
+            details,
+        },
+        error => error,
+    }
+}
+
+// Checks if a function application is a call to one of the assertion builtins
+fn is_assertion(function: &Expr) -> bool {
+    match function {
+        Expr::Variable(ident) => ASSERTIONS.contains(&ident.as_str()),
+        Expr::FnApp(function, _) => is_assertion(function),
+        _ => false,
     }
 }
 
@@ -154,10 +403,15 @@ fn eval_binary(op: BinaryOp, a: Value, b: Value) -> Value {
         BinaryOp::And => a & b,
         BinaryOp::Or => a | b,
         BinaryOp::Xor => a ^ b,
-        BinaryOp::Join => Value::join(a, b),
-        BinaryOp::FlatJoin => Value::flat_join(a, b),
-        BinaryOp::Equal => Value::Bool(a == b),
-        BinaryOp::NotEqual => Value::Bool(a != b),
+        BinaryOp::Join => limit_len(Value::join(a, b)),
+        BinaryOp::FlatJoin => limit_len(Value::flat_join(a, b)),
+        BinaryOp::Concat => limit_len(a.concat(b)),
+        BinaryOp::Sequence => a.sequence(b),
+        BinaryOp::Named => {
+            error("Only a function can be given arguments by name (ex. 'f (x = 1)')")
+        }
+        BinaryOp::Equal => a.equal(b),
+        BinaryOp::NotEqual => a.not_equal(b),
         BinaryOp::LessThan => a.less_than(b),
         BinaryOp::GreaterThan => a.greater_than(b),
         BinaryOp::LessThanEqual => a.less_than_equal(b),
@@ -176,10 +430,41 @@ fn error(message: &str) -> Value {
     Value::Error(message.into())
 }
 
+// With the name that's most likely what was meant, if there's one in scope
+fn undeclared_error(ident: &str, env: &WrappedEnv) -> Value {
+    let idents = Env::idents(env);
+    match suggest::similar(ident, idents.iter().map(String::as_str)) {
+        Some(similar) => error(&format!(
+            "Variable '{}' is not declared ({})",
+            ident,
+            suggest::did_you_mean(similar)
+        )),
+        None => error(&format!("Variable '{}' is not declared", ident)),
+    }
+}
+
+// Tuples (and strings) that are too long for the configured limit are errors,
+// so that a program can't use up all of the host's memory
+fn limit_len(value: Value) -> Value {
+    let len = match &value {
+        Value::Tuple(tuple) => tuple.len(),
+        Value::Boxed(inner) => match &**inner {
+            Value::Tuple(tuple) => tuple.len(),
+            _ => 1,
+        },
+        _ => 1,
+    };
+    match capability::check_tuple_len(len) {
+        Ok(()) => value,
+        Err(error) => Value::Error(error.into()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::value::command::Command;
+    use arbitrary::{Arbitrary, Unstructured};
     use tego_parser::ast::Match;
     use tego_parser::{ExprOutput, MatchOutput};
 
@@ -196,6 +481,34 @@ mod tests {
         assert_eq!(expected, actual);
     }
     #[test]
+    fn eval_short_circuit() {
+        // `undeclared` would be an error if it was evaluated
+        let undeclared = || Expr::variable("undeclared");
+        let eval = |expr| eval_expr(expr, &VarEnv::empty());
+        assert_eq!(
+            eval(Expr::and(Expr::bool(false), undeclared())),
+            Value::Bool(false)
+        );
+        assert_eq!(
+            eval(Expr::or(Expr::bool(true), undeclared())),
+            Value::Bool(true)
+        );
+        assert_eq!(
+            eval(Expr::xor(Expr::variable("left"), undeclared())),
+            error("Variable 'left' is not declared")
+        );
+        assert_eq!(
+            eval(Expr::and(Expr::bool(true), undeclared())),
+            error("Variable 'undeclared' is not declared")
+        );
+        // `xor` always needs both sides
+        assert_eq!(
+            eval(Expr::xor(Expr::bool(true), undeclared())),
+            error("Variable 'undeclared' is not declared")
+        );
+        assert_eq!(eval(Expr::or(Expr::int(1), Expr::int(2))), Value::Int(3));
+    }
+    #[test]
     fn eval_unary() {
         let expected = Value::Int(-3);
         let actual = eval_expr(Expr::negate(Expr::int(3)), &VarEnv::empty());
@@ -392,6 +705,70 @@ mod tests {
         );
     }
     #[test]
+    fn prelude_source_test() {
+        let expr = Expr::fn_app(
+            Expr::fn_app(Expr::variable("map"), Expr::variable("length")),
+            Expr::join(
+                Expr::boxed(Expr::join(Expr::int(1), Expr::int(2))),
+                Expr::int(3),
+            ),
+        );
+        let actual = eval_expr(expr, &import_prelude(&new_env()));
+        assert_eq!(
+            Value::generic_tuple(vec![Value::Int(1), Value::Int(1)]),
+            actual
+        );
+    }
+    basic_test! {
+        assertion_test
+        eval_expr(
+            Expr::fn_app(
+                Expr::fn_app(Expr::variable("assertEq"), Expr::int(3)),
+                Expr::plus(Expr::int(1), Expr::int(2))
+            ),
+            &prelude()
+        ) => Value::Bool(true);
+        eval_expr(
+            Expr::fn_app(
+                Expr::fn_app(Expr::variable("assertEq"), Expr::int(4)),
+                Expr::plus(Expr::int(1), Expr::int(2))
+            ),
+            &prelude()
+        ) => Value::Error(RuntimeError::Assertion {
+            expr: Some("assertEq 4 (1 + 2)".into()),
+            location: None,
+            details: Some(Box::new(("4".into(), "3".into()))),
+        });
+        eval_expr(
+            Expr::fn_app(Expr::variable("assert"), Expr::bool(false)),
+            &prelude()
+        ) => Value::Error(RuntimeError::Assertion {
+            expr: Some("assert false".into()),
+            location: None,
+            details: None,
+        });
+        // Failures are only annotated by the innermost assertion
+        eval_expr(
+            Expr::fn_app(
+                Expr::variable("assert"),
+                Expr::fn_app(Expr::variable("assert"), Expr::bool(false))
+            ),
+            &prelude()
+        ) => Value::Error(RuntimeError::Assertion {
+            expr: Some("assert false".into()),
+            location: None,
+            details: None,
+        });
+        // The declaration it's in is added once it's evaluated
+        eval_expr(
+            Expr::variable("check"),
+            &program_env(&[Decl::Expression(
+                "check".into(),
+                Expr::fn_app(Expr::variable("assert"), Expr::bool(false)),
+            )], Path::new(".")).unwrap()
+        ).to_string() => "Error: Assertion failed in 'check': assert false".to_string()
+    }
+    #[test]
     fn do_expr_test() {
         let expr = Expr::do_expr(
             Expr::fn_app(Expr::variable("return"), Expr::int(1)),
@@ -402,10 +779,176 @@ mod tests {
         assert_eq!(Value::Int(1), result);
     }
 
+    // Evaluates `expr` with the declarations in `decls` (and the prelude)
+    fn eval_source(decls: &str, expr: &str) -> Value {
+        // An empty program doesn't parse
+        let decls = match decls {
+            "" => vec![],
+            decls => match tego_parser::prog(decls.into()).unwrap().1 {
+                Prog::Library(decls) | Prog::Binary(_, decls) => decls,
+            },
+        };
+        let (_, expr) = tego_parser::complete(tego_parser::expr)(expr.into()).unwrap();
+        eval_expr(expr, &program_env(&decls, Path::new(".")).unwrap())
+    }
+
+    basic_test! {
+        assert_eq_test
+        eval_source("", "assertEq [1, [2]] [(1, [2])]") => Value::Bool(true);
+        eval_source("", "assertEq [1] [2]").to_string() => "Error: Assertion failed: assertEq [1] [2]\n  expected: [1]\n  found: [2]";
+        // Functions can't be tested for equality, even in a box
+        eval_source("", "let f = fn x -> x in assertEq f f").to_string()
+            => "Error: Can't perform EQUAL operation on 'Fn' and 'Fn', since functions and commands can't be tested for equality";
+        eval_source("", "let f = fn x -> x in assertEq [f] [f]").to_string()
+            => "Error: Can't perform EQUAL operation on 'Boxed<Fn>' and 'Boxed<Fn>', since functions and commands can't be tested for equality"
+    }
+
+    basic_test! {
+        concat_test
+        eval_source("", "\"ab\" ++ toString 1 ++ \"c\"").to_plain_string() => "ab1c".to_string()
+    }
+
+    basic_test! {
+        index_test
+        eval_source("", "let i = 2 in i (1, 2)").to_string()
+            => "Error: Index 2 is out of bounds in '2 (1, 2)', since the tuple's length is 2";
+        eval_source("", "(-1) (\"ab\" ,, \"é\")") => Value::Error(RuntimeError::IndexOutOfBounds {
+            index: -1,
+            len: 3,
+            expr: "(-1) (\"ab\" ,, \"é\")".into()
+        });
+        eval_source("", "0 true").to_string()
+            => "Error: Can't index a 'Bool' in '0 true', since only tuples can be indexed"
+    }
+
+    basic_test! {
+        pattern_error_test
+        eval_source("", "let (a, 1) = (2, 3) in a").to_string()
+            => "Error: The 'let' pattern '(a, 1)' doesn't match '(2, 3)', since '1' doesn't match '3'";
+        eval_source("", "(fn () -> 1) 2").to_string() => "Error: The parameter '()' doesn't match '2'"
+    }
+
+    basic_test! {
+        let_rec_test
+        eval_source("", "let rec len = fn l -> match l to\n| () -> 0\n| (_, t) -> 1 + len t in len (1, 2, 3)")
+            => Value::Int(3);
+        eval_source("", "let rec a = b and true in let rec b = a in a") => Value::Error(RuntimeError::Cycle("a".into()));
+        eval_source("a = b + 1\nb = a", "a") => Value::Error(RuntimeError::Cycle("a".into()));
+        // A function made while a value is evaluated can use it once it's done
+        eval_source("pair = 1, fn _ -> pair", "0 ((1 pair) ())") => Value::Int(1)
+    }
+
+    basic_test! {
+        params_test
+        eval_source("", "let add = fn x y -> x + y in let inc = add 1 in (inc 2, inc 3)").to_string()
+            => "(3, 4)";
+        eval_source("add x y = x + y", "add 1 2 3 4").to_string()
+            => "Error: 'add' expects 2 arguments, but 4 were given";
+        eval_source("add x y = x + y", "add 1 2 true").to_string()
+            => "Error: 'add' expects 2 arguments, but 3 were given";
+        // A function's result can still be an index
+        eval_source("add x y = x + y", "add 0 1 (5, 6)") => Value::Int(6)
+    }
+
+    basic_test! {
+        named_args_test
+        eval_source("sub x y = x - y", "sub (y = 1, x = 10)") => Value::Int(9);
+        eval_source("box = fn (w, h, d = 1) -> w * h * d", "box (h = 2, w = 3)") => Value::Int(6);
+        eval_source("sub x y = x - y", "sub (y = 1)").to_string()
+            => "Error: Missing the argument 'x', which has to be given by name before 'y' can be";
+        eval_source("sub x y = x - y", "sub (x = 1, z = 2)").to_string()
+            => "Error: Can't give 'z' by name, since there isn't a parameter with that name";
+        eval_source("", "length (x = 1)").to_string()
+            => "Error: Can't give 'x' by name, since there isn't a parameter with that name"
+    }
+
+    basic_test! {
+        builtin_test
+        eval_source("", "sort (\"pear\", \"\", \"Fig\", \"apple\")").to_string()
+            => "(\"\", \"Fig\", \"apple\", \"pear\")";
+        eval_source("", "filter (fn x -> x) (1, 2)").to_string()
+            => "Error: 'filter' expects a 'Bool' from 'pred', found 'Int'";
+        eval_source("", "base64Decode (hexDecode \"2f77\")").to_string()
+            => "Error: 'base64Decode' decoded bytes that aren't UTF-8";
+        eval_source("", "hexEncode (1, 256)").to_string()
+            => "Error: 'hexEncode' expects a 'String' or a tuple of bytes, found '(Int, Int)'";
+        run(eval_source("import Time", "do now in t then return (Time.year t >= 2024)")) => Value::Bool(true)
+    }
+
+    basic_test! {
+        label_test
+        eval_source("", "label (fn break -> 1 + break 2)") => Value::Int(2);
+        eval_source("", "label (fn _ -> 3)") => Value::Int(3);
+        eval_source("", "label (fn outer -> 1 + label (fn inner -> inner 1))") => Value::Int(2);
+        // The `break` is forgotten, so it doesn't stop the other tests on this thread
+        {
+            let value = eval_source("", "let exit = label (fn break -> break) in exit 1");
+            label::reset();
+            value
+        } => Value::Error(RuntimeError::Break)
+    }
+
     fn run(val: Value) -> Value {
         match val {
             Value::Command(command) => command.run(),
             v => panic!("Cannot run {}", v),
         }
     }
+
+    // The names that random expressions use (see `tego_parser::ast::arbitrary`)
+    // are bound to values, and `f` to a builtin, so builtins are given
+    // arguments that don't make sense too
+    const BUILTINS: &[&str] = &[
+        "length", "reverse", "foldr", "show", "parseInt", "toChar", "sort", "tupleGet", "assertEq",
+        "catch", "label",
+    ];
+
+    fn arbitrary_env(rng: &mut fastrand::Rng, prelude: &WrappedEnv) -> WrappedEnv {
+        let builtin = Env::get(prelude, BUILTINS[rng.usize(..BUILTINS.len())]).unwrap();
+        [
+            ("a", Value::Int(rng.i32(..))),
+            ("b", Value::string("ab")),
+            ("f", builtin),
+            ("xs", Value::from((1, 2, 3))),
+        ]
+        .iter()
+        .fold(Rc::clone(prelude), |env, (name, value)| {
+            VarEnv::associate_ident(name.to_string(), value.clone(), env)
+        })
+    }
+
+    // The parser only delays names, but an AST from somewhere else might not
+    #[test]
+    fn delayed_pattern() {
+        let expr = Expr::Delayed(Match::Unit, Box::new(Expr::int(1)), Box::new(Expr::int(2)));
+        assert!(matches!(
+            eval_expr(expr, &VarEnv::empty()),
+            Value::Error(RuntimeError::Internal(_))
+        ));
+    }
+
+    // Errors are values, so no expression should crash the interpreter, on a
+    // test thread's stack (the steps are only limited so the test is quick)
+    #[test]
+    fn no_panics() {
+        let previous = capability::set_config(capability::EvalConfig {
+            max_steps: Some(500),
+            max_tuple_len: Some(1000),
+            ..capability::EvalConfig::default()
+        });
+        let mut rng = fastrand::Rng::with_seed(7);
+        let prelude = prelude();
+        for _ in 0..5000 {
+            let bytes = (0..rng.usize(..1024))
+                .map(|_| rng.u8(..))
+                .collect::<Vec<_>>();
+            let expr = Expr::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+            let env = arbitrary_env(&mut rng, &prelude);
+            // Each expression gets its own steps
+            capability::restart();
+            // Showing the result evaluates what's delayed in it
+            eval_expr(expr, &env).to_string();
+        }
+        capability::set_config(previous);
+    }
 }
@@ -0,0 +1,79 @@
+// Exiting early with `label` and `break` (ex. to stop a search once something
+// is found, without checking a flag at every level of the recursion)
+//
+//     firstOver n list = label (fn break -> foldl (fn _ -> fn x -> if x > n then break x else ()) () list)
+//
+// The `label` builtin gives its function a `break` function, and `break value`
+// stops everything that's being evaluated inside the `label` (the same way as
+// an interrupt, see `capability::interrupt_flag`), which then results in
+// `value`
+// A `label` that results in a Command can also be exited while it's run, and
+// a `break` that's used once its `label` has finished is an error
+
+use crate::error::RuntimeError;
+use crate::value::command::Command;
+use crate::value::Value;
+use std::cell::{Cell, RefCell};
+
+thread_local! {
+    static NEXT_LABEL: Cell<u64> = const { Cell::new(0) };
+    // The label that's being exited, and what it results in
+    static BREAKING: RefCell<Option<(u64, Value)>> = const { RefCell::new(None) };
+}
+
+// The `label` builtin
+pub(crate) fn label_fn() -> Value {
+    Value::internal_fn(|body| {
+        let label = NEXT_LABEL.with(|next| {
+            next.set(next.get() + 1);
+            next.get()
+        });
+        let break_fn = Value::internal_fn(move |value| break_to(label, value));
+        exit(label, body.apply(break_fn))
+    })
+}
+
+// The first `break` is the one that's used, even if the value given to it
+// breaks too (ex. `outer (inner 1)`)
+fn break_to(label: u64, value: Value) -> Value {
+    BREAKING.with(|breaking| {
+        let mut breaking = breaking.borrow_mut();
+        if breaking.is_none() {
+            *breaking = Some((label, value));
+        }
+    });
+    Value::Error(RuntimeError::Break)
+}
+
+// Whatever `label`'s body resulted in is replaced by the value given to its
+// `break`, since the body was stopped
+fn exit(label: u64, result: Value) -> Value {
+    let taken = BREAKING.with(|breaking| {
+        let mut breaking = breaking.borrow_mut();
+        match breaking.take() {
+            Some((breaking_label, value)) if breaking_label == label => Some(value),
+            other => {
+                *breaking = other;
+                None
+            }
+        }
+    });
+    match (taken, result) {
+        (Some(value), _) => value,
+        (None, Value::Command(command)) => Value::Command(
+            Command::unit(Value::unit())
+                .bind(move |_| Ok(Command::unit(exit(label, command.run())))),
+        ),
+        (None, result) => result,
+    }
+}
+
+// Whether a `label` is being exited, so nothing else should be evaluated
+pub(crate) fn breaking() -> bool {
+    BREAKING.with(|breaking| breaking.borrow().is_some())
+}
+
+// Forgets a `break` (ex. one that was used after its `label` finished)
+pub(crate) fn reset() {
+    BREAKING.with(|breaking| *breaking.borrow_mut() = None);
+}
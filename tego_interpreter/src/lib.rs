@@ -9,10 +9,32 @@ macro_rules! basic_test {
     };
 }
 
+pub mod boundary;
+pub mod capability;
+mod encoding;
+pub mod engine;
 mod environment;
+mod error;
+mod format;
+mod hash;
+#[cfg(feature = "http")]
+mod http;
 mod interpreter;
+mod label;
+pub mod module;
+pub mod output;
 pub mod prelude;
+pub mod random;
+pub mod session;
+pub mod suggest;
+mod time;
 mod type_;
+#[cfg(feature = "uuid")]
+mod uuid;
 pub mod value;
 
+pub use engine::Engine;
+pub use error::{RuntimeError, TegoError};
 pub use interpreter::*;
+pub use session::Session;
+pub use type_::Type;
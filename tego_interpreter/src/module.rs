@@ -0,0 +1,705 @@
+use crate::capability;
+use crate::encoding;
+use crate::interpreter::{module_env, VarEnv, WrappedEnv};
+use crate::value::Value;
+use sha2::{Digest, Sha256};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tego_parser::ast::{binary, Decl, Prog};
+
+thread_local! {
+    // Directories that are searched (in order) for imports that aren't found
+    // next to the importing file
+    static SEARCH_PATH: RefCell<Vec<PathBuf>> = const { RefCell::new(vec![]) };
+    // Where parsed files are cached, if anywhere
+    static CACHE_DIR: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+}
+
+pub fn set_search_path(dirs: Vec<PathBuf>) {
+    SEARCH_PATH.with(|search_path| *search_path.borrow_mut() = dirs)
+}
+
+pub fn search_path() -> Vec<PathBuf> {
+    SEARCH_PATH.with(|search_path| search_path.borrow().clone())
+}
+
+// Imported files are cached in `dir` after they're parsed, keyed by a hash of
+// their contents, so that unchanged files aren't parsed again by later runs
+pub fn set_cache_dir(dir: Option<PathBuf>) {
+    CACHE_DIR.with(|cache_dir| *cache_dir.borrow_mut() = dir)
+}
+
+pub fn cache_dir() -> Option<PathBuf> {
+    CACHE_DIR.with(|cache_dir| cache_dir.borrow().clone())
+}
+
+// Modules that are built into the interpreter (see the 'std' directory)
+const STD: &[(&str, &str)] = &[
+    ("List.tgo", include_str!("std/List.tgo")),
+    ("Math.tgo", include_str!("std/Math.tgo")),
+    ("Option.tgo", include_str!("std/Option.tgo")),
+    ("Stream.tgo", include_str!("std/Stream.tgo")),
+    ("Time.tgo", include_str!("std/Time.tgo")),
+];
+
+enum Source {
+    File(PathBuf),
+    Std(&'static str, &'static str), // Name, source code
+}
+
+// Searches the importing file's directory (if it has one), the search path,
+// and then the standard library
+// Only the standard library is searched if reading files isn't allowed
+fn find(path: &str, dir: Option<&Path>) -> Result<Source, String> {
+    if !capability::files_allowed() {
+        return STD
+            .iter()
+            .find(|(name, _)| *name == path)
+            .map(|(name, source)| Source::Std(name, source))
+            .ok_or_else(|| {
+                format!(
+                    "Can't import '{}': reading files isn't allowed (only the standard library can be imported)",
+                    path
+                )
+            });
+    }
+    let dirs = dir
+        .map(Path::to_owned)
+        .into_iter()
+        .chain(search_path())
+        .collect::<Vec<_>>();
+    dirs.iter()
+        .map(|dir| dir.join(path))
+        .find(|path| path.is_file())
+        .map(Source::File)
+        .or_else(|| {
+            STD.iter()
+                .find(|(name, _)| *name == path)
+                .map(|(name, source)| Source::Std(name, source))
+        })
+        .ok_or_else(|| {
+            format!(
+                "Can't import '{}', it wasn't found in any of these directories:{}\n  (the standard library)",
+                path,
+                dirs.iter()
+                    .map(|dir| format!("\n  {}", dir.display()))
+                    .collect::<String>()
+            )
+        })
+}
+
+// The canonical paths of every file that `decls` imports (directly or not),
+// so they can be watched for changes (see `tego run --watch`)
+// Imports that can't be found are left out, and files that can't be parsed
+// are included without their imports
+pub fn dependencies(decls: &[Decl], dir: Option<&Path>) -> Vec<PathBuf> {
+    let mut files = vec![];
+    add_dependencies(decls, dir, &mut files);
+    files
+}
+
+fn add_dependencies(decls: &[Decl], dir: Option<&Path>, files: &mut Vec<PathBuf>) {
+    for path in decls.iter().filter_map(|decl| match decl {
+        Decl::Import(path, ..) => Some(path),
+        _ => None,
+    }) {
+        let path = match find(path, dir).map(|source| match source {
+            Source::File(path) => fs::canonicalize(path).ok(),
+            Source::Std(..) => None,
+        }) {
+            Ok(Some(path)) if !files.contains(&path) => path,
+            _ => continue,
+        };
+        files.push(path.clone());
+        if let Ok(decls) = parse_file(&path, &path.display().to_string()) {
+            add_dependencies(&decls, path.parent(), files);
+        }
+    }
+}
+
+// Every file imported while loading a program, by its canonical path
+#[derive(Default)]
+pub struct Modules {
+    loaded: HashMap<PathBuf, Exports>,
+    // Files that are currently being loaded, in the order they were imported
+    loading: Vec<(PathBuf, String)>,
+}
+
+#[derive(Clone)]
+struct Exports {
+    // Name, value, and the canonical path of the file that declares it
+    public: Vec<(String, Value, PathBuf)>,
+    private: Vec<String>,
+    file: String,
+}
+
+impl Modules {
+    // Results in an environment with the names exported by the files that `decls` imports,
+    // and an environment where each private name is an error explaining that it's private
+    // Import paths are relative to `dir`, the directory of the importing file, or the search path
+    // Names from a module imported with `import Name` are prefixed with `Name.`
+    pub(crate) fn import(
+        &mut self,
+        decls: &[Decl],
+        dir: Option<&Path>,
+    ) -> Result<(WrappedEnv, WrappedEnv), String> {
+        let mut public = VarEnv::empty();
+        let mut private = VarEnv::empty();
+        for (path, namespace, names) in decls.iter().filter_map(|decl| match decl {
+            Decl::Import(path, namespace, names, _) => Some((path, namespace, names)),
+            _ => None,
+        }) {
+            let exports = self.load(find(path, dir)?)?;
+            let qualify = |ident: String| match namespace {
+                Some(namespace) => format!("{}.{}", namespace, ident),
+                None => ident,
+            };
+            // Only the names in an import's list are imported, so none are private
+            let (imported, private_idents) = match names {
+                Some(names) => (select(&exports, names)?, vec![]),
+                None => (exports.public, exports.private),
+            };
+            for (ident, value, _) in imported {
+                public = VarEnv::associate_ident(qualify(ident), value, public);
+            }
+            for ident in private_idents {
+                let ident = qualify(ident);
+                let error = format!("'{}' isn't exported by '{}'", ident, exports.file);
+                private = VarEnv::associate_ident(ident, Value::Error(error.into()), private);
+            }
+        }
+        Ok((public, private))
+    }
+
+    fn load(&mut self, source: Source) -> Result<Exports, String> {
+        let (path, file) = match &source {
+            Source::File(path) => {
+                let file = path.display().to_string();
+                let path = fs::canonicalize(path)
+                    .map_err(|e| format!("Can't import '{}': {}", file, e))?;
+                (path, file)
+            }
+            Source::Std(name, _) => {
+                let file = format!("<std>/{}", name);
+                (PathBuf::from(&file), file)
+            }
+        };
+        if let Some(exports) = self.loaded.get(&path) {
+            return Ok(exports.clone());
+        }
+        if let Some(start) = self
+            .loading
+            .iter()
+            .position(|(loading, _)| *loading == path)
+        {
+            return Err(format!(
+                "Circular import: {} -> '{}'",
+                self.loading[start..]
+                    .iter()
+                    .map(|(_, file)| format!("'{}'", file))
+                    .collect::<Vec<_>>()
+                    .join(" -> "),
+                file
+            ));
+        }
+
+        self.loading.push((path.clone(), file.clone()));
+        let (decls, dir) = match source {
+            Source::File(_) => (parse_file(&path, &file)?, path.parent()),
+            // Standard library modules can only import each other
+            Source::Std(_, code) => (parse_source(code, &file)?, None),
+        };
+        let env = module_env(&decls, dir, self, &[])?;
+        let reexported = self.reexports(&decls, dir)?;
+        let exports = exports(&decls, &env, &path, file, reexported)?;
+        self.loading.pop();
+        self.loaded.insert(path, exports.clone());
+        Ok(exports)
+    }
+}
+
+impl Modules {
+    // The names imported by `export import` declarations, which were already loaded by `import`
+    fn reexports(
+        &mut self,
+        decls: &[Decl],
+        dir: Option<&Path>,
+    ) -> Result<Vec<(String, Value, PathBuf)>, String> {
+        let mut reexported = vec![];
+        for (path, names) in decls.iter().filter_map(|decl| match decl {
+            Decl::Import(path, _, names, true) => Some((path, names)),
+            _ => None,
+        }) {
+            let exports = self.load(find(path, dir)?)?;
+            reexported.extend(match names {
+                Some(names) => select(&exports, names)?,
+                None => exports.public,
+            });
+        }
+        Ok(reexported)
+    }
+}
+
+// The exported values for the names in an import's list
+fn select(exports: &Exports, names: &[String]) -> Result<Vec<(String, Value, PathBuf)>, String> {
+    names
+        .iter()
+        .map(
+            |name| match exports.public.iter().find(|(ident, _, _)| ident == name) {
+                Some(export) => Ok(export.clone()),
+                None if exports.private.contains(name) => {
+                    Err(format!("'{}' isn't exported by '{}'", name, exports.file))
+                }
+                None => Err(format!("'{}' doesn't declare '{}'", exports.file, name)),
+            },
+        )
+        .collect()
+}
+
+// If a file doesn't have any 'export' declarations, everything is exported (except 'main')
+// Re-exported names that the file also declares are replaced by its declarations
+fn exports(
+    decls: &[Decl],
+    env: &WrappedEnv,
+    path: &Path,
+    file: String,
+    reexported: Vec<(String, Value, PathBuf)>,
+) -> Result<Exports, String> {
+    let declared = decls
+        .iter()
+        .filter_map(|decl| match decl {
+            Decl::Expression(ident, _) if ident != "main" => Some(ident.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let export_lists = decls
+        .iter()
+        .filter_map(|decl| match decl {
+            Decl::Export(idents) => Some(idents),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let exported = if export_lists.is_empty() {
+        declared.clone()
+    } else {
+        export_lists
+            .into_iter()
+            .flatten()
+            .map(|ident| match declared.contains(&ident.as_str()) {
+                true => Ok(ident.as_str()),
+                false => Err(format!(
+                    "'{}' exports '{}', but doesn't declare it",
+                    file, ident
+                )),
+            })
+            .collect::<Result<Vec<_>, _>>()?
+    };
+    let mut public = exported
+        .iter()
+        .filter_map(|ident| {
+            VarEnv::get(env, ident)
+                .map(|value| (ident.to_string(), value.into_exported(), path.to_owned()))
+        })
+        .collect::<Vec<_>>();
+    for (ident, value, origin) in reexported {
+        if declared.contains(&ident.as_str()) {
+            continue;
+        }
+        match public.iter().find(|(other, _, _)| *other == ident) {
+            // The same declaration can be re-exported more than once
+            Some((_, _, other_origin)) if *other_origin == origin => (),
+            Some((_, _, other_origin)) => {
+                return Err(format!(
+                    "'{}' re-exports '{}' from both '{}' and '{}'",
+                    file,
+                    ident,
+                    other_origin.display(),
+                    origin.display()
+                ))
+            }
+            None => public.push((ident, value, origin)),
+        }
+    }
+    Ok(Exports {
+        public,
+        private: declared
+            .into_iter()
+            .filter(|ident| !exported.contains(ident))
+            .map(String::from)
+            .collect(),
+        file,
+    })
+}
+
+fn parse_file(path: &Path, file: &str) -> Result<Vec<Decl>, String> {
+    let source = fs::read_to_string(path).map_err(|e| format!("Can't import '{}': {}", file, e))?;
+    let cache_file = cache_dir().map(|dir| dir.join(format!("{}.tgoc", cache_key(&source))));
+    if let Some(decls) = cache_file
+        .as_ref()
+        .and_then(|cache_file| fs::read(cache_file).ok())
+        .and_then(|bytes| binary::decode(&bytes))
+    {
+        return Ok(decls);
+    }
+    let decls = parse_source(&source, file)?;
+    // The cache is only an optimization, so failing to write to it is ignored
+    if let Some(cache_file) = cache_file {
+        let _ = cache_file
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&cache_file, binary::encode(&decls)));
+    }
+    Ok(decls)
+}
+
+// The key is a SHA-256 hash (which, unlike `DefaultHasher`, doesn't change
+// between Rust releases) of the cache format's version and the source, so a
+// file cached by another version of tego is never found
+fn cache_key(source: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update([binary::VERSION]);
+    hasher.update(source.as_bytes());
+    encoding::hex_encode(&hasher.finalize())
+}
+
+fn parse_source(source: &str, file: &str) -> Result<Vec<Decl>, String> {
+    match tego_parser::prog(source.into()) {
+        Ok((_, Prog::Library(decls))) | Ok((_, Prog::Binary(_, decls))) => Ok(decls),
+        Err(error) => Err(format!(
+            "Can't import '{}': {}",
+            file,
+            tego_parser::ParseError::from(error)
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cache_key, dependencies, parse_source, set_cache_dir, set_search_path, STD};
+    use crate::interpreter::{eval_expr, program_env};
+    use crate::value::Value;
+    use std::fs;
+    use std::io::Write;
+    use std::path::Path;
+    use tego_parser::ast::{Decl, Expr};
+    use tego_parser::{DeclOutput, ExprOutput};
+
+    fn write_file(dir: &Path, name: &str, source: &str) {
+        let mut file = fs::File::create(dir.join(name)).unwrap();
+        write!(file, "{}", source).unwrap();
+    }
+
+    #[test]
+    fn import_test() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("lib")).unwrap();
+        write_file(
+            dir.path(),
+            "a.tgo",
+            "import \"lib/b.tgo\"\nexport (a)\na = helper\nhelper = b\nmain = a",
+        );
+        // Imports are relative to the importing file
+        write_file(&dir.path().join("lib"), "b.tgo", "import \"c.tgo\"\nb = c");
+        write_file(&dir.path().join("lib"), "c.tgo", "c = 2");
+
+        // 'lib/b.tgo' is imported twice, but only loaded once
+        let env = program_env(
+            &[Decl::import("a.tgo"), Decl::import("lib/b.tgo")],
+            dir.path(),
+        )
+        .unwrap();
+        assert_eq!(eval_expr(Expr::variable("a"), &env), Value::Int(2));
+        assert_eq!(
+            eval_expr(Expr::variable("helper"), &env),
+            Value::Error(
+                format!(
+                    "'helper' isn't exported by '{}'",
+                    dir.path().join("a.tgo").display()
+                )
+                .into()
+            )
+        );
+        // Imports aren't passed on to the importing file
+        assert_eq!(
+            eval_expr(Expr::variable("c"), &env),
+            Value::Error("Variable 'c' is not declared".into())
+        );
+    }
+
+    #[test]
+    fn qualified_import_test() {
+        let dir = tempfile::tempdir().unwrap();
+        write_file(dir.path(), "A.tgo", "export (get)\nget = 1\nhidden = 2");
+        write_file(dir.path(), "B.tgo", "get = 2");
+
+        let env = program_env(
+            &[Decl::qualified_import("A"), Decl::qualified_import("B")],
+            dir.path(),
+        )
+        .unwrap();
+        assert_eq!(eval_expr(Expr::variable("A.get"), &env), Value::Int(1));
+        assert_eq!(eval_expr(Expr::variable("B.get"), &env), Value::Int(2));
+        assert_eq!(
+            eval_expr(Expr::variable("get"), &env),
+            Value::Error("Variable 'get' is not declared".into())
+        );
+        assert_eq!(
+            eval_expr(Expr::variable("A.hidden"), &env),
+            Value::Error(
+                format!(
+                    "'A.hidden' isn't exported by '{}'",
+                    dir.path().join("A.tgo").display()
+                )
+                .into()
+            )
+        );
+    }
+
+    #[test]
+    fn circular_import_test() {
+        let dir = tempfile::tempdir().unwrap();
+        write_file(dir.path(), "A.tgo", "import B\na = 1");
+        write_file(dir.path(), "B.tgo", "import C\nb = 1");
+        write_file(dir.path(), "C.tgo", "import B\nc = 1");
+        let file = |name| dir.path().join(name).display().to_string();
+
+        assert_eq!(
+            program_env(&[Decl::qualified_import("A")], dir.path()).err(),
+            Some(format!(
+                "Circular import: '{}' -> '{}' -> '{}'",
+                file("B.tgo"),
+                file("C.tgo"),
+                file("B.tgo")
+            ))
+        );
+    }
+
+    #[test]
+    fn selective_import_test() {
+        let dir = tempfile::tempdir().unwrap();
+        write_file(dir.path(), "A.tgo", "export (a, b)\na = 1\nb = 2\nc = 3");
+        let import = |names: Option<Vec<&str>>, namespace| {
+            program_env(&[Decl::import_with("A.tgo", namespace, names)], dir.path())
+        };
+
+        let env = import(Some(vec!["a"]), None).unwrap();
+        assert_eq!(eval_expr(Expr::variable("a"), &env), Value::Int(1));
+        assert!(eval_expr(Expr::variable("b"), &env).is_error());
+        assert!(eval_expr(Expr::variable("A.a"), &env).is_error());
+
+        let env = import(None, Some("Alias")).unwrap();
+        assert_eq!(eval_expr(Expr::variable("Alias.b"), &env), Value::Int(2));
+        assert!(eval_expr(Expr::variable("A.b"), &env).is_error());
+
+        let env = import(Some(vec!["b"]), Some("Alias")).unwrap();
+        assert_eq!(eval_expr(Expr::variable("Alias.b"), &env), Value::Int(2));
+        assert!(eval_expr(Expr::variable("Alias.a"), &env).is_error());
+
+        let file = dir.path().join("A.tgo").display().to_string();
+        assert_eq!(
+            import(Some(vec!["c"]), None).err(),
+            Some(format!("'c' isn't exported by '{}'", file))
+        );
+        assert_eq!(
+            import(Some(vec!["d"]), None).err(),
+            Some(format!("'{}' doesn't declare 'd'", file))
+        );
+    }
+
+    #[test]
+    fn reexport_test() {
+        let dir = tempfile::tempdir().unwrap();
+        write_file(dir.path(), "C.tgo", "c = 3\nshared = 4");
+        write_file(dir.path(), "B.tgo", "export import C\nb = 2");
+        // 'shared' is re-exported through both 'B' and 'C'
+        write_file(
+            dir.path(),
+            "A.tgo",
+            "export import B\nexport import C (c, shared)\nexport (a)\na = 1\nhelper = 0",
+        );
+        let env = program_env(&[Decl::qualified_import("A")], dir.path()).unwrap();
+        for (ident, value) in &[("A.a", 1), ("A.b", 2), ("A.c", 3), ("A.shared", 4)] {
+            assert_eq!(eval_expr(Expr::variable(ident), &env), Value::Int(*value));
+        }
+        assert!(eval_expr(Expr::variable("A.helper"), &env).is_error());
+
+        // Declarations take precedence over re-exported names
+        write_file(dir.path(), "D.tgo", "export import C\nc = 5");
+        let env = program_env(&[Decl::qualified_import("D")], dir.path()).unwrap();
+        assert_eq!(eval_expr(Expr::variable("D.c"), &env), Value::Int(5));
+
+        write_file(dir.path(), "E.tgo", "export import C\nexport import D");
+        let error = program_env(&[Decl::qualified_import("E")], dir.path()).err();
+        let file = |name| fs::canonicalize(dir.path().join(name)).unwrap();
+        assert_eq!(
+            error,
+            Some(format!(
+                "'{}' re-exports 'c' from both '{}' and '{}'",
+                dir.path().join("E.tgo").display(),
+                file("C.tgo").display(),
+                file("D.tgo").display()
+            ))
+        );
+    }
+
+    #[test]
+    fn dependencies_test() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("lib")).unwrap();
+        write_file(
+            dir.path(),
+            "a.tgo",
+            "import \"lib/b.tgo\"\nimport List\na = 1",
+        );
+        write_file(
+            dir.path(),
+            "lib/b.tgo",
+            "import \"c.tgo\"\nimport \"../a.tgo\"\nb = 2",
+        );
+        write_file(dir.path(), "lib/c.tgo", "c =");
+        let file = |name| fs::canonicalize(dir.path().join(name)).unwrap();
+        assert_eq!(
+            dependencies(
+                &[Decl::import("a.tgo"), Decl::import("missing.tgo")],
+                Some(dir.path())
+            ),
+            vec![file("a.tgo"), file("lib/b.tgo"), file("lib/c.tgo")]
+        );
+    }
+
+    #[test]
+    fn std_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = program_env(&[Decl::qualified_import("List")], dir.path()).unwrap();
+        assert_eq!(
+            eval_expr(
+                Expr::fn_app(
+                    Expr::variable("List.sum"),
+                    Expr::join(Expr::int(1), Expr::int(2))
+                ),
+                &env
+            ),
+            Value::Int(3)
+        );
+
+        // Files next to the importing file take precedence
+        write_file(dir.path(), "List.tgo", "sum = 0");
+        let env = program_env(&[Decl::qualified_import("List")], dir.path()).unwrap();
+        assert_eq!(eval_expr(Expr::variable("List.sum"), &env), Value::Int(0));
+    }
+
+    // Makes sure that every module in the standard library parses
+    #[test]
+    fn std_parse_test() {
+        for (name, source) in STD {
+            assert!(
+                parse_source(source, name).is_ok(),
+                "'{}' failed to parse",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn cache_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = dir.path().join("cache");
+        set_cache_dir(Some(cache.clone()));
+        write_file(dir.path(), "A.tgo", "a = 1");
+        let import = || {
+            let env = program_env(&[Decl::qualified_import("A")], dir.path()).unwrap();
+            eval_expr(Expr::variable("A.a"), &env)
+        };
+        assert_eq!(import(), Value::Int(1));
+        let entries = fs::read_dir(&cache)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect::<Vec<_>>();
+        assert_eq!(entries.len(), 1);
+
+        // The cached declarations are used instead of parsing the file again
+        let cached = tego_parser::ast::binary::encode(&[Decl::expression("a", Expr::int(2))]);
+        fs::write(&entries[0], cached).unwrap();
+        assert_eq!(import(), Value::Int(2));
+
+        // A cache file that can't be decoded is replaced
+        fs::write(&entries[0], "corrupt").unwrap();
+        assert_eq!(import(), Value::Int(1));
+        assert_eq!(import(), Value::Int(1));
+
+        // Changing the file changes its key
+        write_file(dir.path(), "A.tgo", "a = 3");
+        assert_eq!(import(), Value::Int(3));
+        assert_eq!(fs::read_dir(&cache).unwrap().count(), 2);
+        set_cache_dir(None);
+    }
+
+    // Keys have to stay the same between builds (and Rust releases) for the
+    // cache to be used
+    basic_test! {
+        cache_key_test
+        cache_key("a = 1") => "f0ff77d115c54acf551e64d3a9223f4c9e722b22c3fa4bfc174244b935ca94b3".to_string()
+    }
+
+    #[test]
+    fn search_path_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = tempfile::tempdir().unwrap();
+        let second = tempfile::tempdir().unwrap();
+        write_file(first.path(), "A.tgo", "a = 1");
+        write_file(second.path(), "A.tgo", "a = 2");
+        write_file(second.path(), "B.tgo", "b = 3");
+        set_search_path(vec![first.path().into(), second.path().into()]);
+
+        let env = program_env(
+            &[Decl::qualified_import("A"), Decl::qualified_import("B")],
+            dir.path(),
+        );
+        let error = program_env(&[Decl::qualified_import("C")], dir.path()).err();
+        set_search_path(vec![]);
+
+        let env = env.unwrap();
+        // Directories are searched in order
+        assert_eq!(eval_expr(Expr::variable("A.a"), &env), Value::Int(1));
+        assert_eq!(eval_expr(Expr::variable("B.b"), &env), Value::Int(3));
+        assert_eq!(
+            error,
+            Some(format!(
+                "Can't import 'C.tgo', it wasn't found in any of these directories:\n  {}\n  {}\n  {}\n  (the standard library)",
+                dir.path().display(),
+                first.path().display(),
+                second.path().display()
+            ))
+        );
+    }
+
+    #[test]
+    fn import_error_test() {
+        let dir = tempfile::tempdir().unwrap();
+        write_file(dir.path(), "bad.tgo", "a =");
+        write_file(dir.path(), "exports.tgo", "export (a)\nb = 1");
+        let error = |file| {
+            program_env(&[Decl::import(file)], dir.path())
+                .err()
+                .unwrap()
+        };
+
+        assert_eq!(
+            error("missing.tgo"),
+            format!(
+                "Can't import 'missing.tgo', it wasn't found in any of these directories:\n  {}\n  (the standard library)",
+                dir.path().display()
+            )
+        );
+        assert!(error("bad.tgo").contains("error[E"));
+        assert_eq!(
+            error("exports.tgo"),
+            format!(
+                "'{}' exports 'a', but doesn't declare it",
+                dir.path().join("exports.tgo").display()
+            )
+        );
+    }
+}
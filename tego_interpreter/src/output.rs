@@ -0,0 +1,69 @@
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+// Where a program's output goes
+pub type Sink = Rc<RefCell<dyn Write>>;
+
+thread_local! {
+    // Output goes to stdout unless the host captures it (see `Engine::set_output`)
+    static OUTPUT: RefCell<Option<Sink>> = RefCell::new(None);
+}
+
+// Results in the sink that was used before
+pub fn set_output(sink: Option<Sink>) -> Option<Sink> {
+    OUTPUT.with(|output| output.replace(sink))
+}
+
+pub(crate) fn write_line(line: &str) -> io::Result<()> {
+    OUTPUT.with(|output| match &*output.borrow() {
+        Some(sink) => writeln!(sink.borrow_mut(), "{}", line),
+        None => writeln!(io::stdout(), "{}", line),
+    })
+}
+
+pub(crate) fn flush() -> io::Result<()> {
+    OUTPUT.with(|output| match &*output.borrow() {
+        Some(sink) => sink.borrow_mut().flush(),
+        None => io::stdout().flush(),
+    })
+}
+
+// Output that's kept in memory, so it can be read once the program has run
+#[derive(Clone, Default)]
+pub struct Buffer(Rc<RefCell<Vec<u8>>>);
+
+impl Buffer {
+    pub fn new() -> Self {
+        Buffer::default()
+    }
+
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.0.borrow()).into_owned()
+    }
+}
+
+impl Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buffer_test() {
+        let buffer = Buffer::new();
+        let previous = set_output(Some(Rc::new(RefCell::new(buffer.clone()))));
+        write_line("hello").unwrap();
+        write_line("world").unwrap();
+        set_output(previous);
+        assert_eq!(buffer.contents(), "hello\nworld\n");
+    }
+}
@@ -1,19 +1,137 @@
-use crate::interpreter::{VarEnv, WrappedEnv};
+use crate::capability;
+use crate::encoding;
+use crate::error::RuntimeError;
+use crate::format;
+use crate::hash;
+use crate::interpreter::{env_from_decls, VarEnv, WrappedEnv};
+use crate::label;
+use crate::time::DateTime;
+use crate::type_::Type;
 use crate::value::command::Command;
 use crate::value::Value;
+#[cfg(feature = "regex")]
+use regex::Regex;
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::rc::Rc;
+use tego_parser::ast::Prog;
+#[cfg(feature = "unicode")]
+use unicode_normalization::UnicodeNormalization;
+#[cfg(feature = "unicode")]
+use unicode_segmentation::UnicodeSegmentation;
+
+const PRELUDE_SOURCE: &str = include_str!("prelude.tgo");
+
+pub(crate) const ASSERTIONS: [&str; 3] = ["assert", "assertEq", "expectError"];
 
 pub fn prelude() -> WrappedEnv {
     let prelude_decls = vec![
         ("return", return_fn()),
         ("println", println_fn()),
         ("readLine", readline_fn()),
-        ("readInt", readint_fn())
+        ("readInt", readint_fn()),
+        ("exit", exit_fn()),
+        ("random", random_fn()),
+        ("randomInt", random_int_fn()),
+        ("exec", exec_fn()),
+        ("getEnv", get_env_fn()),
+        ("now", Value::Command(Command::now())),
+        ("formatTime", format_time_fn()),
+        ("parseTime", parse_time_fn()),
+        ("addSeconds", add_time_fn("addSeconds", 1)),
+        ("addDays", add_time_fn("addDays", 86_400)),
+        ("diffSeconds", diff_time_fn("diffSeconds", 1)),
+        ("diffDays", diff_time_fn("diffDays", 86_400)),
+        ("isDigit", char_fn("isDigit", |c| c.is_ascii_digit().into())),
+        ("isAlpha", char_fn("isAlpha", |c| c.is_alphabetic().into())),
+        ("isSpace", char_fn("isSpace", |c| c.is_whitespace().into())),
+        (
+            "toUpperChar",
+            char_fn("toUpperChar", |c| single_char(c, c.to_uppercase())),
+        ),
+        (
+            "toLowerChar",
+            char_fn("toLowerChar", |c| single_char(c, c.to_lowercase())),
+        ),
+        ("digitToInt", char_fn("digitToInt", digit_to_int)),
+        ("toString", to_string_fn()),
+        ("show", show_fn()),
+        ("parseInt", parse_int_fn()),
+        ("toChar", to_char_fn()),
+        ("compare", compare_fn()),
+        ("sort", sort_fn()),
+        ("sortBy", sort_by_fn()),
+        ("tupleGet", tuple_get_fn()),
+        ("tupleLength", tuple_length_fn()),
+        ("map", map_fn()),
+        ("filter", filter_fn()),
+        ("foldl", foldl_fn()),
+        ("zip", zip_fn()),
+        ("slice", slice_fn()),
+        ("take", take_fn()),
+        ("drop", drop_fn()),
+        ("indexOf", index_of_fn()),
+        ("charAt", char_at_fn()),
+        ("byteLength", byte_length_fn()),
+        (
+            "base64Encode",
+            encode_fn("base64Encode", encoding::base64_encode),
+        ),
+        (
+            "base64Decode",
+            decode_fn("base64Decode", encoding::base64_decode),
+        ),
+        ("hexEncode", encode_fn("hexEncode", encoding::hex_encode)),
+        ("hexDecode", decode_fn("hexDecode", encoding::hex_decode)),
+        ("hash", hash_fn()),
+        ("format", format_fn()),
+        ("assert", assert_fn()),
+        ("assertEq", assert_eq_fn()),
+        ("expectError", expect_error_fn()),
+        ("catch", catch_fn()),
+        ("label", label::label_fn()),
+        ("fromChar", char_fn("fromChar", |c| Value::Int(c as i32))),
+        ("typeOf", type_of_fn()),
+        ("isInt", type_fn(|type_| matches!(type_, Type::Int))),
+        ("isBool", type_fn(|type_| matches!(type_, Type::Bool))),
+        ("isChar", type_fn(|type_| matches!(type_, Type::Char))),
+        ("isString", type_fn(is_string)),
+        ("isTuple", type_fn(|type_| matches!(type_, Type::Tuple(_)))),
+        ("isBoxed", type_fn(|type_| matches!(type_, Type::Boxed(_)))),
+        ("isFunction", type_fn(|type_| matches!(type_, Type::Fn_))),
+        ("isCommand", type_fn(|type_| matches!(type_, Type::Command))),
     ];
-    prelude_decls
+    let builtins = prelude_decls
         .into_iter()
+        .chain(http_decls())
+        .chain(regex_decls())
+        .chain(unicode_decls())
+        .chain(crypto_decls())
+        .chain(uuid_decls())
         .fold(VarEnv::empty(), |parent, (ident, val)| {
             VarEnv::associate_ident(ident.into(), val, parent)
-        })
+        });
+    VarEnv::add_parent(&source_env(), &builtins)
+}
+
+// The name of every builtin, sorted (ex. for completion in editors)
+pub fn names() -> Vec<String> {
+    let mut names = VarEnv::idents(&prelude());
+    names.sort_unstable();
+    names.dedup();
+    names
+}
+
+// Declarations written in tego (see 'prelude.tgo')
+fn source_env() -> WrappedEnv {
+    match tego_parser::prog(PRELUDE_SOURCE.into()) {
+        Ok((_, Prog::Library(decls))) => env_from_decls(&decls),
+        Ok((_, Prog::Binary(_, _))) => unreachable!("The prelude doesn't have a 'main'"),
+        Err(error) => panic!(
+            "The prelude failed to parse: {}",
+            tego_parser::ParseError::from(error)
+        ),
+    }
 }
 
 fn return_fn() -> Value {
@@ -31,3 +149,964 @@ fn readline_fn() -> Value {
 fn readint_fn() -> Value {
     Value::Command(Command::readint())
 }
+
+fn exit_fn() -> Value {
+    Value::internal_fn(|val| match val {
+        Value::Int(code) => Value::Command(Command::exit(code)),
+        Value::Error(_) => val,
+        v => Value::Error(format!("'exit' expects an 'Int', found '{}'", v.type_()).into()),
+    })
+}
+
+fn random_fn() -> Value {
+    Value::Command(Command::random())
+}
+
+fn random_int_fn() -> Value {
+    binary_fn(|lo, hi| match (lo, hi) {
+        (Value::Int(lo), Value::Int(hi)) if lo > hi => Value::Error(
+            format!(
+                "'randomInt' lower bound {} is greater than upper bound {}",
+                lo, hi
+            )
+            .into(),
+        ),
+        (Value::Int(lo), Value::Int(hi)) => Value::Command(Command::random_int(lo, hi)),
+        (lo, hi) => Value::Error(
+            format!(
+                "'randomInt' expects two 'Int's, found '{}' and '{}'",
+                lo.type_(),
+                hi.type_()
+            )
+            .into(),
+        ),
+    })
+}
+
+// Arguments can be a single `String` or a tuple of `String`s
+fn exec_fn() -> Value {
+    binary_fn(|program, args| {
+        let arg_list = match &args {
+            Value::Tuple(args) => args.into_iter().map(|arg| arg.as_string()).collect(),
+            arg => arg.as_string().map(|arg| vec![arg]),
+        };
+        match (program.as_string(), arg_list) {
+            (Some(program), Some(args)) => Value::Command(Command::exec(program, args)),
+            _ => Value::Error(
+                format!(
+                    "'exec' expects a 'String' and a tuple of 'String's, found '{}' and '{}'",
+                    program.type_(),
+                    args.type_()
+                )
+                .into(),
+            ),
+        }
+    })
+}
+
+fn get_env_fn() -> Value {
+    Value::internal_fn(|name| match name.as_string() {
+        Some(name) => Value::Command(Command::get_env(name)),
+        None if name.is_error() => name,
+        None => {
+            Value::Error(format!("'getEnv' expects a 'String', found '{}'", name.type_()).into())
+        }
+    })
+}
+
+fn format_time_fn() -> Value {
+    binary_fn(|pattern, date_time| match pattern.as_string() {
+        Some(pattern) => date_time_fn("formatTime", date_time, |date_time| {
+            match date_time.format(&pattern) {
+                Ok(formatted) => formatted.as_str().into(),
+                Err(e) => Value::Error(e.into()),
+            }
+        }),
+        None => pattern_error("formatTime", &pattern),
+    })
+}
+
+// Results in `[dateTime]`, or `()` if the string doesn't match the pattern
+fn parse_time_fn() -> Value {
+    binary_fn(|pattern, s| match pattern.as_string() {
+        Some(pattern) => string_fn("parseTime", s, |s| match DateTime::parse(&pattern, &s) {
+            Ok(Some(date_time)) => Value::Boxed(Box::new(date_time.to_value())),
+            Ok(None) => Value::unit(),
+            Err(e) => Value::Error(e.into()),
+        }),
+        None => pattern_error("parseTime", &pattern),
+    })
+}
+
+// Adds `n` of a unit of time (in seconds)
+fn add_time_fn(name: &'static str, unit: i64) -> Value {
+    binary_fn(move |n, date_time| match n {
+        Value::Int(n) => date_time_fn(name, date_time, |date_time| {
+            match date_time.add_seconds(i64::from(n) * unit) {
+                Ok(date_time) => date_time.to_value(),
+                Err(e) => Value::Error(e.into()),
+            }
+        }),
+        n => Value::Error(format!("'{}' expects an 'Int', found '{}'", name, n.type_()).into()),
+    })
+}
+
+// How many of a unit of time (in seconds) `a` is after `b`, rounded towards 0
+fn diff_time_fn(name: &'static str, unit: i64) -> Value {
+    binary_fn(move |a, b| {
+        date_time_fn(name, a, |a| {
+            date_time_fn(name, b.clone(), |b| {
+                match i32::try_from((a.to_unix() - b.to_unix()) / unit) {
+                    Ok(diff) => Value::Int(diff),
+                    Err(_) => Value::Error(format!("'{}' is too big for an 'Int'", name).into()),
+                }
+            })
+        })
+    })
+}
+
+fn date_time_fn<F>(name: &str, date_time: Value, f: F) -> Value
+where
+    F: Fn(DateTime) -> Value,
+{
+    match DateTime::from_value(&date_time) {
+        Some(date_time) => f(date_time),
+        None => Value::Error(
+            format!(
+                "'{}' expects a date and time ('[(year, month, day, hour, minute, second)]'), found '{}'",
+                name,
+                date_time.type_()
+            )
+            .into(),
+        ),
+    }
+}
+
+fn pattern_error(name: &str, pattern: &Value) -> Value {
+    Value::Error(
+        format!(
+            "'{}' expects a 'String' pattern, found '{}'",
+            name,
+            pattern.type_()
+        )
+        .into(),
+    )
+}
+
+#[cfg(feature = "http")]
+fn http_decls() -> Vec<(&'static str, Value)> {
+    vec![("httpGet", http_get_fn()), ("httpPost", http_post_fn())]
+}
+
+#[cfg(not(feature = "http"))]
+fn http_decls() -> Vec<(&'static str, Value)> {
+    vec![]
+}
+
+#[cfg(feature = "http")]
+fn http_get_fn() -> Value {
+    Value::internal_fn(|url| match url.as_string() {
+        Some(url) => Value::Command(Command::http_get(url)),
+        None if url.is_error() => url,
+        None => {
+            Value::Error(format!("'httpGet' expects a 'String', found '{}'", url.type_()).into())
+        }
+    })
+}
+
+#[cfg(feature = "http")]
+fn http_post_fn() -> Value {
+    binary_fn(|url, body| match (url.as_string(), body.as_string()) {
+        (Some(url), Some(body)) => Value::Command(Command::http_post(url, body)),
+        _ => Value::Error(
+            format!(
+                "'httpPost' expects two 'String's, found '{}' and '{}'",
+                url.type_(),
+                body.type_()
+            )
+            .into(),
+        ),
+    })
+}
+
+#[cfg(feature = "regex")]
+fn regex_decls() -> Vec<(&'static str, Value)> {
+    vec![
+        (
+            "regexMatch",
+            regex_fn("regexMatch", |regex, s| regex.is_match(&s).into()),
+        ),
+        ("regexFind", regex_fn("regexFind", regex_find)),
+        ("regexReplace", regex_replace_fn()),
+        ("regexSplit", regex_fn("regexSplit", regex_split)),
+    ]
+}
+
+#[cfg(not(feature = "regex"))]
+fn regex_decls() -> Vec<(&'static str, Value)> {
+    vec![]
+}
+
+// Strings split into what a reader sees as single characters (grapheme
+// clusters), and the Unicode normal forms
+#[cfg(feature = "unicode")]
+fn unicode_decls() -> Vec<(&'static str, Value)> {
+    vec![
+        (
+            "lengthGraphemes",
+            unicode_fn("lengthGraphemes", |s| {
+                Value::Int(s.graphemes(true).count() as i32)
+            }),
+        ),
+        (
+            "toGraphemes",
+            unicode_fn("toGraphemes", |s| {
+                Value::from_values(s.graphemes(true).map(Value::from).collect())
+            }),
+        ),
+        (
+            "toNFC",
+            unicode_fn("toNFC", |s| s.nfc().collect::<String>().into()),
+        ),
+        (
+            "toNFD",
+            unicode_fn("toNFD", |s| s.nfd().collect::<String>().into()),
+        ),
+        (
+            "toNFKC",
+            unicode_fn("toNFKC", |s| s.nfkc().collect::<String>().into()),
+        ),
+        (
+            "toNFKD",
+            unicode_fn("toNFKD", |s| s.nfkd().collect::<String>().into()),
+        ),
+    ]
+}
+
+#[cfg(not(feature = "unicode"))]
+fn unicode_decls() -> Vec<(&'static str, Value)> {
+    vec![]
+}
+
+#[cfg(feature = "unicode")]
+fn unicode_fn<F>(name: &'static str, f: F) -> Value
+where
+    F: Fn(&str) -> Value + 'static,
+{
+    Value::internal_fn(move |s| match s {
+        Value::Error(_) => s,
+        s => string_fn(name, s, |s| f(&s)),
+    })
+}
+
+// Hex digests of a string's UTF-8 bytes, or a tuple of bytes
+#[cfg(feature = "crypto")]
+fn crypto_decls() -> Vec<(&'static str, Value)> {
+    vec![
+        ("sha256", encode_fn("sha256", hash::sha256)),
+        ("sha1", encode_fn("sha1", hash::sha1)),
+        ("md5", encode_fn("md5", hash::md5)),
+    ]
+}
+
+#[cfg(not(feature = "crypto"))]
+fn crypto_decls() -> Vec<(&'static str, Value)> {
+    vec![]
+}
+
+#[cfg(feature = "uuid")]
+fn uuid_decls() -> Vec<(&'static str, Value)> {
+    vec![
+        ("uuidV4", Value::Command(Command::uuid_v4())),
+        ("uuidV7", Value::Command(Command::uuid_v7())),
+    ]
+}
+
+#[cfg(not(feature = "uuid"))]
+fn uuid_decls() -> Vec<(&'static str, Value)> {
+    vec![]
+}
+
+// Takes a pattern and then a string
+#[cfg(feature = "regex")]
+fn regex_fn<F>(name: &'static str, f: F) -> Value
+where
+    F: Fn(Regex, String) -> Value + 'static,
+{
+    binary_fn(
+        move |pattern, s| match (pattern.as_string(), s.as_string()) {
+            (Some(pattern), Some(s)) => {
+                match (regex(&pattern), capability::steps(s.len() as u64)) {
+                    (Ok(regex), Ok(())) => f(regex, s),
+                    (Err(e), _) => e,
+                    (_, Err(error)) => Value::Error(error),
+                }
+            }
+            _ => Value::Error(
+                format!(
+                    "'{}' expects two 'String's, found '{}' and '{}'",
+                    name,
+                    pattern.type_(),
+                    s.type_()
+                )
+                .into(),
+            ),
+        },
+    )
+}
+
+// The `regex` crate's syntax errors show the pattern with a marker under the
+// mistake, so only the last line (which says what it is) is kept
+#[cfg(feature = "regex")]
+fn regex(pattern: &str) -> Result<Regex, Value> {
+    Regex::new(pattern).map_err(|error| {
+        let error = error.to_string();
+        let reason = error.lines().last().unwrap_or_default();
+        let reason = reason.strip_prefix("error: ").unwrap_or(reason);
+        Value::Error(format!("Invalid regex '{}': {}", pattern, reason).into())
+    })
+}
+
+// Results in `[text, start, groups]` if there's a match, otherwise `()`,
+// where each group is `[text]` if it was matched, otherwise `()`
+// `start` counts characters, not bytes
+#[cfg(feature = "regex")]
+fn regex_find(regex: Regex, s: String) -> Value {
+    match regex.captures(&s) {
+        Some(captures) => {
+            let found = captures.get(0).unwrap();
+            let groups = captures
+                .iter()
+                .skip(1)
+                .map(|group| match group {
+                    Some(group) => Value::Boxed(Box::new(group.as_str().into())),
+                    None => Value::unit(),
+                })
+                .collect::<Vec<_>>();
+            let record = vec![
+                found.as_str().into(),
+                Value::Int(s[..found.start()].chars().count() as i32),
+                Value::Boxed(Box::new(Value::from_values(groups))),
+            ];
+            Value::Boxed(Box::new(Value::from_values(record)))
+        }
+        None => Value::unit(),
+    }
+}
+
+#[cfg(feature = "regex")]
+fn regex_split(regex: Regex, s: String) -> Value {
+    Value::from_values(regex.split(&s).map(Value::from).collect())
+}
+
+// Takes a pattern, a replacement, and then a string
+// `$n` in the replacement is group `n` (`$0` is the whole match), and
+// `${n}` separates it from any letters or digits after it
+#[cfg(feature = "regex")]
+fn regex_replace_fn() -> Value {
+    binary_fn(|pattern, replacement| {
+        Value::internal_fn(move |s| {
+            match (pattern.as_string(), replacement.as_string(), s.as_string()) {
+                (Some(pattern), Some(replacement), Some(s)) => match regex(&pattern) {
+                    Ok(regex) => regex.replace_all(&s, replacement.as_str()).as_ref().into(),
+                    Err(e) => e,
+                },
+                _ if s.is_error() => s,
+                _ => Value::Error(
+                    format!(
+                        "'regexReplace' expects three 'String's, found '{}', '{}', and '{}'",
+                        pattern.type_(),
+                        replacement.type_(),
+                        s.type_()
+                    )
+                    .into(),
+                ),
+            }
+        })
+    })
+}
+
+fn char_fn<F>(name: &'static str, f: F) -> Value
+where
+    F: Fn(char) -> Value + 'static,
+{
+    Value::internal_fn(move |val| match val {
+        Value::Char(c) => f(c),
+        Value::Error(_) => val,
+        v => Value::Error(format!("'{}' expects a 'Char', found '{}'", name, v.type_()).into()),
+    })
+}
+
+// Some characters change case into multiple characters (ex. 'ß' -> "SS");
+// those are left as they are
+fn single_char(c: char, mut converted: impl Iterator<Item = char>) -> Value {
+    match (converted.next(), converted.next()) {
+        (Some(converted), None) => Value::Char(converted),
+        _ => Value::Char(c),
+    }
+}
+
+fn digit_to_int(c: char) -> Value {
+    match c.to_digit(10) {
+        Some(digit) => Value::Int(digit as i32),
+        None => Value::Error(format!("'{}' is not a digit", c).into()),
+    }
+}
+
+fn to_string_fn() -> Value {
+    Value::internal_fn(|val| match val {
+        Value::Error(_) => val,
+        val => val.to_plain_string().into(),
+    })
+}
+
+// A value written the way it would be in code (unlike `toString`, strings
+// are quoted), like the REPL shows it
+fn show_fn() -> Value {
+    Value::internal_fn(|val| match val {
+        Value::Error(_) => val,
+        val => val.display_pretty().into(),
+    })
+}
+
+// The name of a value's type, written the same way as in a signature
+// (ex. `typeOf [1] == "[Int]"`, `typeOf (1, 'a') == "(Int, Char)"`)
+fn type_of_fn() -> Value {
+    Value::internal_fn(|val| match val {
+        Value::Error(_) => val,
+        val => type_name(&val.type_()).into(),
+    })
+}
+
+fn type_name(type_: &Type) -> String {
+    match type_ {
+        Type::Int => "Int".into(),
+        Type::Bool => "Bool".into(),
+        Type::Char => "Char".into(),
+        type_ if is_string(type_) => "String".into(),
+        Type::Tuple(types) => format!(
+            "({})",
+            types.iter().map(type_name).collect::<Vec<_>>().join(", ")
+        ),
+        Type::Boxed(inner) => format!("[{}]", type_name(inner)),
+        Type::Fn_ => "Function".into(),
+        Type::Command => "Command".into(),
+        Type::Error => "Error".into(),
+    }
+}
+
+// Strings are boxed tuples of `Char`s
+fn is_string(type_: &Type) -> bool {
+    match type_ {
+        Type::Boxed(inner) => match &**inner {
+            Type::Tuple(types) => types.iter().all(|type_| *type_ == Type::Char),
+            Type::Char => true,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+// Whether a value's type is a certain kind of type (ex. `isInt 1 == true`)
+fn type_fn(pred: fn(&Type) -> bool) -> Value {
+    Value::internal_fn(move |val| match val {
+        Value::Error(_) => val,
+        val => Value::Bool(pred(&val.type_())),
+    })
+}
+
+// Results in `[n]` if the string is an `Int`, otherwise `()`
+fn parse_int_fn() -> Value {
+    Value::internal_fn(|val| match val.as_string() {
+        Some(s) => match s.trim().parse::<i32>() {
+            Ok(i) => Value::Boxed(Box::new(Value::Int(i))),
+            Err(_) => Value::unit(),
+        },
+        None if val.is_error() => val,
+        None => {
+            Value::Error(format!("'parseInt' expects a 'String', found '{}'", val.type_()).into())
+        }
+    })
+}
+
+fn to_char_fn() -> Value {
+    Value::internal_fn(|val| match val {
+        Value::Int(i) => match std::char::from_u32(i as u32) {
+            Some(c) if i >= 0 => Value::Char(c),
+            _ => Value::Error(format!("{} is not a valid character code", i).into()),
+        },
+        Value::Error(_) => val,
+        v => Value::Error(format!("'toChar' expects an 'Int', found '{}'", v.type_()).into()),
+    })
+}
+
+// Assertions result in `true` when they pass
+fn assert_fn() -> Value {
+    Value::internal_fn(|cond| match cond {
+        Value::Bool(true) => cond,
+        Value::Bool(false) => assertion_failed(None),
+        Value::Error(_) => cond,
+        cond => Value::Error(format!("'assert' expects a 'Bool', found '{}'", cond.type_()).into()),
+    })
+}
+
+// Errors aren't passed through, so that they're reported as failures
+fn assert_eq_fn() -> Value {
+    Value::internal_fn(|expected| {
+        // The same equality as `==`, so values that can't be tested for
+        // equality (ex. functions) are an error instead of a failure
+        Value::internal_fn(move |actual| match expected.clone().equal(actual.clone()) {
+            Value::Bool(true) => Value::Bool(true),
+            Value::Bool(false) => {
+                assertion_failed(Some((expected.to_string(), actual.to_string())))
+            }
+            error => error,
+        })
+    })
+}
+
+fn expect_error_fn() -> Value {
+    Value::internal_fn(|val| match val {
+        Value::Error(_) => Value::Bool(true),
+        val => assertion_failed(Some(("an error".into(), val.to_string()))),
+    })
+}
+
+// The interpreter adds the assertion and where it was (see `RuntimeError::Assertion`)
+fn assertion_failed(details: Option<(String, String)>) -> Value {
+    Value::Error(RuntimeError::Assertion {
+        expr: None,
+        location: None,
+        details: details.map(Box::new),
+    })
+}
+
+// Applies the handler to an error's message and kind, so the program can
+// recover from it (ex. `catch (1 / 0) (fn (message, kind) -> 0)`), and the
+// errors of a Command are caught when it's run
+// Anything else is the same, and an interrupt (see `capability::interrupt_flag`),
+// a `break` (see `label.rs`), or an `exit` can't be caught, since it has to
+// stop what's being evaluated
+fn catch_fn() -> Value {
+    Value::internal_fn(|value| {
+        Value::internal_fn(move |handler| match value.clone() {
+            Value::Command(command) => {
+                Value::Command(Command::unit(Value::unit()).bind(move |_| {
+                    match handle(command.run(), &handler) {
+                        Value::Command(command) => Ok(command),
+                        value => Ok(Command::unit(value)),
+                    }
+                }))
+            }
+            value => handle(value, &handler),
+        })
+    })
+}
+
+fn handle(value: Value, handler: &Value) -> Value {
+    match value {
+        Value::Error(RuntimeError::Interrupted | RuntimeError::Break | RuntimeError::Exit(_)) => {
+            value
+        }
+        Value::Error(error) => handler.clone().apply(Value::from_values(vec![
+            error.to_string().as_str().into(),
+            error.kind().into(),
+        ])),
+        value => value,
+    }
+}
+
+// Fills in the placeholders in a template (see 'format.rs')
+fn format_fn() -> Value {
+    binary_fn(|template, args| match template.as_string() {
+        Some(template) => match format::format(&template, args.into_values()) {
+            Ok(s) => s.as_str().into(),
+            Err(e) => Value::Error(e.into()),
+        },
+        None => Value::Error(
+            format!(
+                "'format' expects a 'String' template, found '{}'",
+                template.type_()
+            )
+            .into(),
+        ),
+    })
+}
+
+// Results in `-1`, `0`, or `1` (see `Value::compare` for the order)
+fn compare_fn() -> Value {
+    binary_fn(|a, b| match a.compare(&b) {
+        Ok(ordering) => Value::Int(ordering as i32),
+        Err(error) => Value::Error(error),
+    })
+}
+
+fn sort_fn() -> Value {
+    Value::internal_fn(|list| match list {
+        Value::Error(_) => list,
+        list => sort_values(list, |a, b| a.compare(b)),
+    })
+}
+
+// `cmp a b` results in a negative `Int` if `a < b`,
+// `0` if `a == b`, and a positive `Int` if `a > b`
+fn sort_by_fn() -> Value {
+    binary_fn(|cmp, list| {
+        sort_values(list, |a, b| {
+            match cmp.clone().apply(a.clone()).apply(b.clone()) {
+                Value::Int(i) => Ok(i.cmp(&0)),
+                Value::Error(error) => Err(error),
+                v => Err(format!(
+                    "'sortBy' comparison must result in an 'Int', found '{}'",
+                    v.type_()
+                )
+                .into()),
+            }
+        })
+    })
+}
+
+// The sort is stable; the first error found stops the sort
+fn sort_values<F>(list: Value, cmp: F) -> Value
+where
+    F: Fn(&Value, &Value) -> Result<Ordering, RuntimeError>,
+{
+    let mut vals = list.into_values();
+    let mut error = None;
+    vals.sort_by(|a, b| {
+        if error.is_some() {
+            return Ordering::Equal;
+        }
+        capability::step()
+            .and_then(|_| cmp(a, b))
+            .unwrap_or_else(|e| {
+                error = Some(e);
+                Ordering::Equal
+            })
+    });
+    match error {
+        Some(error) => Value::Error(error),
+        None => Value::from_values(vals),
+    }
+}
+
+// Results in `[value]` if `tuple` has a value at `index`, otherwise `()`
+// (applying the index instead is an error if it's out of bounds)
+fn tuple_get_fn() -> Value {
+    binary_fn(|index, tuple| match index {
+        Value::Int(index) => usize::try_from(index)
+            .ok()
+            .and_then(|i| tuple.into_values().into_iter().nth(i))
+            .map_or_else(Value::unit, |val| Value::Boxed(Box::new(val))),
+        index => Value::Error(
+            format!(
+                "'tupleGet' expects an 'Int' index, found '{}'",
+                index.type_()
+            )
+            .into(),
+        ),
+    })
+}
+
+// The number of values in a tuple (a single value is a tuple of one value)
+fn tuple_length_fn() -> Value {
+    Value::internal_fn(|tuple| match tuple {
+        Value::Error(_) => tuple,
+        tuple => Value::Int(tuple.into_values().len() as i32),
+    })
+}
+
+// `map`, `filter`, `foldl`, and `zip` are native, since they're used so often,
+// but they work the same as if they were declared in 'prelude.tgo' like this:
+//
+//     map f list =
+//         match list to
+//         | () -> ()
+//         | head, tail -> f head, map f tail
+
+// Applies `f` to every value in `list`
+fn map_fn() -> Value {
+    binary_fn(|f, list| {
+        let vals = list.into_values().into_iter();
+        joined(vals.map(|val| f.clone().apply(val)).collect())
+    })
+}
+
+// Keeps the values in `list` that satisfy `pred`
+fn filter_fn() -> Value {
+    binary_fn(|pred, list| {
+        let mut kept = vec![];
+        for val in list.into_values() {
+            if let Err(error) = capability::step() {
+                return Value::Error(error);
+            }
+            match pred.clone().apply(val.clone()) {
+                Value::Bool(true) => kept.push(val),
+                Value::Bool(false) => {}
+                error @ Value::Error(_) => return error,
+                v => {
+                    return Value::Error(
+                        format!(
+                            "'filter' expects a 'Bool' from 'pred', found '{}'",
+                            v.type_()
+                        )
+                        .into(),
+                    )
+                }
+            }
+        }
+        Value::from_values(kept)
+    })
+}
+
+// Combines `list` from the left, starting with `acc`
+// `foldl f acc (1, 2)` == `f (f acc 1) 2`
+fn foldl_fn() -> Value {
+    Value::internal_fn(|f| {
+        binary_fn(move |acc, list| {
+            list.into_values()
+                .into_iter()
+                .fold(acc, |acc, val| f.clone().apply(acc).apply(val))
+        })
+    })
+}
+
+// Pairs up the values of two tuples as boxed tuples
+// The result is as long as the shorter tuple
+fn zip_fn() -> Value {
+    binary_fn(|a, b| {
+        let vals = a.into_values().into_iter().zip(b.into_values());
+        let pairs = vals.map(|(a, b)| Value::Boxed(Box::new(Value::join(a, b))));
+        joined(pairs.collect())
+    })
+}
+
+// The same as joining `vals` with `,` (so tuples in it are flattened, and the
+// first error is the result), without joining them one at a time
+fn joined(vals: Vec<Value>) -> Value {
+    if let Err(error) = capability::steps(vals.len() as u64) {
+        return Value::Error(error);
+    }
+    let mut flat = vec![];
+    for val in vals {
+        match val {
+            Value::Error(_) => return val,
+            Value::Tuple(tuple) => flat.extend(tuple),
+            val => flat.push(val),
+        }
+    }
+    if let Err(error) = capability::check_tuple_len(flat.len()) {
+        return Value::Error(error.into());
+    }
+    let chars = flat
+        .iter()
+        .map(|val| match val {
+            Value::Char(c) => Some(*c),
+            _ => None,
+        })
+        .collect::<Option<String>>();
+    match chars {
+        Some(string) if flat.len() > 1 => Value::Tuple(string.into()),
+        _ => Value::from_values(flat),
+    }
+}
+
+// The `Char`s of a string from `start` up to (but not including) `end`
+// Both are clamped to the string, so `slice 2 10 "abc" == "c"`, and the result
+// is `""` if `end` isn't after `start`
+fn slice_fn() -> Value {
+    Value::internal_fn(|start| {
+        binary_fn(move |end, s| match (&start, end) {
+            (Value::Error(_), _) => start.clone(),
+            (Value::Int(start), Value::Int(end)) => {
+                string_fn("slice", s, |s| sliced(&s, *start, end).into())
+            }
+            (start, end) => Value::Error(
+                format!(
+                    "'slice' expects 'Int' indices, found '{}' and '{}'",
+                    start.type_(),
+                    end.type_()
+                )
+                .into(),
+            ),
+        })
+    })
+}
+
+// The first `n` `Char`s of a string, or the first `n` values of a tuple (all
+// of them if there are fewer than `n`)
+fn take_fn() -> Value {
+    binary_fn(|n, list| match n {
+        Value::Int(n) => match string_value(&list) {
+            Some(s) => sliced(&s, 0, n).into(),
+            None => Value::from_values(list.into_values().into_iter().take(count(n)).collect()),
+        },
+        n => Value::Error(format!("'take' expects an 'Int', found '{}'", n.type_()).into()),
+    })
+}
+
+// Everything after the first `n` `Char`s of a string, or the first `n` values
+// of a tuple
+fn drop_fn() -> Value {
+    binary_fn(|n, list| match n {
+        Value::Int(n) => match string_value(&list) {
+            Some(s) => sliced(&s, n, i32::MAX).into(),
+            None => Value::from_values(list.into_values().into_iter().skip(count(n)).collect()),
+        },
+        n => Value::Error(format!("'drop' expects an 'Int', found '{}'", n.type_()).into()),
+    })
+}
+
+// Results in `[index]` of the first place `part` is found in a string (in
+// `Char`s), or `()` if it isn't in it
+fn index_of_fn() -> Value {
+    binary_fn(|part, s| match string_value(&part) {
+        Some(part) => string_fn("indexOf", s, |s| match s.find(&part) {
+            Some(i) => Value::Boxed(Box::new(Value::Int(s[..i].chars().count() as i32))),
+            None => Value::unit(),
+        }),
+        None => Value::Error(
+            format!(
+                "'indexOf' expects a 'String' to find, found '{}'",
+                part.type_()
+            )
+            .into(),
+        ),
+    })
+}
+
+// Results in `[char]` if a string has a `Char` at `index`, otherwise `()`
+fn char_at_fn() -> Value {
+    binary_fn(|index, s| match index {
+        Value::Int(index) => string_fn("charAt", s, |s| {
+            usize::try_from(index)
+                .ok()
+                .and_then(|i| s.chars().nth(i))
+                .map_or_else(Value::unit, |c| Value::Boxed(Box::new(Value::Char(c))))
+        }),
+        index => Value::Error(
+            format!("'charAt' expects an 'Int' index, found '{}'", index.type_()).into(),
+        ),
+    })
+}
+
+// The number of bytes in a string's UTF-8 encoding
+fn byte_length_fn() -> Value {
+    Value::internal_fn(|s| match s {
+        Value::Error(_) => s,
+        s => string_fn("byteLength", s, |s| Value::Int(s.len() as i32)),
+    })
+}
+
+fn hash_fn() -> Value {
+    Value::internal_fn(|val| match hash::hash(&val) {
+        Ok(hash) => Value::Int(hash),
+        Err(error) => Value::Error(error),
+    })
+}
+
+// Encodes a string's UTF-8 bytes, or a tuple of bytes (`Int`s from 0 to 255)
+fn encode_fn(name: &'static str, encode: fn(&[u8]) -> String) -> Value {
+    Value::internal_fn(move |val| match val {
+        Value::Error(_) => val,
+        val => match bytes_value(&val) {
+            Some(bytes) => encode(&bytes).as_str().into(),
+            None => Value::Error(
+                format!(
+                    "'{}' expects a 'String' or a tuple of bytes, found '{}'",
+                    name,
+                    val.type_()
+                )
+                .into(),
+            ),
+        },
+    })
+}
+
+// Decodes to a string, so the bytes have to be UTF-8
+fn decode_fn(name: &'static str, decode: fn(&str) -> Result<Vec<u8>, String>) -> Value {
+    Value::internal_fn(move |s| match s {
+        Value::Error(_) => s,
+        s => string_fn(name, s, |s| match decode(&s) {
+            Ok(bytes) => match String::from_utf8(bytes) {
+                Ok(decoded) => decoded.as_str().into(),
+                Err(_) => {
+                    Value::Error(format!("'{}' decoded bytes that aren't UTF-8", name).into())
+                }
+            },
+            Err(e) => Value::Error(e.into()),
+        }),
+    })
+}
+
+fn bytes_value(val: &Value) -> Option<Vec<u8>> {
+    match string_value(val) {
+        Some(s) => Some(s.into_bytes()),
+        None => val
+            .clone()
+            .into_values()
+            .into_iter()
+            .map(|val| match val {
+                Value::Int(byte) => u8::try_from(byte).ok(),
+                _ => None,
+            })
+            .collect(),
+    }
+}
+
+fn string_fn<F>(name: &str, s: Value, f: F) -> Value
+where
+    F: Fn(String) -> Value,
+{
+    match string_value(&s) {
+        // Going through the string is a step for each `Char`
+        Some(s) => match capability::steps(s.len() as u64) {
+            Ok(()) => f(s),
+            Err(error) => Value::Error(error),
+        },
+        None => {
+            Value::Error(format!("'{}' expects a 'String', found '{}'", name, s.type_()).into())
+        }
+    }
+}
+
+// A string of one `Char` can be a boxed `Char` (ex. `['a']`)
+fn string_value(val: &Value) -> Option<String> {
+    match val {
+        Value::Boxed(inner) => match &**inner {
+            Value::Char(c) => Some(c.to_string()),
+            _ => val.as_string(),
+        },
+        _ => None,
+    }
+}
+
+// The `Char`s of `s` from `start` up to `end`, clamped to `s`
+fn sliced(s: &str, start: i32, end: i32) -> String {
+    let start = count(start);
+    s.chars()
+        .skip(start)
+        .take(count(end).saturating_sub(start))
+        .collect()
+}
+
+// A negative count is 0
+fn count(n: i32) -> usize {
+    usize::try_from(n).unwrap_or(0)
+}
+
+// Curried function of two arguments; errors in either argument are passed along
+fn binary_fn<F>(f: F) -> Value
+where
+    F: Fn(Value, Value) -> Value + 'static,
+{
+    let f = Rc::new(f);
+    Value::internal_fn(move |a| {
+        let f = Rc::clone(&f);
+        Value::internal_fn(move |b| match (&a, b) {
+            (Value::Error(_), _) => a.clone(),
+            (_, b @ Value::Error(_)) => b,
+            (a, b) => f(a.clone(), b),
+        })
+    })
+}
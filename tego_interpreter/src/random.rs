@@ -0,0 +1,53 @@
+use std::cell::RefCell;
+
+thread_local! {
+    // Shared by every random Command that gets run
+    static RNG: RefCell<fastrand::Rng> = RefCell::new(fastrand::Rng::new());
+}
+
+// The seed when evaluation is deterministic (see `EvalConfig::deterministic`)
+pub const FIXED_SEED: u64 = 0;
+
+// Seeds the generator so that runs can be reproduced
+pub fn seed(seed: u64) {
+    RNG.with(|rng| rng.borrow_mut().seed(seed))
+}
+
+// Non-negative `Int`
+pub fn int() -> i32 {
+    RNG.with(|rng| rng.borrow_mut().i32(0..=i32::MAX))
+}
+
+// `Int` between `lo` and `hi`, inclusive (`lo` must not be greater than `hi`)
+pub fn int_between(lo: i32, hi: i32) -> i32 {
+    RNG.with(|rng| rng.borrow_mut().i32(lo..=hi))
+}
+
+// 128 random bits (for UUIDs)
+#[cfg(feature = "uuid")]
+pub fn bits() -> u128 {
+    RNG.with(|rng| rng.borrow_mut().u128(..))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeded_test() {
+        seed(42);
+        let first = (int(), int_between(-5, 5));
+        seed(42);
+        let second = (int(), int_between(-5, 5));
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn bounds_test() {
+        assert_eq!(int_between(3, 3), 3);
+        assert!((0..100)
+            .map(|_| int_between(-2, 2))
+            .all(|i| (-2..=2).contains(&i)));
+        assert!((0..100).map(|_| int()).all(|i| i >= 0));
+    }
+}
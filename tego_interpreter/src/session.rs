@@ -0,0 +1,201 @@
+// Evaluates code one piece at a time, where every declaration is kept for the
+// code that comes after it (like the REPL, or the cells of a notebook)
+//
+//     let mut session = Session::new();
+//     session.eval("double n = n * 2")?;
+//     assert_eq!(session.eval("double 21")?, Some(Value::Int(42)));
+//
+// Declaring a name again replaces the old declaration, and everything that
+// uses it sees the new one.
+
+use crate::engine::Engine;
+use crate::error::TegoError;
+use crate::value::Value;
+use std::path::Path;
+use tego_parser::ast::{Decl, Expr, Prog};
+use tego_parser::ParseError;
+
+#[derive(Default)]
+pub struct Session {
+    engine: Engine,
+    decls: Vec<Decl>,
+}
+
+impl Session {
+    // A session with only the prelude, which imports relative to the current directory
+    pub fn new() -> Self {
+        Session::default()
+    }
+
+    pub fn with_dir<P: AsRef<Path>>(dir: P) -> Self {
+        Session {
+            engine: Engine::with_dir(dir),
+            decls: vec![],
+        }
+    }
+
+    // Declares `source` if it's a declaration (resulting in `None`), or
+    // evaluates it if it's an expression, and runs it if it's a Command
+    // A declaration that fails to load (ex. an import that isn't found) is
+    // forgotten, so the session is the same as it was before
+    pub fn eval(&mut self, source: &str) -> Result<Option<Value>, TegoError> {
+        match parse(source)? {
+            Input::Decl(decl) => {
+                let decls = redeclare(&self.decls, decl);
+                self.engine.load_decls(decls.clone(), None)?;
+                self.decls = decls;
+                Ok(None)
+            }
+            Input::Expr(expr) => Ok(Some(self.engine.eval(expr)?)),
+        }
+    }
+
+    // Declares everything in a program, as if each declaration was evaluated
+    // in turn (its `main`, if it has one, is declared as `main`)
+    // Nothing is declared if any of it fails to load
+    pub fn load_source(&mut self, source: &str) -> Result<(), TegoError> {
+        let decls = match tego_parser::prog(source.into()) {
+            Ok((_, Prog::Binary(main, mut decls))) => {
+                decls.push(Decl::Expression("main".into(), main));
+                decls
+            }
+            Ok((_, Prog::Library(decls))) => decls,
+            Err(error) => return Err(ParseError::from(error).into()),
+        };
+        let decls = decls
+            .into_iter()
+            .fold(self.decls.clone(), |decls, decl| redeclare(&decls, decl));
+        self.engine.load_decls(decls.clone(), None)?;
+        self.decls = decls;
+        Ok(())
+    }
+
+    // Everything that's been declared, in the order it was declared
+    pub fn decls(&self) -> &[Decl] {
+        &self.decls
+    }
+
+    // Forgets every declaration
+    pub fn clear(&mut self) -> Result<(), TegoError> {
+        self.engine.load_decls(vec![], None)?;
+        self.decls.clear();
+        Ok(())
+    }
+
+    // For registering functions, or changing the configuration or output
+    // (loading source with it replaces the session's declarations until the
+    // next declaration)
+    pub fn engine(&mut self) -> &mut Engine {
+        &mut self.engine
+    }
+}
+
+enum Input {
+    Decl(Decl),
+    Expr(Expr),
+}
+
+// When neither a declaration nor an expression can be parsed, the error that
+// was found furthest into the source is the one that's reported, since that's
+// what the source was most likely meant to be
+fn parse(source: &str) -> Result<Input, ParseError> {
+    let source = source.trim();
+    let decl_error = match tego_parser::complete(tego_parser::decl)(source.into()) {
+        Ok((_, decl)) => return Ok(Input::Decl(decl)),
+        Err(error) => ParseError::from(error),
+    };
+    match tego_parser::complete(tego_parser::expr)(source.into()) {
+        Ok((_, expr)) => Ok(Input::Expr(expr)),
+        Err(error) => {
+            let expr_error = ParseError::from(error);
+            if (decl_error.line(), decl_error.column()) > (expr_error.line(), expr_error.column()) {
+                Err(decl_error)
+            } else {
+                Err(expr_error)
+            }
+        }
+    }
+}
+
+// The declarations, with `decl` replacing any earlier declaration of the same
+// name (a new definition also replaces its old signature)
+fn redeclare(decls: &[Decl], decl: Decl) -> Vec<Decl> {
+    let replaced = |old: &Decl| match (old, &decl) {
+        (Decl::Expression(old, _), Decl::Expression(new, _))
+        | (Decl::Signature(old, _), Decl::Signature(new, _))
+        | (Decl::Signature(old, _), Decl::Expression(new, _)) => old == new,
+        (old, new) => old == new,
+    };
+    let mut decls = decls
+        .iter()
+        .filter(|old| !replaced(old))
+        .cloned()
+        .collect::<Vec<_>>();
+    decls.push(decl);
+    decls
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::RuntimeError;
+
+    fn session(inputs: &[&str]) -> Result<Option<Value>, TegoError> {
+        let mut session = Session::new();
+        let mut result = Ok(None);
+        for input in inputs {
+            result = session.eval(input);
+        }
+        result
+    }
+
+    basic_test! {
+        session_test
+        session(&["1 + 2"]) => Ok(Some(Value::Int(3)));
+        session(&["double n = n * 2"]) => Ok(None);
+        session(&["double n = n * 2", "double 21"]) => Ok(Some(Value::Int(42)));
+        session(&["a = 1", "b = a + 1", "a + b"]) => Ok(Some(Value::Int(3)));
+        // Declarations can use names that are declared after them
+        session(&["b = a + 1", "a = 1", "b"]) => Ok(Some(Value::Int(2)));
+        session(&["a = 1", "b = a + 1", "a = 10", "b"]) => Ok(Some(Value::Int(11)));
+        session(&["return 1"]) => Ok(Some(Value::Int(1)));
+        session(&["missing"]) => Err(RuntimeError::Value("Variable 'missing' is not declared".into()).into());
+        session(&["total = 1", "totl"])
+            => Err(RuntimeError::Value("Variable 'totl' is not declared (did you mean 'total'?)".into()).into());
+        matches!(session(&["double n = n *"]), Err(TegoError::Parse(_))) => true;
+        matches!(session(&["import \"missing.tgo\""]), Err(TegoError::Runtime(RuntimeError::Import(_)))) => true
+    }
+
+    basic_test! {
+        load_source_test
+        {
+            let mut session = Session::new();
+            session.eval("a = 1").unwrap();
+            session.load_source("main = a + b\na = 10\nb = 2").unwrap();
+            (session.decls().len(), session.eval("main"))
+        } => (3, Ok(Some(Value::Int(12))));
+        {
+            let mut session = Session::new();
+            session.eval("a = 1").unwrap();
+            session.load_source("a = 2\nimport \"missing.tgo\"").unwrap_err();
+            session.eval("a")
+        } => Ok(Some(Value::Int(1)))
+    }
+
+    basic_test! {
+        redeclare_test
+        {
+            let mut session = Session::new();
+            session.eval("a = 1").unwrap();
+            session.eval("a = 2").unwrap();
+            session.eval("import \"missing.tgo\"").unwrap_err();
+            (session.decls().len(), session.eval("a"))
+        } => (1, Ok(Some(Value::Int(2))));
+        {
+            let mut session = Session::new();
+            session.eval("a = 1").unwrap();
+            session.clear().unwrap();
+            session.eval("a").is_err()
+        } => true
+    }
+}
@@ -0,0 +1,77 @@
+// "Did you mean ...?" for names that aren't declared
+
+// The name in `candidates` that's closest to `name`, if it's close enough to
+// likely be what was meant (at most a third of its letters are different, so
+// names shorter than 3 letters don't have any)
+// When several are just as close, the first one wins (ex. the innermost)
+pub fn similar<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let max = name.chars().count() / 3;
+    if max == 0 {
+        return None;
+    }
+    candidates
+        .into_iter()
+        .filter(|candidate| *candidate != name && !is_generated(candidate))
+        .map(|candidate| (distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+pub fn did_you_mean(name: &str) -> String {
+    format!("did you mean '{}'?", name)
+}
+
+// Names made up by desugarings (ex. `_0`) can't be written
+fn is_generated(name: &str) -> bool {
+    name.strip_prefix('_')
+        .is_some_and(|rest| rest.starts_with(|c: char| c.is_ascii_digit()))
+}
+
+// How many `Char`s have to be inserted, removed, replaced, or swapped with the
+// next one to turn `a` into `b` (ex. `lenght` is 1 away from `length`)
+fn distance(a: &str, b: &str) -> usize {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+    // Distances from the prefixes of `a` to the prefixes of `b` that are one
+    // and two shorter than `b[..j]`
+    let mut before = (0..=b.len()).collect::<Vec<_>>();
+    let mut two_before = before.clone();
+    for i in 1..=a.len() {
+        let mut row = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let replace = before[j - 1] + usize::from(a[i - 1] != b[j - 1]);
+            row[j] = replace.min(before[j] + 1).min(row[j - 1] + 1);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(two_before[j - 2] + 1);
+            }
+        }
+        two_before = std::mem::replace(&mut before, row);
+    }
+    before[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    basic_test! {
+        distance_test
+        distance("length", "length") => 0;
+        distance("lenght", "length") => 1;
+        distance("map", "mop") => 1;
+        distance("fold", "foldl") => 1;
+        distance("", "abc") => 3;
+        distance("kitten", "sitting") => 3
+    }
+
+    basic_test! {
+        similar_test
+        similar("lenght", vec!["map", "length", "filter"]) => Some("length");
+        similar("fitler", vec!["filter", "fitlers"]) => Some("filter");
+        similar("x", vec!["y", "z"]) => None;
+        similar("apple", vec!["orange"]) => None;
+        similar("_1", vec!["_0"]) => None;
+        similar("abc", vec!["abc"]) => None
+    }
+}
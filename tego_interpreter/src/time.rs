@@ -0,0 +1,330 @@
+// Dates and times in UTC, used by the 'now', 'formatTime', 'parseTime',
+// 'addSeconds', 'addDays', 'diffSeconds', and 'diffDays' builtins
+//
+// In tego a date and time is `[(year, month, day, hour, minute, second)]`
+// (boxed, so it's a single value inside of other tuples), and `import Time`
+// has functions for getting its parts
+//
+// A pattern for 'formatTime' and 'parseTime' is text with these placeholders:
+// * `%Y`: the year, with at least 4 digits
+// * `%m`, `%d`: the month and day, with 2 digits
+// * `%H`, `%M`, `%S`: the hour (0 to 23), minute, and second, with 2 digits
+// * `%b`: the month's short name (ex. `Jan`)
+// * `%a`: the weekday's short name (ex. `Mon`)
+// * `%%`: a literal `%`
+
+use crate::capability;
+use crate::value::Value;
+use std::cell::Cell;
+use std::convert::TryFrom;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+// 1970-01-01 was a Thursday
+const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+const SECONDS_PER_DAY: i64 = 86_400;
+// 2000-01-01 00:00:00
+const VIRTUAL_START: i64 = 946_684_800_000;
+
+thread_local! {
+    static VIRTUAL_CLOCK: Cell<i64> = const { Cell::new(VIRTUAL_START) };
+}
+
+// Milliseconds since 1970-01-01 00:00:00, from the system's clock, or from a
+// virtual clock if evaluation is deterministic (see `capability`), which
+// starts at 2000-01-01 00:00:00 and moves forward a second every time it's read
+pub fn unix_millis() -> i64 {
+    if capability::deterministic() {
+        return VIRTUAL_CLOCK.with(|clock| clock.replace(clock.get() + 1000));
+    }
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_millis() as i64,
+        Err(error) => -(error.duration().as_millis() as i64),
+    }
+}
+
+pub fn reset_clock() {
+    VIRTUAL_CLOCK.with(|clock| clock.set(VIRTUAL_START))
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct DateTime {
+    year: i32,
+    month: u32,
+    day: u32,
+    hour: u32,
+    minute: u32,
+    second: u32,
+}
+
+impl DateTime {
+    pub fn now() -> Self {
+        DateTime::from_unix(unix_millis().div_euclid(1000))
+    }
+
+    // Seconds since 1970-01-01 00:00:00
+    pub fn from_unix(seconds: i64) -> Self {
+        let (year, month, day) = civil_from_days(seconds.div_euclid(SECONDS_PER_DAY));
+        let time = seconds.rem_euclid(SECONDS_PER_DAY) as u32;
+        DateTime {
+            year,
+            month,
+            day,
+            hour: time / 3600,
+            minute: time / 60 % 60,
+            second: time % 60,
+        }
+    }
+
+    pub fn to_unix(self) -> i64 {
+        days_from_civil(self.year, self.month, self.day) * SECONDS_PER_DAY
+            + i64::from(self.hour * 3600 + self.minute * 60 + self.second)
+    }
+
+    // `[(year, month, day, hour, minute, second)]`, if each part is in range
+    pub fn from_value(val: &Value) -> Option<Self> {
+        let inner = match val {
+            Value::Boxed(inner) => (**inner).clone(),
+            _ => return None,
+        };
+        let parts = inner
+            .into_values()
+            .into_iter()
+            .map(|val| match val {
+                Value::Int(i) => Some(i),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+        match parts[..] {
+            [year, month, day, hour, minute, second] => {
+                let part = |i: i32, max: u32| u32::try_from(i).ok().filter(|i| *i <= max);
+                let month = part(month, 12).filter(|month| *month >= 1)?;
+                let date_time = DateTime {
+                    year,
+                    month,
+                    day: part(day, days_in_month(year, month)).filter(|day| *day >= 1)?,
+                    hour: part(hour, 23)?,
+                    minute: part(minute, 59)?,
+                    second: part(second, 59)?,
+                };
+                Some(date_time)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn to_value(self) -> Value {
+        let parts = [self.month, self.day, self.hour, self.minute, self.second];
+        let vals = std::iter::once(self.year)
+            .chain(parts.iter().map(|part| *part as i32))
+            .map(Value::Int)
+            .collect::<Vec<_>>();
+        Value::Boxed(Box::new(vals.into()))
+    }
+
+    pub fn add_seconds(self, seconds: i64) -> Result<Self, String> {
+        self.to_unix()
+            .checked_add(seconds)
+            .map(DateTime::from_unix)
+            .filter(|date_time| date_time.year.abs() <= 999_999)
+            .ok_or_else(|| "The date is out of range".into())
+    }
+
+    fn weekday(self) -> &'static str {
+        let days = days_from_civil(self.year, self.month, self.day);
+        WEEKDAYS[days.rem_euclid(7) as usize]
+    }
+
+    pub fn format(self, pattern: &str) -> Result<String, String> {
+        let mut formatted = String::new();
+        let mut chars = pattern.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                formatted.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('Y') => formatted += &format!("{:04}", self.year),
+                Some('m') => formatted += &format!("{:02}", self.month),
+                Some('d') => formatted += &format!("{:02}", self.day),
+                Some('H') => formatted += &format!("{:02}", self.hour),
+                Some('M') => formatted += &format!("{:02}", self.minute),
+                Some('S') => formatted += &format!("{:02}", self.second),
+                Some('b') => formatted += MONTHS[self.month as usize - 1],
+                Some('a') => formatted += self.weekday(),
+                Some('%') => formatted.push('%'),
+                spec => return Err(spec_error("formatTime", spec)),
+            }
+        }
+        Ok(formatted)
+    }
+
+    // The parts that aren't in the pattern are the start of their range
+    // (ex. `parseTime "%Y" "2020"` is midnight on January 1st)
+    pub fn parse(pattern: &str, s: &str) -> Result<Option<Self>, String> {
+        let mut parts = [1970, 1, 1, 0, 0, 0];
+        let mut rest = s;
+        let mut chars = pattern.chars();
+        while let Some(c) = chars.next() {
+            let (index, digits) = match c {
+                '%' => match chars.next() {
+                    Some('Y') => (0, 9),
+                    Some('m') => (1, 2),
+                    Some('d') => (2, 2),
+                    Some('H') => (3, 2),
+                    Some('M') => (4, 2),
+                    Some('S') => (5, 2),
+                    Some('b') => match MONTHS.iter().position(|name| rest.starts_with(name)) {
+                        Some(month) => {
+                            parts[1] = month as i32 + 1;
+                            rest = &rest[3..];
+                            continue;
+                        }
+                        None => return Ok(None),
+                    },
+                    Some('a') => match WEEKDAYS.iter().find(|name| rest.starts_with(*name)) {
+                        Some(_) => {
+                            rest = &rest[3..];
+                            continue;
+                        }
+                        None => return Ok(None),
+                    },
+                    Some('%') => match rest.strip_prefix('%') {
+                        Some(after) => {
+                            rest = after;
+                            continue;
+                        }
+                        None => return Ok(None),
+                    },
+                    spec => return Err(spec_error("parseTime", spec)),
+                },
+                c => match rest.strip_prefix(c) {
+                    Some(after) => {
+                        rest = after;
+                        continue;
+                    }
+                    None => return Ok(None),
+                },
+            };
+            let len = rest
+                .char_indices()
+                .take(digits)
+                .take_while(|(_, c)| c.is_ascii_digit())
+                .count();
+            match rest[..len].parse() {
+                Ok(part) => parts[index] = part,
+                Err(_) => return Ok(None),
+            }
+            rest = &rest[len..];
+        }
+        if !rest.is_empty() {
+            return Ok(None);
+        }
+        let parts = parts
+            .iter()
+            .map(|part| Value::Int(*part))
+            .collect::<Vec<_>>();
+        Ok(DateTime::from_value(&Value::Boxed(Box::new(parts.into()))))
+    }
+}
+
+fn spec_error(name: &str, spec: Option<char>) -> String {
+    match spec {
+        Some(spec) => format!("'{}' pattern has an unknown placeholder '%{}'", name, spec),
+        None => format!("'{}' pattern ends with '%'", name),
+    }
+}
+
+fn is_leap_year(year: i32) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Days since 1970-01-01 (from http://howardhinnant.github.io/date_algorithms.html)
+fn days_from_civil(year: i32, month: u32, day: u32) -> i64 {
+    let year = i64::from(year) - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = i64::from(month);
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i32, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year as i32, month as u32, day as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date_time(year: i32, month: u32, day: u32, hour: u32, minute: u32, second: u32) -> DateTime {
+        DateTime {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+        }
+    }
+
+    basic_test! {
+        unix_test
+        DateTime::from_unix(0) => date_time(1970, 1, 1, 0, 0, 0);
+        DateTime::from_unix(951_782_400) => date_time(2000, 2, 29, 0, 0, 0);
+        DateTime::from_unix(-1) => date_time(1969, 12, 31, 23, 59, 59);
+        date_time(2026, 10, 16, 12, 30, 5).to_unix() => 1_792_153_805;
+        DateTime::from_unix(date_time(2026, 10, 16, 12, 30, 5).to_unix()) => date_time(2026, 10, 16, 12, 30, 5)
+    }
+
+    basic_test! {
+        value_test
+        DateTime::from_value(&date_time(2024, 2, 29, 1, 2, 3).to_value()) => Some(date_time(2024, 2, 29, 1, 2, 3));
+        DateTime::from_value(&date_time(2023, 2, 29, 1, 2, 3).to_value()) => None;
+        DateTime::from_value(&Value::Int(1)) => None
+    }
+
+    basic_test! {
+        format_test
+        date_time(2026, 1, 5, 9, 3, 0).format("%Y-%m-%d %H:%M:%S") => Ok("2026-01-05 09:03:00".into());
+        date_time(2026, 10, 16, 0, 0, 0).format("%a %d %b, 100%%") => Ok("Fri 16 Oct, 100%".into());
+        date_time(2026, 1, 5, 9, 3, 0).format("%q") => Err("'formatTime' pattern has an unknown placeholder '%q'".into());
+        date_time(2026, 1, 5, 9, 3, 0).format("%") => Err("'formatTime' pattern ends with '%'".into())
+    }
+
+    basic_test! {
+        parse_test
+        DateTime::parse("%Y-%m-%d %H:%M:%S", "2026-01-05 09:03:00") => Ok(Some(date_time(2026, 1, 5, 9, 3, 0)));
+        DateTime::parse("%d %b %Y", "16 Oct 2026") => Ok(Some(date_time(2026, 10, 16, 0, 0, 0)));
+        DateTime::parse("%Y/%m/%d", "2026/2/30") => Ok(None);
+        DateTime::parse("%Y", "2026 ") => Ok(None);
+        DateTime::parse("%Q", "") => Err("'parseTime' pattern has an unknown placeholder '%Q'".into())
+    }
+}
@@ -0,0 +1,68 @@
+// Random UUIDs (RFC 9562), used by the 'uuidV4' and 'uuidV7' builtins
+// The random bits come from the same generator as `random`, so they're the
+// same on every run with `--seed` (and `uuidV7`'s time is from the same clock
+// as `now`)
+
+use crate::random;
+use crate::time;
+use std::convert::TryFrom;
+
+const VERSION_MASK: u128 = 0xf << 76;
+const VARIANT_MASK: u128 = 0b11 << 62;
+const VARIANT: u128 = 0b10 << 62;
+
+// All random, other than the version and variant
+pub fn v4() -> String {
+    format(with_version(random::bits(), 4))
+}
+
+// Milliseconds since 1970 in the first 48 bits, so they sort by when they
+// were made, and random after that
+pub fn v7() -> String {
+    let millis = u128::try_from(time::unix_millis()).unwrap_or(0);
+    format(v7_bits(millis, random::bits()))
+}
+
+fn v7_bits(millis: u128, random: u128) -> u128 {
+    let millis = (millis & 0xffff_ffff_ffff) << 80;
+    with_version(millis | (random >> 48), 7)
+}
+
+fn with_version(bits: u128, version: u128) -> u128 {
+    bits & !VERSION_MASK & !VARIANT_MASK | version << 76 | VARIANT
+}
+
+// `xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx`
+fn format(bits: u128) -> String {
+    let hex = format!("{:032x}", bits);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    basic_test! {
+        format_test
+        format(0x0123_4567_89ab_cdef_0123_4567_89ab_cdef) => "01234567-89ab-cdef-0123-456789abcdef";
+        format(with_version(0, 4)) => "00000000-0000-4000-8000-000000000000";
+        format(with_version(u128::MAX, 4)) => "ffffffff-ffff-4fff-bfff-ffffffffffff";
+        format(v7_bits(0x0192_0000_0001, u128::MAX)) => "01920000-0001-7fff-bfff-ffffffffffff"
+    }
+
+    #[test]
+    fn seeded_test() {
+        random::seed(7);
+        let first = (v4(), v7());
+        random::seed(7);
+        assert_eq!(first.0, v4());
+        assert_eq!(first.1[14..], v7()[14..]);
+    }
+}
@@ -1,10 +1,13 @@
+use crate::capability;
 use crate::environment::{Env, EnvVal};
+use crate::error::RuntimeError;
 use crate::interpreter::{eval_expr, VarEnv, WrappedEnv};
 use crate::type_::Type;
 use crate::value::command::Command;
 use crate::value::function::Function;
-use crate::value::tuple::Tuple;
+pub use crate::value::tuple::Tuple;
 use std::cell::RefCell;
+use std::cmp::Ordering;
 use std::fmt;
 use std::ops;
 use std::rc::{Rc, Weak};
@@ -93,7 +96,10 @@ macro_rules! conversion {
 }
 
 pub mod command;
-mod function;
+mod convert;
+pub(crate) mod function;
+#[cfg(feature = "serde")]
+mod serialize;
 mod tuple;
 
 #[derive(Debug, PartialEq, Clone)]
@@ -110,7 +116,12 @@ pub enum Value {
         self_ptr: StoredEnv,
         outer_env: StoredEnv,
     },
-    Error(String),
+    Error(RuntimeError),
+}
+
+thread_local! {
+    // The delayed values that are being evaluated
+    static EVALUATING: RefCell<Vec<*const RefCell<Env<Value>>>> = const { RefCell::new(vec![]) };
 }
 
 impl Value {
@@ -120,14 +131,37 @@ impl Value {
                 value,
                 self_ptr,
                 outer_env,
-            } => Env::get_evaluated_value(&self_ptr.clone().unwrap()).unwrap_or_else(|_| {
-                let val = eval_expr(
-                    *value.clone(),
-                    &Env::with_parent(&outer_env.clone().unwrap(), &env.unwrap_or_else(Env::empty)),
-                );
-                Env::set_value(&self_ptr.clone().unwrap(), val.clone());
-                val
-            }),
+            } => {
+                let self_ptr = match self_ptr.upgrade() {
+                    Ok(self_ptr) => self_ptr,
+                    Err(error) => return Value::Error(error),
+                };
+                Env::get_evaluated_value(&self_ptr).unwrap_or_else(|_| {
+                    // Using the value while it's being evaluated would never finish
+                    // (ex. `let rec a = a + 1`), so it's an error until then
+                    // The value isn't replaced with the error meanwhile, since
+                    // functions made while it's evaluated have copies of the
+                    // environment, and they can use the value once it's done
+                    // (ex. `ones = cons 1 (defer (fn _ -> ones))`)
+                    let ptr = Rc::as_ptr(&self_ptr);
+                    if EVALUATING.with(|evaluating| evaluating.borrow().contains(&ptr)) {
+                        return Value::Error(RuntimeError::Cycle(name_of(&self_ptr)));
+                    }
+                    let outer_env = match outer_env.upgrade() {
+                        Ok(outer_env) => outer_env,
+                        Err(error) => return Value::Error(error),
+                    };
+                    EVALUATING.with(|evaluating| evaluating.borrow_mut().push(ptr));
+                    let val = eval_expr(
+                        *value,
+                        &Env::with_parent(&outer_env, &env.unwrap_or_else(Env::empty)),
+                    );
+                    EVALUATING.with(|evaluating| evaluating.borrow_mut().retain(|p| *p != ptr));
+                    let val = with_location(val, &self_ptr);
+                    Env::set_value(&self_ptr, val.clone());
+                    val
+                })
+            }
             v => v,
         }
     }
@@ -146,13 +180,104 @@ impl Value {
         }
     }
 
+    // Declarations only hold weak references to their environment (to avoid
+    // reference cycles), so values taken out of a module need strong ones
+    pub fn into_exported(self) -> Self {
+        match self {
+            Value::Function(Function::UserDef(param, body, env)) => match env.upgrade() {
+                Ok(env) => Value::Function(Function::UserDef(param, body, StoredEnv::Expr(env))),
+                Err(error) => Value::Error(error),
+            },
+            Value::Function(Function::Checked(decl, type_, function)) => Value::Function(
+                Function::Checked(decl, type_, Box::new(function.into_exported())),
+            ),
+            Value::Delayed {
+                value,
+                self_ptr,
+                outer_env,
+            } => match outer_env.upgrade() {
+                Ok(outer_env) => Value::Delayed {
+                    value,
+                    self_ptr,
+                    outer_env: StoredEnv::Expr(outer_env),
+                },
+                Err(error) => Value::Error(error),
+            },
+            v => v,
+        }
+    }
+
     pub fn is_error(&self) -> bool {
+        matches!(self, Value::Error(_))
+    }
+
+    pub fn apply(self, arg: Value) -> Value {
         match self {
-            Value::Error(_) => true,
-            _ => false,
+            Value::Function(function) => function.eval(arg),
+            Value::Error(_) => self,
+            v => Value::Error(format!("Can't apply argument to type '{}'", v.type_()).into()),
         }
     }
 
+    // Values can only be compared with values of the same type:
+    // * A single value is compared as a tuple of one value
+    // * `Int`s are compared by value, and `Char`s by code point
+    // * `false` is less than `true`
+    // * Tuples (and strings and lists) are compared lexicographically, so
+    //   `(1, 2) < (1, 2, 3)` and `"Zoo" < "apple" < "apples"`
+    // * Boxed values are compared by their contents
+    // Functions and Commands can't be compared, even inside of a tuple
+    pub fn compare(&self, other: &Value) -> Result<Ordering, RuntimeError> {
+        self.ordering(other)
+            .map_err(|incomparable| match incomparable {
+                Incomparable::Error(error) => error,
+                Incomparable::Types(a, b) if is_opaque(&a, &b) => {
+                    format!("Can't compare '{}' and '{}', since {}", a, b, NO_ORDER).into()
+                }
+                Incomparable::Types(a, b) => format!("Can't compare '{}' and '{}'", a, b).into(),
+            })
+    }
+
+    fn ordering(&self, other: &Value) -> Result<Ordering, Incomparable> {
+        match (self, other) {
+            (Value::Error(error), _) | (_, Value::Error(error)) => {
+                Err(Incomparable::Error(error.clone()))
+            }
+            (Value::Int(a), Value::Int(b)) => Ok(a.cmp(b)),
+            (Value::Char(a), Value::Char(b)) => Ok(a.cmp(b)),
+            (Value::Bool(a), Value::Bool(b)) => Ok(a.cmp(b)),
+            (Value::Tuple(Tuple::String(a)), Value::Tuple(Tuple::String(b))) => Ok(a.cmp(b)),
+            (Value::Tuple(a), Value::Tuple(b)) => {
+                let mut b_vals = b.into_iter();
+                for a_val in a {
+                    match b_vals.next() {
+                        Some(b_val) => match a_val.ordering(&b_val)? {
+                            Ordering::Equal => continue,
+                            ordering => return Ok(ordering),
+                        },
+                        None => return Ok(Ordering::Greater),
+                    }
+                }
+                Ok(match b_vals.next() {
+                    Some(_) => Ordering::Less,
+                    None => Ordering::Equal,
+                })
+            }
+            (Value::Boxed(a), Value::Boxed(b)) => a.ordering(b),
+            (a @ Value::Delayed { .. }, b) => a.clone().eval(None).ordering(b),
+            (a, b @ Value::Delayed { .. }) => a.ordering(&b.clone().eval(None)),
+            (a @ Value::Function(_), b)
+            | (a @ Value::Command(_), b)
+            | (a, b @ Value::Function(_))
+            | (a, b @ Value::Command(_)) => Err(Incomparable::Types(a.type_(), b.type_())),
+            // A single value is treated as a tuple of one value
+            (Value::Tuple(_), b) => self.ordering(&vec![b.clone()].into()),
+            (a, Value::Tuple(_)) => Value::from(vec![a.clone()]).ordering(other),
+            (a, b) => Err(Incomparable::Types(a.type_(), b.type_())),
+        }
+    }
+
+    #[allow(clippy::result_unit_err)]
     pub fn run(&self) -> Result<Value, ()> {
         match self {
             Value::Command(command) => Ok(command.run()),
@@ -175,6 +300,31 @@ impl Value {
         Value::Function(Function::Internal(Rc::new(f)))
     }
 
+    // A function that takes `arity` arguments (at least one), and is called
+    // with them as a tuple
+    pub fn native<F>(arity: usize, f: F) -> Self
+    where
+        F: Fn(Value) -> Result<Value, RuntimeError> + 'static,
+    {
+        Value::Function(Function::Native(arity.max(1), vec![], Rc::new(f)))
+    }
+
+    // A function like `native`, but its result is a Command that calls `f`
+    // when it's run, so callbacks with side effects (like logging) happen in
+    // the same order as the program's other Commands
+    pub fn command_fn<F>(arity: usize, f: F) -> Self
+    where
+        F: Fn(Value) -> Result<Value, RuntimeError> + 'static,
+    {
+        let f = Rc::new(f);
+        Value::native(arity, move |args| {
+            let f = Rc::clone(&f);
+            Ok(Value::Command(Command::unit(args).bind(move |args| {
+                f(args).map(Command::unit).map_err(Value::Error)
+            })))
+        })
+    }
+
     pub fn delayed(value: Expr, self_ptr: Weak<RefCell<VarEnv>>, outer_env: WrappedEnv) -> Self {
         Value::Delayed {
             value: Box::new(value),
@@ -203,10 +353,76 @@ impl Value {
         vec.into()
     }
 
+    // A tuple with only one value is that value
+    pub fn from_values(mut vals: Vec<Value>) -> Self {
+        if vals.len() == 1 {
+            vals.remove(0)
+        } else {
+            vals.into()
+        }
+    }
+
+    // A single value is a tuple of one value
+    pub fn into_values(self) -> Vec<Value> {
+        match self {
+            Value::Tuple(tuple) => tuple.into_iter().collect(),
+            val => vec![val],
+        }
+    }
+
     pub fn string(s: &str) -> Self {
         s.into()
     }
 
+    // The canonical rendering of a value (used by the REPL, `show`, and
+    // errors), which parses back to an equal value, except for functions,
+    // commands, and errors (ex. `("a", ['b'], [(1, 2)], <fn>)`)
+    // Strings are quoted, even when they're a list of `Char`s
+    // A string that was unboxed by `,,` is quoted too (so joined text stays
+    // readable), even though it parses back as a boxed string
+    pub fn display_pretty(&self) -> String {
+        match self {
+            Value::Int(i) => i.to_string(),
+            Value::Bool(b) => b.to_string(),
+            Value::Char(c) => format!("'{}'", c),
+            Value::Tuple(tuple) => tuple.to_string(),
+            Value::Boxed(inner) => match (&**inner, self.as_string()) {
+                // `[()]` is an empty list, and `""` is an empty string
+                (Value::Tuple(Tuple::String(_)), Some(string)) => quoted(&string),
+                (_, Some(string)) if !string.is_empty() => quoted(&string),
+                (inner, _) => format!("[{}]", inner.display_pretty()),
+            },
+            Value::Function(_) => "<fn>".into(),
+            Value::Command(_) => "<command>".into(),
+            v @ Value::Delayed { .. } => v.clone().eval(None).display_pretty(),
+            Value::Error(error) => format!("Error: {}", error),
+        }
+    }
+
+    // Strings are rendered as they are (without quotes or brackets);
+    // everything else is rendered the same way the REPL renders it
+    pub fn to_plain_string(&self) -> String {
+        self.as_string().unwrap_or_else(|| format!("{}", self))
+    }
+
+    // Strings are boxed tuples of `Char`s
+    pub fn as_string(&self) -> Option<String> {
+        match self {
+            Value::Boxed(inner) => match &**inner {
+                Value::Tuple(Tuple::String(s)) => Some(s.clone()),
+                Value::Tuple(tuple) => tuple
+                    .into_iter()
+                    .map(|val| match val {
+                        Value::Char(c) => Some(c),
+                        _ => None,
+                    })
+                    .collect(),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
     impl_op!(join, "join":
         Value::Tuple(a_vals), Value::Tuple(b_vals) =>
             Value::Tuple(a_vals.append(b_vals)),
@@ -233,24 +449,107 @@ impl Value {
         a, b => Value::join(a, b)
     );
 
+    // `++` joins two lists (or strings) into one, so unlike `,,` the result
+    // is still boxed
+    pub fn concat(self, other: Self) -> Self {
+        match (self, other) {
+            (error @ Value::Error(_), _) | (_, error @ Value::Error(_)) => error,
+            (Value::Boxed(a), Value::Boxed(b)) => Value::Boxed(Box::new(Value::join(*a, *b))),
+            (a, b) => concat_error(&a, &b),
+        }
+    }
+
+    // `a; b` runs `a` before `b` if they're Commands, and otherwise `a` has to
+    // be `()`, since its value is discarded
+    pub fn sequence(self, other: Self) -> Self {
+        match (self, other) {
+            (error @ Value::Error(_), _) => error,
+            (Value::Command(a), Value::Command(b)) => {
+                Value::Command(a.bind(move |_| Ok(b.clone())))
+            }
+            (Value::Command(_), error @ Value::Error(_)) => error,
+            (Value::Command(_), b) => Value::Error(
+                format!(
+                    "Can't run '{}' after a Command with ';', since it isn't a Command (use 'return' to make it one)",
+                    b
+                )
+                .into(),
+            ),
+            (Value::Tuple(a), b) if a.is_empty() => b,
+            (a, _) => Value::Error(
+                format!(
+                    "Can't discard '{}' with ';', since only '()' and Commands can be (use 'let _ = ... in' to ignore a value)",
+                    a
+                )
+                .into(),
+            ),
+        }
+    }
+
+    // `==` is structural, like `compare`: boxed values are equal if their
+    // contents are, and a single value is equal to a tuple of just that value
+    // Values of different types aren't equal, but functions and commands can't
+    // be tested for equality at all (even inside of a tuple), so `==` is
+    // reflexive and symmetric for every value that it works on
+    fn equality(&self, other: &Value) -> Result<bool, Incomparable> {
+        match (self, other) {
+            (Value::Error(error), _) | (_, Value::Error(error)) => {
+                Err(Incomparable::Error(error.clone()))
+            }
+            (Value::Tuple(Tuple::String(a)), Value::Tuple(Tuple::String(b))) => Ok(a == b),
+            (Value::Tuple(a), Value::Tuple(b)) => {
+                if a.len() != b.len() {
+                    return Ok(false);
+                }
+                for (a_val, b_val) in a.into_iter().zip(b) {
+                    if !a_val.equality(&b_val)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            (Value::Boxed(a), Value::Boxed(b)) => a.equality(b),
+            (a @ Value::Delayed { .. }, b) => a.clone().eval(None).equality(b),
+            (a, b @ Value::Delayed { .. }) => a.equality(&b.clone().eval(None)),
+            (a @ Value::Function(_), b)
+            | (a @ Value::Command(_), b)
+            | (a, b @ Value::Function(_))
+            | (a, b @ Value::Command(_)) => Err(Incomparable::Types(a.type_(), b.type_())),
+            (Value::Tuple(_), b) => self.equality(&vec![b.clone()].into()),
+            (a, Value::Tuple(_)) => Value::from(vec![a.clone()]).equality(other),
+            (a, b) => Ok(a == b),
+        }
+    }
+
+    impl_op!(equal, "equal":
+        a, b => match a.equality(&b) {
+            Ok(equal) => Value::Bool(equal),
+            Err(incomparable) => incomparable_error("equal", &a, &b, incomparable, NO_EQUALITY),
+        }
+    );
+
+    impl_op!(not_equal, "not equal":
+        a, b => match a.equality(&b) {
+            Ok(equal) => Value::Bool(!equal),
+            Err(incomparable) => incomparable_error("not equal", &a, &b, incomparable, NO_EQUALITY),
+        }
+    );
+
+    // The ordering operators work on every value that `compare` does
     impl_op!(less_than, "less than":
-        Value::Int(a), Value::Int(b) => Value::Bool(a < b),
-        Value::Char(a), Value::Char(b) => Value::Bool(a < b)
+        a, b => ordered(&a, &b, "less than", Ordering::is_lt)
     );
 
     impl_op!(greater_than, "greater than":
-        Value::Int(a), Value::Int(b) => Value::Bool(a > b),
-        Value::Char(a), Value::Char(b) => Value::Bool(a > b)
+        a, b => ordered(&a, &b, "greater than", Ordering::is_gt)
     );
 
     impl_op!(less_than_equal, "less than/equal to":
-        Value::Int(a), Value::Int(b) => Value::Bool(a <= b),
-        Value::Char(a), Value::Char(b) => Value::Bool(a <= b)
+        a, b => ordered(&a, &b, "less than/equal to", Ordering::is_le)
     );
 
     impl_op!(greater_than_equal, "greater than/equal to":
-        Value::Int(a), Value::Int(b) => Value::Bool(a >= b),
-        Value::Char(a), Value::Char(b) => Value::Bool(a >= b)
+        a, b => ordered(&a, &b, "greater than/equal to", Ordering::is_ge)
     );
 }
 
@@ -258,13 +557,13 @@ impl EnvVal for Value {
     fn unwrap_matches(&self, pattern: &Match) -> Result<Vec<(String, Self)>, String> {
         match (pattern, self) {
             (Match::Ident(ident), val) => Ok(vec![(ident.into(), val.clone())]),
-            (Match::Tuple(tup_match), Value::Tuple(tup_val)) => unwrap_tuple(&tup_match, &tup_val),
-            (Match::Tuple(tup_match), val) => unwrap_tuple(&tup_match, &vec![val.clone()].into()),
+            (Match::Tuple(tup_match), Value::Tuple(tup_val)) => unwrap_tuple(tup_match, tup_val),
+            (Match::Tuple(tup_match), val) => unwrap_tuple(tup_match, &vec![val.clone()].into()),
             (Match::Unit, Value::Tuple(tup_val)) => {
-                if tup_val.len() == 0 {
+                if tup_val.is_empty() {
                     Ok(vec![])
                 } else {
-                    Err("Tried to match non-empty tuple against '()'".into())
+                    match_error(pattern, self)
                 }
             }
             (Match::Boxed(boxed_match), Value::Boxed(boxed_value)) => {
@@ -274,32 +573,32 @@ impl EnvVal for Value {
                 if a == b {
                     Ok(vec![])
                 } else {
-                    Err(format!("Expected '{}', found '{}'", a, b))
+                    match_error(pattern, self)
                 }
             }
             (Match::Value(MatchVal::Bool(a)), Value::Bool(b)) => {
                 if a == b {
                     Ok(vec![])
                 } else {
-                    Err(format!("Expected '{}', found '{}'", a, b))
+                    match_error(pattern, self)
                 }
             }
             (Match::Value(MatchVal::Char(a)), Value::Char(b)) => {
                 if a == b {
                     Ok(vec![])
                 } else {
-                    Err(format!("Expected '{}', found '{}'", a, b))
+                    match_error(pattern, self)
                 }
             }
             (Match::Value(MatchVal::String(a)), Value::Tuple(b)) => {
                 if a.chars()
                     .map(Value::Char)
-                    .zip(b.into_iter())
+                    .zip(b)
                     .all(|(val_a, val_b): (Value, Value)| val_a == val_b)
                 {
                     Ok(vec![])
                 } else {
-                    Err(format!("Expected string \"{}\", found {}", a, b))
+                    match_error(pattern, self)
                 }
             }
             (Match::Ignore, _) => Ok(vec![]),
@@ -308,10 +607,28 @@ impl EnvVal for Value {
     }
 
     fn is_evaluated(&self) -> bool {
-        match self {
-            Value::Delayed { .. } => false,
-            _ => true,
-        }
+        !matches!(self, Value::Delayed { .. })
+    }
+}
+
+// The name of a declaration (or `let rec`) from its environment
+fn name_of(env: &WrappedEnv) -> String {
+    Env::idents(env).into_iter().next().unwrap_or_default()
+}
+
+// A failed assertion is in the innermost declaration that was being evaluated
+fn with_location(value: Value, env: &WrappedEnv) -> Value {
+    match value {
+        Value::Error(RuntimeError::Assertion {
+            expr,
+            location: None,
+            details,
+        }) => Value::Error(RuntimeError::Assertion {
+            expr,
+            location: Some(name_of(env)),
+            details,
+        }),
+        value => value,
     }
 }
 
@@ -320,89 +637,103 @@ fn unwrap_tuple(tup_match: &[Match], tup_val: &Tuple) -> Result<Vec<(String, Val
     let tup_val_len = tup_val.len();
     match (tup_match_len, tup_val_len) {
         (0, 0) => Ok(vec![]),
-        (0, _) => Err("Tried to match non-empty tuple against '()'".into()),
+        (0, _) => match_error(&Match::Unit, &Value::Tuple(tup_val.clone())),
         (1, 0) => Value::unit().unwrap_matches(&tup_match[0]),
         (1, 1) => tup_val.index(0).unwrap_matches(&tup_match[0]),
         (1, _) => Value::Tuple(tup_val.clone()).unwrap_matches(&tup_match[0]),
         (_, 0) => Value::unit()
             .unwrap_matches(&tup_match[0])
             .and_then(|mut vals| {
-                unwrap_tuple(&tup_match[1..], tup_val).and_then(|mut rest| {
+                unwrap_tuple(&tup_match[1..], tup_val).map(|mut rest| {
                     vals.append(&mut rest);
-                    Ok(vals)
+                    vals
                 })
             }),
         (_, _) => tup_val
             .index(0)
             .unwrap_matches(&tup_match[0])
             .and_then(|mut vals| {
-                unwrap_tuple(&tup_match[1..], &tup_val.from(1)).and_then(|mut rest| {
+                unwrap_tuple(&tup_match[1..], &tup_val.from(1)).map(|mut rest| {
                     vals.append(&mut rest);
-                    Ok(vals)
+                    vals
                 })
             }),
     }
 }
 
-fn match_error(expected: &Match, found: &Value) -> Result<Vec<(String, Value)>, String> {
+// Names the part of the pattern that didn't match, and the part of the value
+// that it was matched against
+fn match_error(pattern: &Match, value: &Value) -> Result<Vec<(String, Value)>, String> {
     Err(format!(
-        "Expected {}, found {}",
-        match expected {
-            Match::Ident(_) => "value".into(),
-            Match::Tuple(_) => "tuple".into(),
-            Match::Unit => "unit".into(),
-            Match::Boxed(_) => "boxed value".into(),
-            Match::Value(v) => format!("'{}'", v),
-            Match::Ignore => "anything".into(),
-        },
-        found.type_()
+        "'{}' doesn't match '{}'",
+        pattern_text(pattern),
+        value
     ))
 }
 
+// Tuple patterns are written in parentheses, like the values they match
+pub(crate) fn pattern_text(pattern: &Match) -> String {
+    match pattern {
+        Match::Tuple(_) => format!("({})", pattern),
+        pattern => pattern.to_string(),
+    }
+}
+
 impl fmt::Display for Value {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                Value::Int(i) => i32::to_string(i),
-                Value::Bool(b) => bool::to_string(b),
-                Value::Char(c) => format!("'{}'", char::to_string(c)),
-                Value::Tuple(vals) => format!("{}", vals),
-                Value::Function(_) => "<fn>".into(),
-                Value::Boxed(val) => format!("[{}]", val),
-                Value::Command(_) => "<command>".into(),
-                v @ Value::Delayed { .. } => format!("{}", v.clone().eval(None)),
-                Value::Error(error) => format!("Error: {}", error),
-            }
-        )
+        write!(f, "{}", self.display_pretty())
+    }
+}
+
+// Strings don't have escapes, so each `"` in a string is written as a `Char`
+// that's joined to the rest of it (ex. `["say " ,, '"' ,, "hi" ,, '"']`)
+fn quoted(string: &str) -> String {
+    if !string.contains('"') {
+        return format!("\"{}\"", string);
+    }
+    let mut parts = vec![];
+    for (i, part) in string.split('"').enumerate() {
+        if i > 0 {
+            parts.push("'\"'".to_string());
+        }
+        if !part.is_empty() {
+            parts.push(format!("\"{}\"", part));
+        }
     }
+    format!("[{}]", parts.join(" ,, "))
 }
 
 impl_op! {
     ops::Add, add, "add":
-        Value::Int(a), Value::Int(b) => Value::Int(a + b)
+        Value::Int(a), Value::Int(b) =>
+            checked(a.checked_add(*b), a.wrapping_add(*b), || format!("{} + {}", a, b))
 }
 
 impl_op! {
     ops::Sub, sub, "subtract":
-        Value::Int(a), Value::Int(b) => Value::Int(a - b)
+        Value::Int(a), Value::Int(b) =>
+            checked(a.checked_sub(*b), a.wrapping_sub(*b), || format!("{} - {}", a, b))
 }
 
 impl_op! {
     ops::Mul, mul, "multiply":
-        Value::Int(a), Value::Int(b) => Value::Int(a * b)
+        Value::Int(a), Value::Int(b) =>
+            checked(a.checked_mul(*b), a.wrapping_mul(*b), || format!("{} * {}", a, b))
 }
 
 impl_op! {
     ops::Div, div, "divide":
-        Value::Int(_), Value::Int(b) if *b == 0 => Value::Error("Divide by 0 error".into()),
-        Value::Int(a), Value::Int(b) => Value::Int(a / b)
+        Value::Int(a), Value::Int(0) => Value::Error(RuntimeError::DivisionByZero(format!("{} / 0", a))),
+        // Only `-2147483648 / -1` overflows
+        Value::Int(a), Value::Int(b) =>
+            checked(a.checked_div(*b), a.wrapping_div(*b), || format!("{} / {}", a, b))
 }
 
 impl_op! {
     ops::Rem, rem, "modulo":
-        Value::Int(a), Value::Int(b) => Value::Int(a % b)
+        Value::Int(a), Value::Int(0) => Value::Error(RuntimeError::DivisionByZero(format!("{} % 0", a))),
+        // `-2147483648 % -1` is 0, but it overflows while it's calculated
+        Value::Int(a), Value::Int(b) => Value::Int(a.wrapping_rem(*b))
 }
 
 impl_op! {
@@ -425,7 +756,7 @@ impl_op! {
 
 impl_op! {
     ops::Neg, neg, "negate":
-        Value::Int(a) => Value::Int(-a)
+        Value::Int(a) => checked(a.checked_neg(), a.wrapping_neg(), || format!("-({})", a))
 }
 
 impl_op! {
@@ -433,21 +764,119 @@ impl_op! {
         Value::Bool(a) => Value::Bool(!a)
 }
 
+// `Int` arithmetic that overflows (`result` is `None`) is an error, unless
+// wrapping arithmetic is enabled
+fn checked(result: Option<i32>, wrapped: i32, operation: impl FnOnce() -> String) -> Value {
+    match result {
+        Some(result) => Value::Int(result),
+        None if capability::wrapping() => Value::Int(wrapped),
+        None => Value::Error(RuntimeError::Overflow(operation())),
+    }
+}
+
+// Why two values can't be compared: an error in one of them, or the first two
+// values (at the same position inside of them) that can't be compared
+enum Incomparable {
+    Error(RuntimeError),
+    Types(Type, Type),
+}
+
+const NO_ORDER: &str = "functions and commands have no order";
+const NO_EQUALITY: &str = "functions and commands can't be tested for equality";
+
+fn is_opaque(a: &Type, b: &Type) -> bool {
+    matches!(
+        (a, b),
+        (Type::Fn_, _) | (_, Type::Fn_) | (Type::Command, _) | (_, Type::Command)
+    )
+}
+
+fn ordered(a: &Value, b: &Value, op: &str, f: fn(Ordering) -> bool) -> Value {
+    match a.ordering(b) {
+        Ok(ordering) => Value::Bool(f(ordering)),
+        Err(incomparable) => incomparable_error(op, a, b, incomparable, NO_ORDER),
+    }
+}
+
+// `opaque` is why functions and commands don't work with `op`
+fn incomparable_error(
+    op: &str,
+    a: &Value,
+    b: &Value,
+    incomparable: Incomparable,
+    opaque: &str,
+) -> Value {
+    let (inner_a, inner_b) = match incomparable {
+        Incomparable::Error(error) => return Value::Error(error),
+        Incomparable::Types(inner_a, inner_b) => (inner_a, inner_b),
+    };
+    let (type_a, type_b) = (a.type_(), b.type_());
+    let reason = if is_opaque(&inner_a, &inner_b) {
+        opaque.to_string()
+    } else if (&inner_a, &inner_b) != (&type_a, &type_b) {
+        format!("'{}' and '{}' can't be compared", inner_a, inner_b)
+    } else {
+        return binary_op_error(op, type_a, type_b);
+    };
+    Value::Error(
+        format!(
+            "Can't perform {} operation on '{}' and '{}', since {}",
+            op.to_uppercase(),
+            type_a,
+            type_b,
+            reason
+        )
+        .into(),
+    )
+}
+
 fn binary_op_error(op: &str, type_a: Type, type_b: Type) -> Value {
-    Value::Error(format!(
-        "Can't perform {} operation on '{}' and '{}'",
-        op.to_uppercase(),
-        type_a,
-        type_b
-    ))
+    Value::Error(
+        format!(
+            "Can't perform {} operation on '{}' and '{}'",
+            op.to_uppercase(),
+            type_a,
+            type_b
+        )
+        .into(),
+    )
+}
+
+// Also says how to make the value that isn't a list into one
+fn concat_error(a: &Value, b: &Value) -> Value {
+    let hint = match (a, b) {
+        (Value::Boxed(_), other) | (other, Value::Boxed(_)) => {
+            if a.as_string().is_some() || b.as_string().is_some() {
+                format!(
+                    "use 'toString' to turn the '{}' into a string",
+                    other.type_()
+                )
+            } else {
+                format!("put the '{}' in a list, like '[x]'", other.type_())
+            }
+        }
+        _ => "use ',,' to join tuples".into(),
+    };
+    Value::Error(
+        format!(
+            "Can't perform CONCATENATE operation on '{}' and '{}', since '++' only joins lists and strings ({})",
+            a.type_(),
+            b.type_(),
+            hint
+        )
+        .into(),
+    )
 }
 
 fn unary_op_error(op: &str, type_: Type) -> Value {
-    Value::Error(format!(
-        "Can't perform {} operation on '{}'",
-        op.to_uppercase(),
-        type_
-    ))
+    Value::Error(
+        format!(
+            "Can't perform {} operation on '{}'",
+            op.to_uppercase(),
+            type_
+        )
+        .into(),
+    )
 }
 
 conversion!( Value[i: i32] => Value::Int(i));
@@ -474,13 +903,14 @@ pub enum StoredEnv {
 }
 
 impl StoredEnv {
-    pub fn unwrap(self) -> WrappedEnv {
+    // A declaration's environment is only dropped along with its values, so
+    // failing to upgrade it is a bug
+    pub fn upgrade(self) -> Result<WrappedEnv, RuntimeError> {
         match self {
-            StoredEnv::Expr(env) => env,
-            StoredEnv::Decl(env) => match env.upgrade() {
-                Some(env) => env,
-                None => unreachable!(),
-            },
+            StoredEnv::Expr(env) => Ok(env),
+            StoredEnv::Decl(env) => env.upgrade().ok_or_else(|| {
+                RuntimeError::Internal("a declaration's environment was dropped".into())
+            }),
         }
     }
 }
@@ -518,6 +948,30 @@ mod tests {
             binary_op_error("add", Type::Int, Type::Bool)
     );
 
+    basic_test!(
+        add_overflow
+        Value::Int(i32::MAX) + Value::Int(1) =>
+            Value::Error(RuntimeError::Overflow("2147483647 + 1".into()));
+        Value::Int(i32::MIN) - Value::Int(1) =>
+            Value::Error(RuntimeError::Overflow("-2147483648 - 1".into()));
+        Value::Int(i32::MIN) / Value::Int(-1) =>
+            Value::Error(RuntimeError::Overflow("-2147483648 / -1".into()));
+        -Value::Int(i32::MIN) => Value::Error(RuntimeError::Overflow("-(-2147483648)".into()));
+        Value::Int(i32::MAX) - Value::Int(i32::MAX) => Value::Int(0)
+    );
+
+    #[test]
+    fn wrapping_arithmetic() {
+        let previous = capability::set_config(capability::EvalConfig {
+            wrapping: true,
+            ..capability::EvalConfig::default()
+        });
+        assert_eq!(Value::Int(i32::MAX) + Value::Int(1), Value::Int(i32::MIN));
+        assert_eq!(Value::Int(i32::MAX) * Value::Int(2), Value::Int(-2));
+        assert_eq!(-Value::Int(i32::MIN), Value::Int(i32::MIN));
+        capability::set_config(previous);
+    }
+
     // SUBTRACTION TESTS
     basic_test!(
         sub_int_int
@@ -560,6 +1014,58 @@ mod tests {
         Value::Int(3) / Value::Int(2) => Value::Int(1)
     );
 
+    basic_test!(
+        div_by_zero
+        Value::Int(3) / Value::Int(0) => Value::Error(RuntimeError::DivisionByZero("3 / 0".into()));
+        Value::Int(-3) % Value::Int(0) => Value::Error(RuntimeError::DivisionByZero("-3 % 0".into()));
+        Value::Int(i32::MIN) % Value::Int(-1) => Value::Int(0)
+    );
+
+    basic_test!(
+        concat
+        Value::string("ab").concat(Value::string("cd")).as_string() => Some("abcd".into());
+        Value::Boxed(Box::new(Value::from(vec![Value::Int(1), Value::Int(2)]))).concat(Value::Boxed(Box::new(Value::Int(3)))).to_string()
+            => "[(1, 2, 3)]";
+        Value::string("").concat(Value::string("")).as_string() => Some("".into());
+        Value::string("a").concat(Value::Int(1)) => Value::Error(
+            "Can't perform CONCATENATE operation on 'Boxed<String>' and 'Int', since '++' only joins lists and strings (use 'toString' to turn the 'Int' into a string)".into()
+        );
+        Value::Bool(true).concat(Value::Boxed(Box::new(Value::from(vec![Value::Int(1), Value::Int(2)])))) => Value::Error(
+            "Can't perform CONCATENATE operation on 'Bool' and 'Boxed<(Int, Int)>', since '++' only joins lists and strings (put the 'Bool' in a list, like '[x]')".into()
+        );
+        Value::Int(1).concat(Value::Int(2)) => Value::Error(
+            "Can't perform CONCATENATE operation on 'Int' and 'Int', since '++' only joins lists and strings (use ',,' to join tuples)".into()
+        )
+    );
+
+    basic_test!(
+        sequence
+        Value::unit().sequence(Value::Int(1)) => Value::Int(1);
+        Value::Error("a".into()).sequence(Value::Int(1)) => Value::Error("a".into());
+        Value::Int(1).sequence(Value::unit()) => Value::Error(
+            "Can't discard '1' with ';', since only '()' and Commands can be (use 'let _ = ... in' to ignore a value)".into()
+        );
+        Value::Command(Command::unit(Value::Int(1))).sequence(Value::Int(2)) => Value::Error(
+            "Can't run '2' after a Command with ';', since it isn't a Command (use 'return' to make it one)".into()
+        );
+        match Value::Command(Command::unit(Value::Int(1))).sequence(Value::Command(Command::unit(Value::Int(2)))) {
+            Value::Command(command) => command.run(),
+            value => value,
+        } => Value::Int(2)
+    );
+
+    basic_test!(
+        display_pretty
+        Value::from((1, "a", 'b')).display_pretty() => "(1, \"a\", 'b')";
+        Value::string("").display_pretty() => "\"\"";
+        Value::string("say \"hi\"").display_pretty() => "[\"say \" ,, '\"' ,, \"hi\" ,, '\"']";
+        Value::Boxed(Box::new(Value::unit())).display_pretty() => "[()]";
+        Value::Boxed(Box::new(Value::from(vec![Value::Char('a'), Value::Char('b')]))).display_pretty() => "\"ab\"";
+        Value::Boxed(Box::new(Value::Char('a'))).display_pretty() => "['a']";
+        Value::from(vec![Value::Int(-1), Value::Boxed(Box::new(Value::string("a")))]).display_pretty() => "(-1, [\"a\"])";
+        Value::internal_fn(|a| a).display_pretty() => "<fn>"
+    );
+
     basic_test!(
         div_deep_error_left
         (Value::Int(1) + Value::Bool(true)) / Value::Int(2) =>
@@ -794,7 +1300,13 @@ mod tests {
             vec![Value::Int(1), Value::Int(2)]
         ).unwrap_matches(
             &Match::Tuple(vec![])
-        ) => Err("Tried to match non-empty tuple against '()'".into());
+        ) => Err("'()' doesn't match '(1, 2)'".into());
+
+        Value::generic_tuple(
+            vec![Value::Int(1), Value::Bool(true)]
+        ).unwrap_matches(
+            &Match::tuple(Match::ident("a"), Match::bool(false))
+        ) => Err("'false' doesn't match 'true'".into());
 
         Value::Int(1).unwrap_matches(
             &Match::tuple(Match::ident("a"), Match::ident("b"))
@@ -804,4 +1316,132 @@ mod tests {
                 ("b".into(), Value::unit())
             ])
     );
+
+    // COMPARE TESTS
+    basic_test!(
+        compare_values
+        Value::Int(1).compare(&Value::Int(2)) => Ok(Ordering::Less);
+        Value::Bool(true).compare(&Value::Bool(false)) => Ok(Ordering::Greater);
+        Value::string("abc").compare(&Value::string("abc")) => Ok(Ordering::Equal);
+        Value::string("ab").compare(&Value::string("abc")) => Ok(Ordering::Less);
+        Value::generic_tuple(vec![Value::Int(1), Value::Int(3)])
+            .compare(&Value::generic_tuple(vec![Value::Int(1), Value::Int(2), Value::Int(0)])) =>
+                Ok(Ordering::Greater);
+        Value::Int(1).compare(&Value::generic_tuple(vec![Value::Int(1), Value::Int(2)])) =>
+            Ok(Ordering::Less)
+    );
+
+    basic_test!(
+        compare_errors
+        Value::Int(1).compare(&Value::Bool(true)) =>
+            Err("Can't compare 'Int' and 'Bool'".into());
+        Value::internal_fn(|a| a).compare(&Value::internal_fn(|a| a)) =>
+            Err("Can't compare 'Fn' and 'Fn', since functions and commands have no order".into());
+        Value::from(vec![Value::Int(1), Value::internal_fn(|a| a)])
+            .compare(&Value::from(vec![Value::Int(1), Value::Bool(true)])) =>
+                Err("Can't compare 'Fn' and 'Bool', since functions and commands have no order".into());
+        // Values before the first difference don't have to be comparable
+        Value::from(vec![Value::Int(1), Value::internal_fn(|a| a)])
+            .compare(&Value::from(vec![Value::Int(2), Value::internal_fn(|a| a)])) =>
+                Ok(Ordering::Less)
+    );
+
+    basic_test!(
+        ordering_ops
+        Value::string("abc").less_than(Value::string("abd")) => Value::Bool(true);
+        Value::Bool(false).greater_than_equal(Value::Bool(true)) => Value::Bool(false);
+        Value::Boxed(Box::new(Value::Int(2))).greater_than(Value::Boxed(Box::new(Value::Int(1)))) => Value::Bool(true);
+        Value::Char('a').less_than(Value::Char('b')) => Value::Bool(true);
+        // Lists are boxed tuples
+        Value::Boxed(Box::new(Value::from(vec![Value::Int(1), Value::Int(2)])))
+            .greater_than(Value::Boxed(Box::new(Value::from(vec![Value::Int(1)])))) => Value::Bool(true);
+        Value::Int(1).less_than_equal(Value::Bool(true)) =>
+            binary_op_error("less than/equal to", Type::Int, Type::Bool);
+        Value::internal_fn(|a| a).less_than(Value::internal_fn(|a| a)) =>
+            Value::Error("Can't perform LESS THAN operation on 'Fn' and 'Fn', since functions and commands have no order".into());
+        Value::from(vec![Value::Int(1), Value::Bool(true)]).greater_than(Value::from(vec![Value::Int(1), Value::Int(2)])) =>
+            Value::Error("Can't perform GREATER THAN operation on '(Int, Bool)' and '(Int, Int)', since 'Bool' and 'Int' can't be compared".into())
+    );
+
+    // Strings are ordered the same way whether they're stored as a `String`
+    // or as a tuple of `Char`s
+    basic_test!(
+        text_ordering
+        Value::string("Zoo").less_than(Value::string("apple")) => Value::Bool(true);
+        Value::string("").less_than(Value::string("a")) => Value::Bool(true);
+        Value::string("é").greater_than(Value::string("z")) => Value::Bool(true);
+        Value::Char('é').greater_than(Value::Char('z')) => Value::Bool(true);
+        Value::string("éa").compare(&Value::Boxed(Box::new(Value::from(vec![Value::Char('é'), Value::Char('b')]))))
+            => Ok(Ordering::Less);
+        Value::string("b").greater_than_equal(Value::Boxed(Box::new(Value::from(vec![Value::Char('a'), Value::Char('z')]))))
+            => Value::Bool(true);
+        Value::string("a").less_than(Value::Char('b')) =>
+            binary_op_error("less than", Type::Boxed(Box::new(Type::Tuple(vec![Type::Char]))), Type::Char)
+    );
+
+    basic_test!(
+        equality_ops
+        Value::string("abc").equal(Value::Boxed(Box::new(Value::from(vec![Value::Char('a'), Value::Char('b'), Value::Char('c')])))) => Value::Bool(true);
+        Value::Boxed(Box::new(Value::Int(1))).equal(Value::Boxed(Box::new(Value::Int(2)))) => Value::Bool(false);
+        Value::Int(1).equal(Value::from(vec![Value::Int(1)])) => Value::Bool(true);
+        // Values of different types just aren't equal
+        Value::Int(1).not_equal(Value::Bool(true)) => Value::Bool(true);
+        Value::internal_fn(|a| a).equal(Value::internal_fn(|a| a)) =>
+            Value::Error("Can't perform EQUAL operation on 'Fn' and 'Fn', since functions and commands can't be tested for equality".into());
+        Value::from(vec![Value::Int(1), Value::internal_fn(|a| a)]).not_equal(Value::from(vec![Value::Int(1), Value::internal_fn(|a| a)])) =>
+            Value::Error("Can't perform NOT EQUAL operation on '(Int, Fn)' and '(Int, Fn)', since functions and commands can't be tested for equality".into());
+        Value::Error("a".into()).equal(Value::Int(1)) => Value::Error("a".into())
+    );
+
+    // A random value that `==` works on
+    fn arbitrary(rng: &mut fastrand::Rng, depth: u32) -> Value {
+        match rng.u8(..if depth == 0 { 3 } else { 6 }) {
+            0 => Value::Int(rng.i32(-2..2)),
+            1 => Value::Bool(rng.bool()),
+            2 => Value::Char(rng.char('a'..'c')),
+            3 => Value::string(
+                &(0..rng.usize(..3))
+                    .map(|_| rng.char('a'..'c'))
+                    .collect::<String>(),
+            ),
+            4 => Value::Boxed(Box::new(arbitrary(rng, depth - 1))),
+            _ => (0..rng.usize(..3))
+                .map(|_| arbitrary(rng, depth - 1))
+                .collect::<Vec<_>>()
+                .into(),
+        }
+    }
+
+    #[test]
+    fn equality_properties() {
+        let mut rng = fastrand::Rng::with_seed(7);
+        for _ in 0..2000 {
+            let a = arbitrary(&mut rng, 3);
+            let b = arbitrary(&mut rng, 3);
+            assert_eq!(
+                a.clone().equal(a.clone()),
+                Value::Bool(true),
+                "{} == {}",
+                a,
+                a
+            );
+            assert_eq!(
+                a.clone().equal(b.clone()),
+                b.clone().equal(a.clone()),
+                "{} == {}",
+                a,
+                b
+            );
+            // Equal values are also equal when ordered
+            if let Ok(ordering) = a.compare(&b) {
+                assert_eq!(
+                    a.clone().equal(b.clone()),
+                    Value::Bool(ordering == Ordering::Equal),
+                    "{} == {}",
+                    a,
+                    b
+                );
+            }
+        }
+    }
 }
@@ -1,8 +1,18 @@
-use crate::value::tuple::Tuple;
+use crate::capability;
+use crate::error::RuntimeError;
+#[cfg(feature = "http")]
+use crate::http;
+use crate::output;
+use crate::random;
+use crate::time::DateTime;
+#[cfg(feature = "uuid")]
+use crate::uuid;
 use crate::value::Value;
+use std::env;
 use std::fmt;
-use std::rc::Rc;
 use std::io::{self, BufRead};
+use std::process::Command as Process;
+use std::rc::Rc;
 
 #[derive(Clone)]
 pub enum Command {
@@ -11,6 +21,20 @@ pub enum Command {
     Println(Rc<Value>),
     ReadLine,
     ReadInt,
+    Exit(i32),
+    Random,
+    RandomInt(i32, i32),
+    Exec(String, Vec<String>),
+    GetEnv(String),
+    Now,
+    #[cfg(feature = "http")]
+    HttpGet(String),
+    #[cfg(feature = "http")]
+    HttpPost(String, String),
+    #[cfg(feature = "uuid")]
+    UuidV4,
+    #[cfg(feature = "uuid")]
+    UuidV7,
 }
 
 impl Command {
@@ -31,7 +55,21 @@ impl Command {
             Command::Compound(first, next) => run_compound(first, next),
             Command::Println(val) => run_println(val),
             Command::ReadLine => run_readline(),
-            Command::ReadInt => run_readint()
+            Command::ReadInt => run_readint(),
+            Command::Exit(code) => run_exit(*code),
+            Command::Random => Value::Int(random::int()),
+            Command::RandomInt(lo, hi) => Value::Int(random::int_between(*lo, *hi)),
+            Command::Exec(program, args) => run_exec(program, args),
+            Command::GetEnv(name) => run_get_env(name),
+            Command::Now => DateTime::now().to_value(),
+            #[cfg(feature = "http")]
+            Command::HttpGet(url) => run_http(http::get(url)),
+            #[cfg(feature = "http")]
+            Command::HttpPost(url, body) => run_http(http::post(url, body)),
+            #[cfg(feature = "uuid")]
+            Command::UuidV4 => uuid::v4().as_str().into(),
+            #[cfg(feature = "uuid")]
+            Command::UuidV7 => uuid::v7().as_str().into(),
         }
     }
     
@@ -46,6 +84,50 @@ impl Command {
     pub fn readint() -> Self {
         Command::ReadInt
     }
+
+    pub fn exit(code: i32) -> Self {
+        Command::Exit(code)
+    }
+
+    pub fn random() -> Self {
+        Command::Random
+    }
+
+    pub fn random_int(lo: i32, hi: i32) -> Self {
+        Command::RandomInt(lo, hi)
+    }
+
+    pub fn exec(program: String, args: Vec<String>) -> Self {
+        Command::Exec(program, args)
+    }
+
+    pub fn get_env(name: String) -> Self {
+        Command::GetEnv(name)
+    }
+
+    pub fn now() -> Self {
+        Command::Now
+    }
+
+    #[cfg(feature = "http")]
+    pub fn http_get(url: String) -> Self {
+        Command::HttpGet(url)
+    }
+
+    #[cfg(feature = "http")]
+    pub fn http_post(url: String, body: String) -> Self {
+        Command::HttpPost(url, body)
+    }
+
+    #[cfg(feature = "uuid")]
+    pub fn uuid_v4() -> Self {
+        Command::UuidV4
+    }
+
+    #[cfg(feature = "uuid")]
+    pub fn uuid_v7() -> Self {
+        Command::UuidV7
+    }
 }
 
 impl PartialEq for Command {
@@ -64,7 +146,21 @@ impl fmt::Debug for Command {
             Command::Compound(first, _) => write!(f, "Command({:?}, Next(<fn>))", first),
             Command::Println(val) => write!(f, "Command(Println({:?}))", val),
             Command::ReadLine => write!(f, "Command(ReadLine)"),
-            Command::ReadInt => write!(f, "Command(ReadInt)")
+            Command::ReadInt => write!(f, "Command(ReadInt)"),
+            Command::Exit(code) => write!(f, "Command(Exit({}))", code),
+            Command::Random => write!(f, "Command(Random)"),
+            Command::RandomInt(lo, hi) => write!(f, "Command(RandomInt({}, {}))", lo, hi),
+            Command::Exec(program, args) => write!(f, "Command(Exec({:?}, {:?}))", program, args),
+            Command::GetEnv(name) => write!(f, "Command(GetEnv({:?}))", name),
+            Command::Now => write!(f, "Command(Now)"),
+            #[cfg(feature = "http")]
+            Command::HttpGet(url) => write!(f, "Command(HttpGet({:?}))", url),
+            #[cfg(feature = "http")]
+            Command::HttpPost(url, body) => write!(f, "Command(HttpPost({:?}, {:?}))", url, body),
+            #[cfg(feature = "uuid")]
+            Command::UuidV4 => write!(f, "Command(UuidV4)"),
+            #[cfg(feature = "uuid")]
+            Command::UuidV7 => write!(f, "Command(UuidV7)"),
         }
     }
 }
@@ -74,45 +170,122 @@ fn run_unit(value: &Value) -> Value {
 }
 
 fn run_compound(first: &Command, next: &Rc<dyn Fn(Value) -> Result<Command, Value>>) -> Value {
-    let result = first.run();
-    match next(result) {
-        Ok(command) => command.run(),
-        Err(value) => value
+    match first.run() {
+        // Nothing is run after `exit`, since the program is over
+        exit @ Value::Error(RuntimeError::Exit(_)) => exit,
+        result => match next(result) {
+            Ok(command) => command.run(),
+            Err(value) => value,
+        },
     }
 }
 
 fn run_println(value: &Value) -> Value {
-    match value {
-        Value::Tuple(Tuple::String(ref s)) => println!("{}", s),
-        ref v => println!("{}", v),
+    match output::write_line(&value.to_plain_string()) {
+        Ok(()) => Value::unit(),
+        Err(error) => Value::Error(error.to_string().into()),
     }
-    Value::unit()
 }
 
 fn run_readline() -> Value {
+    if !capability::stdin_allowed() {
+        return Value::Error("Reading input isn't allowed".into());
+    }
     let mut string = String::new();
     let result = io::stdin().read_line(&mut string);
     match result {
         Ok(_) => string.into(),
-        Err(error) => Value::Error(error.to_string())
+        Err(error) => Value::Error(error.to_string().into()),
     }
 }
 
 fn run_readint() -> Value {
+    if !capability::stdin_allowed() {
+        return Value::Error("Reading input isn't allowed".into());
+    }
     let stdin = io::stdin();
     let mut lock = stdin.lock();
     let mut input = vec![];
     let string = lock.read_until(b' ', &mut input).or_else(|_| lock.read_until(b'\n', &mut input));
     let string = match string {
         Ok(_) => String::from_utf8(input),
-        Err(error) => return Value::Error(error.to_string())
+        Err(error) => return Value::Error(error.to_string().into()),
     };
     let int = match string {
         Ok(string) => string.trim().parse::<i32>(),
-        Err(error) => return Value::Error(error.to_string())
+        Err(error) => return Value::Error(error.to_string().into()),
     };
     match int {
         Ok(i) => Value::Int(i),
-        Err(error) => Value::Error(error.to_string())
+        Err(error) => Value::Error(error.to_string().into()),
+    }
+}
+
+// The program ends with the code, but the process is only exited by whatever
+// is running it (see `RuntimeError::Exit`)
+fn run_exit(code: i32) -> Value {
+    if !capability::exit_allowed() {
+        return Value::Error("Exiting isn't allowed".into());
+    }
+    // Anything printed so far has to make it out before the process ends
+    output::flush().unwrap_or(());
+    Value::Error(RuntimeError::Exit(code))
+}
+
+// The result is `code, stdout, stderr`
+fn run_exec(program: &str, args: &[String]) -> Value {
+    if !capability::exec_allowed() {
+        return Value::Error(
+            format!(
+                "Running '{}' isn't allowed (subprocesses are disabled)",
+                program
+            )
+            .into(),
+        );
+    }
+    match Process::new(program).args(args).output() {
+        Ok(output) => vec![
+            // Processes killed by a signal don't have an exit code
+            Value::Int(output.status.code().unwrap_or(-1)),
+            String::from_utf8_lossy(&output.stdout).into_owned().into(),
+            String::from_utf8_lossy(&output.stderr).into_owned().into(),
+        ]
+        .into(),
+        Err(error) => Value::Error(format!("Couldn't run '{}': {}", program, error).into()),
+    }
+}
+
+// The result is `[value]`, or `()` if the variable isn't set
+fn run_get_env(name: &str) -> Value {
+    if !capability::env_vars_allowed() {
+        return Value::Error(
+            format!(
+                "Reading '{}' isn't allowed (environment variables are disabled)",
+                name
+            )
+            .into(),
+        );
+    }
+    env::var(name).ok().into()
+}
+
+// A response is `status, [[name, value], ...], body`
+#[cfg(feature = "http")]
+fn run_http(response: Result<http::Response, String>) -> Value {
+    match response {
+        Ok(response) => {
+            let headers = response
+                .headers
+                .into_iter()
+                .map(|(name, value)| Value::Boxed(Box::new(vec![name.into(), value.into()].into())))
+                .collect::<Vec<_>>();
+            vec![
+                Value::Int(response.status),
+                Value::Boxed(Box::new(headers.into())),
+                response.body.into(),
+            ]
+            .into()
+        }
+        Err(error) => Value::Error(error.into()),
     }
 }
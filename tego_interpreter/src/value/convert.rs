@@ -0,0 +1,230 @@
+// Conversions between values and Rust types, for code that embeds tego
+//
+// Optional values are `[value]` or `()`, the same as the result of `parseInt`,
+// and a tuple of one value is that value, so `Vec`s of one value convert to
+// and from that value.
+
+use crate::error::RuntimeError;
+use crate::value::Value;
+use std::convert::{TryFrom, TryInto};
+use std::iter::FromIterator;
+
+conversion!( Value[i: i16] => Value::Int(i.into()));
+conversion!( Value[i: u8] => Value::Int(i.into()));
+conversion!( Value[i: u16] => Value::Int(i.into()));
+conversion!( Value[_unit: ()] => Value::unit());
+
+macro_rules! int_conversion {
+    ( $( $type:ty ),+ ) => {
+        $(
+            impl TryFrom<$type> for Value {
+                type Error = RuntimeError;
+
+                fn try_from(i: $type) -> Result<Self, RuntimeError> {
+                    i32::try_from(i)
+                        .map(Value::Int)
+                        .map_err(|_| format!("{} is too big to be an 'Int'", i).into())
+                }
+            }
+        )+
+    };
+}
+
+int_conversion!(i64, u32, u64, isize, usize);
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(option: Option<T>) -> Self {
+        match option {
+            Some(value) => Value::Boxed(Box::new(value.into())),
+            None => Value::unit(),
+        }
+    }
+}
+
+impl<A: Into<Value>, B: Into<Value>> From<(A, B)> for Value {
+    fn from((a, b): (A, B)) -> Self {
+        Value::from_values(vec![a.into(), b.into()])
+    }
+}
+
+impl<A: Into<Value>, B: Into<Value>, C: Into<Value>> From<(A, B, C)> for Value {
+    fn from((a, b, c): (A, B, C)) -> Self {
+        Value::from_values(vec![a.into(), b.into(), c.into()])
+    }
+}
+
+impl<T: Into<Value>> FromIterator<T> for Value {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Value::from_values(iter.into_iter().map(Into::into).collect())
+    }
+}
+
+fn expected(type_: &str, value: &Value) -> RuntimeError {
+    match value {
+        Value::Error(error) => error.clone(),
+        value => format!("expected '{}', found '{}'", type_, value.type_()).into(),
+    }
+}
+
+impl TryFrom<Value> for i32 {
+    type Error = RuntimeError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Int(i) => Ok(i),
+            value => Err(expected("Int", &value)),
+        }
+    }
+}
+
+impl TryFrom<Value> for i64 {
+    type Error = RuntimeError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        i32::try_from(value).map(i64::from)
+    }
+}
+
+impl TryFrom<Value> for bool {
+    type Error = RuntimeError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Bool(b) => Ok(b),
+            value => Err(expected("Bool", &value)),
+        }
+    }
+}
+
+impl TryFrom<Value> for char {
+    type Error = RuntimeError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Char(c) => Ok(c),
+            value => Err(expected("Char", &value)),
+        }
+    }
+}
+
+impl TryFrom<Value> for String {
+    type Error = RuntimeError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value.as_string() {
+            Some(string) => Ok(string),
+            None => match &value {
+                Value::Boxed(inner) => match **inner {
+                    Value::Char(c) => Ok(c.to_string()),
+                    _ => Err(expected("String", &value)),
+                },
+                _ => Err(expected("String", &value)),
+            },
+        }
+    }
+}
+
+impl TryFrom<Value> for () {
+    type Error = RuntimeError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Tuple(tuple) if tuple.is_unit() => Ok(()),
+            value => Err(expected("()", &value)),
+        }
+    }
+}
+
+impl<T: TryFrom<Value, Error = RuntimeError>> TryFrom<Value> for Option<T> {
+    type Error = RuntimeError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Boxed(inner) => (*inner).try_into().map(Some),
+            Value::Tuple(tuple) if tuple.is_unit() => Ok(None),
+            value => Err(expected("[a]' or '()", &value)),
+        }
+    }
+}
+
+impl<T: TryFrom<Value, Error = RuntimeError>> TryFrom<Value> for Vec<T> {
+    type Error = RuntimeError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        values(value).into_iter().map(T::try_from).collect()
+    }
+}
+
+impl<A, B> TryFrom<Value> for (A, B)
+where
+    A: TryFrom<Value, Error = RuntimeError>,
+    B: TryFrom<Value, Error = RuntimeError>,
+{
+    type Error = RuntimeError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match <[Value; 2]>::try_from(values(value)) {
+            Ok([a, b]) => Ok((a.try_into()?, b.try_into()?)),
+            Err(values) => Err(length(2, values.len())),
+        }
+    }
+}
+
+impl<A, B, C> TryFrom<Value> for (A, B, C)
+where
+    A: TryFrom<Value, Error = RuntimeError>,
+    B: TryFrom<Value, Error = RuntimeError>,
+    C: TryFrom<Value, Error = RuntimeError>,
+{
+    type Error = RuntimeError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match <[Value; 3]>::try_from(values(value)) {
+            Ok([a, b, c]) => Ok((a.try_into()?, b.try_into()?, c.try_into()?)),
+            Err(values) => Err(length(3, values.len())),
+        }
+    }
+}
+
+// The values in a tuple, where anything that isn't a tuple is a tuple of one value
+fn values(value: Value) -> Vec<Value> {
+    match value {
+        Value::Tuple(tuple) => tuple.into_iter().collect(),
+        value => vec![value],
+    }
+}
+
+fn length(expected: usize, found: usize) -> RuntimeError {
+    format!("expected a tuple of {} values, found {}", expected, found).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    basic_test! {
+        into_value_test
+        Value::from(3u8) => Value::Int(3);
+        Value::try_from(3i64) => Ok(Value::Int(3));
+        Value::try_from(i64::MAX) => Err(RuntimeError::Value("9223372036854775807 is too big to be an 'Int'".into()));
+        Value::from(Some(1)) => Value::Boxed(Box::new(Value::Int(1)));
+        Value::from(None::<i32>) => Value::unit();
+        Value::from((1, "a")).to_string() => "(1, \"a\")";
+        vec![1, 2, 3].into_iter().collect::<Value>().to_string() => "(1, 2, 3)"
+    }
+
+    basic_test! {
+        from_value_test
+        i32::try_from(Value::Int(1)) => Ok(1);
+        i32::try_from(Value::Bool(true)) => Err(RuntimeError::Value("expected 'Int', found 'Bool'".into()));
+        i32::try_from(Value::Error("oops".into())) => Err(RuntimeError::Value("oops".into()));
+        String::try_from(Value::from("abc")) => Ok("abc".to_string());
+        Option::<i32>::try_from(Value::from(Some(2))) => Ok(Some(2));
+        Option::<i32>::try_from(Value::unit()) => Ok(None);
+        Vec::<i32>::try_from(Value::from(vec![Value::Int(1), Value::Int(2)])) => Ok(vec![1, 2]);
+        Vec::<i32>::try_from(Value::Int(1)) => Ok(vec![1]);
+        Vec::<i32>::try_from(Value::unit()) => Ok(vec![]);
+        <(i32, String)>::try_from(Value::from((1, "a"))) => Ok((1, "a".to_string()));
+        <(i32, bool)>::try_from(Value::Int(1)) => Err(RuntimeError::Value("expected a tuple of 2 values, found 1".into()))
+    }
+}
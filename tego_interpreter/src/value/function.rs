@@ -1,34 +1,185 @@
-use crate::interpreter::eval_expr;
-use crate::value::{StoredEnv, Value, VarEnv};
+use crate::boundary;
+use crate::error::RuntimeError;
+use crate::interpreter::{bind, eval_expr};
+use crate::value::{StoredEnv, Value};
 use std::fmt;
 use std::rc::Rc;
-use tego_parser::ast::{Expr, Match};
+use tego_parser::ast::{Expr, Match, TypeExpr};
 
 #[derive(Clone)]
 pub enum Function {
     UserDef(Match, Box<Expr>, StoredEnv),
     Internal(Rc<dyn Fn(Value) -> Value>),
+    // A function that's checked against a function type, and the declaration
+    // that the type is from (see `boundary`)
+    Checked(String, TypeExpr, Box<Value>),
+    // A function from the host (see `Engine::register_fn`), and the arguments
+    // that have been applied to it so far
+    Native(usize, Vec<Value>, Rc<NativeFn>),
 }
 
+pub type NativeFn = dyn Fn(Value) -> Result<Value, RuntimeError>;
+
 impl Function {
     pub fn eval(self, arg: Value) -> Value {
         match self {
-            Function::UserDef(param, body, fn_env) => {
-                match VarEnv::associate(param, arg, &fn_env.unwrap()) {
+            Function::UserDef(param, body, fn_env) => match fn_env.upgrade() {
+                Ok(fn_env) => match bind("The parameter", &param, arg, &fn_env) {
                     Ok(fn_env) => eval_expr(*body, &fn_env),
-                    Err(error) => Value::Error(error),
+                    Err(error) => error,
+                },
+                Err(error) => Value::Error(error),
+            },
+            Function::Internal(f) => f(arg),
+            Function::Checked(decl, type_, function) => match type_ {
+                TypeExpr::Fn_(param, result) => match boundary::check(&decl, &param, arg) {
+                    Value::Error(error) => Value::Error(error),
+                    arg => boundary::check(&decl, &result, function.apply(arg)),
+                },
+                _ => function.apply(arg),
+            },
+            // Once every argument has been applied, they're passed as a tuple
+            // (so a single argument is passed as it is)
+            Function::Native(_, _, _) if arg.is_error() => arg,
+            Function::Native(arity, mut args, f) => {
+                args.push(arg);
+                if args.len() < arity {
+                    Value::Function(Function::Native(arity, args, f))
+                } else {
+                    f(Value::from_values(args)).unwrap_or_else(Value::Error)
                 }
             }
-            Function::Internal(f) => f(arg),
         }
     }
 }
 
+impl Function {
+    // Applies arguments given by the names of the function's parameters (ex.
+    // `f (y = 2, x = 1)`), which can be for several parameters in a row
+    // A parameter that's a tuple of names is given each of them, and the parts
+    // with defaults are `()` if they aren't given (so the default is used)
+    pub fn apply_named(self, mut args: Vec<(String, Value)>) -> Value {
+        if let Some((_, error)) = args.iter().find(|(_, value)| value.is_error()) {
+            return error.clone();
+        }
+        let params = self.param_names();
+        let unknown = args.iter().find(|(arg, _)| {
+            !params
+                .iter()
+                .any(|names| names.iter().any(|(name, _)| name == arg))
+        });
+        if let Some((arg, _)) = unknown {
+            return Value::Error(
+                format!(
+                    "Can't give '{}' by name, since there isn't a parameter with that name",
+                    arg
+                )
+                .into(),
+            );
+        }
+        let mut function = Value::Function(self);
+        for names in params {
+            let next = match args.first() {
+                Some((next, _)) => next.clone(),
+                None => break,
+            };
+            let mut values = vec![];
+            for (name, has_default) in names {
+                match args.iter().position(|(arg, _)| *arg == name) {
+                    Some(i) => values.push(args.remove(i).1),
+                    None if has_default => values.push(Value::unit()),
+                    None => {
+                        return Value::Error(
+                            format!(
+                                "Missing the argument '{}', which has to be given by name before '{}' can be",
+                                name, next
+                            )
+                            .into(),
+                        )
+                    }
+                }
+            }
+            function = function.apply(Value::from_values(values));
+        }
+        function
+    }
+
+    // The names of the parts of each of the function's parameters (including
+    // the parameters of the function that it results in, and so on), and
+    // whether they have defaults, as long as they're all names
+    fn param_names(&self) -> Vec<Vec<(String, bool)>> {
+        let (mut param, mut body) = match self {
+            Function::UserDef(param, body, _) => (param, &**body),
+            Function::Checked(_, _, function) => match &**function {
+                Value::Function(function) => return function.param_names(),
+                _ => return vec![],
+            },
+            Function::Internal(_) | Function::Native(_, _, _) => return vec![],
+        };
+        let mut params = vec![];
+        loop {
+            let (defaults, inner) = default_names(body);
+            let name = |part: &Match| match part {
+                Match::Ident(ident) => Some(
+                    defaults
+                        .iter()
+                        .find(|(generated, _)| *generated == ident)
+                        .map_or((ident.clone(), false), |(_, name)| ((*name).clone(), true)),
+                ),
+                _ => None,
+            };
+            let names = match param {
+                Match::Tuple(parts) => parts.iter().map(name).collect(),
+                param => name(param).map(|name| vec![name]),
+            };
+            match names {
+                Some(names) => params.push(names),
+                None => break,
+            }
+            match inner {
+                Expr::Fn_(inner_param, inner_body) => {
+                    param = inner_param;
+                    body = inner_body;
+                }
+                _ => break,
+            }
+        }
+        params
+    }
+}
+
+// The parts of a parameter with defaults are bound to `_0`, `_1`, ..., and
+// then to their names by the `let`s at the start of the body (see
+// `ExprOutput::fn_defaults`), which are followed by the rest of the body
+fn default_names(mut body: &Expr) -> (Vec<(&String, &String)>, &Expr) {
+    let mut names = vec![];
+    while let Expr::Let(Match::Ident(name), value, inner) = body {
+        match &**value {
+            Expr::Match(value, _) => match &**value {
+                Expr::Variable(generated)
+                    if generated.starts_with('_')
+                        && generated[1..].chars().all(|c| c.is_ascii_digit()) =>
+                {
+                    names.push((generated, name))
+                }
+                _ => break,
+            },
+            _ => break,
+        }
+        body = inner;
+    }
+    (names, body)
+}
+
 impl fmt::Debug for Function {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Function::UserDef(match_, body, env) => write!(f, "UserDef{:?}", (match_, body, env)),
             Function::Internal(_) => write!(f, "Internal"),
+            Function::Checked(decl, type_, function) => {
+                write!(f, "Checked{:?}", (decl, type_, function))
+            }
+            Function::Native(arity, args, _) => write!(f, "Native{:?}", (arity, args)),
         }
     }
 }
@@ -0,0 +1,148 @@
+// Serde support for values (only with the `serde` feature enabled)
+//
+// Tuples are sequences, and boxed values are sequences of one value, so the
+// same value is written the same way in tego and in JSON (ex. `[1]`, `(1, 2)`
+// as `[1, 2]`). Strings are strings, and `()` is an empty sequence (or `null`).
+// Maps become tuples of boxed pairs, like the result of `zip`
+// (ex. `{"a": 1}` is `[("a", 1)]`).
+// Functions, Commands, and errors can't be serialized.
+
+use crate::value::Value;
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{self, Serialize, SerializeSeq, Serializer};
+use std::convert::TryFrom;
+use std::fmt;
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if let Some(string) = self.as_string() {
+            return serializer.serialize_str(&string);
+        }
+        match self {
+            Value::Int(i) => serializer.serialize_i32(*i),
+            Value::Bool(b) => serializer.serialize_bool(*b),
+            Value::Char(c) => serializer.serialize_char(*c),
+            Value::Tuple(tuple) => {
+                let mut seq = serializer.serialize_seq(Some(tuple.len()))?;
+                for value in tuple {
+                    seq.serialize_element(&value)?;
+                }
+                seq.end()
+            }
+            Value::Boxed(inner) => {
+                let mut seq = serializer.serialize_seq(Some(1))?;
+                seq.serialize_element(&**inner)?;
+                seq.end()
+            }
+            Value::Error(error) => Err(ser::Error::custom(error)),
+            value => Err(ser::Error::custom(format!(
+                "a value of type '{}' can't be serialized",
+                value.type_()
+            ))),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "an integer, a bool, a string, a sequence, or a map")
+    }
+
+    fn visit_bool<E: de::Error>(self, b: bool) -> Result<Value, E> {
+        Ok(Value::Bool(b))
+    }
+
+    fn visit_i64<E: de::Error>(self, i: i64) -> Result<Value, E> {
+        Value::try_from(i).map_err(|error| E::custom(error))
+    }
+
+    fn visit_u64<E: de::Error>(self, i: u64) -> Result<Value, E> {
+        Value::try_from(i).map_err(|error| E::custom(error))
+    }
+
+    fn visit_char<E: de::Error>(self, c: char) -> Result<Value, E> {
+        Ok(Value::Char(c))
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<Value, E> {
+        Ok(s.into())
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::unit())
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::unit())
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        Value::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut values = vec![];
+        while let Some(value) = seq.next_element()? {
+            values.push(value);
+        }
+        match values.len() {
+            1 => Ok(Value::Boxed(Box::new(values.remove(0)))),
+            _ => Ok(Value::generic_tuple(values)),
+        }
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut pairs = vec![];
+        while let Some((key, value)) = map.next_entry::<Value, Value>()? {
+            pairs.push(Value::Boxed(Box::new(Value::generic_tuple(vec![
+                key, value,
+            ]))));
+        }
+        Ok(Value::from_values(pairs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_json(value: &Value) -> Result<String, String> {
+        serde_json::to_string(value).map_err(|error| error.to_string())
+    }
+
+    fn from_json(json: &str) -> Value {
+        serde_json::from_str(json).unwrap()
+    }
+
+    basic_test! {
+        serialize_test
+        to_json(&Value::Int(1)) => Ok("1".into());
+        to_json(&"abc".into()) => Ok("\"abc\"".into());
+        to_json(&Value::from(vec![Value::Int(1), Value::Bool(true)])) => Ok("[1,true]".into());
+        to_json(&Value::Boxed(Box::new(Value::Int(1)))) => Ok("[1]".into());
+        to_json(&Value::unit()) => Ok("[]".into());
+        to_json(&Value::internal_fn(|v| v)) => Err("a value of type 'Fn' can't be serialized".into())
+    }
+
+    basic_test! {
+        deserialize_test
+        from_json("1") => Value::Int(1);
+        from_json("\"abc\"") => Value::from("abc");
+        from_json("[1, true]") => Value::from(vec![Value::Int(1), Value::Bool(true)]);
+        from_json("[1]") => Value::Boxed(Box::new(Value::Int(1)));
+        from_json("null") => Value::unit();
+        from_json("{\"a\": 1}").to_string() => "[(\"a\", 1)]";
+        serde_json::from_str::<Value>("1.5").is_err() => true;
+        serde_json::from_str::<Value>("3000000000").is_err() => true
+    }
+}
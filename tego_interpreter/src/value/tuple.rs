@@ -1,3 +1,4 @@
+use crate::error::RuntimeError;
 use crate::value::Value;
 use owned_chars::OwnedCharsExt;
 use std::fmt;
@@ -13,15 +14,23 @@ impl Tuple {
     pub fn len(&self) -> usize {
         match self {
             Self::Generic(vec) => vec.len(),
-            Self::String(string) => string.len(),
+            // In `Char`s, not bytes
+            Self::String(string) => string.chars().count(),
         }
     }
 
+    // Callers check the length first, so an index that's too big is a bug
     pub fn index(&self, index: usize) -> Value {
         match self {
-            Self::Generic(vec) => vec[index].clone(),
-            Self::String(string) => Value::Char(string.chars().nth(index).unwrap()),
+            Self::Generic(vec) => vec.get(index).cloned(),
+            Self::String(string) => string.chars().nth(index).map(Value::Char),
         }
+        .unwrap_or_else(|| {
+            Value::Error(RuntimeError::Internal(format!(
+                "index {} is past the end of a tuple",
+                index
+            )))
+        })
     }
 
     pub fn from(&self, from: usize) -> Tuple {
@@ -37,18 +46,14 @@ impl Tuple {
                 str1.push_str(&str2);
                 str1.into()
             }
-            (Self::String(str1), Self::Generic(tup2)) => str1
-                .chars()
-                .map(Value::Char)
-                .chain(tup2.into_iter())
-                .collect(),
+            (Self::String(str1), Self::Generic(tup2)) => {
+                str1.chars().map(Value::Char).chain(tup2).collect()
+            }
             (Self::Generic(tup1), Self::String(str2)) => tup1
                 .into_iter()
                 .chain(str2.chars().map(Value::Char))
                 .collect(),
-            (Self::Generic(tup1), Self::Generic(tup2)) => {
-                tup1.into_iter().chain(tup2.into_iter()).collect()
-            }
+            (Self::Generic(tup1), Self::Generic(tup2)) => tup1.into_iter().chain(tup2).collect(),
         }
     }
 
@@ -80,18 +85,19 @@ impl Tuple {
         }
     }
 
+    // The empty tuple is unit
     pub fn is_unit(&self) -> bool {
+        self.is_empty()
+    }
+
+    pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
 
-    pub fn get(&self, index: usize) -> Value {
+    pub fn get(&self, index: usize) -> Option<Value> {
         match self {
-            Self::Generic(vec) => vec.get(index).unwrap_or(&Value::unit()).clone(),
-            Self::String(string) => string
-                .chars()
-                .map(Value::Char)
-                .nth(index)
-                .unwrap_or_else(Value::unit),
+            Self::Generic(vec) => vec.get(index).cloned(),
+            Self::String(string) => string.chars().nth(index).map(Value::Char),
         }
     }
 }
@@ -104,7 +110,7 @@ impl PartialEq for Tuple {
             match (self, other) {
                 (Self::Generic(a), Self::Generic(b)) => a == b,
                 (Self::String(a), Self::String(b)) => a == b,
-                (a, b) => a.into_iter().eq(b.into_iter()),
+                (a, b) => a.into_iter().eq(b),
             }
         }
     }
@@ -122,7 +128,9 @@ impl IntoIterator for Tuple {
     fn into_iter(self) -> Self::IntoIter {
         match self {
             Tuple::Generic(tuple) => TupleIter::new(Box::new(tuple.into_iter())),
-            Tuple::String(string) => TupleIter::new(Box::new(string.into_chars().map(Value::Char))),
+            Tuple::String(string) => {
+                TupleIter::new(Box::new(OwnedCharsExt::into_chars(string).map(Value::Char)))
+            }
         }
     }
 }
@@ -146,9 +154,9 @@ impl IntoIterator for &Tuple {
     fn into_iter(self) -> Self::IntoIter {
         match self {
             Tuple::Generic(tuple) => TupleIter::new(Box::new(tuple.clone().into_iter())),
-            Tuple::String(string) => {
-                TupleIter::new(Box::new(string.clone().into_chars().map(Value::Char)))
-            }
+            Tuple::String(string) => TupleIter::new(Box::new(
+                OwnedCharsExt::into_chars(string.clone()).map(Value::Char),
+            )),
         }
     }
 }
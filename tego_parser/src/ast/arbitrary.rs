@@ -0,0 +1,241 @@
+// Random ASTs for fuzzing and property tests (with the `arbitrary` feature)
+//
+// Only ASTs that the parser can make are generated (ex. tuple patterns aren't
+// nested, and `Int`s aren't negative, since `-1` is parsed as a negation), so
+// a generated AST that's written as source and parsed again is the same AST
+//
+// The random expressions only use the names in `NAMES`, so they don't use
+// builtins (ex. `exit`) that would stop whatever is running them
+
+use crate::ast::{BinaryOp, Decl, Expr, ExprValue, Match, MatchVal, Prog, UnaryOp};
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+// How deeply expressions and patterns are nested
+const DEPTH: u32 = 4;
+
+const NAMES: &[&str] = &["a", "b", "f", "xs"];
+
+const DECL_NAMES: &[&str] = &["main", "f", "g", "h"];
+
+const TEXT: &[char] = &['a', 'b', 'z', '0', '1', ' '];
+
+const BINARY_OPS: &[BinaryOp] = &[
+    BinaryOp::Plus,
+    BinaryOp::Minus,
+    BinaryOp::Multiply,
+    BinaryOp::Divide,
+    BinaryOp::Modulo,
+    BinaryOp::And,
+    BinaryOp::Or,
+    BinaryOp::Xor,
+    BinaryOp::Join,
+    BinaryOp::FlatJoin,
+    BinaryOp::Concat,
+    BinaryOp::Sequence,
+    BinaryOp::Equal,
+    BinaryOp::NotEqual,
+    BinaryOp::LessThan,
+    BinaryOp::GreaterThan,
+    BinaryOp::LessThanEqual,
+    BinaryOp::GreaterThanEqual,
+];
+
+impl<'a> Arbitrary<'a> for Expr {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        expr(u, DEPTH)
+    }
+}
+
+impl<'a> Arbitrary<'a> for Match {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        pattern(u, DEPTH)
+    }
+}
+
+// A program's declarations have different names, since the equations of a
+// function are written as declarations with the same name
+impl<'a> Arbitrary<'a> for Prog {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut names = DECL_NAMES.to_vec();
+        let mut decls = vec![];
+        for _ in 0..u.int_in_range(1..=4)? {
+            let decl = match u.int_in_range(0..=5)? {
+                0 => Decl::Import(
+                    u.choose(&["a.tgo", "lib/b.tgo"])?.to_string(),
+                    optional(u, |u| Ok(u.choose(&["A", "B"])?.to_string()))?,
+                    optional(u, |u| some_of(u, &["f", "g"]))?,
+                    u.arbitrary()?,
+                ),
+                1 => Decl::Export(some_of(u, DECL_NAMES)?),
+                _ if names.is_empty() => Decl::Export(some_of(u, DECL_NAMES)?),
+                _ => {
+                    let name = names.remove(u.choose_index(names.len())?);
+                    Decl::Expression(name.into(), expr(u, DEPTH)?)
+                }
+            };
+            decls.push(decl);
+        }
+        let main = decls.iter().find_map(|decl| match decl {
+            Decl::Expression(name, body) if name == "main" => Some(body.clone()),
+            _ => None,
+        });
+        Ok(match main {
+            Some(main) => Prog::Binary(main, decls),
+            None => Prog::Library(decls),
+        })
+    }
+}
+
+fn expr(u: &mut Unstructured<'_>, depth: u32) -> Result<Expr> {
+    if depth == 0 || u.is_empty() {
+        return leaf(u);
+    }
+    let depth = depth - 1;
+    let sub = |u: &mut Unstructured<'_>| expr(u, depth).map(Box::new);
+    Ok(match u.int_in_range(0..=13)? {
+        0 if u.arbitrary()? => Expr::Unary(UnaryOp::Negate, sub(u)?),
+        0 => Expr::Unary(UnaryOp::Not, sub(u)?),
+        1 | 2 => Expr::Binary(sub(u)?, u.choose(BINARY_OPS)?.clone(), sub(u)?),
+        3 => Expr::If(sub(u)?, sub(u)?, sub(u)?),
+        4 => Expr::Let(pattern(u, depth)?, sub(u)?, sub(u)?),
+        // The parser only delays names
+        5 => Expr::Delayed(Match::Ident(name(u)?), sub(u)?, sub(u)?),
+        6 => Expr::Fn_(pattern(u, depth)?, sub(u)?),
+        7 | 8 => Expr::FnApp(sub(u)?, sub(u)?),
+        // Arguments given by name are only in an application
+        9 => Expr::FnApp(sub(u)?, Box::new(named_args(u, depth)?)),
+        10 => {
+            let mut arms = vec![];
+            for _ in 0..u.int_in_range(1..=3)? {
+                arms.push((pattern(u, depth)?, expr(u, depth)?));
+            }
+            Expr::Match(sub(u)?, arms)
+        }
+        11 => Expr::Boxed(sub(u)?),
+        12 => Expr::Do(sub(u)?, pattern(u, depth)?, sub(u)?),
+        _ => leaf(u)?,
+    })
+}
+
+fn leaf(u: &mut Unstructured<'_>) -> Result<Expr> {
+    Ok(match u.int_in_range(0..=5)? {
+        0 => Expr::Literal(ExprValue::Int(u.int_in_range(0..=i32::MAX)?)),
+        1 => Expr::Literal(ExprValue::Bool(u.arbitrary()?)),
+        2 => Expr::Literal(ExprValue::Unit),
+        3 => Expr::Literal(ExprValue::String(text(u)?)),
+        4 => Expr::Literal(ExprValue::Char(*u.choose(TEXT)?)),
+        _ => Expr::Variable(name(u)?),
+    })
+}
+
+// `(a = 1, b = 2)`
+fn named_args(u: &mut Unstructured<'_>, depth: u32) -> Result<Expr> {
+    let mut args = vec![];
+    for name in some_of(u, NAMES)? {
+        let name = Box::new(Expr::Literal(ExprValue::String(name)));
+        args.push(Expr::Binary(name, BinaryOp::Named, expr(u, depth)?.into()));
+    }
+    Ok(args
+        .into_iter()
+        .reduce(|a, b| Expr::Binary(a.into(), BinaryOp::Join, b.into()))
+        .unwrap_or(Expr::Literal(ExprValue::Unit)))
+}
+
+fn pattern(u: &mut Unstructured<'_>, depth: u32) -> Result<Match> {
+    let max = if depth == 0 || u.is_empty() { 5 } else { 7 };
+    Ok(match u.int_in_range(0..=max)? {
+        0 | 1 => Match::Ident(name(u)?),
+        2 => Match::Ignore,
+        3 => Match::Unit,
+        4 => Match::Value(match u.int_in_range(0..=2)? {
+            0 => MatchVal::Int(u.int_in_range(0..=i32::MAX)?),
+            1 => MatchVal::Bool(u.arbitrary()?),
+            _ => MatchVal::Char(*u.choose(TEXT)?),
+        }),
+        // Strings are matched as boxed values
+        5 => Match::Boxed(Box::new(Match::Value(MatchVal::String(text(u)?)))),
+        6 => Match::Boxed(Box::new(pattern(u, depth - 1)?)),
+        // Tuples in tuples are joined into one
+        _ => {
+            let mut parts = vec![];
+            for _ in 0..u.int_in_range(2..=3)? {
+                parts.push(match pattern(u, depth - 1)? {
+                    Match::Tuple(_) => Match::Ignore,
+                    part => part,
+                });
+            }
+            Match::Tuple(parts)
+        }
+    })
+}
+
+fn name(u: &mut Unstructured<'_>) -> Result<String> {
+    Ok(u.choose(NAMES)?.to_string())
+}
+
+fn text(u: &mut Unstructured<'_>) -> Result<String> {
+    let mut text = String::new();
+    for _ in 0..u.int_in_range(0..=3)? {
+        text.push(*u.choose(TEXT)?);
+    }
+    Ok(text)
+}
+
+fn optional<T>(
+    u: &mut Unstructured<'_>,
+    value: impl FnOnce(&mut Unstructured<'_>) -> Result<T>,
+) -> Result<Option<T>> {
+    Ok(if u.arbitrary()? {
+        Some(value(u)?)
+    } else {
+        None
+    })
+}
+
+// At least one of `names`, in order
+fn some_of(u: &mut Unstructured<'_>, names: &[&str]) -> Result<Vec<String>> {
+    let first = u.choose_index(names.len())?;
+    let mut some = vec![names[first].to_string()];
+    for name in &names[first + 1..] {
+        if u.arbitrary()? {
+            some.push(name.to_string());
+        }
+    }
+    Ok(some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Span;
+
+    // Random bytes for `Unstructured`, which are the same every time
+    fn bytes(rng: &mut fastrand::Rng) -> Vec<u8> {
+        (0..rng.usize(..1024)).map(|_| rng.u8(..)).collect()
+    }
+
+    #[test]
+    fn expr_round_trip() {
+        let mut rng = fastrand::Rng::with_seed(7);
+        for _ in 0..2000 {
+            let bytes = bytes(&mut rng);
+            let expr = Expr::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+            let source = expr.to_string();
+            let parsed =
+                crate::complete(crate::expr::<Expr>)(Span::new(&source)).map(|(_, expr)| expr);
+            assert_eq!(parsed, Ok(expr), "{}", source);
+        }
+    }
+
+    #[test]
+    fn prog_round_trip() {
+        let mut rng = fastrand::Rng::with_seed(7);
+        for _ in 0..500 {
+            let bytes = bytes(&mut rng);
+            let prog = Prog::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+            let source = prog.to_string();
+            let parsed = crate::prog::<Prog>(Span::new(&source)).map(|(_, prog)| prog);
+            assert_eq!(parsed, Ok(prog), "{}", source);
+        }
+    }
+}
@@ -0,0 +1,521 @@
+// A compact binary form of parsed declarations, used to cache modules on disk
+//
+// Decoding results in `None` for anything that wasn't made by `encode` (or was
+// made by a different `VERSION`), so a bad cache file is just re-parsed
+
+use crate::ast::{BinaryOp, Decl, Expr, ExprValue, Match, MatchVal, TypeExpr, UnaryOp};
+
+const MAGIC: &[u8] = b"TGOC";
+// Bump this whenever the AST (or its encoding) changes
+pub const VERSION: u8 = 8;
+
+pub fn encode(decls: &[Decl]) -> Vec<u8> {
+    let mut encoder = Encoder(MAGIC.to_vec());
+    encoder.0.push(VERSION);
+    encoder.len(decls.len());
+    for decl in decls {
+        encoder.decl(decl);
+    }
+    encoder.0
+}
+
+pub fn decode(bytes: &[u8]) -> Option<Vec<Decl>> {
+    if !bytes.starts_with(MAGIC) || bytes.get(MAGIC.len()) != Some(&VERSION) {
+        return None;
+    }
+    let mut decoder = Decoder {
+        bytes,
+        pos: MAGIC.len() + 1,
+    };
+    let decls = decoder.list(Decoder::decl)?;
+    // Trailing bytes mean the file is corrupt
+    if decoder.pos == bytes.len() {
+        Some(decls)
+    } else {
+        None
+    }
+}
+
+struct Encoder(Vec<u8>);
+
+impl Encoder {
+    fn tag(&mut self, tag: u8) {
+        self.0.push(tag);
+    }
+
+    // LEB128, so that small lengths only take up a byte
+    fn len(&mut self, mut len: usize) {
+        loop {
+            let byte = (len & 0x7f) as u8;
+            len >>= 7;
+            if len == 0 {
+                self.0.push(byte);
+                return;
+            }
+            self.0.push(byte | 0x80);
+        }
+    }
+
+    fn string(&mut self, s: &str) {
+        self.len(s.len());
+        self.0.extend_from_slice(s.as_bytes());
+    }
+
+    fn strings(&mut self, strings: &[String]) {
+        self.len(strings.len());
+        for s in strings {
+            self.string(s);
+        }
+    }
+
+    fn int(&mut self, i: i32) {
+        self.0.extend_from_slice(&i.to_le_bytes());
+    }
+
+    fn char(&mut self, c: char) {
+        self.0.extend_from_slice(&(c as u32).to_le_bytes());
+    }
+
+    fn decl(&mut self, decl: &Decl) {
+        match decl {
+            Decl::Expression(ident, body) => {
+                self.tag(0);
+                self.string(ident);
+                self.expr(body);
+            }
+            Decl::Import(path, namespace, names, reexport) => {
+                self.tag(1);
+                self.string(path);
+                match namespace {
+                    Some(namespace) => {
+                        self.tag(1);
+                        self.string(namespace);
+                    }
+                    None => self.tag(0),
+                }
+                match names {
+                    Some(names) => {
+                        self.tag(1);
+                        self.strings(names);
+                    }
+                    None => self.tag(0),
+                }
+                self.tag(*reexport as u8);
+            }
+            Decl::Export(idents) => {
+                self.tag(2);
+                self.strings(idents);
+            }
+            Decl::Signature(ident, type_) => {
+                self.tag(3);
+                self.string(ident);
+                self.type_(type_);
+            }
+        }
+    }
+
+    fn type_(&mut self, type_: &TypeExpr) {
+        match type_ {
+            TypeExpr::Named(name, args) => {
+                self.tag(0);
+                self.string(name);
+                self.len(args.len());
+                for arg in args {
+                    self.type_(arg);
+                }
+            }
+            TypeExpr::Var(ident) => {
+                self.tag(1);
+                self.string(ident);
+            }
+            TypeExpr::Boxed(inner) => {
+                self.tag(2);
+                self.type_(inner);
+            }
+            TypeExpr::Fn_(param, result) => {
+                self.tag(3);
+                self.type_(param);
+                self.type_(result);
+            }
+            TypeExpr::Constrained(constraints, type_) => {
+                self.tag(4);
+                self.len(constraints.len());
+                for (class, var) in constraints {
+                    self.string(class);
+                    self.string(var);
+                }
+                self.type_(type_);
+            }
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Do(a, pattern, b) => {
+                self.tag(0);
+                self.expr(a);
+                self.match_(pattern);
+                self.expr(b);
+            }
+            Expr::If(cond, a, b) => {
+                self.tag(1);
+                self.expr(cond);
+                self.expr(a);
+                self.expr(b);
+            }
+            Expr::Let(pattern, val, body) => {
+                self.tag(2);
+                self.match_(pattern);
+                self.expr(val);
+                self.expr(body);
+            }
+            Expr::Fn_(pattern, body) => {
+                self.tag(3);
+                self.match_(pattern);
+                self.expr(body);
+            }
+            Expr::FnApp(f, arg) => {
+                self.tag(4);
+                self.expr(f);
+                self.expr(arg);
+            }
+            Expr::Match(val, arms) => {
+                self.tag(5);
+                self.expr(val);
+                self.len(arms.len());
+                for (pattern, body) in arms {
+                    self.match_(pattern);
+                    self.expr(body);
+                }
+            }
+            Expr::Delayed(pattern, val, body) => {
+                self.tag(6);
+                self.match_(pattern);
+                self.expr(val);
+                self.expr(body);
+            }
+            Expr::Boxed(a) => {
+                self.tag(7);
+                self.expr(a);
+            }
+            Expr::Variable(ident) => {
+                self.tag(8);
+                self.string(ident);
+            }
+            Expr::Unary(op, a) => {
+                self.tag(9);
+                self.tag(match op {
+                    UnaryOp::Negate => 0,
+                    UnaryOp::Not => 1,
+                });
+                self.expr(a);
+            }
+            Expr::Binary(a, op, b) => {
+                self.tag(10);
+                self.expr(a);
+                self.tag(binary_op_tag(op));
+                self.expr(b);
+            }
+            Expr::Literal(val) => {
+                self.tag(11);
+                match val {
+                    ExprValue::Int(i) => {
+                        self.tag(0);
+                        self.int(*i);
+                    }
+                    ExprValue::Bool(b) => {
+                        self.tag(1);
+                        self.tag(*b as u8);
+                    }
+                    ExprValue::Unit => self.tag(2),
+                    ExprValue::String(s) => {
+                        self.tag(3);
+                        self.string(s);
+                    }
+                    ExprValue::Char(c) => {
+                        self.tag(4);
+                        self.char(*c);
+                    }
+                }
+            }
+        }
+    }
+
+    fn match_(&mut self, pattern: &Match) {
+        match pattern {
+            Match::Ident(ident) => {
+                self.tag(0);
+                self.string(ident);
+            }
+            Match::Tuple(patterns) => {
+                self.tag(1);
+                self.len(patterns.len());
+                for pattern in patterns {
+                    self.match_(pattern);
+                }
+            }
+            Match::Boxed(pattern) => {
+                self.tag(2);
+                self.match_(pattern);
+            }
+            Match::Value(val) => {
+                self.tag(3);
+                match val {
+                    MatchVal::Int(i) => {
+                        self.tag(0);
+                        self.int(*i);
+                    }
+                    MatchVal::Bool(b) => {
+                        self.tag(1);
+                        self.tag(*b as u8);
+                    }
+                    MatchVal::Char(c) => {
+                        self.tag(2);
+                        self.char(*c);
+                    }
+                    MatchVal::String(s) => {
+                        self.tag(3);
+                        self.string(s);
+                    }
+                }
+            }
+            Match::Unit => self.tag(4),
+            Match::Ignore => self.tag(5),
+        }
+    }
+}
+
+const BINARY_OPS: [BinaryOp; 19] = [
+    BinaryOp::Plus,
+    BinaryOp::Minus,
+    BinaryOp::Multiply,
+    BinaryOp::Divide,
+    BinaryOp::Modulo,
+    BinaryOp::And,
+    BinaryOp::Or,
+    BinaryOp::Xor,
+    BinaryOp::Join,
+    BinaryOp::FlatJoin,
+    BinaryOp::Equal,
+    BinaryOp::NotEqual,
+    BinaryOp::LessThan,
+    BinaryOp::GreaterThan,
+    BinaryOp::LessThanEqual,
+    BinaryOp::GreaterThanEqual,
+    BinaryOp::Concat,
+    BinaryOp::Sequence,
+    BinaryOp::Named,
+];
+
+fn binary_op_tag(op: &BinaryOp) -> u8 {
+    BINARY_OPS.iter().position(|other| other == op).unwrap() as u8
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        let bytes = self.bytes.get(self.pos..self.pos.checked_add(n)?)?;
+        self.pos += n;
+        Some(bytes)
+    }
+
+    fn tag(&mut self) -> Option<u8> {
+        self.take(1).map(|bytes| bytes[0])
+    }
+
+    fn len(&mut self) -> Option<usize> {
+        let mut len = 0usize;
+        for shift in (0..usize::BITS).step_by(7) {
+            let byte = self.tag()?;
+            len |= ((byte & 0x7f) as usize).checked_shl(shift)?;
+            if byte & 0x80 == 0 {
+                return Some(len);
+            }
+        }
+        None
+    }
+
+    fn list<T>(&mut self, item: impl Fn(&mut Self) -> Option<T>) -> Option<Vec<T>> {
+        let len = self.len()?;
+        // Don't trust the length for the capacity, it could be huge
+        let mut items = Vec::with_capacity(len.min(self.bytes.len()));
+        for _ in 0..len {
+            items.push(item(self)?);
+        }
+        Some(items)
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let len = self.len()?;
+        String::from_utf8(self.take(len)?.to_vec()).ok()
+    }
+
+    fn int(&mut self) -> Option<i32> {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(self.take(4)?);
+        Some(i32::from_le_bytes(bytes))
+    }
+
+    fn char(&mut self) -> Option<char> {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(self.take(4)?);
+        std::char::from_u32(u32::from_le_bytes(bytes))
+    }
+
+    fn bool(&mut self) -> Option<bool> {
+        match self.tag()? {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+
+    fn boxed(&mut self) -> Option<Box<Expr>> {
+        self.expr().map(Box::new)
+    }
+
+    fn decl(&mut self) -> Option<Decl> {
+        Some(match self.tag()? {
+            0 => Decl::Expression(self.string()?, self.expr()?),
+            1 => {
+                let path = self.string()?;
+                let namespace = match self.tag()? {
+                    0 => None,
+                    1 => Some(self.string()?),
+                    _ => return None,
+                };
+                let names = match self.tag()? {
+                    0 => None,
+                    1 => Some(self.list(Decoder::string)?),
+                    _ => return None,
+                };
+                Decl::Import(path, namespace, names, self.bool()?)
+            }
+            2 => Decl::Export(self.list(Decoder::string)?),
+            3 => Decl::Signature(self.string()?, self.type_()?),
+            _ => return None,
+        })
+    }
+
+    fn type_(&mut self) -> Option<TypeExpr> {
+        Some(match self.tag()? {
+            0 => TypeExpr::Named(self.string()?, self.list(Decoder::type_)?),
+            1 => TypeExpr::Var(self.string()?),
+            2 => TypeExpr::Boxed(Box::new(self.type_()?)),
+            3 => TypeExpr::Fn_(Box::new(self.type_()?), Box::new(self.type_()?)),
+            4 => TypeExpr::Constrained(
+                self.list(|decoder| Some((decoder.string()?, decoder.string()?)))?,
+                Box::new(self.type_()?),
+            ),
+            _ => return None,
+        })
+    }
+
+    fn expr(&mut self) -> Option<Expr> {
+        Some(match self.tag()? {
+            0 => Expr::Do(self.boxed()?, self.match_()?, self.boxed()?),
+            1 => Expr::If(self.boxed()?, self.boxed()?, self.boxed()?),
+            2 => Expr::Let(self.match_()?, self.boxed()?, self.boxed()?),
+            3 => Expr::Fn_(self.match_()?, self.boxed()?),
+            4 => Expr::FnApp(self.boxed()?, self.boxed()?),
+            5 => Expr::Match(
+                self.boxed()?,
+                self.list(|decoder| Some((decoder.match_()?, decoder.expr()?)))?,
+            ),
+            6 => Expr::Delayed(self.match_()?, self.boxed()?, self.boxed()?),
+            7 => Expr::Boxed(self.boxed()?),
+            8 => Expr::Variable(self.string()?),
+            9 => {
+                let op = match self.tag()? {
+                    0 => UnaryOp::Negate,
+                    1 => UnaryOp::Not,
+                    _ => return None,
+                };
+                Expr::Unary(op, self.boxed()?)
+            }
+            10 => {
+                let a = self.boxed()?;
+                let op = BINARY_OPS.get(self.tag()? as usize)?.clone();
+                Expr::Binary(a, op, self.boxed()?)
+            }
+            11 => Expr::Literal(match self.tag()? {
+                0 => ExprValue::Int(self.int()?),
+                1 => ExprValue::Bool(self.bool()?),
+                2 => ExprValue::Unit,
+                3 => ExprValue::String(self.string()?),
+                4 => ExprValue::Char(self.char()?),
+                _ => return None,
+            }),
+            _ => return None,
+        })
+    }
+
+    fn match_(&mut self) -> Option<Match> {
+        Some(match self.tag()? {
+            0 => Match::Ident(self.string()?),
+            1 => Match::Tuple(self.list(Decoder::match_)?),
+            2 => Match::Boxed(Box::new(self.match_()?)),
+            3 => Match::Value(match self.tag()? {
+                0 => MatchVal::Int(self.int()?),
+                1 => MatchVal::Bool(self.bool()?),
+                2 => MatchVal::Char(self.char()?),
+                3 => MatchVal::String(self.string()?),
+                _ => return None,
+            }),
+            4 => Match::Unit,
+            5 => Match::Ignore,
+            _ => return None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::Prog;
+    use crate::Span;
+
+    fn decls(source: &str) -> Vec<Decl> {
+        match crate::prog(Span::new(source)).unwrap().1 {
+            Prog::Binary(_, decls) | Prog::Library(decls) => decls,
+        }
+    }
+
+    #[test]
+    fn round_trip_test() {
+        let decls = decls(
+            "import \"lib/a.tgo\"
+import List
+import Math as M (pow)
+import \"lib/b.tgo\" (b)
+export import Option
+export (main, f)
+f : (a -> Int) -> [a] -> Command (Bool -> b)
+main = f (1, -2) 'c' \"s\"
+g : (Eq a, Show b) => a -> b
+f a b c = if not true then () else match a to
+	| 1, [x] -> x * 300 ,, (c == 'c')
+	| \"s\", _ -> let y = b in y
+	| () -> do print b in _ then 0
+g = delay x = 1 in [x]",
+        );
+        assert_eq!(decode(&encode(&decls)), Some(decls));
+    }
+
+    #[test]
+    fn invalid_test() {
+        let bytes = encode(&decls("a = 1"));
+        assert_eq!(decode(&bytes[..bytes.len() - 1]), None);
+        assert_eq!(decode(&[bytes.as_slice(), &[0]].concat()), None);
+        assert_eq!(decode(b"TGOC"), None);
+        assert_eq!(decode(b"not a cache file"), None);
+
+        let mut old = bytes.clone();
+        old[MAGIC.len()] = VERSION + 1;
+        assert_eq!(decode(&old), None);
+    }
+}
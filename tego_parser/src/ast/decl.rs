@@ -1,18 +1,57 @@
-use crate::ast::Expr;
+use crate::ast::{Expr, TypeExpr};
 use crate::DeclOutput;
+use std::fmt;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Decl {
     Expression(String, Expr),
+    // Path to the imported file, its namespace, the names to import (or all of them),
+    // and whether the imported names are exported again
+    Import(String, Option<String>, Option<Vec<String>>, bool),
+    Export(Vec<String>),
+    Signature(String, TypeExpr),
 }
 
 impl DeclOutput for Decl {
     type Expr = Expr;
+    type Type = TypeExpr;
 
     fn expression(ident: &str, body: Expr) -> Self {
         Decl::Expression(ident.into(), body)
     }
 
+    fn import(path: &str) -> Self {
+        Decl::Import(path.into(), None, None, false)
+    }
+
+    fn qualified_import(module: &str) -> Self {
+        Decl::Import(format!("{}.tgo", module), Some(module.into()), None, false)
+    }
+
+    fn import_with(path: &str, namespace: Option<&str>, names: Option<Vec<&str>>) -> Self {
+        Decl::Import(
+            path.into(),
+            namespace.map(String::from),
+            names.map(|names| names.into_iter().map(String::from).collect()),
+            false,
+        )
+    }
+
+    fn export(idents: Vec<&str>) -> Self {
+        Decl::Export(idents.into_iter().map(String::from).collect())
+    }
+
+    fn reexport(import: Self) -> Self {
+        match import {
+            Decl::Import(path, namespace, names, _) => Decl::Import(path, namespace, names, true),
+            other => other,
+        }
+    }
+
+    fn signature(ident: &str, type_: TypeExpr) -> Self {
+        Decl::Signature(ident.into(), type_)
+    }
+
     fn to_main(&self, main_fn_ident: &str) -> Option<Expr> {
         match self {
             Decl::Expression(ref ident, ref body) if ident == main_fn_ident => Some(body.clone()),
@@ -20,3 +59,28 @@ impl DeclOutput for Decl {
         }
     }
 }
+
+// Renders a declaration as tego source, with functions written as `fn`s (ex.
+// `id = fn a -> a`)
+impl fmt::Display for Decl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Decl::Expression(ident, body) => write!(f, "{} = {}", ident, body),
+            Decl::Import(path, namespace, names, reexport) => {
+                if *reexport {
+                    write!(f, "export ")?;
+                }
+                write!(f, "import \"{}\"", path)?;
+                if let Some(namespace) = namespace {
+                    write!(f, " as {}", namespace)?;
+                }
+                match names {
+                    Some(names) => write!(f, " ({})", names.join(", ")),
+                    None => Ok(()),
+                }
+            }
+            Decl::Export(idents) => write!(f, "export ({})", idents.join(", ")),
+            Decl::Signature(ident, type_) => write!(f, "{} : {}", ident, type_),
+        }
+    }
+}
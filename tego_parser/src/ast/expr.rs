@@ -1,5 +1,6 @@
 use crate::ast::Match;
 use crate::ExprOutput;
+use std::fmt;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Expr {
@@ -79,6 +80,19 @@ impl Expr {
         Expr::binary(a, BinaryOp::FlatJoin, b)
     }
 
+    pub fn concat(a: Self, b: Self) -> Self {
+        Expr::binary(a, BinaryOp::Concat, b)
+    }
+
+    pub fn sequence(a: Self, b: Self) -> Self {
+        Expr::binary(a, BinaryOp::Sequence, b)
+    }
+
+    // `name = value`, an argument given by name (ex. `f (x = 1, y = 2)`)
+    pub fn named(name: &str, value: Self) -> Self {
+        Expr::binary(Expr::string(name), BinaryOp::Named, value)
+    }
+
     pub fn equal(a: Self, b: Self) -> Self {
         Expr::binary(a, BinaryOp::Equal, b)
     }
@@ -102,6 +116,23 @@ impl Expr {
     pub fn greater_than_equal(a: Self, b: Self) -> Self {
         Expr::binary(a, BinaryOp::GreaterThanEqual, b)
     }
+
+    // The names and values of the arguments, if every part of a tuple is
+    // given by name
+    pub fn named_args(&self) -> Option<Vec<(&str, &Expr)>> {
+        match self {
+            Expr::Binary(a, BinaryOp::Join, b) => {
+                let mut args = a.named_args()?;
+                args.extend(b.named_args()?);
+                Some(args)
+            }
+            Expr::Binary(name, BinaryOp::Named, value) => match &**name {
+                Expr::Literal(ExprValue::String(name)) => Some(vec![(name.as_str(), &**value)]),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
 }
 
 impl ExprOutput for Expr {
@@ -209,6 +240,9 @@ pub enum BinaryOp {
     Xor,
     Join,     // ',' operator, creates a tuple
     FlatJoin, // ',,' operator
+    Concat,   // '++' operator, joins lists (and strings)
+    Sequence, // ';' operator, runs a Command (or `()`) before the next expression
+    Named,    // '=' in an argument given by name, whose name is a string literal
     Equal,
     NotEqual,
     LessThan,
@@ -230,6 +264,9 @@ impl From<&str> for BinaryOp {
             "xor" => BinaryOp::Xor,
             "," => BinaryOp::Join,
             ",," => BinaryOp::FlatJoin,
+            "++" => BinaryOp::Concat,
+            ";" => BinaryOp::Sequence,
+            "=" => BinaryOp::Named,
             "==" => BinaryOp::Equal,
             "/=" => BinaryOp::NotEqual,
             "<" => BinaryOp::LessThan,
@@ -240,3 +277,141 @@ impl From<&str> for BinaryOp {
         }
     }
 }
+
+// Renders an expression as tego source (used to show expressions in error messages)
+// Parentheses are added around anything that isn't a single value
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Do(command, result_match, body) => {
+                write!(f, "do {} in {} then {}", Head(command), result_match, body)
+            }
+            Expr::If(cond, t, e) => write!(f, "if {} then {} else {}", Head(cond), t, e),
+            Expr::Let(ident, value, inner) => {
+                write!(f, "let {} = {} in {}", ident, Head(value), inner)
+            }
+            // `delay` is the older way of writing it
+            Expr::Delayed(ident, value, inner) => {
+                write!(f, "let rec {} = {} in {}", ident, Head(value), inner)
+            }
+            Expr::Fn_(param, body) => write!(f, "fn {} -> {}", param, body),
+            Expr::FnApp(function, arg) => match **function {
+                Expr::FnApp(..) => write!(f, "{} {}", function, Grouped(arg)),
+                _ => write!(f, "{} {}", Grouped(function), Grouped(arg)),
+            },
+            // Only the last arm can end with another `match`
+            Expr::Match(val, patterns) => {
+                write!(f, "match {} to", Head(val))?;
+                patterns
+                    .iter()
+                    .enumerate()
+                    .try_for_each(|(i, (pattern, body))| {
+                        if i + 1 < patterns.len() {
+                            write!(f, " | {} -> {}", pattern, Head(body))
+                        } else {
+                            write!(f, " | {} -> {}", pattern, body)
+                        }
+                    })
+            }
+            Expr::Boxed(inner) => write!(f, "[{}]", inner),
+            Expr::Variable(ident) => write!(f, "{}", ident),
+            Expr::Unary(UnaryOp::Negate, a) => write!(f, "-{}", Grouped(a)),
+            Expr::Unary(UnaryOp::Not, a) => write!(f, "not {}", Grouped(a)),
+            // Arguments given by name aren't grouped, since they're only in tuples
+            Expr::Binary(a, BinaryOp::Join, b) => {
+                let part = |expr: &Expr| match expr {
+                    Expr::Binary(_, BinaryOp::Named, _) => expr.to_string(),
+                    expr => Grouped(expr).to_string(),
+                };
+                match **a {
+                    Expr::Binary(_, BinaryOp::Join, _) => write!(f, "{}, {}", a, part(b)),
+                    _ => write!(f, "{}, {}", part(a), part(b)),
+                }
+            }
+            Expr::Binary(a, BinaryOp::Named, b) => match **a {
+                Expr::Literal(ExprValue::String(ref name)) => {
+                    write!(f, "{} = {}", name, Grouped(b))
+                }
+                _ => write!(f, "{} = {}", Grouped(a), Grouped(b)),
+            },
+            Expr::Binary(a, BinaryOp::Sequence, b) => match **a {
+                Expr::Binary(_, BinaryOp::Sequence, _) => write!(f, "{}; {}", a, Grouped(b)),
+                _ => write!(f, "{}; {}", Grouped(a), Grouped(b)),
+            },
+            Expr::Binary(a, op, b) => write!(f, "{} {} {}", Grouped(a), op, Grouped(b)),
+            Expr::Literal(val) => write!(f, "{}", val),
+        }
+    }
+}
+
+struct Grouped<'a>(&'a Expr);
+
+// An expression before a keyword (ex. an `if`'s condition, before `then`),
+// which is grouped if it could take the keyword as its own
+struct Head<'a>(&'a Expr);
+
+impl fmt::Display for Head<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            Expr::Do(..)
+            | Expr::If(..)
+            | Expr::Let(..)
+            | Expr::Delayed(..)
+            | Expr::Fn_(..)
+            | Expr::Match(..)
+            | Expr::Binary(_, BinaryOp::Sequence, _)
+            | Expr::Binary(_, BinaryOp::Named, _) => write!(f, "({})", self.0),
+            expr => write!(f, "{}", expr),
+        }
+    }
+}
+
+impl fmt::Display for Grouped<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            expr @ Expr::Literal(_) | expr @ Expr::Variable(_) | expr @ Expr::Boxed(_) => {
+                write!(f, "{}", expr)
+            }
+            expr => write!(f, "({})", expr),
+        }
+    }
+}
+
+impl fmt::Display for ExprValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExprValue::Int(i) => write!(f, "{}", i),
+            ExprValue::Bool(b) => write!(f, "{}", b),
+            ExprValue::Unit => write!(f, "()"),
+            ExprValue::String(s) => write!(f, "\"{}\"", s),
+            ExprValue::Char(c) => write!(f, "'{}'", c),
+        }
+    }
+}
+
+impl fmt::Display for BinaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let op = match self {
+            BinaryOp::Plus => "+",
+            BinaryOp::Minus => "-",
+            BinaryOp::Multiply => "*",
+            BinaryOp::Divide => "/",
+            BinaryOp::Modulo => "%",
+            BinaryOp::And => "and",
+            BinaryOp::Or => "or",
+            BinaryOp::Xor => "xor",
+            BinaryOp::Join => ",",
+            BinaryOp::FlatJoin => ",,",
+            BinaryOp::Concat => "++",
+            BinaryOp::Sequence => ";",
+            BinaryOp::Named => "=",
+            BinaryOp::Equal => "==",
+            BinaryOp::NotEqual => "/=",
+            BinaryOp::LessThan => "<",
+            BinaryOp::GreaterThan => ">",
+            BinaryOp::LessThanEqual => "<=",
+            BinaryOp::GreaterThanEqual => ">=",
+        };
+        write!(f, "{}", op)
+    }
+}
@@ -71,6 +71,33 @@ pub enum MatchVal {
     String(String),
 }
 
+impl fmt::Display for Match {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Match::Ident(ident) => write!(f, "{}", ident),
+            Match::Tuple(vals) => write!(
+                f,
+                "{}",
+                vals.iter()
+                    .map(|val| match val {
+                        Match::Tuple(_) => format!("({})", val),
+                        val => val.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Match::Boxed(inner) => match **inner {
+                // Strings are stored as boxed values
+                Match::Value(MatchVal::String(_)) => write!(f, "{}", inner),
+                _ => write!(f, "[{}]", inner),
+            },
+            Match::Value(val) => write!(f, "{}", val),
+            Match::Unit => write!(f, "()"),
+            Match::Ignore => write!(f, "_"),
+        }
+    }
+}
+
 impl fmt::Display for MatchVal {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
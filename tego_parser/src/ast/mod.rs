@@ -1,7 +1,11 @@
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
+pub mod binary;
 mod decl;
 mod expr;
 mod match_;
 mod prog;
+mod type_;
 
 pub use decl::Decl;
 pub use expr::BinaryOp;
@@ -11,3 +15,4 @@ pub use expr::UnaryOp;
 pub use match_::Match;
 pub use match_::MatchVal;
 pub use prog::Prog;
+pub use type_::TypeExpr;
@@ -1,5 +1,6 @@
 use crate::ast::{Decl, Expr};
 use crate::ProgOutput;
+use std::fmt;
 
 #[derive(PartialEq, Debug, Clone)]
 pub enum Prog {
@@ -17,3 +18,15 @@ impl ProgOutput for Prog {
         Prog::Library(decls)
     }
 }
+
+// Renders a program as tego source, one declaration per line (`main` is one of
+// the declarations)
+impl fmt::Display for Prog {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Prog::Library(decls) | Prog::Binary(_, decls) => {
+                decls.iter().try_for_each(|decl| writeln!(f, "{}", decl))
+            }
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use crate::TypeOutput;
+use std::fmt;
+
+// A type written in a type signature (ex. `Int -> [a]`)
+#[derive(Debug, PartialEq, Clone)]
+pub enum TypeExpr {
+    // A type name and its arguments (ex. `Int`, `Command a`)
+    Named(String, Vec<TypeExpr>),
+    Var(String),
+    Boxed(Box<TypeExpr>),
+    Fn_(Box<TypeExpr>, Box<TypeExpr>),
+    // Classes that type variables have to be instances of, and the type they're used in
+    // (ex. `Eq a => a -> a -> Bool`)
+    Constrained(Vec<(String, String)>, Box<TypeExpr>),
+}
+
+impl TypeOutput for TypeExpr {
+    fn named(name: &str, args: Vec<Self>) -> Self {
+        TypeExpr::Named(name.into(), args)
+    }
+
+    fn var(ident: &str) -> Self {
+        TypeExpr::Var(ident.into())
+    }
+
+    fn boxed(inner: Self) -> Self {
+        TypeExpr::Boxed(Box::new(inner))
+    }
+
+    fn function(param: Self, result: Self) -> Self {
+        TypeExpr::Fn_(Box::new(param), Box::new(result))
+    }
+
+    fn constrained(constraints: Vec<(&str, &str)>, type_: Self) -> Self {
+        TypeExpr::Constrained(
+            constraints
+                .into_iter()
+                .map(|(class, var)| (class.into(), var.into()))
+                .collect(),
+            Box::new(type_),
+        )
+    }
+}
+
+impl fmt::Display for TypeExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TypeExpr::Named(name, args) => {
+                write!(f, "{}", name)?;
+                args.iter().try_for_each(|arg| match arg {
+                    TypeExpr::Named(_, args) if args.is_empty() => write!(f, " {}", arg),
+                    TypeExpr::Named(..) | TypeExpr::Fn_(..) => write!(f, " ({})", arg),
+                    arg => write!(f, " {}", arg),
+                })
+            }
+            TypeExpr::Var(ident) => write!(f, "{}", ident),
+            TypeExpr::Boxed(inner) => write!(f, "[{}]", inner),
+            // `->` is right associative
+            TypeExpr::Fn_(param, result) => match **param {
+                TypeExpr::Fn_(..) => write!(f, "({}) -> {}", param, result),
+                _ => write!(f, "{} -> {}", param, result),
+            },
+            TypeExpr::Constrained(constraints, type_) => {
+                let constraints = constraints
+                    .iter()
+                    .map(|(class, var)| format!("{} {}", class, var))
+                    .collect::<Vec<_>>();
+                match constraints.as_slice() {
+                    [constraint] => write!(f, "{} => {}", constraint, type_),
+                    _ => write!(f, "({}) => {}", constraints.join(", "), type_),
+                }
+            }
+        }
+    }
+}
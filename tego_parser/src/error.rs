@@ -13,16 +13,16 @@ pub struct ParseError {
 
 impl ParseError {
     fn is_unhandled(&self) -> bool {
-        match self.kind {
+        matches!(
+            self.kind,
             ErrorKind::Reserved(_)
-            | ErrorKind::Char
-            | ErrorKind::String
-            | ErrorKind::Number
-            | ErrorKind::Keyword
-            | ErrorKind::UnknownNomError
-            | ErrorKind::UnhandledError => true,
-            _ => false,
-        }
+                | ErrorKind::Char
+                | ErrorKind::String
+                | ErrorKind::Number
+                | ErrorKind::Keyword
+                | ErrorKind::UnknownNomError
+                | ErrorKind::UnhandledError
+        )
     }
 
     fn new_from(input: Input<'_>, error: Self, kind: ErrorKind) -> nom::Err<(Input<'_>, Self)> {
@@ -33,6 +33,30 @@ impl ParseError {
         nom::Err::Error((input, error))
     }
 
+    // Where the error was found (both start at 1)
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    // The kind of error, which is written as `E0007` (see `tego explain`)
+    pub fn number(&self) -> u16 {
+        u16::from(self.kind)
+    }
+
+    // Where the parenthesis or bracket that wasn't closed was opened, and which
+    // one it is
+    pub fn opening(&self) -> Option<(usize, usize, &'static str)> {
+        match self.kind {
+            ErrorKind::TerminatingParen(line, column) => Some((line, column, "parenthesis")),
+            ErrorKind::TerminatingBracket(line, column) => Some((line, column, "bracket")),
+            _ => None,
+        }
+    }
+
     pub fn verbose_from_source(&self, source: &str, writer: &mut impl io::Write) -> io::Result<()> {
         writeln!(writer, "{}", self)?;
         writeln!(writer)?;
@@ -162,9 +186,21 @@ impl fmt::Display for ParseError {
             ErrorKind::EndOfExpr => "unexpected end of expr".into(),
             ErrorKind::DoIn => "missing 'in' in do expression".into(),
             ErrorKind::DoThen => "missing 'then' in do expression".into(),
+            ErrorKind::CondArrow => "missing '->' between cond condition and cond body".into(),
+            ErrorKind::CondElse => "missing '| else -> ...' at the end of cond expression".into(),
 
             // Decl Errors
             ErrorKind::DeclAssign => "missing '=' in expression declaration".into(),
+            ErrorKind::ImportPath => {
+                "missing file path string or module name after 'import'".into()
+            }
+            ErrorKind::ExportList => "missing parenthesized list of names after 'export'".into(),
+            ErrorKind::ImportAlias => "missing module name after 'as'".into(),
+            ErrorKind::ImportList => "invalid list of names to import".into(),
+            ErrorKind::SignatureType => "missing type after ':' in type signature".into(),
+            ErrorKind::ClauseParams => {
+                "each equation of a function has to have the same number of parameters".into()
+            }
 
             // Other Errors
             ErrorKind::TerminatingNewline => "missing newline (expected here)".into(),
@@ -293,7 +329,7 @@ error_type! {
     starts_with [literal_error, ErrorKind::InvalidCharacter]
     '"' => ErrorKind::String,
     '\'' => ErrorKind::Char,
-    |c: char| c.is_digit(10) => ErrorKind::Number,
+    |c: char| c.is_ascii_digit() => ErrorKind::Number,
     char::is_alphabetic => ErrorKind::Keyword
 }
 error_type!(terminating_paren_error, ErrorKind::TerminatingParen(open_paren_loc.0, open_paren_loc.1); open_paren_loc: (usize, usize));
@@ -321,6 +357,15 @@ error_type! {
     "else" => ErrorKind::Else,
     ":" => ErrorKind::Else
 }
+error_type! {
+    token [cond_arm_error]
+    "->" => ErrorKind::CondArrow
+}
+error_type! {
+    token [cond_else_error]
+    "|" => ErrorKind::CondElse,
+    "else" => ErrorKind::CondElse
+}
 error_type! {
     token [let_assign_error]
     "=" => ErrorKind::LetAssign,
@@ -343,7 +388,7 @@ error_type! {
     starts_with [basic_match_error, ErrorKind::InvalidCharacter]
     '"' => ErrorKind::String,
     '\'' => ErrorKind::Char,
-    |c: char| c.is_digit(10) => ErrorKind::Number,
+    |c: char| c.is_ascii_digit() => ErrorKind::Number,
     char::is_alphabetic => ErrorKind::Keyword
 }
 error_type!(grouping_match_error, ErrorKind::TerminatingParen(open_paren_loc.0, open_paren_loc.1); open_paren_loc: (usize, usize));
@@ -353,6 +398,12 @@ error_type! {
     token [decl_expr_error]
     "=" => ErrorKind::DeclAssign
 }
+error_type!(import_path_error, ErrorKind::ImportPath);
+error_type!(export_list_error, ErrorKind::ExportList);
+error_type!(import_alias_error, ErrorKind::ImportAlias);
+error_type!(import_list_error, ErrorKind::ImportList);
+error_type!(signature_type_error, ErrorKind::SignatureType);
+error_type!(clause_params_error, ErrorKind::ClauseParams);
 
 // Other Errors
 error_type!(newline_error, ErrorKind::TerminatingNewline);
@@ -383,15 +434,24 @@ enum ErrorKind {
     EndOfExpr,
     DoIn,
     DoThen,
+    CondArrow,
+    CondElse,
 
     // Decl Errors
     DeclAssign,
+    ImportPath,
+    ExportList,
+    ImportAlias,
+    ImportList,
+    SignatureType,
+    ClauseParams,
 
     // Other Errors
     TerminatingNewline,
     Eof,
     Incomplete,
     UnknownNomError,
+    #[allow(dead_code)]
     UnhandledError,
 }
 
@@ -441,6 +501,14 @@ impl From<&ErrorKind> for u16 {
             ErrorKind::Incomplete => 25,
             ErrorKind::DoIn => 26,
             ErrorKind::DoThen => 27,
+            ErrorKind::ImportPath => 28,
+            ErrorKind::ExportList => 29,
+            ErrorKind::ImportAlias => 30,
+            ErrorKind::ImportList => 31,
+            ErrorKind::SignatureType => 32,
+            ErrorKind::ClauseParams => 33,
+            ErrorKind::CondArrow => 34,
+            ErrorKind::CondElse => 35,
         }
     }
 }
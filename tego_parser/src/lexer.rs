@@ -0,0 +1,221 @@
+// Splits source code into tokens, with where each one is in the source (see
+// `tego tokens`)
+// The tokens are found with the same parsers the grammar uses, but the lexer
+// never fails: anything it doesn't recognize becomes an `Unknown` token, so it
+// can be used on code that's still being written
+// Spaces and tabs aren't tokens, but newlines are, since they end declarations
+
+use crate::parsers::tokens;
+use crate::{Input, ParseResult, Span};
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{anychar, line_ending, not_line_ending, space0},
+    combinator::{map, opt, recognize},
+    sequence::{pair, preceded},
+};
+use std::fmt;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum TokenKind {
+    Keyword,
+    Identifier,
+    Number,
+    String,
+    Char,
+    Operator,
+    // Brackets and parentheses
+    Punctuation,
+    Comment,
+    Newline,
+    Unknown,
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self {
+            TokenKind::Keyword => "keyword",
+            TokenKind::Identifier => "identifier",
+            TokenKind::Number => "number",
+            TokenKind::String => "string",
+            TokenKind::Char => "char",
+            TokenKind::Operator => "operator",
+            TokenKind::Punctuation => "punctuation",
+            TokenKind::Comment => "comment",
+            TokenKind::Newline => "newline",
+            TokenKind::Unknown => "unknown",
+        };
+        write!(f, "{}", kind)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    // The whole lexeme (ex. a string's quotes are included)
+    pub span: Span<'a>,
+}
+
+pub fn tokenize(source: &str) -> Vec<Token<'_>> {
+    let mut tokens = vec![];
+    let mut input = Span::new(source);
+    loop {
+        input = match space0::<_, (Input<'_>, crate::ParseError)>(input) {
+            Ok((rest, _)) => rest,
+            Err(_) => input,
+        };
+        if input.to_str().is_empty() {
+            return tokens;
+        }
+        // The last alternative accepts any character
+        let (rest, token) = next_token(input).expect("A token is always found");
+        tokens.push(token);
+        input = rest;
+    }
+}
+
+fn next_token(input: Input<'_>) -> ParseResult<'_, Token<'_>> {
+    alt((
+        kind(TokenKind::Newline, line_ending),
+        // The newline after a comment is its own token
+        kind(
+            TokenKind::Comment,
+            recognize(preceded(tag("--"), not_line_ending)),
+        ),
+        kind(TokenKind::Comment, recognize(tokens::multi_comment)),
+        kind(TokenKind::String, recognize(tokens::string)),
+        kind(TokenKind::Char, recognize(tokens::char)),
+        kind(TokenKind::Number, tokens::number),
+        kind(TokenKind::Identifier, tokens::qualified_identifier),
+        word,
+        kind(TokenKind::Operator, operator),
+        kind(
+            TokenKind::Punctuation,
+            alt((tag("("), tag(")"), tag("["), tag("]"))),
+        ),
+        kind(TokenKind::Unknown, recognize(anychar)),
+    ))(input)
+}
+
+fn kind<'a, F>(kind: TokenKind, parser: F) -> impl Fn(Input<'a>) -> ParseResult<'a, Token<'a>>
+where
+    F: Fn(Input<'a>) -> ParseResult<'a, Input<'a>>,
+{
+    move |input| map(&parser, |span| Token { kind, span })(input)
+}
+
+// Keywords and identifiers (`_` on its own is the ignore pattern, so it's a
+// keyword)
+fn word(input: Input<'_>) -> ParseResult<'_, Token<'_>> {
+    map(
+        alt((
+            recognize(pair(opt(tag("_")), tokens::identifier_chars)),
+            tag("_"),
+        )),
+        |span: Input<'_>| {
+            let kind = match span.to_str() {
+                "_" => TokenKind::Keyword,
+                word if tokens::is_keyword(word) => TokenKind::Keyword,
+                _ => TokenKind::Identifier,
+            };
+            Token { kind, span }
+        },
+    )(input)
+}
+
+// Longer operators come first, so `->` isn't lexed as `-` and `>`
+fn operator(input: Input<'_>) -> ParseResult<'_, Input<'_>> {
+    alt((
+        alt((
+            tag(",,"),
+            tag("++"),
+            tag("=="),
+            tag("/="),
+            tag("<="),
+            tag(">="),
+            tag("->"),
+            tag("=>"),
+        )),
+        alt((
+            tag(","),
+            tag("+"),
+            tag("-"),
+            tag("*"),
+            tag("/"),
+            tag("%"),
+            tag("<"),
+            tag(">"),
+            tag("?"),
+            tag("="),
+            tag("|"),
+            tag("."),
+            tag(":"),
+            tag(";"),
+        )),
+    ))(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lex(source: &str) -> Vec<(TokenKind, &str)> {
+        tokenize(source)
+            .into_iter()
+            .map(|token| (token.kind, token.span.to_str()))
+            .collect()
+    }
+
+    basic_test! {
+        tokenize_test
+        lex("double n = n * 2 -- twice\n") => vec![
+            (TokenKind::Identifier, "double"),
+            (TokenKind::Identifier, "n"),
+            (TokenKind::Operator, "="),
+            (TokenKind::Identifier, "n"),
+            (TokenKind::Operator, "*"),
+            (TokenKind::Number, "2"),
+            (TokenKind::Comment, "-- twice"),
+            (TokenKind::Newline, "\n"),
+        ];
+        lex("f = fn _ -> List.map x' [\"a b\"], 'c' {- c -}") => vec![
+            (TokenKind::Identifier, "f"),
+            (TokenKind::Operator, "="),
+            (TokenKind::Keyword, "fn"),
+            (TokenKind::Keyword, "_"),
+            (TokenKind::Operator, "->"),
+            (TokenKind::Identifier, "List.map"),
+            (TokenKind::Identifier, "x'"),
+            (TokenKind::Punctuation, "["),
+            (TokenKind::String, "\"a b\""),
+            (TokenKind::Punctuation, "]"),
+            (TokenKind::Operator, ","),
+            (TokenKind::Char, "'c'"),
+            (TokenKind::Comment, "{- c -}"),
+        ];
+        lex("if a /= b then 1 else 2 ~") => vec![
+            (TokenKind::Keyword, "if"),
+            (TokenKind::Identifier, "a"),
+            (TokenKind::Operator, "/="),
+            (TokenKind::Identifier, "b"),
+            (TokenKind::Keyword, "then"),
+            (TokenKind::Number, "1"),
+            (TokenKind::Keyword, "else"),
+            (TokenKind::Number, "2"),
+            (TokenKind::Unknown, "~"),
+        ];
+        lex("a ++ +b") => vec![
+            (TokenKind::Identifier, "a"),
+            (TokenKind::Operator, "++"),
+            (TokenKind::Operator, "+"),
+            (TokenKind::Identifier, "b"),
+        ];
+        lex("\"unclosed") => vec![(TokenKind::Unknown, "\""), (TokenKind::Identifier, "unclosed")]
+    }
+
+    basic_test! {
+        position_test
+        tokenize("a\n\tb").iter().map(|token| (token.span.line(), token.span.column())).collect::<Vec<_>>()
+            => vec![(1, 1), (1, 2), (2, 5)]
+    }
+}
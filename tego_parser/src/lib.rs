@@ -22,6 +22,7 @@ macro_rules! parser_test {
 
 pub mod ast;
 mod error;
+mod lexer;
 mod parsers;
 mod span;
 mod traits;
@@ -31,25 +32,30 @@ pub use crate::parsers::decl::decl;
 pub use crate::parsers::expr::expr;
 pub use crate::parsers::match_::match_;
 pub use crate::parsers::prog::prog;
+pub use crate::parsers::type_::{signature_type, type_};
 pub use nom::combinator::all_consuming as complete;
 
 // Utilities
 pub use crate::error::ParseError;
+pub use crate::lexer::{tokenize, Token, TokenKind};
 pub use crate::span::Span;
 
 // Traits for parser output
+pub use crate::traits::Clause;
 pub use crate::traits::DeclOutput;
 pub use crate::traits::ExprOutput;
 pub use crate::traits::MatchOutput;
 pub use crate::traits::ProgOutput;
+pub use crate::traits::TypeOutput;
 
 type Input<'a> = Span<'a>;
 type ParseResult<'a, O> = nom::IResult<Input<'a>, O, (Input<'a>, ParseError)>;
 
+#[cfg(test)]
 mod test {
     use crate::span;
     pub use crate::Span;
-    pub use crate::{DeclOutput, ExprOutput, MatchOutput, ProgOutput};
+    pub use crate::{DeclOutput, ExprOutput, MatchOutput, TypeOutput};
 
     #[allow(dead_code)]
     pub fn empty_span(input: Span<'_>) -> Span<'_> {
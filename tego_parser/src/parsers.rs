@@ -3,3 +3,4 @@ pub mod expr;
 pub mod match_;
 pub mod prog;
 pub mod tokens;
+pub mod type_;
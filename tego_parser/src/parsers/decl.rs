@@ -2,46 +2,167 @@ use crate::error::*;
 use crate::expr;
 use crate::match_;
 use crate::parsers::tokens::*;
+use crate::parsers::type_::signature_type;
 use crate::DeclOutput;
 use crate::ExprOutput;
 use crate::Input;
 use crate::ParseResult;
 
-use nom::{multi::many0, sequence::tuple};
+use nom::{
+    error::ErrorKind as NomErrorKind,
+    multi::{many0, many1, separated_nonempty_list},
+    sequence::{delimited, preceded, terminated, tuple},
+};
 
 type DeclResult<'a, D> = ParseResult<'a, D>;
+type Match<D> = <<D as DeclOutput>::Expr as ExprOutput>::Match;
 
 pub fn decl<D>(input: Input<'_>) -> DeclResult<'_, D>
 where
     D: DeclOutput,
 {
-    req_nl(expression)(input)
+    req_nl(import_decl)(input)
+}
+
+fn import_decl<D>(input: Input<'_>) -> DeclResult<'_, D>
+where
+    D: DeclOutput,
+{
+    import(input)
+        .and_then(|(input, _)| import_body(input))
+        .or_else(try_parser(export_decl, input))
+}
+
+// Everything in an import declaration after `import`
+fn import_body<D>(input: Input<'_>) -> DeclResult<'_, D>
+where
+    D: DeclOutput,
+{
+    let (input, (path, module)) = string(input)
+        .map(|(input, path)| (input, (path.to_str().to_string(), None)))
+        .or_else(|_| {
+            module_name(input)
+                .map(|(input, module)| (input, (format!("{}.tgo", module.to_str()), Some(module))))
+        })
+        .map_err(import_path_error)?;
+    let (input, alias) = match as_(input) {
+        Ok((input, _)) => module_name(input)
+            .map(|(input, alias)| (input, Some(alias)))
+            .map_err(import_alias_error)?,
+        Err(_) => (input, None),
+    };
+    let (input, names) = match left_paren(input) {
+        Ok(_) => name_list(input)
+            .map(|(input, names)| (input, Some(names)))
+            .map_err(import_list_error)?,
+        Err(_) => (input, None),
+    };
+    let decl = match (module, alias, names) {
+        (None, None, None) => D::import(&path),
+        (Some(module), None, None) => D::qualified_import(module.to_str()),
+        // Names imported from a list aren't qualified, unless there's an alias
+        (_, alias, names) => D::import_with(
+            &path,
+            alias.map(|alias| alias.to_str()),
+            names.map(|names| names.iter().map(|name| name.to_str()).collect()),
+        ),
+    };
+    Ok((input, decl))
+}
+
+// A parenthesized list of names (ex. `(a, b)`)
+fn name_list(input: Input<'_>) -> ParseResult<'_, Vec<Input<'_>>> {
+    delimited(
+        opt_nl(left_paren),
+        separated_nonempty_list(opt_nl(comma), opt_nl(identifier)),
+        right_paren,
+    )(input)
+}
+
+fn export_decl<D>(input: Input<'_>) -> DeclResult<'_, D>
+where
+    D: DeclOutput,
+{
+    export(input)
+        .and_then(|(input, _)| {
+            if let Ok((input, _)) = import(input) {
+                return import_body(input).map(|(input, decl)| (input, D::reexport(decl)));
+            }
+            name_list(input)
+                .map_err(export_list_error)
+                .map(|(input, idents)| {
+                    (
+                        input,
+                        D::export(idents.iter().map(|ident| ident.to_str()).collect()),
+                    )
+                })
+        })
+        .or_else(try_parser(signature, input))
+}
+
+// `ident : type`
+fn signature<D>(input: Input<'_>) -> DeclResult<'_, D>
+where
+    D: DeclOutput,
+{
+    terminated(identifier, colon)(input)
+        .and_then(|(input, ident)| {
+            signature_type(input)
+                .map_err(signature_type_error)
+                .map(|(input, type_)| (input, D::signature(ident.to_str(), type_)))
+        })
+        .or_else(try_parser(expression, input))
 }
 
 fn expression<D>(input: Input<'_>) -> DeclResult<'_, D>
+where
+    D: DeclOutput,
+{
+    let (mut input, (ident, params, body)) = clause::<D>(input).map_err(decl_expr_error)?;
+    // Later equations of the same function (ex. `fact n = ...` after `fact 0 = 1`)
+    let mut clauses = vec![];
+    while !params.is_empty() {
+        let next = preceded(many1(newlines(true)), clause::<D>)(input);
+        match next {
+            Ok((rest, (next_ident, next_params, next_body)))
+                if next_ident.to_str() == ident.to_str() =>
+            {
+                if next_params.len() != params.len() {
+                    let (start, _) = many1(newlines(true))(input)?;
+                    let error =
+                        nom::error::ParseError::from_error_kind(start, NomErrorKind::Verify);
+                    return Err(clause_params_error(nom::Err::Error(error)));
+                }
+                clauses.push((next_ident.to_str(), next_params, next_body));
+                input = rest;
+            }
+            _ => break,
+        }
+    }
+    if !clauses.is_empty() {
+        clauses.insert(0, (ident.to_str(), params, body));
+        return Ok((input, D::clauses(clauses)));
+    }
+    let body = params
+        .into_iter()
+        .rev()
+        .fold(body, |body, param| D::Expr::fn_expr(param, body));
+    Ok((input, D::expression(ident.to_str(), body)))
+}
+
+// `ident params = body`
+fn clause<D>(input: Input<'_>) -> ParseResult<'_, (Input<'_>, Vec<Match<D>>, D::Expr)>
 where
     D: DeclOutput,
 {
     tuple((identifier, many0(match_), opt_nl(assign), expr))(input)
-        .map_err(decl_expr_error)
-        .map(|(input, (ident, params, _, body))| {
-            (
-                input,
-                D::expression(
-                    ident.to_str(),
-                    params
-                        .into_iter()
-                        .rev()
-                        .fold(body, |body, param| D::Expr::fn_expr(param, body)),
-                ),
-            )
-        })
+        .map(|(input, (ident, params, _, body))| (input, (ident, params, body)))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::ast::{Decl, Expr, Match};
+    use crate::ast::{Decl, Expr, Match, TypeExpr};
     use crate::test::*;
     use crate::Span;
 
@@ -75,4 +196,98 @@ mod tests {
                 Expr::int(1)
             )
     }
+
+    parser_test! {
+        clauses_test
+        (decl): "flip true = false\nflip false = true\n" =>
+            Decl::expression(
+                "flip",
+                Expr::fn_expr(
+                    Match::ident("_0"),
+                    Expr::match_(
+                        Expr::variable("_0"),
+                        vec![
+                            (Match::bool(true), Expr::bool(false)),
+                            (Match::bool(false), Expr::bool(true))
+                        ]
+                    )
+                )
+            );
+        // Each parameter is boxed, so that tuples aren't joined
+        (decl): "f 0 (a, b) = a\n\nf n _ = n" =>
+            Decl::expression(
+                "f",
+                Expr::fn_expr(
+                    Match::ident("_0"),
+                    Expr::fn_expr(
+                        Match::ident("_1"),
+                        Expr::match_(
+                            Expr::binary(
+                                Expr::boxed(Expr::variable("_0")),
+                                ",",
+                                Expr::boxed(Expr::variable("_1"))
+                            ),
+                            vec![
+                                (
+                                    Match::tuple(
+                                        Match::boxed(Match::int(0)),
+                                        Match::boxed(Match::tuple(Match::ident("a"), Match::ident("b")))
+                                    ),
+                                    Expr::variable("a")
+                                ),
+                                (
+                                    Match::tuple(Match::boxed(Match::ident("n")), Match::boxed(Match::Ignore)),
+                                    Expr::variable("n")
+                                )
+                            ]
+                        )
+                    )
+                )
+            )
+    }
+
+    basic_test! {
+        clauses_error_test
+        decl::<Decl>(Span::new("f 0 = 1\nf a b = 2\n")).map_err(ParseError::from).map_err(|error| error.to_string())
+            => Err("error[E0033]: each equation of a function has to have the same number of parameters (found on line 2, column 1)".to_string());
+        // Declarations with other names, or without parameters, aren't equations
+        decl::<Decl>(Span::new("f 0 = 1\ng 1 = 2\n")).map(|(input, _)| input.to_str()) => Ok("g 1 = 2\n");
+        decl::<Decl>(Span::new("f = 1\nf = 2\n")).map(|(input, _)| input.to_str()) => Ok("f = 2\n")
+    }
+
+    parser_test! {
+        import_test
+        (decl): "import \"lib/other.tgo\"\n" => Decl::import("lib/other.tgo");
+        (decl): "import \"other.tgo\"" => Decl::import("other.tgo");
+        // Identifiers can start with 'import'
+        (decl): "imports = 1" => Decl::expression("imports", Expr::int(1));
+        (decl): "import List" => Decl::qualified_import("List");
+        (decl): "import Math (sqrt, pi)" => Decl::import_with("Math.tgo", None, Some(vec!["sqrt", "pi"]));
+        (decl): "import Math as M" => Decl::import_with("Math.tgo", Some("M"), None);
+        (decl): "import \"lib/m.tgo\" as M (f)" => Decl::import_with("lib/m.tgo", Some("M"), Some(vec!["f"]));
+        // 'as' is only a keyword in imports
+        (decl): "as = 1" => Decl::expression("as", Expr::int(1))
+    }
+
+    parser_test! {
+        export_test
+        (decl): "export (a)\n" => Decl::export(vec!["a"]);
+        (decl): "export (a, b,\n\tc)" => Decl::export(vec!["a", "b", "c"]);
+        (decl): "export import List" => Decl::reexport(Decl::qualified_import("List"));
+        (decl): "export import \"a.tgo\" (a)" => Decl::reexport(Decl::import_with("a.tgo", None, Some(vec!["a"])))
+    }
+
+    parser_test! {
+        signature_test
+        (decl): "add : Int -> Int -> Int\n" =>
+            Decl::signature(
+                "add",
+                TypeExpr::function(
+                    TypeExpr::named("Int", vec![]),
+                    TypeExpr::function(TypeExpr::named("Int", vec![]), TypeExpr::named("Int", vec![]))
+                )
+            );
+        (decl): "id: a -> a" =>
+            Decl::signature("id", TypeExpr::function(TypeExpr::var("a"), TypeExpr::var("a")))
+    }
 }
@@ -7,9 +7,9 @@ use crate::ParseResult;
 
 use nom::{
     branch::alt,
-    combinator::opt,
-    multi::{fold_many0, many1},
-    sequence::{pair, preceded, separated_pair, terminated, tuple},
+    combinator::{map, opt, peek, verify},
+    multi::{many0, many1, separated_nonempty_list},
+    sequence::{delimited, pair, preceded, separated_pair, terminated, tuple},
 };
 
 type ExprResult<'a, E> = ParseResult<'a, E>;
@@ -46,7 +46,7 @@ macro_rules! unary_expr {
             E: ExprOutput,
         {
             pair($op_func, $name)(input)
-                .and_then(|(input, (op, a))| Ok((input, E::unary(op.to_str(), a))))
+                .map(|(rest, (op, a))| (rest, located(input, rest, E::unary(op.to_str(), a))))
                 .or_else(try_parser($next_precedence, input))
         }
     };
@@ -56,9 +56,12 @@ pub fn expr<E>(input: Input<'_>) -> ExprResult<'_, E>
 where
     E: ExprOutput,
 {
-    do_expr(input)
+    sequence_expr(input)
 }
 
+// `a; b` can continue on the next line after the `;`
+binary_expr!(sequence_expr, semicolon, do_expr);
+
 pub fn do_expr<E>(input: Input<'_>) -> ExprResult<'_, E>
 where
     E: ExprOutput,
@@ -71,6 +74,7 @@ where
                 (input, E::do_expr(command, command_match.unwrap_or_else(E::Match::ignore), body))
             )
         )
+        .map(|(rest, expr)| (rest, located(input, rest, expr)))
         .or_else(try_parser(let_expr, input))
 }
 
@@ -80,6 +84,15 @@ where
 {
     alt((let_, delay))(input)
         .and_then(|(input, let_token)| match let_token.into() {
+            // `let rec` is the same as `delay`, but its value is parsed like a `let`'s
+            "let" if terminated(rec, peek(variable::<E::Match>))(input).is_ok() => {
+                let (input, _) = rec(input)?;
+                separated_pair(separated_pair(variable, assign, if_expr), opt_nl(in_), expr)(input)
+                    .map_err(let_assign_error)
+                    .map(|(input, ((ident, value), inner))| {
+                        (input, E::delayed(ident, value, inner))
+                    })
+            }
             "let" => {
                 separated_pair(separated_pair(match_, assign, if_expr), opt_nl(in_), expr)(input)
                     .map_err(let_assign_error)
@@ -96,6 +109,7 @@ where
             .map(|(input, ((ident, value), inner))| (input, E::delayed(ident, value, inner))),
             _ => unreachable!(),
         })
+        .map(|(rest, expr)| (rest, located(input, rest, expr)))
         .or_else(try_parser(if_expr, input))
 }
 
@@ -113,9 +127,42 @@ where
                         .map(|(input, (t, f))| (input, E::if_expr(cond, t, f)))
                 })
         })
+        .map(|(rest, expr)| (rest, located(input, rest, expr)))
+        .or_else(try_parser(cond_expr, input))
+}
+
+// `cond | a -> x | b -> y | else -> z`, the same as
+// `if a then x else if b then y else z`
+pub fn cond_expr<E>(input: Input<'_>) -> ExprResult<'_, E>
+where
+    E: ExprOutput,
+{
+    cond(input)
+        .and_then(|(input, _)| {
+            pair(
+                many1(preceding_opt_nl(cond_arm)),
+                preceding_opt_nl(preceded(pair(bar, else_), preceded(opt_nl(arrow), expr))),
+            )(input)
+            .map_err(cond_else_error)
+            .map(|(input, (arms, otherwise))| {
+                let expr = arms
+                    .into_iter()
+                    .rev()
+                    .fold(otherwise, |f, (cond, t)| E::if_expr(cond, t, f));
+                (input, expr)
+            })
+        })
+        .map(|(rest, expr)| (rest, located(input, rest, expr)))
         .or_else(try_parser(match_expr, input))
 }
 
+fn cond_arm<E>(input: Input<'_>) -> ParseResult<'_, (E, E)>
+where
+    E: ExprOutput,
+{
+    preceded(bar, separated_pair(join_expr, opt_nl(arrow), expr))(input).map_err(cond_arm_error)
+}
+
 pub fn match_expr<E>(input: Input<'_>) -> ExprResult<'_, E>
 where
     E: ExprOutput,
@@ -128,9 +175,10 @@ where
                     // nl has to be preceding so as not to conflict with
                     // the `req_nl` parser that likely directly follows the match expr
                     many1(preceding_opt_nl(match_arm))(input)
-                        .and_then(|(input, patterns)| Ok((input, E::match_(val, patterns))))
+                        .map(|(input, patterns)| (input, E::match_(val, patterns)))
                 })
         })
+        .map(|(rest, expr)| (rest, located(input, rest, expr)))
         .or_else(try_parser(join_expr, input))
 }
 
@@ -150,8 +198,9 @@ binary_expr!(equal_expr, alt((equal, not_equal)), compare_expr);
 binary_expr!(
     compare_expr,
     alt((less_than_equal, greater_than_equal, less_than, greater_than)),
-    add_expr
+    concat_expr
 );
+binary_expr!(concat_expr, concat, add_expr);
 binary_expr!(add_expr, alt((plus, minus)), mult_expr);
 binary_expr!(mult_expr, alt((star, slash, modulo)), negate_expr);
 
@@ -164,42 +213,117 @@ where
 {
     fn_(input)
         .and_then(|(input, _)| {
-            separated_pair(match_, opt_nl(arrow), expr)(input)
-                .map_err(fn_expr_error)
-                .map(|(input, (param, body))| (input, E::fn_expr(param, body)))
+            if let Ok((input, params)) = defaults::<E>(input) {
+                return preceded(opt_nl(arrow), expr)(input)
+                    .map_err(fn_expr_error)
+                    .map(|(input, body)| (input, E::fn_defaults(params, body)));
+            }
+            // `fn a b -> body` is the same as `fn a -> fn b -> body`
+            separated_pair(
+                pair(match_, many0(crate::parsers::match_::grouping)),
+                opt_nl(arrow),
+                expr,
+            )(input)
+            .map_err(fn_expr_error)
+            .map(|(input, ((param, params), body))| {
+                let body = params
+                    .into_iter()
+                    .rev()
+                    .fold(body, |body, param| E::fn_expr(param, body));
+                (input, E::fn_expr(param, body))
+            })
         })
+        .map(|(rest, expr)| (rest, located(input, rest, expr)))
         .or_else(try_parser(fn_application, input))
 }
 
+// `(a, b = default)`, where at least one part of the parameter has a default
+// A default can't have a `,` outside of parentheses, since it's the next part
+fn defaults<E>(input: Input<'_>) -> ParseResult<'_, Vec<(E::Match, Option<E>)>>
+where
+    E: ExprOutput,
+{
+    verify(
+        delimited(
+            opt_nl(left_paren),
+            separated_nonempty_list(
+                opt_nl(comma),
+                pair(
+                    crate::parsers::match_::grouping,
+                    opt(preceded(assign, opt_nl(flat_join_expr))),
+                ),
+            ),
+            right_paren,
+        ),
+        |params: &[(E::Match, Option<E>)]| params.iter().any(|(_, default)| default.is_some()),
+    )(input)
+}
+
 fn fn_application<E>(input: Input<'_>) -> ExprResult<'_, E>
 where
     E: ExprOutput,
 {
-    dot_expr(input).and_then(|(input, val)| fold_many0(dot_expr, val, E::fn_app)(input))
+    dot_expr(input).and_then(|(input, function)| {
+        many0(alt((map(underscore, |_| None), map(dot_expr, Some))))(input).map(|(input, args)| {
+            // `_` for an argument makes the application a function of it
+            if args.iter().any(Option::is_none) {
+                (input, E::holes(function, args))
+            } else {
+                (input, args.into_iter().flatten().fold(function, E::fn_app))
+            }
+        })
+    })
 }
 
 fn dot_expr<E>(input: Input<'_>) -> ExprResult<'_, E>
 where
     E: ExprOutput,
 {
-    pair(
-        grouping,
-        opt(many1(pair(opt_nl(dot), fn_application))),
+    pair(grouping, opt(many1(pair(opt_nl(dot), fn_application))))(input).map(
+        |(input, (a, other))| match other {
+            // Operators found (left to right)
+            Some(others) => (
+                input,
+                others.into_iter().fold(a, |a, (_, b)| E::fn_app(b, a)),
+            ),
+            // No operators found
+            None => (input, a),
+        },
+    )
+}
+
+fn grouping<E>(input: Input<'_>) -> ExprResult<'_, E>
+where
+    E: ExprOutput,
+{
+    named_args(input).or_else(|_| parenthesized(input))
+}
+
+// `(x = 1, y = 2)`, arguments given by the names of a function's parameters
+// Like with defaults, a value can't have a `,` outside of parentheses
+fn named_args<E>(input: Input<'_>) -> ExprResult<'_, E>
+where
+    E: ExprOutput,
+{
+    delimited(
+        opt_nl(left_paren),
+        separated_nonempty_list(
+            opt_nl(comma),
+            separated_pair(identifier, assign, opt_nl(flat_join_expr)),
+        ),
+        right_paren,
     )(input)
-    .and_then(|(input, (a, other))| match other {
-        // Operators found (left to right)
-        Some(others) => Ok((
-            input,
-            others
-                .into_iter()
-                .fold(a, |a, (_, b)| E::fn_app(b, a)),
-        )),
-        // No operators found
-        None => Ok((input, a)),
+    .map(|(input, args)| {
+        let args = args
+            .into_iter()
+            .map(|(name, value)| E::binary(E::string(name.to_str()), "=", value))
+            .reduce(|a, b| E::binary(a, ",", b))
+            .unwrap_or_else(E::unit);
+        (input, args)
     })
 }
 
-fn grouping<E>(input: Input<'_>) -> ExprResult<'_, E>
+fn parenthesized<E>(input: Input<'_>) -> ExprResult<'_, E>
 where
     E: ExprOutput,
 {
@@ -213,11 +337,12 @@ where
                     open_paren.column(),
                 )))
         })
+        .map(|(rest, expr)| (rest, located(input, rest, expr)))
         .or_else(try_parser(
             |input| {
-                opt_nl(left_bracket)(input).and_then(|(input, open_bracket)| {
-                    terminated(opt_nl(expr), right_bracket)(input)
-                        .map(|(input, inner)| (input, E::boxed(inner)))
+                opt_nl(left_bracket)(input).and_then(|(rest, open_bracket)| {
+                    terminated(opt_nl(expr), right_bracket)(rest)
+                        .map(|(rest, inner)| (rest, located(input, rest, E::boxed(inner))))
                         .map_err(terminating_bracket_error((
                             open_bracket.line(),
                             open_bracket.column(),
@@ -233,21 +358,38 @@ fn literal<E>(input: Input<'_>) -> ExprResult<'_, E>
 where
     E: ExprOutput,
 {
-    alt((true_val, false_val, number, identifier))(input)
-        .and_then(|(new_input, token)| match token.to_str() {
-            "true" => Ok((new_input, E::bool(true))),
-            "false" => Ok((new_input, E::bool(false))),
-            lexeme => {
-                if let Ok(i) = lexeme.parse::<i32>() {
-                    Ok((new_input, E::int(i)))
-                } else {
-                    Ok((new_input, E::variable(lexeme)))
-                }
-            } // Has to be done seperately so that it doesn't get mixed up as an identifier
-        })
-        .or_else(|_| string(input).map(|(input, s)| (input, E::string(s.into()))))
-        .or_else(|_| char(input).map(|(input, c)| (input, E::char(c))))
-        .map_err(literal_error)
+    alt((
+        true_val,
+        false_val,
+        number,
+        qualified_identifier,
+        identifier,
+    ))(input)
+    .map(|(new_input, token)| match token.to_str() {
+        "true" => (new_input, E::bool(true)),
+        "false" => (new_input, E::bool(false)),
+        lexeme => {
+            if let Ok(i) = lexeme.parse::<i32>() {
+                (new_input, E::int(i))
+            } else {
+                (new_input, E::variable(lexeme))
+            }
+        } // Has to be done seperately so that it doesn't get mixed up as an identifier
+    })
+    .or_else(|_| string(input).map(|(input, s)| (input, E::string(s.into()))))
+    .or_else(|_| char(input).map(|(input, c)| (input, E::char(c))))
+    .map(|(rest, expr)| (rest, located(input, rest, expr)))
+    .map_err(literal_error)
+}
+
+// Gives `expr` the source that it was parsed from, which is between `input`
+// and `rest`
+fn located<E>(input: Input<'_>, rest: Input<'_>, expr: E) -> E
+where
+    E: ExprOutput,
+{
+    let (input, rest) = (input.to_str(), rest.to_str());
+    expr.located(&input[..input.len() - rest.len()])
 }
 
 #[cfg(test)]
@@ -255,6 +397,7 @@ mod tests {
     use super::*;
     use crate::ast::{Expr, Match};
     use crate::test::*;
+    use crate::{ParseError, Span};
 
     parser_test! {
         literal_test
@@ -401,6 +544,32 @@ mod tests {
                 Expr::int(1),
                 Expr::int(2))
     }
+    parser_test! {
+        cond_test
+        (expr): "cond\n| a -> 1\n| b -> 2\n| else -> 3" =>
+            Expr::if_expr(
+                Expr::variable("a"),
+                Expr::int(1),
+                Expr::if_expr(
+                    Expr::variable("b"),
+                    Expr::int(2),
+                    Expr::int(3)));
+        // The `else` ends it, so it can be in a match arm
+        (expr): "match x to | 1 -> cond | a -> 2 | else -> 3 | _ -> 4" =>
+            Expr::match_(
+                Expr::variable("x"),
+                vec![
+                    (Match::int(1), Expr::if_expr(Expr::variable("a"), Expr::int(2), Expr::int(3))),
+                    (Match::ignore(), Expr::int(4))
+                ])
+    }
+    basic_test! {
+        cond_error_test
+        expr::<Expr>(Span::new("cond | a -> 1")).map_err(ParseError::from).map_err(|error| error.to_string())
+            => Err("error[E0035]: missing '| else -> ...' at the end of cond expression (found on line 1, column 14)".to_string());
+        expr::<Expr>(Span::new("cond | a 1 | else -> 2")).map_err(ParseError::from).map_err(|error| error.to_string())
+            => Err("error[E0034]: missing '->' between cond condition and cond body (found on line 1, column 12)".to_string())
+    }
     parser_test! {
         variable_test
         (expr): "abc" =>
@@ -434,6 +603,88 @@ mod tests {
                     Expr::variable("a"),
                     Expr::int(1)))
     }
+    parser_test! {
+        fn_params_test
+        (expr): "fn a (b, c) [d] -> a" =>
+            Expr::fn_expr(
+                Match::ident("a"),
+                Expr::fn_expr(
+                    Match::tuple(Match::ident("b"), Match::ident("c")),
+                    Expr::fn_expr(Match::boxed(Match::ident("d")), Expr::variable("a"))));
+        (expr): "fn a, b -> a" =>
+            Expr::fn_expr(Match::tuple(Match::ident("a"), Match::ident("b")), Expr::variable("a"))
+    }
+    parser_test! {
+        holes_test
+        (expr): "f _ a _" =>
+            Expr::fn_expr(
+                Match::ident("_0"),
+                Expr::fn_expr(
+                    Match::ident("_1"),
+                    Expr::fn_app(
+                        Expr::fn_app(
+                            Expr::fn_app(Expr::variable("f"), Expr::variable("_0")),
+                            Expr::variable("a")),
+                        Expr::variable("_1"))));
+        // The hole is only in the application it's an argument of
+        (expr): "g (f _) a" =>
+            Expr::fn_app(
+                Expr::fn_app(
+                    Expr::variable("g"),
+                    Expr::fn_expr(
+                        Match::ident("_0"),
+                        Expr::fn_app(Expr::variable("f"), Expr::variable("_0")))),
+                Expr::variable("a"))
+    }
+    parser_test! {
+        fn_defaults_test
+        (expr): "fn (a, b = a + 1) -> b" =>
+            Expr::fn_expr(
+                Match::tuple(Match::ident("a"), Match::ident("_1")),
+                Expr::let_expr(
+                    Match::ident("b"),
+                    Expr::match_(
+                        Expr::variable("_1"),
+                        vec![
+                            (Match::unit(), Expr::plus(Expr::variable("a"), Expr::int(1))),
+                            (Match::ignore(), Expr::variable("_1"))
+                        ]
+                    ),
+                    Expr::variable("b")));
+        // A default can be a tuple in parentheses
+        (expr): "fn ([a] = [(1, 2)]) -> a" =>
+            Expr::fn_expr(
+                Match::ident("_0"),
+                Expr::let_expr(
+                    Match::boxed(Match::ident("a")),
+                    Expr::match_(
+                        Expr::variable("_0"),
+                        vec![
+                            (Match::unit(), Expr::boxed(Expr::binary(Expr::int(1), ",", Expr::int(2)))),
+                            (Match::ignore(), Expr::variable("_0"))
+                        ]
+                    ),
+                    Expr::variable("a")));
+        // Without a default, the parameter is a tuple pattern
+        (expr): "fn (a, b) -> a" =>
+            Expr::fn_expr(
+                Match::tuple(Match::ident("a"), Match::ident("b")),
+                Expr::variable("a"))
+    }
+    parser_test! {
+        named_args_test
+        (expr): "f (x = 1, y = a + 1)" =>
+            Expr::fn_app(
+                Expr::variable("f"),
+                Expr::join(Expr::named("x", Expr::int(1)), Expr::named("y", Expr::plus(Expr::variable("a"), Expr::int(1)))));
+        (expr): "f (\n\tx = (1, 2)\n)" =>
+            Expr::fn_app(
+                Expr::variable("f"),
+                Expr::named("x", Expr::join(Expr::int(1), Expr::int(2))));
+        // `==` isn't a name
+        (expr): "f (x == 1)" =>
+            Expr::fn_app(Expr::variable("f"), Expr::equal(Expr::variable("x"), Expr::int(1)))
+    }
     parser_test! {
         fn_application_test
         (expr): "a 1" =>
@@ -472,6 +723,36 @@ mod tests {
                 Expr::variable("a")
             )
     }
+    parser_test! {
+        sequence_test
+        (expr): "println 1;\n\tlet a = 2 in a; b" => Expr::sequence(
+            Expr::fn_app(Expr::variable("println"), Expr::int(1)),
+            Expr::let_expr(
+                Match::ident("a"),
+                Expr::int(2),
+                Expr::sequence(Expr::variable("a"), Expr::variable("b"))
+            )
+        );
+        (expr): "a; b, c; d" => Expr::sequence(
+            Expr::sequence(
+                Expr::variable("a"),
+                Expr::join(Expr::variable("b"), Expr::variable("c"))
+            ),
+            Expr::variable("d")
+        )
+    }
+    parser_test! {
+        let_rec_test
+        (expr): "let rec f = fn n -> f n in f" =>
+            Expr::delayed(
+                Match::ident("f"),
+                Expr::fn_expr(Match::ident("n"), Expr::fn_app(Expr::variable("f"), Expr::variable("n"))),
+                Expr::variable("f")
+            );
+        // `rec` is still a name everywhere else
+        (expr): "let rec = 1 in rec" =>
+            Expr::let_expr(Match::ident("rec"), Expr::int(1), Expr::variable("rec"))
+    }
     parser_test! {
         do_expr_test
         (expr): "do println 1 in a then b" =>
@@ -503,4 +784,43 @@ mod tests {
         flat_join_test
         (expr): "1 ,, 2" => Expr::flat_join(Expr::int(1), Expr::int(2))
     }
+    parser_test! {
+        concat_test
+        (expr): "a ++ b + 1 == c" => Expr::equal(
+            Expr::concat(
+                Expr::variable("a"),
+                Expr::plus(Expr::variable("b"), Expr::int(1))
+            ),
+            Expr::variable("c")
+        )
+    }
+
+    fn display(source: &str) -> String {
+        expr::<Expr>(Span::new(source)).unwrap().1.to_string()
+    }
+
+    basic_test! {
+        display_test
+        display("assertEq 3 (add 1 2)") => "assertEq 3 (add 1 2)";
+        display("1 + 2 * 3") => "1 + (2 * 3)";
+        display("-a, not b") => "(-a), (not b)";
+        display("[1, \"a\", 'b']") => "[1, \"a\", 'b']";
+        display("fn a, [b] -> a") => "fn a, [b] -> a";
+        display("let a = 1 in a") => "let a = 1 in a";
+        display("if a then 1 else 2") => "if a then 1 else 2";
+        display("match a to | 1 -> true | _ -> false") => "match a to | 1 -> true | _ -> false";
+        display("do println 1 in a then b") => "do println 1 in a then b";
+        display("a.f") => "f a"
+    }
+
+    parser_test! {
+        qualified_identifier_test
+        (expr): "List.map f" =>
+            Expr::fn_app(Expr::variable("List.map"), Expr::variable("f"));
+        // With spaces, it's the `.` operator
+        (expr): "List . map" =>
+            Expr::fn_app(Expr::variable("map"), Expr::variable("List"));
+        (expr): "list.map" =>
+            Expr::fn_app(Expr::variable("map"), Expr::variable("list"))
+    }
 }
@@ -57,6 +57,7 @@ where
             input,
         ))
         .or_else(try_parser(atom, input))
+        .map(|(rest, pattern)| (rest, located(input, rest, pattern)))
 }
 
 fn atom<M>(input: Input<'_>) -> MatchResult<'_, M>
@@ -64,15 +65,15 @@ where
     M: MatchOutput,
 {
     alt((true_val, false_val, underscore, number, identifier))(input)
-        .and_then(|(new_input, token)| match token.into() {
-            "true" => Ok((new_input, M::bool(true))),
-            "false" => Ok((new_input, M::bool(false))),
-            "_" => Ok((new_input, M::ignore())),
+        .map(|(new_input, token)| match token.into() {
+            "true" => (new_input, M::bool(true)),
+            "false" => (new_input, M::bool(false)),
+            "_" => (new_input, M::ignore()),
             lexeme => {
                 if let Ok(i) = lexeme.parse::<i32>() {
-                    Ok((new_input, M::int(i)))
+                    (new_input, M::int(i))
                 } else {
-                    Ok((new_input, M::ident(lexeme)))
+                    (new_input, M::ident(lexeme))
                 }
             }
         })
@@ -86,10 +87,20 @@ where
     M: MatchOutput,
 {
     identifier(input)
-        .map(|(input, lexeme)| (input, M::ident(lexeme.into())))
+        .map(|(rest, lexeme)| (rest, located(input, rest, M::ident(lexeme.into()))))
         .map_err(ident_match_error)
 }
 
+// Gives `pattern` the source that it was parsed from, which is between `input`
+// and `rest`
+fn located<M>(input: Input<'_>, rest: Input<'_>, pattern: M) -> M
+where
+    M: MatchOutput,
+{
+    let (input, rest) = (input.to_str(), rest.to_str());
+    pattern.located(&input[..input.len() - rest.len()])
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -98,7 +109,9 @@ mod tests {
 
     parser_test! {
         ident_test
-        (match_): "abc" => Match::ident("abc")
+        (match_): "abc" => Match::ident("abc");
+        (match_): "_abc" => Match::ident("_abc");
+        (match_): "_" => Match::ignore()
     }
     parser_test! {
         tuple_test
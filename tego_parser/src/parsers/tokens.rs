@@ -2,21 +2,22 @@ use crate::error::*;
 use crate::{Input, ParseResult};
 use nom::{
     branch::alt,
-    bytes::complete::{is_not, tag, take_until, take_while1},
+    bytes::complete::{tag, take_until, take_while, take_while1},
     character::complete::{anychar, digit1, line_ending, multispace0, not_line_ending, space0},
-    combinator::{all_consuming, map, map_res, opt, peek, rest_len, verify},
+    combinator::{all_consuming, map, map_res, opt, peek, recognize, rest_len, verify},
     multi::many0,
-    sequence::{preceded, terminated, tuple},
+    sequence::{pair, preceded, terminated, tuple},
+    InputTake,
 };
 
-const KEYWORDS: &[&str; 16] = &[
+const KEYWORDS: &[&str; 19] = &[
     "and", "or", "xor", "not", "true", "false", "if", "then", "else", "let", "in", "fn", "match",
-    "to", "delay", "do"
+    "to", "delay", "do", "import", "export", "cond",
 ];
 
-pub fn newlines<'a>(
-    is_req: bool,
-) -> impl Fn(Input<'a>) -> ParseResult<'a, (Vec<Input<'a>>, Option<Input<'a>>, Vec<Input<'a>>)> {
+type Newlines<'a> = (Vec<Input<'a>>, Option<Input<'a>>, Vec<Input<'a>>);
+
+pub fn newlines<'a>(is_req: bool) -> impl Fn(Input<'a>) -> ParseResult<'a, Newlines<'a>> {
     move |input| {
         map_res(
             tuple((
@@ -45,7 +46,7 @@ pub fn multicomment0(input: Input<'_>) -> ParseResult<'_, Vec<Input<'_>>> {
     )(input)
 }
 
-fn single_comment(input: Input<'_>) -> ParseResult<'_, Input<'_>> {
+pub(crate) fn single_comment(input: Input<'_>) -> ParseResult<'_, Input<'_>> {
     preceded(
         tag("--"),
         alt((terminated(not_line_ending, line_ending), not_line_ending)),
@@ -59,7 +60,7 @@ fn inline_comment(input: Input<'_>) -> ParseResult<'_, Input<'_>> {
     )(input)
 }
 
-fn multi_comment(input: Input<'_>) -> ParseResult<'_, Input<'_>> {
+pub(crate) fn multi_comment(input: Input<'_>) -> ParseResult<'_, Input<'_>> {
     terminated(preceded(tag("{-"), take_until("-}")), tag("-}"))(input)
 }
 
@@ -100,7 +101,7 @@ macro_rules! reserved {
             token(terminated(
                 tag($lexeme_str),
                 alt((
-                    peek(verify(anychar, |c| !is_identifier_char(*c))),
+                    peek(verify(anychar, |c| !is_identifier_rest_char(*c))),
                     map(
                         // Only used for typechecking purposes
                         verify(rest_len, |len| *len == 0),
@@ -123,9 +124,10 @@ pub fn char(input: Input<'_>) -> ParseResult<'_, char> {
     token(terminated(preceded(single_quote, anychar), single_quote))(input).map_err(char_error)
 }
 
+// Strings can be empty
 pub fn string(input: Input<'_>) -> ParseResult<'_, Input<'_>> {
     token(terminated(
-        preceded(double_quote, is_not("\"")),
+        preceded(double_quote, take_while(|c| c != '"')),
         double_quote,
     ))(input)
     .map_err(string_error)
@@ -136,20 +138,81 @@ pub fn number(input: Input<'_>) -> ParseResult<'_, Input<'_>> {
 }
 
 pub fn identifier(input: Input<'_>) -> ParseResult<'_, Input<'_>> {
-    token(verify(take_while1(is_identifier_char), |id: &Input| {
-        !is_keyword(id.to_str()) && !id.to_str().starts_with('\'')
+    token(unqualified_identifier)(input).map_err(ident_error)
+}
+
+// A name from a module imported with `import Name` (ex. `List.map`)
+// There can't be any spaces around the `.`, otherwise it's the `.` operator
+pub fn qualified_identifier(input: Input<'_>) -> ParseResult<'_, Input<'_>> {
+    token(|input: Input<'_>| {
+        tuple((module_name, tag("."), unqualified_identifier))(input).map(
+            |(rest, (module, _, name))| {
+                let (_, lexeme) = input.take_split(module.to_str().len() + 1 + name.to_str().len());
+                (rest, lexeme)
+            },
+        )
+    })(input)
+    .map_err(ident_error)
+}
+
+// Module names start with an uppercase letter (ex. `List`)
+pub fn module_name(input: Input<'_>) -> ParseResult<'_, Input<'_>> {
+    token(verify(identifier_chars, |id: &Input| {
+        id.to_str().starts_with(|c: char| c.is_ascii_uppercase())
     }))(input)
     .map_err(ident_error)
 }
 
+// Identifiers can start with `_` (ex. `_unused`), but `_` on its own is the ignore pattern
+fn unqualified_identifier<'a>(input: Input<'a>) -> ParseResult<'a, Input<'a>> {
+    verify(
+        |input: Input<'a>| {
+            tuple((opt(tag("_")), identifier_chars))(input).map(|(rest, (underscore, name))| {
+                let len = underscore.map_or(0, |_| 1) + name.to_str().len();
+                let (_, lexeme) = input.take_split(len);
+                (rest, lexeme)
+            })
+        },
+        |id: &Input| {
+            let id = id.to_str();
+            !is_keyword(id) && !id.trim_start_matches('_').starts_with('\'')
+        },
+    )(input)
+}
+
+// Names can have digits, but not at the start (ex. `base64`), so the names
+// made up by desugarings (ex. `_0`) can't be written
+pub(crate) fn identifier_chars(input: Input<'_>) -> ParseResult<'_, Input<'_>> {
+    recognize(pair(
+        take_while1(is_identifier_char),
+        take_while(is_identifier_rest_char),
+    ))(input)
+}
+
 fn is_identifier_char(c: char) -> bool {
     c.is_ascii_alphabetic() || c == '\''
 }
 
+fn is_identifier_rest_char(c: char) -> bool {
+    is_identifier_char(c) || c.is_ascii_digit()
+}
+
 reserved!(comma, ",");
-reserved!(plus, "+");
-reserved!(minus, "-");
+reserved!(semicolon, ";");
+
+// `+` isn't the start of `++`
+pub fn plus(input: Input<'_>) -> ParseResult<'_, Input<'_>> {
+    token(terminated(tag("+"), nom::combinator::not(tag("+"))))(input).map_err(reserved_error("+"))
+}
+
+reserved!(concat, "++");
 reserved!(star, "*");
+
+// `--` starts a comment, so it isn't a minus followed by a negation
+pub fn minus(input: Input<'_>) -> ParseResult<'_, Input<'_>> {
+    token(terminated(tag("-"), nom::combinator::not(tag("-"))))(input).map_err(reserved_error("-"))
+}
+
 reserved!(slash, "/");
 reserved!(modulo, "%");
 reserved!(keyword and, "and");
@@ -175,10 +238,12 @@ reserved!(keyword in_, "in");
 reserved!(assign, "=");
 reserved!(keyword fn_, "fn");
 reserved!(arrow, "->");
+reserved!(fat_arrow, "=>");
 reserved!(keyword match_kw, "match");
 reserved!(keyword to, "to");
+reserved!(keyword cond, "cond");
 reserved!(bar, "|");
-reserved!(underscore, "_");
+reserved!(keyword underscore, "_");
 reserved!(keyword delay, "delay");
 reserved!(single_quote, "'");
 reserved!(double_quote, "\"");
@@ -187,8 +252,15 @@ reserved!(right_bracket, "]");
 reserved!(double_comma, ",,");
 reserved!(keyword do_, "do");
 reserved!(dot, ".");
+reserved!(keyword import, "import");
+reserved!(keyword export, "export");
+// Only a keyword in imports, so it can still be used as a name
+reserved!(keyword as_, "as");
+// Only a keyword right after `let`, and before a name
+reserved!(keyword rec, "rec");
+reserved!(colon, ":");
 
-fn is_keyword(lexeme: &str) -> bool {
+pub(crate) fn is_keyword(lexeme: &str) -> bool {
     KEYWORDS.iter().any(|keyword| keyword == &lexeme)
 }
 
@@ -209,8 +281,14 @@ mod tests {
 
     // Reserved token parsing
     parser_test!(comma_test (comma): "," => ",".into());
+    parser_test!(semicolon_test (semicolon): ";" => ";".into());
     parser_test!(plus_test (plus): "+" => "+".into());
     parser_test!(minus_test (minus): "-" => "-".into());
+
+    #[test]
+    fn minus_comment_test() {
+        assert!(minus("-- comment".into()).is_err());
+    }
     parser_test!(star_test (star): "*" => "*".into());
     parser_test!(slash_test (slash): "/" => "/".into());
     parser_test!(modulo_test (modulo): "%" => "%".into());
@@ -227,11 +305,21 @@ mod tests {
     parser_test!(q_mark_test (q_mark): "?" => "?".into());
     parser_test!(else_test (else_): "else" => "else".into());
     parser_test!(identifier_test (identifier): "aBc'" => "aBc'".into());
+    parser_test!(underscore_identifier_test (identifier): "_aBc" => "_aBc".into());
+    parser_test!(digit_identifier_test (identifier): "base64" => "base64".into());
+    basic_test! {
+        digit_start_identifier_test
+        identifier("_0".into()).is_err() => true;
+        identifier("1a".into()).is_err() => true;
+        in_("in1".into()).is_err() => true
+    }
+    parser_test!(qualified_identifier_test (qualified_identifier): "List.map" => "List.map".into());
     parser_test!(let_test (let_): "let" => "let".into());
     parser_test!(in_test (in_): "in" => "in".into());
     parser_test!(assign_test (assign): "=" => "=".into());
     parser_test!(fn_test (fn_): "fn" => "fn".into());
     parser_test!(arrow_test (arrow): "->" => "->".into());
+    parser_test!(fat_arrow_test (fat_arrow): "=>" => "=>".into());
     parser_test!(match_kw_test (match_kw): "match" => "match".into());
     parser_test!(to_test (to): "to" => "to".into());
     parser_test!(bar_test (bar): "|" => "|".into());
@@ -244,6 +332,7 @@ mod tests {
     parser_test!(double_comma_test (double_comma): ",," => ",,".into());
     parser_test!(do_test (do_): "do" => "do".into());
     parser_test!(dot_test (dot): "." => ".".into());
+    parser_test!(colon_test (colon): ":" => ":".into());
     // Use find and replace
     // Find: reserved!\(([a-z_]+), ("[^"]+")\);
     // Replace: parser_test!($1_test ($1): $2 => $2.into());
@@ -251,6 +340,7 @@ mod tests {
     // Literal parsing
     parser_test!(number_test (number): "12" => "12".into());
     parser_test!(string_test (string): "\"abc\"" => span_at("abc", 2, 1, 1));
+    parser_test!(empty_string_test (string): "\"\"" => span_at("", 2, 1, 1));
     basic_test!(char_test char("'a'".into()) => Ok((span_at("", 4, 1, 3), 'a')));
 
     // Comment tests
@@ -0,0 +1,162 @@
+use crate::error::*;
+use crate::parsers::tokens::*;
+use crate::Input;
+use crate::ParseResult;
+use crate::TypeOutput;
+
+use nom::{
+    combinator::opt,
+    multi::{many0, separated_nonempty_list},
+    sequence::{delimited, pair, preceded, terminated},
+};
+
+type TypeResult<'a, T> = ParseResult<'a, T>;
+
+// A type with optional constraints (ex. `(Eq a, Show b) => a -> b -> String`)
+pub fn signature_type<T>(input: Input<'_>) -> TypeResult<'_, T>
+where
+    T: TypeOutput,
+{
+    match terminated(constraints, opt_nl(fat_arrow))(input) {
+        Ok((input, constraints)) => type_(input).map(|(input, type_)| {
+            let constraints = constraints
+                .iter()
+                .map(|(class, var)| (class.to_str(), var.to_str()))
+                .collect();
+            (input, T::constrained(constraints, type_))
+        }),
+        // Without `=>`, it's the start of the type (ex. `(a -> b) -> a`)
+        Err(_) => type_(input),
+    }
+}
+
+fn constraints(input: Input<'_>) -> ParseResult<'_, Vec<(Input<'_>, Input<'_>)>> {
+    delimited(
+        left_paren,
+        separated_nonempty_list(opt_nl(comma), opt_nl(pair(module_name, identifier))),
+        opt_nl(right_paren),
+    )(input)
+    .or_else(|_| pair(module_name, identifier)(input).map(|(input, c)| (input, vec![c])))
+}
+
+// `->` is right associative, so `a -> b -> c` is `a -> (b -> c)`
+pub fn type_<T>(input: Input<'_>) -> TypeResult<'_, T>
+where
+    T: TypeOutput,
+{
+    pair(type_app, opt(preceded(opt_nl(arrow), type_)))(input).map(|(input, (param, result))| {
+        match result {
+            Some(result) => (input, T::function(param, result)),
+            None => (input, param),
+        }
+    })
+}
+
+// A type name followed by its arguments (ex. `Command Int`)
+fn type_app<T>(input: Input<'_>) -> TypeResult<'_, T>
+where
+    T: TypeOutput,
+{
+    pair(module_name, many0(type_atom))(input)
+        .map(|(input, (name, args))| (input, T::named(name.to_str(), args)))
+        .or_else(try_parser(type_atom, input))
+}
+
+fn type_atom<T>(input: Input<'_>) -> TypeResult<'_, T>
+where
+    T: TypeOutput,
+{
+    left_paren(input)
+        .and_then(|(input, open_paren)| {
+            terminated(opt_nl(type_), right_paren)(input).map_err(terminating_paren_error((
+                open_paren.line(),
+                open_paren.column(),
+            )))
+        })
+        .or_else(try_parser(
+            |input| {
+                left_bracket(input).and_then(|(input, open_bracket)| {
+                    terminated(opt_nl(type_), right_bracket)(input)
+                        .map(|(input, inner)| (input, T::boxed(inner)))
+                        .map_err(terminating_bracket_error((
+                            open_bracket.line(),
+                            open_bracket.column(),
+                        )))
+                })
+            },
+            input,
+        ))
+        .or_else(try_parser(
+            |input| {
+                module_name(input).map(|(input, name)| (input, T::named(name.to_str(), vec![])))
+            },
+            input,
+        ))
+        .or_else(try_parser(
+            |input| identifier(input).map(|(input, ident)| (input, T::var(ident.to_str()))),
+            input,
+        ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::TypeExpr;
+    use crate::test::*;
+
+    fn named(name: &str) -> TypeExpr {
+        TypeExpr::named(name, vec![])
+    }
+
+    parser_test! {
+        named_test
+        (type_): "Int" => named("Int");
+        (type_): "Command Int" => TypeExpr::named("Command", vec![named("Int")]);
+        (type_): "Command [a]" =>
+            TypeExpr::named("Command", vec![TypeExpr::boxed(TypeExpr::var("a"))])
+    }
+
+    parser_test! {
+        function_test
+        (type_): "Int -> Bool -> a" =>
+            TypeExpr::function(
+                named("Int"),
+                TypeExpr::function(named("Bool"), TypeExpr::var("a"))
+            );
+        (type_): "(a -> b) -> [a] -> [b]" =>
+            TypeExpr::function(
+                TypeExpr::function(TypeExpr::var("a"), TypeExpr::var("b")),
+                TypeExpr::function(
+                    TypeExpr::boxed(TypeExpr::var("a")),
+                    TypeExpr::boxed(TypeExpr::var("b"))
+                )
+            )
+    }
+
+    parser_test! {
+        constrained_test
+        (signature_type): "Eq a => a -> Bool" =>
+            TypeExpr::constrained(
+                vec![("Eq", "a")],
+                TypeExpr::function(TypeExpr::var("a"), named("Bool"))
+            );
+        (signature_type): "(Ord a, Show b) => a -> b" =>
+            TypeExpr::constrained(
+                vec![("Ord", "a"), ("Show", "b")],
+                TypeExpr::function(TypeExpr::var("a"), TypeExpr::var("b"))
+            );
+        (signature_type): "(a -> b) -> a" =>
+            TypeExpr::function(
+                TypeExpr::function(TypeExpr::var("a"), TypeExpr::var("b")),
+                TypeExpr::var("a")
+            )
+    }
+
+    basic_test! {
+        display_test
+        type_::<TypeExpr>("(a -> b) -> Command (Command a) -> [b]".into()).unwrap().1.to_string()
+            => "(a -> b) -> Command (Command a) -> [b]";
+        signature_type::<TypeExpr>("(Eq a, Show b) => a -> b".into()).unwrap().1.to_string()
+            => "(Eq a, Show b) => a -> b"
+    }
+}
@@ -40,7 +40,8 @@ impl<'a> Span<'a> {
 }
 
 // Used only in the parser crate
-pub fn span_at(lexeme: &str, column: usize, line: usize, offset: usize) -> Span {
+#[cfg(test)]
+pub fn span_at(lexeme: &str, column: usize, line: usize, offset: usize) -> Span<'_> {
     Span {
         lexeme,
         column,
@@ -148,6 +149,13 @@ impl<'a> FindSubstring<&str> for Span<'a> {
     }
 }
 
+// The distance in bytes between two spans of the same source
+impl<'a> Offset for Span<'a> {
+    fn offset(&self, second: &Self) -> usize {
+        self.lexeme.offset(second.lexeme)
+    }
+}
+
 macro_rules! impl_slice_for_range {
     ($range:ty) => {
         impl<'a> Slice<$range> for Span<'a> {
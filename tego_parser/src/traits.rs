@@ -8,6 +8,16 @@ pub trait MatchOutput {
     fn ident(s: &str) -> Self;
     fn string(s: &str) -> Self;
     fn char(c: char) -> Self;
+
+    // The part of the source that the pattern was parsed from (which can
+    // include the spaces around it), for outputs that keep track of where
+    // things are (ex. to say where a warning is)
+    fn located(self, _text: &str) -> Self
+    where
+        Self: Sized,
+    {
+        self
+    }
 }
 
 pub trait ExprOutput: Sized + Clone {
@@ -29,12 +39,142 @@ pub trait ExprOutput: Sized + Clone {
     fn string(s: &str) -> Self;
     fn char(c: char) -> Self;
     fn do_expr(command: Self, result_match: Self::Match, body: Self) -> Self;
+
+    // The same as `MatchOutput::located`, for an expression
+    fn located(self, _text: &str) -> Self {
+        self
+    }
+
+    // `fn (a, b = default) -> body`, where a part of the parameter is its
+    // default if the argument doesn't have it (the parts that are missing from
+    // a tuple are `()`)
+    // By default the parts with defaults are called `_0`, `_1`, ... (by their
+    // position), and each default is evaluated by a `let` in the body, so it
+    // can use the other parts
+    fn fn_defaults(params: Vec<(Self::Match, Option<Self>)>, body: Self) -> Self {
+        let mut lets = vec![];
+        let param = params
+            .into_iter()
+            .enumerate()
+            .map(|(i, (pattern, default))| match default {
+                Some(default) => {
+                    let name = format!("_{}", i);
+                    let value = Self::match_(
+                        Self::variable(&name),
+                        vec![
+                            (Self::Match::unit(), default),
+                            (Self::Match::ignore(), Self::variable(&name)),
+                        ],
+                    );
+                    lets.push((pattern, value));
+                    Self::Match::ident(&name)
+                }
+                None => pattern,
+            })
+            .reduce(Self::Match::tuple)
+            .unwrap_or_else(Self::Match::unit);
+        let body = lets.into_iter().rev().fold(body, |body, (pattern, value)| {
+            Self::let_expr(pattern, value, body)
+        });
+        Self::fn_expr(param, body)
+    }
+
+    // `f a _ b`, an application with `_` for some of its arguments, which is
+    // a function of the missing ones (ex. `map (pow 2 _) xs`)
+    // By default the missing arguments are parameters called `_0`, `_1`, ...
+    // (in order)
+    fn holes(function: Self, args: Vec<Option<Self>>) -> Self {
+        let mut names = vec![];
+        let body = args.into_iter().fold(function, |function, arg| {
+            let arg = arg.unwrap_or_else(|| {
+                let name = format!("_{}", names.len());
+                let arg = Self::variable(&name);
+                names.push(name);
+                arg
+            });
+            Self::fn_app(function, arg)
+        });
+        names.iter().rev().fold(body, |body, name| {
+            Self::fn_expr(Self::Match::ident(name), body)
+        })
+    }
 }
 
+pub trait TypeOutput: Sized {
+    fn named(name: &str, args: Vec<Self>) -> Self;
+    fn var(ident: &str) -> Self;
+    fn boxed(inner: Self) -> Self;
+    fn function(param: Self, result: Self) -> Self;
+    // `Class var => type` (ex. `Eq a => a -> a -> Bool`)
+    fn constrained(constraints: Vec<(&str, &str)>, type_: Self) -> Self;
+}
+
+// One of the equations of a function: its name, parameters, and body
+pub type Clause<'a, E> = (&'a str, Vec<<E as ExprOutput>::Match>, E);
+
 pub trait DeclOutput {
     type Expr: ExprOutput;
+    type Type: TypeOutput;
 
     fn expression(ident: &str, body: Self::Expr) -> Self;
+    fn import(path: &str) -> Self;
+    fn qualified_import(module: &str) -> Self;
+    // An import with an `as Name` alias and/or a list of the names to import
+    fn import_with(path: &str, namespace: Option<&str>, names: Option<Vec<&str>>) -> Self;
+    fn export(idents: Vec<&str>) -> Self;
+    // `export import ...`, where `import` is an import declaration
+    fn reexport(import: Self) -> Self;
+    // `ident : type`, the type signature of a declaration
+    fn signature(ident: &str, type_: Self::Type) -> Self;
+    // A function declared by several equations with the same number of
+    // parameters (ex. `fact 0 = 1` and `fact n = n * fact (n - 1)`)
+    // By default it's a `match` on the parameters, which are called `_0`,
+    // `_1`, ... (identifiers can't start with digits, so they can't be written)
+    fn clauses(clauses: Vec<Clause<Self::Expr>>) -> Self
+    where
+        Self: Sized,
+    {
+        type Match<D> = <<D as DeclOutput>::Expr as ExprOutput>::Match;
+
+        let ident = clauses.first().map_or("", |(ident, _, _)| ident);
+        let count = clauses.first().map_or(0, |(_, params, _)| params.len());
+        let names = (0..count).map(|i| format!("_{}", i)).collect::<Vec<_>>();
+        // Each parameter is boxed, so tuples aren't joined into one
+        let arms = clauses
+            .into_iter()
+            .map(|(_, params, body)| {
+                let pattern = if count == 1 {
+                    params
+                        .into_iter()
+                        .next()
+                        .unwrap_or_else(Match::<Self>::unit)
+                } else {
+                    params
+                        .into_iter()
+                        .map(Match::<Self>::boxed)
+                        .reduce(Match::<Self>::tuple)
+                        .unwrap_or_else(Match::<Self>::unit)
+                };
+                (pattern, body)
+            })
+            .collect();
+        let value = if count == 1 {
+            Self::Expr::variable(&names[0])
+        } else {
+            names
+                .iter()
+                .map(|name| Self::Expr::boxed(Self::Expr::variable(name)))
+                .reduce(|a, b| Self::Expr::binary(a, ",", b))
+                .unwrap_or_else(Self::Expr::unit)
+        };
+        let body = names
+            .iter()
+            .rev()
+            .fold(Self::Expr::match_(value, arms), |body, name| {
+                Self::Expr::fn_expr(Match::<Self>::ident(name), body)
+            });
+        Self::expression(ident, body)
+    }
 
     fn to_main(&self, _main_fn_ident: &str) -> Option<Self::Expr> {
         None
@@ -84,10 +224,25 @@ impl ExprOutput for () {
     fn do_expr(_: Self, _: Self::Match, _: Self) -> Self {}
 }
 
+impl TypeOutput for () {
+    fn named(_: &str, _: Vec<Self>) -> Self {}
+    fn var(_: &str) -> Self {}
+    fn boxed(_: Self) -> Self {}
+    fn function(_: Self, _: Self) -> Self {}
+    fn constrained(_: Vec<(&str, &str)>, _: Self) -> Self {}
+}
+
 impl DeclOutput for () {
     type Expr = ();
+    type Type = ();
 
     fn expression(_: &str, _: Self::Expr) -> Self {}
+    fn import(_: &str) -> Self {}
+    fn qualified_import(_: &str) -> Self {}
+    fn import_with(_: &str, _: Option<&str>, _: Option<Vec<&str>>) -> Self {}
+    fn export(_: Vec<&str>) -> Self {}
+    fn reexport(_: Self) -> Self {}
+    fn signature(_: &str, _: Self::Type) -> Self {}
 }
 
 impl ProgOutput for () {